@@ -0,0 +1,33 @@
+//! Пример встраивания интерпретатора: свой builtin и вывод в stdout программы.
+//!
+//! Запуск: `cargo run --example embed`.
+
+use se_cli::Shell;
+
+fn main() -> std::io::Result<()> {
+    let mut shell = Shell::builder()
+        .env("PROJECT", "se-cli")
+        .builtin("version", |call| {
+            writeln!(
+                call.stdout,
+                "{} {}",
+                call.env["PROJECT"],
+                env!("CARGO_PKG_VERSION")
+            )?;
+            Ok(0)
+        })
+        .build();
+
+    // Захват вывода и измененных переменных.
+    let out = shell.eval("version | tr a-z A-Z\nBUILD=release");
+    print!("{}", out.stdout_text());
+    println!("changed: {:?}, status: {}", out.vars, out.status.code);
+
+    // Потоковый вывод: команды пишут прямо в stdout/stderr процесса.
+    let status = shell.eval_to(
+        "echo build: $BUILD\nls Cargo.toml missing.txt",
+        std::io::stdout(),
+        std::io::stderr(),
+    )?;
+    std::process::exit(status.code);
+}
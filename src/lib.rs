@@ -1,19 +1,86 @@
-//! `se-cli` — простой интерпретатор командной строки.
+//! `se-cli` — интерпретатор командной строки в стиле bash.
 //!
-//! На текущем этапе реализованы:
-//! - REPL (read-execute-print loop)
-//! - встроенные команды: `cat`, `echo`, `wc`, `pwd`, `exit`
-//! - запуск внешних команд (если команда не builtin)
-//! - разбор аргументов с одинарными/двойными кавычками
-//! - присваивания переменных окружения вида `NAME=value`
-//! - подстановки переменных окружения `$NAME`
-//! - пайплайны `cmd1 | cmd2 | cmd3`
+//! Реализованы:
+//! - REPL (read-execute-print loop) с историей, алиасами и стартовым файлом;
+//! - списки команд (`;`, `&&`, `||`), пайплайны, подоболочки `( )` и группы `{ }`,
+//!   перенаправления, here-documents, подстановка процессов `<(...)`;
+//! - присваивания `NAME=value`, подстановки `$NAME` и `$((...))`, фигурные
+//!   скобки, тильда, разбиение на поля по `IFS`;
+//! - builtins: `cat`, `echo`, `printf`, `grep`, `wc`, `head`, `tail`, `sort`,
+//!   `uniq`, `cut`, `tr`, `tee`, `ls`, `mkdir`, `rm`, `cp`, `mv`, `touch`,
+//!   `stat`, `find`, `xargs`, `test`/`[`/`[[`, `pwd`, `exit`, и команды,
+//!   меняющие состояние шелла: `cd`, `let`/`(( ))`, `set`, `alias`/`unalias`,
+//!   `source`/`.`, `history`, `type`, `which`, `command`, `hash`, `timeout`, `ulimit`;
+//! - запуск внешних программ из `PATH`, ограниченный режим (`--restricted`).
+//!
+//! # Встраивание
+//!
+//! Интерпретатор можно запускать из программы: `Shell` выполняет скрипты и
+//! хранит состояние (переменные, рабочую директорию, алиасы) между вызовами,
+//! `ShellBuilder` задает окружение, рабочую директорию, собственные builtins,
+//! файловую систему (`vfs`), исполнитель внешних программ (`executor`,
+//! `mock`) и срок для них. `run` и `run_with_executor` запускают REPL над
+//! stdin/stdout процесса.
+//!
+//! ```
+//! use std::io::Write;
+//!
+//! let mut shell = se_cli::Shell::builder()
+//!     .env("USER_NAME", "ann")
+//!     .builtin("greet", |call| {
+//!         let name = call.args.first().map_or("stranger", String::as_str);
+//!         writeln!(call.stdout, "hi, {name}")?;
+//!         Ok(0)
+//!     })
+//!     .build();
+//!
+//! let out = shell.eval("greet $USER_NAME | grep hi\nCOUNT=2");
+//! assert_eq!(out.status.code, 0);
+//! assert_eq!(out.stdout_text(), "hi, ann\n");
+//! assert_eq!(out.vars["COUNT"], "2");
+//!
+//! // Вывод можно писать в любые `Write` по мере выполнения.
+//! let status = shell.eval_to("echo $COUNT", std::io::stdout(), std::io::stderr())?;
+//! assert!(status.success());
+//! # Ok::<(), std::io::Error>(())
+//! ```
 
 mod shell;
 
-/// Запускает интерактивный REPL.
+use std::sync::Arc;
+
+pub use shell::{BuiltinCall, Output, Shell, ShellBuilder, Status, executor, mock, vfs};
+
+/// Запускает интерактивный REPL с опциями из аргументов командной строки
+/// (`-i`, `--rcfile FILE`, `--norc`, `--dump-tokens`, `--dump-ast`,
+/// `--dump-format pretty|json`, `-r`/`--restricted`, `--restricted-root DIR`,
+/// `--allow NAME`, `--timeout DURATION`).
+///
+/// Шелл интерактивный, если stdin — терминал или задан `-i`.
 ///
 /// Возвращаемое значение — код завершения процесса интерпретатора.
 pub fn run() -> i32 {
-    shell::run_repl(std::io::stdin(), std::io::stdout(), std::io::stderr())
+    run_with_executor(Arc::new(executor::StdProcessExecutor::new()))
+}
+
+/// То же, что `run`, но внешние программы запускает `executor` (например,
+/// `mock::MockExecutor` или обертка, которая пишет журнал запусков).
+pub fn run_with_executor(executor: Arc<dyn executor::ProcessExecutor>) -> i32 {
+    use std::io::IsTerminal;
+
+    let mut startup = match shell::Startup::from_args(std::env::args_os()) {
+        Ok(startup) => startup,
+        Err(e) => {
+            let _ = e.print();
+            return e.exit_code();
+        }
+    };
+    startup.interactive |= std::io::stdin().is_terminal();
+    shell::run_repl_with_executor(
+        std::io::stdin(),
+        std::io::stdout(),
+        std::io::stderr(),
+        startup,
+        executor,
+    )
 }
@@ -0,0 +1,439 @@
+//! Целочисленная арифметика шелла: `$((...))`, `let`, `(( ... ))`.
+//!
+//! Выражение разбирается в дерево методом precedence climbing и затем вычисляется
+//! (дерево нужно, чтобы `&&`, `||` и `?:` не выполняли присваивания в невыбранной ветке).
+//! Значения — `i64`; переполнение и деление на ноль — ошибки.
+//! Переменные упоминаются без `$`; пустая или неустановленная переменная равна 0.
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Ошибка разбора или вычисления арифметического выражения.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ArithError {
+    /// Исходное выражение (как его ввел пользователь).
+    pub(crate) expr: String,
+    /// Описание ошибки.
+    pub(crate) message: String,
+}
+
+impl fmt::Display for ArithError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.expr.trim(), self.message)
+    }
+}
+
+impl std::error::Error for ArithError {}
+
+/// Хранилище переменных, доступное вычислителю.
+pub(crate) trait ArithVars {
+    fn get(&self, name: &str) -> Option<&str>;
+    fn set(&mut self, name: &str, value: String);
+}
+
+impl ArithVars for HashMap<String, String> {
+    fn get(&self, name: &str) -> Option<&str> {
+        HashMap::get(self, name).map(String::as_str)
+    }
+
+    fn set(&mut self, name: &str, value: String) {
+        self.insert(name.to_string(), value);
+    }
+}
+
+/// Вычисляет выражение, читая и изменяя переменные в `vars`.
+pub(crate) fn evaluate(expr: &str, vars: &mut dyn ArithVars) -> Result<i64, ArithError> {
+    evaluate_at_depth(expr, vars, 0)
+}
+
+/// Глубина рекурсивного вычисления значений переменных (`a=b`, `b=a+1`, ...).
+const MAX_DEPTH: usize = 32;
+
+fn evaluate_at_depth(
+    expr: &str,
+    vars: &mut dyn ArithVars,
+    depth: usize,
+) -> Result<i64, ArithError> {
+    let error = |message: String| ArithError {
+        expr: expr.to_string(),
+        message,
+    };
+    if depth > MAX_DEPTH {
+        return Err(error("expression recursion level exceeded".to_string()));
+    }
+    let tokens = lex(expr).map_err(error)?;
+    if tokens.is_empty() {
+        return Ok(0);
+    }
+    let mut parser = Parser { tokens, pos: 0 };
+    let node = parser.parse_comma().map_err(error)?;
+    if let Some(tok) = parser.tokens.get(parser.pos) {
+        return Err(error(format!("syntax error: unexpected `{tok}`")));
+    }
+    Evaluator { vars, depth }.eval(&node).map_err(error)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Num(i64),
+    Ident(String),
+    Op(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Num(n) => write!(f, "{n}"),
+            Token::Ident(s) => write!(f, "{s}"),
+            Token::Op(op) => write!(f, "{op}"),
+        }
+    }
+}
+
+/// Операторы в порядке убывания длины: лексер выбирает самое длинное совпадение.
+const OPERATORS: &[&str] = &[
+    "<<=", ">>=", "**", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+=", "-=",
+    "*=", "/=", "%=", "&=", "^=", "|=", "+", "-", "*", "/", "%", "<", ">", "&", "^", "|", "!", "~",
+    "?", ":", "=", "(", ")", ",",
+];
+
+fn lex(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if c.is_ascii_digit() {
+            let end = rest
+                .find(|ch: char| !ch.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            tokens.push(Token::Num(parse_number(&rest[..end])?));
+            rest = &rest[end..];
+            continue;
+        }
+        if c == '_' || c.is_ascii_alphabetic() {
+            let end = rest
+                .find(|ch: char| !(ch == '_' || ch.is_ascii_alphanumeric()))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+            continue;
+        }
+        let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) else {
+            return Err(format!("syntax error: invalid character `{c}`"));
+        };
+        tokens.push(Token::Op(op));
+        rest = &rest[op.len()..];
+    }
+    Ok(tokens)
+}
+
+/// Разбирает целочисленный литерал: десятичный, `0x` (hex) или с ведущим `0` (octal).
+fn parse_number(text: &str) -> Result<i64, String> {
+    let (digits, radix) =
+        if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+            (hex, 16)
+        } else if text.len() > 1 && text.starts_with('0') {
+            (&text[1..], 8)
+        } else {
+            (text, 10)
+        };
+    i64::from_str_radix(digits, radix).map_err(|e| match e.kind() {
+        std::num::IntErrorKind::PosOverflow => format!("value too great for base: {text}"),
+        _ => format!("invalid number: {text}"),
+    })
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Num(i64),
+    Var(String),
+    Unary(&'static str, Box<Node>),
+    Binary(&'static str, Box<Node>, Box<Node>),
+    /// `a && b`, `a || b`: правая часть вычисляется по необходимости.
+    Logical(&'static str, Box<Node>, Box<Node>),
+    Conditional(Box<Node>, Box<Node>, Box<Node>),
+    /// `name = expr`, `name += expr` и т.п. (оператор без `=`; для `=` — пустая строка).
+    Assign(String, &'static str, Box<Node>),
+    /// `++name`, `--name` (`post == false`) и `name++`, `name--` (`post == true`).
+    IncDec {
+        name: String,
+        delta: i64,
+        post: bool,
+    },
+    Comma(Box<Node>, Box<Node>),
+}
+
+/// Приоритет бинарного оператора (больше — связывает сильнее) и правоассоциативность.
+fn binary_precedence(op: &str) -> Option<(u8, bool)> {
+    Some(match op {
+        "||" => (1, false),
+        "&&" => (2, false),
+        "|" => (3, false),
+        "^" => (4, false),
+        "&" => (5, false),
+        "==" | "!=" => (6, false),
+        "<" | ">" | "<=" | ">=" => (7, false),
+        "<<" | ">>" => (8, false),
+        "+" | "-" => (9, false),
+        "*" | "/" | "%" => (10, false),
+        "**" => (11, true),
+        _ => return None,
+    })
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), String> {
+        if self.peek_op() == Some(op) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn unexpected(&self) -> String {
+        match self.tokens.get(self.pos) {
+            Some(tok) => format!("syntax error: unexpected `{tok}`"),
+            None => "syntax error: operand expected".to_string(),
+        }
+    }
+
+    fn parse_comma(&mut self) -> Result<Node, String> {
+        let mut node = self.parse_assignment()?;
+        while self.peek_op() == Some(",") {
+            self.pos += 1;
+            let rhs = self.parse_assignment()?;
+            node = Node::Comma(Box::new(node), Box::new(rhs));
+        }
+        Ok(node)
+    }
+
+    fn parse_assignment(&mut self) -> Result<Node, String> {
+        if let Some(Token::Ident(name)) = self.tokens.get(self.pos)
+            && let Some(Token::Op(op)) = self.tokens.get(self.pos + 1)
+            && let Some(bin) = op.strip_suffix('=')
+            && matches!(
+                bin,
+                "" | "+" | "-" | "*" | "/" | "%" | "<<" | ">>" | "&" | "^" | "|"
+            )
+        {
+            let name = name.clone();
+            let bin = OPERATORS.iter().copied().find(|o| *o == bin).unwrap_or("");
+            self.pos += 2;
+            let value = self.parse_assignment()?;
+            return Ok(Node::Assign(name, bin, Box::new(value)));
+        }
+        self.parse_conditional()
+    }
+
+    fn parse_conditional(&mut self) -> Result<Node, String> {
+        let cond = self.parse_binary(1)?;
+        if self.peek_op() != Some("?") {
+            return Ok(cond);
+        }
+        self.pos += 1;
+        let then = self.parse_assignment()?;
+        self.expect(":")?;
+        let otherwise = self.parse_assignment()?;
+        Ok(Node::Conditional(
+            Box::new(cond),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn parse_binary(&mut self, min_prec: u8) -> Result<Node, String> {
+        let mut lhs = self.parse_unary()?;
+        while let Some(op) = self.peek_op()
+            && let Some((prec, right_assoc)) = binary_precedence(op)
+            && prec >= min_prec
+        {
+            self.pos += 1;
+            let next_min = if right_assoc { prec } else { prec + 1 };
+            let rhs = self.parse_binary(next_min)?;
+            lhs = if matches!(op, "&&" | "||") {
+                Node::Logical(op, Box::new(lhs), Box::new(rhs))
+            } else {
+                Node::Binary(op, Box::new(lhs), Box::new(rhs))
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, String> {
+        match self.peek_op() {
+            Some(op @ ("++" | "--")) => {
+                self.pos += 1;
+                let Some(Token::Ident(name)) = self.tokens.get(self.pos) else {
+                    return Err(format!("syntax error: `{op}` requires a variable"));
+                };
+                let name = name.clone();
+                self.pos += 1;
+                Ok(Node::IncDec {
+                    name,
+                    delta: if op == "++" { 1 } else { -1 },
+                    post: false,
+                })
+            }
+            Some(op @ ("-" | "+" | "!" | "~")) => {
+                self.pos += 1;
+                let operand = self.parse_unary()?;
+                Ok(Node::Unary(op, Box::new(operand)))
+            }
+            _ => self.parse_postfix(),
+        }
+    }
+
+    fn parse_postfix(&mut self) -> Result<Node, String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Num(n)) => Ok(Node::Num(n)),
+            Some(Token::Ident(name)) => match self.peek_op() {
+                Some(op @ ("++" | "--")) => {
+                    self.pos += 1;
+                    Ok(Node::IncDec {
+                        name,
+                        delta: if op == "++" { 1 } else { -1 },
+                        post: true,
+                    })
+                }
+                _ => Ok(Node::Var(name)),
+            },
+            Some(Token::Op("(")) => {
+                let inner = self.parse_comma()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            _ => {
+                self.pos -= 1;
+                Err(self.unexpected())
+            }
+        }
+    }
+}
+
+struct Evaluator<'a> {
+    vars: &'a mut dyn ArithVars,
+    depth: usize,
+}
+
+impl Evaluator<'_> {
+    fn eval(&mut self, node: &Node) -> Result<i64, String> {
+        match node {
+            Node::Num(n) => Ok(*n),
+            Node::Var(name) => self.read_var(name),
+            Node::Unary(op, operand) => {
+                let v = self.eval(operand)?;
+                match *op {
+                    "-" => v.checked_neg().ok_or_else(overflow),
+                    "+" => Ok(v),
+                    "!" => Ok((v == 0) as i64),
+                    _ => Ok(!v),
+                }
+            }
+            Node::Binary(op, lhs, rhs) => {
+                let l = self.eval(lhs)?;
+                let r = self.eval(rhs)?;
+                apply_binary(op, l, r)
+            }
+            Node::Logical(op, lhs, rhs) => {
+                let l = self.eval(lhs)? != 0;
+                let value = match *op {
+                    "&&" => l && self.eval(rhs)? != 0,
+                    _ => l || self.eval(rhs)? != 0,
+                };
+                Ok(value as i64)
+            }
+            Node::Conditional(cond, then, otherwise) => {
+                if self.eval(cond)? != 0 {
+                    self.eval(then)
+                } else {
+                    self.eval(otherwise)
+                }
+            }
+            Node::Assign(name, op, value) => {
+                let rhs = self.eval(value)?;
+                let new = if op.is_empty() {
+                    rhs
+                } else {
+                    apply_binary(op, self.read_var(name)?, rhs)?
+                };
+                self.vars.set(name, new.to_string());
+                Ok(new)
+            }
+            Node::IncDec { name, delta, post } => {
+                let old = self.read_var(name)?;
+                let new = old.checked_add(*delta).ok_or_else(overflow)?;
+                self.vars.set(name, new.to_string());
+                Ok(if *post { old } else { new })
+            }
+            Node::Comma(lhs, rhs) => {
+                self.eval(lhs)?;
+                self.eval(rhs)
+            }
+        }
+    }
+
+    /// Значение переменной: пустое — 0, иначе число или (рекурсивно) выражение.
+    fn read_var(&mut self, name: &str) -> Result<i64, String> {
+        let value = self.vars.get(name).unwrap_or("").trim().to_string();
+        if value.is_empty() {
+            return Ok(0);
+        }
+        if let Ok(n) = value.parse::<i64>() {
+            return Ok(n);
+        }
+        evaluate_at_depth(&value, self.vars, self.depth + 1).map_err(|e| e.message)
+    }
+}
+
+fn overflow() -> String {
+    "integer overflow".to_string()
+}
+
+fn apply_binary(op: &str, l: i64, r: i64) -> Result<i64, String> {
+    let checked = match op {
+        "+" => l.checked_add(r),
+        "-" => l.checked_sub(r),
+        "*" => l.checked_mul(r),
+        "/" | "%" if r == 0 => return Err("division by 0".to_string()),
+        "/" => l.checked_div(r),
+        "%" => l.checked_rem(r),
+        "**" => {
+            if r < 0 {
+                return Err("exponent less than 0".to_string());
+            }
+            u32::try_from(r).ok().and_then(|r| l.checked_pow(r))
+        }
+        "<<" | ">>" if !(0..64).contains(&r) => return Err("shift count out of range".to_string()),
+        "<<" => Some(l << r),
+        ">>" => Some(l >> r),
+        "&" => Some(l & r),
+        "^" => Some(l ^ r),
+        "|" => Some(l | r),
+        "==" => Some((l == r) as i64),
+        "!=" => Some((l != r) as i64),
+        "<" => Some((l < r) as i64),
+        ">" => Some((l > r) as i64),
+        "<=" => Some((l <= r) as i64),
+        ">=" => Some((l >= r) as i64),
+        _ => return Err(format!("unknown operator `{op}`")),
+    };
+    checked.ok_or_else(overflow)
+}
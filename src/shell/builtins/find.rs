@@ -0,0 +1,544 @@
+//! `find`: обход дерева файлов с выражением-фильтром.
+//!
+//! Поддерживаются тесты `-name`, `-iname`, `-type`, `-mtime`, `-size`,
+//! действия `-print`, `-print0`, `-exec ... ;` / `-exec ... {} +`,
+//! глобальные опции `-maxdepth`, `-mindepth` и операторы `!`, `-a`, `-o`, `( )`.
+
+use std::path::Path;
+use std::time::SystemTime;
+
+use super::super::types::{IoStreams, ShellControl, ShellError, ShellResult};
+use super::super::vfs::Metadata;
+use super::{BuiltinContext, io_error_message};
+
+/// Проверяет совпадение имени с шаблоном в стиле shell (`*`, `?`, `[...]`).
+pub(super) fn glob_match(pattern: &str, name: &str, ignore_case: bool) -> bool {
+    let fold = |s: &str| -> Vec<char> {
+        if ignore_case {
+            s.to_lowercase().chars().collect()
+        } else {
+            s.chars().collect()
+        }
+    };
+    glob_match_chars(&fold(pattern), &fold(name))
+}
+
+fn glob_match_chars(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // Позиция последней `*` в шаблоне и позиция имени, с которой она сопоставлена.
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        let step = match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+                continue;
+            }
+            Some('?') => Some(1),
+            Some('[') => match match_bracket(&pattern[p..], name[n]) {
+                Some((true, len)) => Some(len),
+                Some((false, _)) => None,
+                // Незакрытая `[` — обычный символ.
+                None => (name[n] == '[').then_some(1),
+            },
+            Some('\\') if p + 1 < pattern.len() => (pattern[p + 1] == name[n]).then_some(2),
+            Some(&c) => (c == name[n]).then_some(1),
+            None => None,
+        };
+        match step {
+            Some(len) => {
+                p += len;
+                n += 1;
+            }
+            None => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Сопоставляет символ с классом `[...]` в начале шаблона.
+///
+/// Возвращает (совпал ли символ, длина класса в шаблоне) или `None`, если класс не закрыт.
+fn match_bracket(pattern: &[char], c: char) -> Option<(bool, usize)> {
+    let mut i = 1;
+    let negate = matches!(pattern.get(i), Some('!' | '^'));
+    if negate {
+        i += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let &cur = pattern.get(i)?;
+        if cur == ']' && !first {
+            return Some((matched != negate, i + 1));
+        }
+        first = false;
+        if pattern.get(i + 1) == Some(&'-') && pattern.get(i + 2).is_some_and(|&e| e != ']') {
+            let end = pattern[i + 2];
+            matched |= cur <= c && c <= end;
+            i += 3;
+        } else {
+            matched |= cur == c;
+            i += 1;
+        }
+    }
+}
+
+/// Сравнение числового аргумента `N`, `+N`, `-N`.
+#[derive(Debug, Clone, Copy)]
+enum Cmp {
+    Exactly(u64),
+    MoreThan(u64),
+    LessThan(u64),
+}
+
+impl Cmp {
+    fn parse(s: &str) -> Option<Self> {
+        if let Some(n) = s.strip_prefix('+') {
+            n.parse().ok().map(Cmp::MoreThan)
+        } else if let Some(n) = s.strip_prefix('-') {
+            n.parse().ok().map(Cmp::LessThan)
+        } else {
+            s.parse().ok().map(Cmp::Exactly)
+        }
+    }
+
+    fn matches(self, value: u64) -> bool {
+        match self {
+            Cmp::Exactly(n) => value == n,
+            Cmp::MoreThan(n) => value > n,
+            Cmp::LessThan(n) => value < n,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Expr {
+    True,
+    Name {
+        pattern: String,
+        ignore_case: bool,
+    },
+    Type(char),
+    Mtime(Cmp),
+    Size {
+        cmp: Cmp,
+        unit: u64,
+    },
+    Print,
+    Print0,
+    Exec {
+        argv: Vec<String>,
+        batch: Option<usize>,
+    },
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn has_action(&self) -> bool {
+        match self {
+            Expr::Print | Expr::Print0 | Expr::Exec { .. } => true,
+            Expr::Not(e) => e.has_action(),
+            Expr::And(a, b) | Expr::Or(a, b) => a.has_action() || b.has_action(),
+            _ => false,
+        }
+    }
+}
+
+/// Разбор выражения `find` рекурсивным спуском.
+struct ExprParser<'a> {
+    tokens: &'a [String],
+    pos: usize,
+    max_depth: Option<usize>,
+    min_depth: usize,
+    batches: usize,
+}
+
+impl ExprParser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next_value(&mut self, primary: &str) -> Result<String, String> {
+        let v = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| format!("missing argument to '{primary}'"))?;
+        self.pos += 1;
+        Ok(v)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_and()?;
+        while matches!(self.peek(), Some("-o" | "-or")) {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_unary()?;
+        loop {
+            match self.peek() {
+                None | Some("-o" | "-or" | ")") => return Ok(left),
+                Some("-a" | "-and") => self.pos += 1,
+                Some(_) => {}
+            }
+            let right = self.parse_unary()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some("!" | "-not") => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.parse_unary()?)))
+            }
+            Some("(") => {
+                self.pos += 1;
+                let inner = self.parse_or()?;
+                if self.peek() != Some(")") {
+                    return Err("missing closing ')'".to_string());
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Some(_) => self.parse_primary(),
+            None => Err("expected an expression".to_string()),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        let primary = self.next_value("")?;
+        match primary.as_str() {
+            "-name" | "-iname" => Ok(Expr::Name {
+                pattern: self.next_value(&primary)?,
+                ignore_case: primary == "-iname",
+            }),
+            "-type" => {
+                let v = self.next_value(&primary)?;
+                match v.as_str() {
+                    "f" | "d" | "l" => Ok(Expr::Type(v.chars().next().unwrap_or('f'))),
+                    _ => Err(format!("unknown argument to -type: {v}")),
+                }
+            }
+            "-mtime" => {
+                let v = self.next_value(&primary)?;
+                Cmp::parse(&v)
+                    .map(Expr::Mtime)
+                    .ok_or_else(|| format!("invalid argument '{v}' to -mtime"))
+            }
+            "-size" => {
+                let v = self.next_value(&primary)?;
+                let (num, unit) = match v.chars().last() {
+                    Some('c') => (&v[..v.len() - 1], 1),
+                    Some('w') => (&v[..v.len() - 1], 2),
+                    Some('b') => (&v[..v.len() - 1], 512),
+                    Some('k') => (&v[..v.len() - 1], 1024),
+                    Some('M') => (&v[..v.len() - 1], 1024 * 1024),
+                    Some('G') => (&v[..v.len() - 1], 1024 * 1024 * 1024),
+                    _ => (v.as_str(), 512),
+                };
+                Cmp::parse(num)
+                    .map(|cmp| Expr::Size { cmp, unit })
+                    .ok_or_else(|| format!("invalid argument '{v}' to -size"))
+            }
+            "-maxdepth" | "-mindepth" => {
+                let v = self.next_value(&primary)?;
+                let n: usize = v
+                    .parse()
+                    .map_err(|_| format!("invalid argument '{v}' to {primary}"))?;
+                if primary == "-maxdepth" {
+                    self.max_depth = Some(n);
+                } else {
+                    self.min_depth = n;
+                }
+                Ok(Expr::True)
+            }
+            "-print" => Ok(Expr::Print),
+            "-print0" => Ok(Expr::Print0),
+            "-exec" => {
+                let mut argv = Vec::new();
+                loop {
+                    let Some(tok) = self.tokens.get(self.pos) else {
+                        return Err("missing argument to '-exec'".to_string());
+                    };
+                    self.pos += 1;
+                    match tok.as_str() {
+                        ";" | "\\;" => break,
+                        "+" if argv.last().map(String::as_str) == Some("{}") => {
+                            argv.pop();
+                            let id = self.batches;
+                            self.batches += 1;
+                            if argv.is_empty() {
+                                return Err("missing argument to '-exec'".to_string());
+                            }
+                            return Ok(Expr::Exec {
+                                argv,
+                                batch: Some(id),
+                            });
+                        }
+                        _ => argv.push(tok.clone()),
+                    }
+                }
+                if argv.is_empty() {
+                    return Err("missing argument to '-exec'".to_string());
+                }
+                Ok(Expr::Exec { argv, batch: None })
+            }
+            other => Err(format!("unknown predicate '{other}'")),
+        }
+    }
+}
+
+/// Состояние обхода: вывод, пакеты для `-exec ... +`, признак ошибки.
+struct Walker<'a, 'io> {
+    ctx: BuiltinContext<'a>,
+    io: &'a mut IoStreams<'io>,
+    expr: Expr,
+    print_by_default: bool,
+    max_depth: Option<usize>,
+    min_depth: usize,
+    batches: Vec<Vec<String>>,
+    now: SystemTime,
+    had_error: bool,
+}
+
+impl Walker<'_, '_> {
+    fn walk(&mut self, shown: &str, path: &Path, depth: usize) -> ShellResult<()> {
+        let meta = match self.ctx.fs.symlink_metadata(path) {
+            Ok(m) => m,
+            Err(e) => {
+                let msg = io_error_message(&e);
+                writeln!(self.io.stderr, "find: {shown}: {msg}").map_err(ShellError::Io)?;
+                self.had_error = true;
+                return Ok(());
+            }
+        };
+
+        if depth >= self.min_depth {
+            let expr = std::mem::replace(&mut self.expr, Expr::True);
+            let result = self.eval(&expr, shown, &meta);
+            self.expr = expr;
+            if result? && self.print_by_default {
+                writeln!(self.io.stdout, "{shown}").map_err(ShellError::Io)?;
+            }
+        }
+
+        if !meta.is_dir() || self.max_depth.is_some_and(|m| depth >= m) {
+            return Ok(());
+        }
+        let mut names = match self.ctx.fs.read_dir(path) {
+            Ok(names) => names,
+            Err(e) => {
+                let msg = io_error_message(&e);
+                writeln!(self.io.stderr, "find: {shown}: {msg}").map_err(ShellError::Io)?;
+                self.had_error = true;
+                return Ok(());
+            }
+        };
+        names.sort();
+        for name in names {
+            let child_shown = if shown.ends_with('/') {
+                format!("{shown}{name}")
+            } else {
+                format!("{shown}/{name}")
+            };
+            self.walk(&child_shown, &path.join(&name), depth + 1)?;
+        }
+        Ok(())
+    }
+
+    fn eval(&mut self, expr: &Expr, shown: &str, meta: &Metadata) -> ShellResult<bool> {
+        Ok(match expr {
+            Expr::True => true,
+            Expr::Name {
+                pattern,
+                ignore_case,
+            } => {
+                let trimmed = shown.trim_end_matches('/');
+                let base = trimmed.rsplit('/').next().unwrap_or(trimmed);
+                let base = if base.is_empty() { "/" } else { base };
+                glob_match(pattern, base, *ignore_case)
+            }
+            Expr::Type(t) => match t {
+                'd' => meta.is_dir(),
+                'l' => meta.is_symlink(),
+                _ => meta.is_file(),
+            },
+            Expr::Mtime(cmp) => {
+                let age = meta
+                    .modified()
+                    .and_then(|m| self.now.duration_since(m).ok())
+                    .map(|d| d.as_secs() / 86_400)
+                    .unwrap_or(0);
+                cmp.matches(age)
+            }
+            Expr::Size { cmp, unit } => cmp.matches(meta.size().div_ceil(*unit)),
+            Expr::Print => {
+                writeln!(self.io.stdout, "{shown}").map_err(ShellError::Io)?;
+                true
+            }
+            Expr::Print0 => {
+                write!(self.io.stdout, "{shown}\0").map_err(ShellError::Io)?;
+                true
+            }
+            Expr::Exec {
+                batch: Some(id), ..
+            } => {
+                self.batches[*id].push(shown.to_string());
+                true
+            }
+            Expr::Exec { argv, batch: None } => {
+                let args: Vec<String> = argv[1..].iter().map(|a| a.replace("{}", shown)).collect();
+                self.exec(&argv[0].replace("{}", shown), &args)?
+            }
+            Expr::Not(e) => !self.eval(e, shown, meta)?,
+            Expr::And(a, b) => self.eval(a, shown, meta)? && self.eval(b, shown, meta)?,
+            Expr::Or(a, b) => self.eval(a, shown, meta)? || self.eval(b, shown, meta)?,
+        })
+    }
+
+    /// Запускает команду через шелл и пересылает ее вывод. Возвращает успех команды.
+    fn exec(&mut self, name: &str, args: &[String]) -> ShellResult<bool> {
+        match self.ctx.runner.run_command(name, args, None) {
+            Ok(result) => {
+                self.io
+                    .stdout
+                    .write_all(&result.stdout)
+                    .map_err(ShellError::Io)?;
+                self.io
+                    .stderr
+                    .write_all(&result.stderr)
+                    .map_err(ShellError::Io)?;
+                Ok(result.exit_code == 0)
+            }
+            Err(ShellError::Spawn(e)) => {
+                writeln!(self.io.stderr, "find: {e}").map_err(ShellError::Io)?;
+                self.had_error = true;
+                Ok(false)
+            }
+            Err(ShellError::Process(msg)) => {
+                writeln!(self.io.stderr, "find: {msg}").map_err(ShellError::Io)?;
+                self.had_error = true;
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Выполняет накопленные `-exec ... {} +`.
+    fn run_batches(&mut self) -> ShellResult<()> {
+        let expr = std::mem::replace(&mut self.expr, Expr::True);
+        let mut execs = Vec::new();
+        collect_batch_execs(&expr, &mut execs);
+        for (id, argv) in execs {
+            let paths = std::mem::take(&mut self.batches[id]);
+            if paths.is_empty() {
+                continue;
+            }
+            let mut args = argv[1..].to_vec();
+            args.extend(paths);
+            if !self.exec(&argv[0], &args)? {
+                self.had_error = true;
+            }
+        }
+        self.expr = expr;
+        Ok(())
+    }
+}
+
+fn collect_batch_execs(expr: &Expr, out: &mut Vec<(usize, Vec<String>)>) {
+    match expr {
+        Expr::Exec {
+            argv,
+            batch: Some(id),
+        } => out.push((*id, argv.clone())),
+        Expr::Not(e) => collect_batch_execs(e, out),
+        Expr::And(a, b) | Expr::Or(a, b) => {
+            collect_batch_execs(a, out);
+            collect_batch_execs(b, out);
+        }
+        _ => {}
+    }
+}
+
+/// Ищет файлы: `find [path...] [expression]`.
+///
+/// Без путей обходится `.`; без действий в выражении совпавшие пути печатаются.
+/// Записи директорий обходятся в отсортированном порядке, симлинки не разыменовываются.
+/// Команды `-exec` запускаются по обычным правилам шелла (builtin или внешняя программа).
+///
+/// Коды возврата: 0 — успех, 1 — ошибки обхода/`-exec`/выражения.
+pub(super) fn run_find(
+    args: &[String],
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let split = args
+        .iter()
+        .position(|a| a.starts_with('-') || a == "!" || a == "(")
+        .unwrap_or(args.len());
+    let (paths, expr_tokens) = args.split_at(split);
+
+    let mut parser = ExprParser {
+        tokens: expr_tokens,
+        pos: 0,
+        max_depth: None,
+        min_depth: 0,
+        batches: 0,
+    };
+    let expr = if expr_tokens.is_empty() {
+        Ok(Expr::True)
+    } else {
+        parser.parse_or().and_then(|e| match parser.peek() {
+            None => Ok(e),
+            Some(extra) => Err(format!("unexpected '{extra}'")),
+        })
+    };
+    let expr = match expr {
+        Ok(e) => e,
+        Err(msg) => {
+            writeln!(io.stderr, "find: {msg}").map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(1));
+        }
+    };
+
+    let paths: Vec<String> = if paths.is_empty() {
+        vec![".".to_string()]
+    } else {
+        paths.to_vec()
+    };
+
+    let mut walker = Walker {
+        ctx,
+        io,
+        print_by_default: !expr.has_action(),
+        expr,
+        max_depth: parser.max_depth,
+        min_depth: parser.min_depth,
+        batches: vec![Vec::new(); parser.batches],
+        now: SystemTime::now(),
+        had_error: false,
+    };
+    for p in &paths {
+        walker.walk(p, &ctx.resolve(p), 0)?;
+    }
+    walker.run_batches()?;
+
+    Ok(ShellControl::Continue(if walker.had_error { 1 } else { 0 }))
+}
@@ -0,0 +1,775 @@
+//! Файловые builtins: `ls`, `mkdir`, `rm`, `cp`, `mv`, `touch`, `stat`.
+//!
+//! Пути-операнды разрешаются относительно рабочей директории шелла
+//! (`BuiltinContext::cwd`), а в диагностике печатаются так, как их ввел пользователь.
+//! Файлы читаются и меняются через файловую систему шелла (`BuiltinContext::fs`).
+//! Время выводится в UTC.
+
+use std::io::BufRead;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap::Parser;
+
+use super::super::types::{IoStreams, ShellControl, ShellError, ShellResult};
+use super::super::vfs::{Metadata, copy_tree, normalize};
+use super::{BuiltinContext, io_error_message};
+
+/// Разбирает аргументы clap-структуры; при ошибке печатает ее и возвращает `None`.
+fn parse_cli<T: Parser>(
+    cmd: &str,
+    args: &[String],
+    io: &mut IoStreams<'_>,
+) -> ShellResult<Option<T>> {
+    let argv = std::iter::once(cmd.to_string()).chain(args.iter().cloned());
+    match T::try_parse_from(argv) {
+        Ok(p) => Ok(Some(p)),
+        Err(e) => {
+            writeln!(io.stderr, "{cmd}: {e}").map_err(ShellError::Io)?;
+            Ok(None)
+        }
+    }
+}
+
+fn report(io: &mut IoStreams<'_>, cmd: &str, path: &str, e: &std::io::Error) -> ShellResult<()> {
+    let msg = io_error_message(e);
+    writeln!(io.stderr, "{cmd}: {path}: {msg}").map_err(ShellError::Io)
+}
+
+fn exit_status(had_error: bool) -> ShellControl {
+    ShellControl::Continue(if had_error { 1 } else { 0 })
+}
+
+/// Переводит число дней от 1970-01-01 в (год, месяц, день) григорианского календаря.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Обратное к [`civil_from_days`].
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let m = i64::from(month);
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn unix_seconds(t: SystemTime) -> i64 {
+    match t.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+/// Форматирует время как `YYYY-MM-DD HH:MM:SS` (UTC).
+pub(crate) fn format_timestamp(t: SystemTime) -> String {
+    let secs = unix_seconds(t);
+    let (y, mo, d) = civil_from_days(secs.div_euclid(86_400));
+    let rem = secs.rem_euclid(86_400);
+    format!(
+        "{y:04}-{mo:02}-{d:02} {:02}:{:02}:{:02}",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Короткий формат времени для `ls -l`: `Mon DD HH:MM`.
+fn format_ls_time(t: SystemTime) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let secs = unix_seconds(t);
+    let (_, mo, d) = civil_from_days(secs.div_euclid(86_400));
+    let rem = secs.rem_euclid(86_400);
+    format!(
+        "{} {d:>2} {:02}:{:02}",
+        MONTHS[(mo - 1) as usize],
+        rem / 3600,
+        rem % 3600 / 60
+    )
+}
+
+/// Разбирает дату для `touch -d`: `@EPOCH`, `YYYY-MM-DD`, `YYYY-MM-DD HH:MM[:SS]`
+/// (допустим и разделитель `T`). Время трактуется как UTC.
+fn parse_date(s: &str) -> Option<SystemTime> {
+    let s = s.trim();
+    if let Some(epoch) = s.strip_prefix('@') {
+        let secs: i64 = epoch.parse().ok()?;
+        return Some(if secs >= 0 {
+            UNIX_EPOCH + Duration::from_secs(secs as u64)
+        } else {
+            UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs())
+        });
+    }
+
+    let (date, time) = match s.split_once([' ', 'T']) {
+        Some((d, t)) => (d, Some(t)),
+        None => (s, None),
+    };
+    let mut parts = date.split('-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let mut secs_of_day = 0i64;
+    if let Some(time) = time {
+        let mut parts = time.split(':');
+        let h: i64 = parts.next()?.parse().ok()?;
+        let m: i64 = parts.next()?.parse().ok()?;
+        let sec: i64 = match parts.next() {
+            Some(v) => v.parse().ok()?,
+            None => 0,
+        };
+        if parts.next().is_some() || h > 23 || m > 59 || sec > 60 {
+            return None;
+        }
+        secs_of_day = h * 3600 + m * 60 + sec;
+    }
+
+    let secs = days_from_civil(year, month, day) * 86_400 + secs_of_day;
+    Some(if secs >= 0 {
+        UNIX_EPOCH + Duration::from_secs(secs as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs())
+    })
+}
+
+/// Строка прав в стиле `ls -l` (`drwxr-xr-x`).
+fn mode_string(meta: &Metadata) -> String {
+    let kind = if meta.is_symlink() {
+        'l'
+    } else if meta.is_dir() {
+        'd'
+    } else {
+        '-'
+    };
+    let mut s = String::with_capacity(10);
+    s.push(kind);
+    for shift in [6u32, 3, 0] {
+        let bits = (meta.mode() >> shift) & 0o7;
+        s.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        s.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        s.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+    s
+}
+
+/// Размер в "человеческом" виде (`-h`): `512`, `1.5K`, `12M`.
+fn human_size(size: u64) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+    if size < 1024 {
+        return size.to_string();
+    }
+    let mut value = size as f64;
+    let mut unit = "";
+    for u in UNITS {
+        value /= 1024.0;
+        unit = u;
+        if value < 1024.0 {
+            break;
+        }
+    }
+    if value < 10.0 {
+        format!("{:.1}{unit}", (value * 10.0).ceil() / 10.0)
+    } else {
+        format!("{:.0}{unit}", value.ceil())
+    }
+}
+
+fn file_type_name(meta: &Metadata) -> &'static str {
+    if meta.is_symlink() {
+        "symbolic link"
+    } else if meta.is_dir() {
+        "directory"
+    } else if meta.is_file() {
+        if meta.size() == 0 {
+            "regular empty file"
+        } else {
+            "regular file"
+        }
+    } else {
+        "special file"
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "ls", disable_help_flag = true, disable_version_flag = true)]
+struct LsCli {
+    /// Use a long listing format.
+    #[arg(short = 'l')]
+    long: bool,
+
+    /// Do not ignore entries starting with `.`.
+    #[arg(short = 'a')]
+    all: bool,
+
+    /// With -l, print sizes like 1K 234M 2G.
+    #[arg(short = 'h')]
+    human: bool,
+
+    /// List subdirectories recursively.
+    #[arg(short = 'R')]
+    recursive: bool,
+
+    /// One entry per line (the default for non-terminal output).
+    #[arg(short = '1')]
+    one_per_line: bool,
+
+    /// Files or directories to list. Defaults to the working directory.
+    paths: Vec<String>,
+}
+
+/// Строка `ls` для одного элемента.
+fn ls_entry_line(cli: &LsCli, name: &str, meta: &Metadata) -> String {
+    if !cli.long {
+        return name.to_string();
+    }
+    let (owner, group) = meta.owner();
+    let size = if cli.human {
+        human_size(meta.size())
+    } else {
+        meta.size().to_string()
+    };
+    let mtime = meta
+        .modified()
+        .map(format_ls_time)
+        .unwrap_or_else(|| "?".to_string());
+    format!(
+        "{} {:>2} {owner} {group} {size:>5} {mtime} {name}",
+        mode_string(meta),
+        meta.links()
+    )
+}
+
+/// Печатает содержимое одной директории (и, при `-R`, поддиректорий).
+fn ls_dir(
+    cli: &LsCli,
+    shown: &str,
+    dir: &Path,
+    header: bool,
+    first: &mut bool,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<bool> {
+    let names = match ctx.fs.read_dir(dir) {
+        Ok(names) => names,
+        Err(e) => {
+            report(io, "ls", shown, &e)?;
+            return Ok(false);
+        }
+    };
+
+    let mut entries: Vec<(String, Metadata)> = Vec::new();
+    if cli.all {
+        for special in [".", ".."] {
+            if let Ok(meta) = ctx.fs.metadata(&normalize(&dir.join(special))) {
+                entries.push((special.to_string(), meta));
+            }
+        }
+    }
+    for name in names {
+        if !cli.all && name.starts_with('.') {
+            continue;
+        }
+        if let Ok(meta) = ctx.fs.symlink_metadata(&dir.join(&name)) {
+            entries.push((name, meta));
+        }
+    }
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    if header {
+        if !*first {
+            writeln!(io.stdout).map_err(ShellError::Io)?;
+        }
+        writeln!(io.stdout, "{shown}:").map_err(ShellError::Io)?;
+    }
+    *first = false;
+
+    if cli.long {
+        let blocks: u64 = entries.iter().map(|(_, m)| m.size().div_ceil(1024)).sum();
+        writeln!(io.stdout, "total {blocks}").map_err(ShellError::Io)?;
+    }
+    for (name, meta) in &entries {
+        writeln!(io.stdout, "{}", ls_entry_line(cli, name, meta)).map_err(ShellError::Io)?;
+    }
+
+    let mut ok = true;
+    if cli.recursive {
+        for (name, meta) in &entries {
+            if name == "." || name == ".." || !meta.is_dir() {
+                continue;
+            }
+            let child_shown = if shown.ends_with('/') {
+                format!("{shown}{name}")
+            } else {
+                format!("{shown}/{name}")
+            };
+            ok &= ls_dir(cli, &child_shown, &dir.join(name), true, first, ctx, io)?;
+        }
+    }
+    Ok(ok)
+}
+
+/// Выводит список файлов.
+///
+/// Флаги: `-l` (подробный формат), `-a` (включая скрытые), `-h` (размеры `1.5K`),
+/// `-R` (рекурсивно), `-1`. Сначала печатаются операнды-файлы, затем директории.
+///
+/// Коды возврата: 0 — успех, 1 — хотя бы один операнд недоступен, 2 — неверные флаги.
+pub(super) fn run_ls(
+    args: &[String],
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(cli) = parse_cli::<LsCli>("ls", args, io)? else {
+        return Ok(ShellControl::Continue(2));
+    };
+    let paths = if cli.paths.is_empty() {
+        vec![".".to_string()]
+    } else {
+        cli.paths.clone()
+    };
+
+    let mut had_error = false;
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    for p in &paths {
+        match ctx.fs.metadata(&ctx.resolve(p)) {
+            Ok(meta) if meta.is_dir() => dirs.push(p.clone()),
+            Ok(meta) => files.push((p.clone(), meta)),
+            Err(e) => {
+                report(io, "ls", p, &e)?;
+                had_error = true;
+            }
+        }
+    }
+
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    dirs.sort();
+    for (name, meta) in &files {
+        writeln!(io.stdout, "{}", ls_entry_line(&cli, name, meta)).map_err(ShellError::Io)?;
+    }
+
+    let header = cli.recursive || paths.len() > 1;
+    let mut first = files.is_empty();
+    for d in &dirs {
+        let ok = ls_dir(&cli, d, &ctx.resolve(d), header, &mut first, ctx, io)?;
+        had_error |= !ok;
+    }
+    Ok(exit_status(had_error))
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "mkdir", disable_help_flag = true, disable_version_flag = true)]
+struct MkdirCli {
+    /// No error if existing, make parent directories as needed.
+    #[arg(short = 'p')]
+    parents: bool,
+
+    /// Directories to create.
+    #[arg(required = true)]
+    dirs: Vec<String>,
+}
+
+/// Создает директории (`-p` — вместе с родительскими, без ошибки для существующих).
+///
+/// Коды возврата: 0 — успех, 1 — ошибка хотя бы для одной директории/аргументов.
+pub(super) fn run_mkdir(
+    args: &[String],
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(cli) = parse_cli::<MkdirCli>("mkdir", args, io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+    let mut had_error = false;
+    for d in &cli.dirs {
+        let path = ctx.resolve(d);
+        let result = if cli.parents {
+            ctx.fs.create_dir_all(&path)
+        } else {
+            ctx.fs.create_dir(&path)
+        };
+        if let Err(e) = result {
+            report(io, "mkdir", d, &e)?;
+            had_error = true;
+        }
+    }
+    Ok(exit_status(had_error))
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "rm", disable_help_flag = true, disable_version_flag = true)]
+struct RmCli {
+    /// Remove directories and their contents recursively.
+    #[arg(short = 'r', short_alias = 'R')]
+    recursive: bool,
+
+    /// Ignore nonexistent files, never prompt.
+    #[arg(short = 'f')]
+    force: bool,
+
+    /// Prompt before every removal.
+    #[arg(short = 'i')]
+    interactive: bool,
+
+    /// Files to remove.
+    files: Vec<String>,
+}
+
+/// Удаляет файлы и директории.
+///
+/// Флаги: `-r` (рекурсивно), `-f` (не сообщать об отсутствующих, не спрашивать),
+/// `-i` (спрашивать перед удалением каждого операнда). Ответы для `-i` читаются
+/// из stdin команды по строке на вопрос (например, `yes | rm -i a b`); без
+/// stdin ответ — "нет".
+///
+/// Коды возврата: 0 — успех, 1 — ошибка хотя бы для одного операнда/аргументов.
+pub(super) fn run_rm(
+    args: &[String],
+    mut stdin: Option<&mut dyn BufRead>,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(cli) = parse_cli::<RmCli>("rm", args, io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+    if cli.files.is_empty() {
+        if cli.force {
+            return Ok(ShellControl::Continue(0));
+        }
+        writeln!(io.stderr, "rm: missing operand").map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(1));
+    }
+
+    let interactive = cli.interactive && !cli.force;
+
+    let mut had_error = false;
+    for f in &cli.files {
+        let trimmed = f.trim_end_matches('/');
+        if trimmed.is_empty() && !f.is_empty() {
+            writeln!(io.stderr, "rm: it is dangerous to operate on '/'").map_err(ShellError::Io)?;
+            had_error = true;
+            continue;
+        }
+        if matches!(trimmed, "." | "..") || trimmed.ends_with("/.") || trimmed.ends_with("/..") {
+            writeln!(
+                io.stderr,
+                "rm: refusing to remove '.' or '..' directory: skipping '{f}'"
+            )
+            .map_err(ShellError::Io)?;
+            had_error = true;
+            continue;
+        }
+
+        let path = ctx.resolve(f);
+        let meta = match ctx.fs.symlink_metadata(&path) {
+            Ok(m) => m,
+            Err(e) => {
+                if !(cli.force && e.kind() == std::io::ErrorKind::NotFound) {
+                    report(io, "rm", f, &e)?;
+                    had_error = true;
+                }
+                continue;
+            }
+        };
+
+        if meta.is_dir() && !cli.recursive {
+            writeln!(io.stderr, "rm: {f}: Is a directory").map_err(ShellError::Io)?;
+            had_error = true;
+            continue;
+        }
+
+        if interactive {
+            write!(io.stderr, "rm: remove '{f}'? ").map_err(ShellError::Io)?;
+            let mut answer = Vec::new();
+            if let Some(stdin) = stdin.as_deref_mut() {
+                stdin
+                    .read_until(b'\n', &mut answer)
+                    .map_err(ShellError::Io)?;
+            }
+            let yes = matches!(answer.trim_ascii_start().first(), Some(b'y' | b'Y'));
+            writeln!(io.stderr).map_err(ShellError::Io)?;
+            if !yes {
+                continue;
+            }
+        }
+
+        let result = if meta.is_dir() {
+            ctx.fs.remove_dir_all(&path)
+        } else {
+            ctx.fs.remove_file(&path)
+        };
+        if let Err(e) = result {
+            report(io, "rm", f, &e)?;
+            had_error = true;
+        }
+    }
+    Ok(exit_status(had_error))
+}
+
+/// Определяет пары (источник, назначение) для `cp`/`mv`.
+///
+/// Если назначение — существующая директория, источники кладутся внутрь нее.
+/// Несколько источников допустимы только с директорией назначения.
+fn plan_transfer(
+    cmd: &str,
+    operands: &[String],
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<Option<Vec<(String, std::path::PathBuf)>>> {
+    if operands.len() < 2 {
+        let msg = if operands.is_empty() {
+            "missing file operand".to_string()
+        } else {
+            format!("missing destination file operand after '{}'", operands[0])
+        };
+        writeln!(io.stderr, "{cmd}: {msg}").map_err(ShellError::Io)?;
+        return Ok(None);
+    }
+
+    let (dst, srcs) = operands.split_last().expect("at least two operands");
+    let dst_path = ctx.resolve(dst);
+    let dst_is_dir = ctx.fs.metadata(&dst_path).is_ok_and(|m| m.is_dir());
+    if srcs.len() > 1 && !dst_is_dir {
+        writeln!(io.stderr, "{cmd}: target '{dst}' is not a directory").map_err(ShellError::Io)?;
+        return Ok(None);
+    }
+
+    let plan = srcs
+        .iter()
+        .map(|s| {
+            let target = if dst_is_dir {
+                let name = Path::new(s.trim_end_matches('/'))
+                    .file_name()
+                    .map(|n| n.to_os_string())
+                    .unwrap_or_else(|| s.into());
+                dst_path.join(name)
+            } else {
+                dst_path.clone()
+            };
+            (s.clone(), target)
+        })
+        .collect();
+    Ok(Some(plan))
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "cp", disable_help_flag = true, disable_version_flag = true)]
+struct CpCli {
+    /// Copy directories recursively.
+    #[arg(short = 'r', short_alias = 'R')]
+    recursive: bool,
+
+    /// Sources followed by the destination.
+    operands: Vec<String>,
+}
+
+/// Копирует файлы: `cp [-r] SRC DST` или `cp [-r] SRC... DIR`.
+///
+/// Коды возврата: 0 — успех, 1 — ошибка хотя бы для одного источника/аргументов.
+pub(super) fn run_cp(
+    args: &[String],
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(cli) = parse_cli::<CpCli>("cp", args, io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+    let Some(plan) = plan_transfer("cp", &cli.operands, ctx, io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+
+    let mut had_error = false;
+    for (src, dst) in plan {
+        let src_path = ctx.resolve(&src);
+        let result = match ctx.fs.metadata(&src_path) {
+            Ok(meta) if meta.is_dir() && !cli.recursive => {
+                writeln!(
+                    io.stderr,
+                    "cp: -r not specified; omitting directory '{src}'"
+                )
+                .map_err(ShellError::Io)?;
+                had_error = true;
+                continue;
+            }
+            Ok(meta) if meta.is_dir() && dst.starts_with(&src_path) => {
+                writeln!(
+                    io.stderr,
+                    "cp: cannot copy a directory, '{src}', into itself"
+                )
+                .map_err(ShellError::Io)?;
+                had_error = true;
+                continue;
+            }
+            Ok(_) => copy_tree(ctx.fs, &src_path, &dst),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            report(io, "cp", &src, &e)?;
+            had_error = true;
+        }
+    }
+    Ok(exit_status(had_error))
+}
+
+/// Перемещает (переименовывает) файлы: `mv SRC DST` или `mv SRC... DIR`.
+///
+/// Если `rename` невозможен между файловыми системами — копирует и удаляет источник.
+///
+/// Коды возврата: 0 — успех, 1 — ошибка хотя бы для одного источника/аргументов.
+pub(super) fn run_mv(
+    args: &[String],
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(plan) = plan_transfer("mv", args, ctx, io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+
+    let mut had_error = false;
+    for (src, dst) in plan {
+        let src_path = ctx.resolve(&src);
+        let result = match ctx.fs.rename(&src_path, &dst) {
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+                copy_tree(ctx.fs, &src_path, &dst).and_then(|_| {
+                    if ctx.fs.metadata(&src_path).is_ok_and(|m| m.is_dir()) {
+                        ctx.fs.remove_dir_all(&src_path)
+                    } else {
+                        ctx.fs.remove_file(&src_path)
+                    }
+                })
+            }
+            other => other,
+        };
+        if let Err(e) = result {
+            report(io, "mv", &src, &e)?;
+            had_error = true;
+        }
+    }
+    Ok(exit_status(had_error))
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "touch", disable_help_flag = true, disable_version_flag = true)]
+struct TouchCli {
+    /// Do not create any files.
+    #[arg(short = 'c')]
+    no_create: bool,
+
+    /// Use DATE instead of the current time (`@EPOCH`, `YYYY-MM-DD[ HH:MM[:SS]]`, UTC).
+    #[arg(short = 'd', value_name = "DATE")]
+    date: Option<String>,
+
+    /// Files to touch.
+    #[arg(required = true)]
+    files: Vec<String>,
+}
+
+/// Обновляет время доступа/модификации файлов, создавая отсутствующие.
+///
+/// Флаги: `-c` (не создавать), `-d DATE` (время вместо текущего).
+///
+/// Коды возврата: 0 — успех, 1 — ошибка хотя бы для одного файла/аргументов.
+pub(super) fn run_touch(
+    args: &[String],
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(cli) = parse_cli::<TouchCli>("touch", args, io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+    let time = match &cli.date {
+        Some(d) => match parse_date(d) {
+            Some(t) => t,
+            None => {
+                writeln!(io.stderr, "touch: invalid date format '{d}'").map_err(ShellError::Io)?;
+                return Ok(ShellControl::Continue(1));
+            }
+        },
+        None => SystemTime::now(),
+    };
+
+    let mut had_error = false;
+    for f in &cli.files {
+        let path = ctx.resolve(f);
+        let exists = ctx.fs.metadata(&path).is_ok();
+        if cli.no_create && !exists {
+            continue;
+        }
+        let created = if exists {
+            Ok(())
+        } else {
+            ctx.fs.open_write(&path, true).map(drop)
+        };
+        let result = created.and_then(|_| ctx.fs.set_times(&path, time, time));
+        if let Err(e) = result {
+            report(io, "touch", f, &e)?;
+            had_error = true;
+        }
+    }
+    Ok(exit_status(had_error))
+}
+
+/// Печатает сведения о файлах: размер, тип, права, время доступа и изменения.
+///
+/// Коды возврата: 0 — успех, 1 — ошибка хотя бы для одного файла/аргументов.
+pub(super) fn run_stat(
+    args: &[String],
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    if args.is_empty() {
+        writeln!(io.stderr, "stat: missing operand").map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(1));
+    }
+
+    let mut had_error = false;
+    for f in args {
+        let meta = match ctx.fs.symlink_metadata(&ctx.resolve(f)) {
+            Ok(m) => m,
+            Err(e) => {
+                report(io, "stat", f, &e)?;
+                had_error = true;
+                continue;
+            }
+        };
+        let (owner, group) = meta.owner();
+        let time =
+            |t: Option<SystemTime>| t.map(format_timestamp).unwrap_or_else(|| "-".to_string());
+        writeln!(io.stdout, "  File: {f}").map_err(ShellError::Io)?;
+        writeln!(
+            io.stdout,
+            "  Size: {:<15} Type: {}",
+            meta.size(),
+            file_type_name(&meta)
+        )
+        .map_err(ShellError::Io)?;
+        writeln!(
+            io.stdout,
+            "Access: ({:04o}/{})  Uid: {owner}  Gid: {group}  Links: {}",
+            meta.mode(),
+            mode_string(&meta),
+            meta.links()
+        )
+        .map_err(ShellError::Io)?;
+        writeln!(io.stdout, "Access: {}", time(meta.accessed())).map_err(ShellError::Io)?;
+        writeln!(io.stdout, "Modify: {}", time(meta.modified())).map_err(ShellError::Io)?;
+    }
+    Ok(exit_status(had_error))
+}
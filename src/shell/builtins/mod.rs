@@ -0,0 +1,744 @@
+//! Реализация встроенных команд.
+
+mod find;
+mod fs;
+mod printf;
+mod test;
+mod text;
+mod xargs;
+
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use clap::Parser;
+use regex::Regex;
+use regex::RegexBuilder;
+
+use super::types::{CommandRunner, IoStreams, ShellControl, ShellError, ShellResult};
+use super::vfs::FileSystem;
+
+pub(super) use fs::format_timestamp;
+pub(super) use test::run_conditional;
+
+pub(super) fn io_error_message(e: &std::io::Error) -> String {
+    // `std::io::Error` форматируется так: "No such file or directory (os error 2)".
+    // Для консистентности сообщений убираем числовой суффикс ОС.
+    let s = e.to_string();
+    match s.split_once(" (os error") {
+        Some((prefix, _)) => prefix.to_string(),
+        None => s,
+    }
+}
+
+/// Контекст исполнения builtin-команды (то, что ей нужно от состояния шелла).
+#[derive(Clone, Copy)]
+pub(crate) struct BuiltinContext<'a> {
+    /// Рабочая директория шелла.
+    pub(crate) cwd: &'a Path,
+    /// Файловая система шелла.
+    pub(crate) fs: &'a dyn FileSystem,
+    /// Запуск вложенных команд (для `xargs`, `find -exec`).
+    pub(crate) runner: &'a dyn CommandRunner,
+    /// Срок `timeout` / `--timeout`: после него `tail -f` перестает ждать данных.
+    pub(crate) deadline: Option<Instant>,
+}
+
+impl BuiltinContext<'_> {
+    /// Разрешает путь-операнд относительно рабочей директории шелла.
+    pub(crate) fn resolve(&self, path: &str) -> PathBuf {
+        self.cwd.join(path)
+    }
+
+    /// Читает файл-операнд `path` через файловую систему шелла.
+    pub(crate) fn read(&self, path: &str) -> std::io::Result<Vec<u8>> {
+        self.fs.read(&self.resolve(path))
+    }
+}
+
+/// Имена всех builtins (для подсказок при опечатке в имени команды).
+pub(crate) const BUILTIN_NAMES: &[&str] = &[
+    "cat", "echo", "printf", "grep", "wc", "head", "tail", "sort", "uniq", "cut", "tr", "tee",
+    "ls", "mkdir", "rm", "cp", "mv", "touch", "stat", "find", "xargs", "test", "[", "pwd", "exit",
+];
+
+/// Перечисление встроенных команд, поддерживаемых на этом этапе.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Builtin {
+    Cat,
+    Echo,
+    Printf,
+    Grep,
+    Wc,
+    Head,
+    Tail,
+    Sort,
+    Uniq,
+    Cut,
+    Tr,
+    Tee,
+    Ls,
+    Mkdir,
+    Rm,
+    Cp,
+    Mv,
+    Touch,
+    Stat,
+    Find,
+    Xargs,
+    Test,
+    Bracket,
+    Pwd,
+    Exit,
+}
+
+impl Builtin {
+    /// Возвращает builtin по имени команды (если она поддерживается).
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cat" => Some(Builtin::Cat),
+            "echo" => Some(Builtin::Echo),
+            "printf" => Some(Builtin::Printf),
+            "grep" => Some(Builtin::Grep),
+            "wc" => Some(Builtin::Wc),
+            "head" => Some(Builtin::Head),
+            "tail" => Some(Builtin::Tail),
+            "sort" => Some(Builtin::Sort),
+            "uniq" => Some(Builtin::Uniq),
+            "cut" => Some(Builtin::Cut),
+            "tr" => Some(Builtin::Tr),
+            "tee" => Some(Builtin::Tee),
+            "ls" => Some(Builtin::Ls),
+            "mkdir" => Some(Builtin::Mkdir),
+            "rm" => Some(Builtin::Rm),
+            "cp" => Some(Builtin::Cp),
+            "mv" => Some(Builtin::Mv),
+            "touch" => Some(Builtin::Touch),
+            "stat" => Some(Builtin::Stat),
+            "find" => Some(Builtin::Find),
+            "xargs" => Some(Builtin::Xargs),
+            "test" => Some(Builtin::Test),
+            "[" => Some(Builtin::Bracket),
+            "pwd" => Some(Builtin::Pwd),
+            "exit" => Some(Builtin::Exit),
+            _ => None,
+        }
+    }
+
+    /// Может ли команда с аргументами `args` читать stdin (фильтры вроде `cat`,
+    /// `grep`, `xargs`; `rm -i` читает из него ответы).
+    ///
+    /// Нужно группам команд: вход группы достается первой команде, читающей stdin.
+    pub(crate) fn reads_stdin(self, args: &[String]) -> bool {
+        if self == Builtin::Rm {
+            return args
+                .iter()
+                .take_while(|arg| *arg != "--")
+                .any(|arg| arg.starts_with('-') && !arg.starts_with("--") && arg.contains('i'));
+        }
+        matches!(
+            self,
+            Builtin::Cat
+                | Builtin::Grep
+                | Builtin::Wc
+                | Builtin::Head
+                | Builtin::Tail
+                | Builtin::Sort
+                | Builtin::Uniq
+                | Builtin::Cut
+                | Builtin::Tr
+                | Builtin::Tee
+                | Builtin::Xargs
+        )
+    }
+}
+
+/// Выполняет builtin-команду, опционально получая stdin (для пайпов и here-documents).
+///
+/// `cat`, `grep`, `wc`, `head` и `tail` читают stdin потоком и не держат его в
+/// памяти целиком; `head` и `grep -m` перестают читать, получив нужные строки.
+/// Остальным командам stdin нужен целиком (`read_stdin`).
+///
+/// Возвращает:
+/// - `ShellControl::Continue(code)` для продолжения REPL (где `code` — exit code команды)
+/// - `ShellControl::Exit(code)` для завершения REPL
+pub(crate) fn run_builtin_with_input(
+    builtin: Builtin,
+    args: &[String],
+    stdin: Option<&mut dyn BufRead>,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    match builtin {
+        Builtin::Echo => run_echo(args, io),
+        Builtin::Printf => printf::run_printf(args, io),
+        Builtin::Pwd => run_pwd(ctx, io),
+        Builtin::Exit => run_exit(args),
+        Builtin::Cat => run_cat(args, stdin, ctx, io),
+        Builtin::Grep => run_grep(args, stdin, ctx, io),
+        Builtin::Wc => run_wc(args, stdin, ctx, io),
+        Builtin::Head => text::run_head(args, stdin, ctx, io),
+        Builtin::Tail => text::run_tail(args, stdin, ctx, io),
+        Builtin::Sort => text::run_sort(args, read_stdin(stdin)?.as_deref(), ctx, io),
+        Builtin::Uniq => text::run_uniq(args, read_stdin(stdin)?.as_deref(), ctx, io),
+        Builtin::Cut => text::run_cut(args, read_stdin(stdin)?.as_deref(), ctx, io),
+        Builtin::Tr => text::run_tr(args, read_stdin(stdin)?.as_deref(), io),
+        Builtin::Tee => text::run_tee(args, read_stdin(stdin)?.as_deref(), ctx, io),
+        Builtin::Ls => fs::run_ls(args, ctx, io),
+        Builtin::Mkdir => fs::run_mkdir(args, ctx, io),
+        Builtin::Rm => fs::run_rm(args, stdin, ctx, io),
+        Builtin::Cp => fs::run_cp(args, ctx, io),
+        Builtin::Mv => fs::run_mv(args, ctx, io),
+        Builtin::Touch => fs::run_touch(args, ctx, io),
+        Builtin::Stat => fs::run_stat(args, ctx, io),
+        Builtin::Find => find::run_find(args, ctx, io),
+        Builtin::Xargs => xargs::run_xargs(args, read_stdin(stdin)?.as_deref(), ctx, io),
+        Builtin::Test => test::run_test(args, test::TestForm::Test, ctx, io),
+        Builtin::Bracket => test::run_test(args, test::TestForm::Bracket, ctx, io),
+    }
+}
+
+/// Читает stdin целиком — для команд, которым нужен весь вход сразу.
+fn read_stdin(stdin: Option<&mut dyn BufRead>) -> ShellResult<Option<Vec<u8>>> {
+    let Some(reader) = stdin else {
+        return Ok(None);
+    };
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).map_err(ShellError::Io)?;
+    Ok(Some(data))
+}
+
+/// Печатает аргументы, разделяя их пробелами, и перевод строки в конце.
+///
+/// Ведущие аргументы из флагов `-n`, `-e`, `-E` (в т.ч. слитных, `-ne`) — опции:
+/// - `-n`: не печатать перевод строки
+/// - `-e`: раскрывать escape-последовательности (`\n`, `\t`, `\x41`, `\c` и т.д.)
+/// - `-E`: не раскрывать (по умолчанию)
+fn run_echo(args: &[String], io: &mut IoStreams<'_>) -> ShellResult<ShellControl> {
+    let mut newline = true;
+    let mut escapes = false;
+    let mut idx = 0;
+    while let Some(flags) = args.get(idx).and_then(|a| a.strip_prefix('-')) {
+        if flags.is_empty() || !flags.chars().all(|c| matches!(c, 'n' | 'e' | 'E')) {
+            break;
+        }
+        for c in flags.chars() {
+            match c {
+                'n' => newline = false,
+                'e' => escapes = true,
+                _ => escapes = false,
+            }
+        }
+        idx += 1;
+    }
+
+    let text = args[idx..].join(" ");
+    if escapes {
+        let escaped = printf::expand_escapes(&text, printf::OctalEscape::ZeroPrefixed);
+        io.stdout
+            .write_all(&escaped.bytes)
+            .map_err(ShellError::Io)?;
+        if escaped.stop {
+            return Ok(ShellControl::Continue(0));
+        }
+    } else {
+        write!(io.stdout, "{text}").map_err(ShellError::Io)?;
+    }
+    if newline {
+        writeln!(io.stdout).map_err(ShellError::Io)?;
+    }
+    Ok(ShellControl::Continue(0))
+}
+
+/// Печатает рабочую директорию шелла и перевод строки.
+fn run_pwd(ctx: BuiltinContext<'_>, io: &mut IoStreams<'_>) -> ShellResult<ShellControl> {
+    writeln!(io.stdout, "{}", ctx.cwd.display()).map_err(ShellError::Io)?;
+    Ok(ShellControl::Continue(0))
+}
+
+/// Завершает REPL.
+///
+/// Если указан аргумент, он трактуется как код возврата (i32). Некорректный аргумент -> 0.
+fn run_exit(args: &[String]) -> ShellResult<ShellControl> {
+    let code = args
+        .first()
+        .and_then(|s| s.parse::<i32>().ok())
+        .unwrap_or(0);
+    Ok(ShellControl::Exit(code))
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "cat", disable_help_flag = true, disable_version_flag = true)]
+struct CatCli {
+    /// Number all output lines.
+    #[arg(short = 'n')]
+    number: bool,
+
+    /// Number nonempty output lines, overrides -n.
+    #[arg(short = 'b')]
+    number_nonblank: bool,
+
+    /// Suppress repeated empty output lines.
+    #[arg(short = 's')]
+    squeeze_blank: bool,
+
+    /// Equivalent to -vET.
+    #[arg(short = 'A')]
+    show_all: bool,
+
+    /// Use ^ and M- notation, except for LFD and TAB.
+    #[arg(short = 'v')]
+    show_nonprinting: bool,
+
+    /// Display $ at end of each line.
+    #[arg(short = 'E')]
+    show_ends: bool,
+
+    /// Display TAB characters as ^I.
+    #[arg(short = 'T')]
+    show_tabs: bool,
+
+    /// Files to concatenate. `-` means stdin (pipeline input).
+    files: Vec<String>,
+}
+
+/// Форматирование вывода `cat` с учетом флагов.
+///
+/// Состояние (номер строки, была ли предыдущая строка пустой) сохраняется
+/// между файлами, как в GNU cat: нумерация сквозная.
+struct CatFormatter {
+    number: bool,
+    number_nonblank: bool,
+    squeeze_blank: bool,
+    show_nonprinting: bool,
+    show_ends: bool,
+    show_tabs: bool,
+    line_number: usize,
+    at_line_start: bool,
+    prev_blank: bool,
+}
+
+impl CatFormatter {
+    fn new(cli: &CatCli) -> Self {
+        Self {
+            number: cli.number || cli.number_nonblank,
+            number_nonblank: cli.number_nonblank,
+            squeeze_blank: cli.squeeze_blank,
+            show_nonprinting: cli.show_nonprinting || cli.show_all,
+            show_ends: cli.show_ends || cli.show_all,
+            show_tabs: cli.show_tabs || cli.show_all,
+            line_number: 0,
+            at_line_start: true,
+            prev_blank: false,
+        }
+    }
+
+    /// Без флагов содержимое копируется как есть.
+    fn is_verbatim(&self) -> bool {
+        !(self.number
+            || self.squeeze_blank
+            || self.show_nonprinting
+            || self.show_ends
+            || self.show_tabs)
+    }
+
+    /// Форматирует поток по частям, не читая его целиком.
+    fn copy(
+        &mut self,
+        input: &mut dyn BufRead,
+        out: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        loop {
+            let chunk = input.fill_buf()?;
+            if chunk.is_empty() {
+                return Ok(());
+            }
+            let len = chunk.len();
+            self.write(chunk, out)?;
+            input.consume(len);
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8], out: &mut dyn std::io::Write) -> std::io::Result<()> {
+        if self.is_verbatim() {
+            return out.write_all(bytes);
+        }
+
+        let mut buf = Vec::with_capacity(bytes.len());
+        for &b in bytes {
+            if self.at_line_start {
+                let blank = b == b'\n';
+                if blank && self.prev_blank && self.squeeze_blank {
+                    continue;
+                }
+                self.prev_blank = blank;
+                if self.number && !(blank && self.number_nonblank) {
+                    self.line_number += 1;
+                    buf.extend_from_slice(format!("{:>6}\t", self.line_number).as_bytes());
+                }
+                self.at_line_start = false;
+            }
+
+            match b {
+                b'\n' => {
+                    if self.show_ends {
+                        buf.push(b'$');
+                    }
+                    buf.push(b'\n');
+                    self.at_line_start = true;
+                }
+                b'\t' if self.show_tabs => buf.extend_from_slice(b"^I"),
+                b'\t' => buf.push(b),
+                _ if self.show_nonprinting => push_nonprinting(&mut buf, b),
+                _ => buf.push(b),
+            }
+        }
+        out.write_all(&buf)
+    }
+}
+
+/// Записывает байт в нотации `^X` / `M-X` (как `cat -v`).
+fn push_nonprinting(buf: &mut Vec<u8>, b: u8) {
+    let mut c = b;
+    if c >= 128 {
+        buf.extend_from_slice(b"M-");
+        c -= 128;
+    }
+    match c {
+        0..=31 => {
+            buf.push(b'^');
+            buf.push(c + 64);
+        }
+        127 => buf.extend_from_slice(b"^?"),
+        _ => buf.push(c),
+    }
+}
+
+/// Выводит содержимое файлов подряд.
+///
+/// Поддерживаемые флаги: `-n`, `-b`, `-s`, `-A`, `-v`, `-E`, `-T`.
+/// Операнд `-` означает stdin (вход пайплайна); stdin можно прочитать только один раз.
+///
+/// Коды возврата:
+/// - 0: все файлы прочитаны успешно
+/// - 1: хотя бы один файл не прочитан
+/// - 2: не передан ни один путь либо неверные флаги
+fn run_cat(
+    args: &[String],
+    stdin: Option<&mut dyn BufRead>,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let argv = std::iter::once("cat".to_string())
+        .chain(args.iter().cloned())
+        .collect::<Vec<_>>();
+
+    let parsed = match CatCli::try_parse_from(argv) {
+        Ok(p) => p,
+        Err(e) => {
+            writeln!(io.stderr, "cat: {e}").map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(2));
+        }
+    };
+
+    let mut formatter = CatFormatter::new(&parsed);
+
+    if parsed.files.is_empty() {
+        if let Some(input) = stdin {
+            formatter.copy(input, io.stdout).map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(0));
+        } else {
+            writeln!(io.stderr, "cat: missing file operand").map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(2));
+        }
+    }
+
+    let mut stdin = stdin;
+    let mut exit_code = 0;
+    for path in &parsed.files {
+        if path == "-" {
+            if let Some(input) = stdin.take() {
+                formatter.copy(input, io.stdout).map_err(ShellError::Io)?;
+            }
+            continue;
+        }
+        match ctx.read(path) {
+            Ok(bytes) => {
+                formatter.write(&bytes, io.stdout).map_err(ShellError::Io)?;
+            }
+            Err(e) => {
+                let msg = io_error_message(&e);
+                writeln!(io.stderr, "cat: {path}: {msg}").map_err(ShellError::Io)?;
+                exit_code = 1;
+            }
+        }
+    }
+    Ok(ShellControl::Continue(exit_code))
+}
+
+/// Печатает количество строк/слов/байт для одного файла.
+///
+/// Формат вывода: `<lines> <words> <bytes>`.
+///
+/// Коды возврата:
+/// - 0: успех
+/// - 1: ошибка чтения файла
+/// - 2: неверное число аргументов
+fn run_wc(
+    args: &[String],
+    stdin: Option<&mut dyn BufRead>,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    if args.is_empty() {
+        if let Some(input) = stdin {
+            let (line_count, word_count, byte_count) = count_wc(input).map_err(ShellError::Io)?;
+            writeln!(io.stdout, "{line_count} {word_count} {byte_count}")
+                .map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(0));
+        }
+        writeln!(io.stderr, "wc: missing file operand").map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(2));
+    }
+    if args.len() != 1 {
+        writeln!(io.stderr, "wc: expected exactly one file path").map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(2));
+    }
+    let path = &args[0];
+
+    let bytes = match ctx.read(path) {
+        Ok(b) => b,
+        Err(e) => {
+            let msg = io_error_message(&e);
+            writeln!(io.stderr, "wc: {path}: {msg}").map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(1));
+        }
+    };
+
+    let (line_count, word_count, byte_count) =
+        count_wc(&mut bytes.as_slice()).map_err(ShellError::Io)?;
+
+    writeln!(io.stdout, "{line_count} {word_count} {byte_count}").map_err(ShellError::Io)?;
+    Ok(ShellControl::Continue(0))
+}
+
+/// Считает строки, слова и байты потока построчно, не читая его целиком.
+fn count_wc(input: &mut dyn BufRead) -> std::io::Result<(usize, usize, usize)> {
+    let (mut line_count, mut word_count, mut byte_count) = (0, 0, 0);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let len = input.read_until(b'\n', &mut line)?;
+        if len == 0 {
+            return Ok((line_count, word_count, byte_count));
+        }
+        line_count += 1;
+        byte_count += len;
+        word_count += String::from_utf8_lossy(&line).split_whitespace().count();
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "grep", disable_help_flag = true, disable_version_flag = true)]
+struct GrepCli {
+    /// Search only for whole words.
+    #[arg(short = 'w')]
+    word: bool,
+
+    /// Case-insensitive search.
+    #[arg(short = 'i')]
+    ignore_case: bool,
+
+    /// Print NUM lines of trailing context after matching lines.
+    #[arg(short = 'A', value_name = "NUM", default_value_t = 0)]
+    after: usize,
+
+    /// Prefix each output line with its 1-based line number.
+    #[arg(short = 'n')]
+    line_number: bool,
+
+    /// Stop reading a file after NUM matching lines.
+    #[arg(short = 'm', value_name = "NUM")]
+    max_count: Option<usize>,
+
+    /// Regular expression pattern.
+    pattern: String,
+
+    /// Files to search. If omitted, grep reads from stdin (pipeline input).
+    files: Vec<String>,
+}
+
+/// Печатает строки, которые матчатся по regex-шаблону.
+///
+/// Поддерживаемые флаги:
+/// - `-w`: совпадение только по целому слову (границы слова определяем как не-`[\p{L}\p{N}_]`)
+/// - `-i`: регистронезависимый поиск
+/// - `-A N`: печатать N строк после совпадения (пересекающиеся области не дублируются)
+/// - `-n`: печатать номер строки (`N:` для совпадений, `N-` для контекста)
+/// - `-m N`: перестать читать файл после N совпавших строк (и их контекста `-A`)
+///
+/// Вход читается построчно: с `-m` остаток пайпа не читается.
+///
+/// Коды возврата (как в grep):
+/// - 0: найдено хотя бы одно совпадение
+/// - 1: совпадений нет
+/// - 2: ошибка аргументов/regex/чтения
+fn run_grep(
+    args: &[String],
+    stdin: Option<&mut dyn BufRead>,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let argv = std::iter::once("grep".to_string())
+        .chain(args.iter().cloned())
+        .collect::<Vec<_>>();
+
+    let parsed = match GrepCli::try_parse_from(argv) {
+        Ok(p) => p,
+        Err(e) => {
+            writeln!(io.stderr, "grep: {e}").map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(2));
+        }
+    };
+
+    let re = match build_regex(&parsed.pattern, parsed.ignore_case) {
+        Ok(r) => r,
+        Err(msg) => {
+            writeln!(io.stderr, "grep: {msg}").map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(2));
+        }
+    };
+
+    let mut found_any = false;
+    let mut had_error = false;
+
+    // Если файлы не заданы — читаем из stdin.
+    if parsed.files.is_empty() {
+        let Some(input) = stdin else {
+            writeln!(io.stderr, "grep: missing file operand").map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(2));
+        };
+
+        let found = grep_bytes_into_output(&re, &parsed, None, input, io)?;
+        found_any |= found;
+    } else {
+        let prefix = parsed.files.len() > 1;
+        for path in &parsed.files {
+            match ctx.read(path) {
+                Ok(bytes) => {
+                    let found = grep_bytes_into_output(
+                        &re,
+                        &parsed,
+                        if prefix { Some(path.as_str()) } else { None },
+                        &mut bytes.as_slice(),
+                        io,
+                    )?;
+                    found_any |= found;
+                }
+                Err(e) => {
+                    let msg = io_error_message(&e);
+                    writeln!(io.stderr, "grep: {path}: {msg}").map_err(ShellError::Io)?;
+                    had_error = true;
+                }
+            }
+        }
+    }
+
+    let code = if had_error {
+        2
+    } else if found_any {
+        0
+    } else {
+        1
+    };
+    Ok(ShellControl::Continue(code))
+}
+
+fn grep_bytes_into_output(
+    re: &Regex,
+    opts: &GrepCli,
+    file_prefix: Option<&str>,
+    input: &mut dyn BufRead,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<bool> {
+    let mut found = false;
+    let mut matches = 0;
+    let mut print_until: isize = -1;
+    let mut buf = Vec::new();
+    for idx in 0usize.. {
+        let done = opts.max_count.is_some_and(|max| matches >= max);
+        if done && idx as isize > print_until {
+            break;
+        }
+        buf.clear();
+        if input.read_until(b'\n', &mut buf).map_err(ShellError::Io)? == 0 {
+            break;
+        }
+        // Конец строки — `\n` или `\r\n`, как у `str::lines`.
+        if buf.ends_with(b"\n") {
+            buf.pop();
+            if buf.ends_with(b"\r") {
+                buf.pop();
+            }
+        }
+        let line = String::from_utf8_lossy(&buf);
+        let is_match = !done
+            && if opts.word {
+                line_has_whole_word_match(re, &line)
+            } else {
+                re.is_match(&line)
+            };
+
+        if is_match {
+            found = true;
+            matches += 1;
+            let end = idx.saturating_add(opts.after) as isize;
+            if end > print_until {
+                print_until = end;
+            }
+        }
+
+        if (idx as isize) <= print_until {
+            let sep = if is_match { ':' } else { '-' };
+            if let Some(prefix) = file_prefix {
+                write!(io.stdout, "{prefix}{sep}").map_err(ShellError::Io)?;
+            }
+            if opts.line_number {
+                write!(io.stdout, "{}{sep}", idx + 1).map_err(ShellError::Io)?;
+            }
+            writeln!(io.stdout, "{line}").map_err(ShellError::Io)?;
+        }
+    }
+
+    Ok(found)
+}
+
+fn build_regex(pattern: &str, ignore_case: bool) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| format!("invalid regex: {e}"))
+}
+
+fn is_word_constituent(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn line_has_whole_word_match(re: &Regex, line: &str) -> bool {
+    for m in re.find_iter(line) {
+        if m.start() == m.end() {
+            continue;
+        }
+
+        let before = line[..m.start()].chars().last();
+        let after = line[m.end()..].chars().next();
+
+        let ok_before = before.is_none_or(|c| !is_word_constituent(c));
+        let ok_after = after.is_none_or(|c| !is_word_constituent(c));
+
+        if ok_before && ok_after {
+            return true;
+        }
+    }
+    false
+}
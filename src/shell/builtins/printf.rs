@@ -0,0 +1,606 @@
+//! `printf` и обработка escape-последовательностей (общая с `echo -e`).
+
+use super::super::types::{IoStreams, ShellControl, ShellError, ShellResult};
+
+/// Режим разбора восьмеричных escape-последовательностей.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum OctalEscape {
+    /// `\0nnn` (как в `echo -e` и аргументах `%b`).
+    ZeroPrefixed,
+    /// `\nnn` (как в строке формата `printf`).
+    Bare,
+}
+
+/// Результат раскрытия escape-последовательностей.
+pub(super) struct Escaped {
+    pub(super) bytes: Vec<u8>,
+    /// Встречен `\c`: весь дальнейший вывод нужно подавить.
+    pub(super) stop: bool,
+}
+
+/// Раскрывает `\n`, `\t`, `\xHH`, `\uHHHH`, восьмеричные коды и т.п.
+///
+/// Неизвестные последовательности остаются как есть (вместе с `\`).
+pub(super) fn expand_escapes(s: &str, octal: OctalEscape) -> Escaped {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            let mut tmp = [0u8; 4];
+            bytes.extend_from_slice(ch.encode_utf8(&mut tmp).as_bytes());
+            continue;
+        }
+
+        let Some(next) = chars.next() else {
+            bytes.push(b'\\');
+            break;
+        };
+        match next {
+            '\\' => bytes.push(b'\\'),
+            'a' => bytes.push(0x07),
+            'b' => bytes.push(0x08),
+            'e' | 'E' => bytes.push(0x1b),
+            'f' => bytes.push(0x0c),
+            'n' => bytes.push(b'\n'),
+            'r' => bytes.push(b'\r'),
+            't' => bytes.push(b'\t'),
+            'v' => bytes.push(0x0b),
+            '"' => bytes.push(b'"'),
+            '\'' => bytes.push(b'\''),
+            'c' => return Escaped { bytes, stop: true },
+            'x' => match read_digits(&mut chars, 16, 2) {
+                Some(v) => bytes.push(v as u8),
+                None => bytes.extend_from_slice(b"\\x"),
+            },
+            'u' | 'U' => {
+                let max = if next == 'u' { 4 } else { 8 };
+                match read_digits(&mut chars, 16, max).and_then(char::from_u32) {
+                    Some(c) => {
+                        let mut tmp = [0u8; 4];
+                        bytes.extend_from_slice(c.encode_utf8(&mut tmp).as_bytes());
+                    }
+                    None => {
+                        bytes.push(b'\\');
+                        bytes.push(next as u8);
+                    }
+                }
+            }
+            '0' if octal == OctalEscape::ZeroPrefixed => {
+                let v = read_digits(&mut chars, 8, 3).unwrap_or(0);
+                bytes.push(v as u8);
+            }
+            '0'..='7' if octal == OctalEscape::Bare => {
+                let mut v = next.to_digit(8).unwrap_or(0);
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(d) => {
+                            v = v * 8 + d;
+                            let _ = chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                bytes.push(v as u8);
+            }
+            other => {
+                bytes.push(b'\\');
+                let mut tmp = [0u8; 4];
+                bytes.extend_from_slice(other.encode_utf8(&mut tmp).as_bytes());
+            }
+        }
+    }
+
+    Escaped { bytes, stop: false }
+}
+
+fn read_digits<I>(chars: &mut std::iter::Peekable<I>, radix: u32, max: usize) -> Option<u32>
+where
+    I: Iterator<Item = char>,
+{
+    let mut value = 0u32;
+    let mut count = 0;
+    while count < max {
+        match chars.peek().and_then(|c| c.to_digit(radix)) {
+            Some(d) => {
+                // Не больше 8 цифр, так что `u32` хватает; `checked_*` — на всякий случай.
+                value = value
+                    .checked_mul(radix)
+                    .and_then(|v| v.checked_add(d))
+                    .unwrap_or(u32::MAX);
+                let _ = chars.next();
+                count += 1;
+            }
+            None => break,
+        }
+    }
+    if count == 0 { None } else { Some(value) }
+}
+
+/// Спецификатор формата `%[flags][width][.precision]conv`.
+#[derive(Debug, Default)]
+struct Spec {
+    left: bool,
+    plus: bool,
+    space: bool,
+    zero: bool,
+    alt: bool,
+    width: Option<usize>,
+    precision: Option<usize>,
+}
+
+/// Источник аргументов `printf` с подсчетом ошибок преобразования.
+struct Args<'a> {
+    args: &'a [String],
+    pos: usize,
+    errors: Vec<String>,
+}
+
+impl<'a> Args<'a> {
+    /// Следующий аргумент, не забирая его (пустая строка, если аргументов нет).
+    fn peek(&self) -> &'a str {
+        self.args.get(self.pos).map_or("", String::as_str)
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let arg = self.args.get(self.pos)?;
+        self.pos += 1;
+        Some(arg.as_str())
+    }
+
+    fn next_int(&mut self) -> i64 {
+        let Some(arg) = self.next() else {
+            return 0;
+        };
+        match parse_int(arg) {
+            Ok(v) => v,
+            Err(IntError::Invalid(v)) => {
+                self.errors.push(format!("{arg}: invalid number"));
+                v
+            }
+            Err(IntError::TooLarge(v)) => {
+                self.errors.push(format!("{arg}: Result too large"));
+                v
+            }
+        }
+    }
+
+    fn next_float(&mut self) -> f64 {
+        let Some(arg) = self.next() else {
+            return 0.0;
+        };
+        if let Some(c) = char_constant(arg) {
+            return c as f64;
+        }
+        match arg.trim().parse::<f64>() {
+            Ok(v) => v,
+            Err(_) => {
+                self.errors.push(format!("{arg}: invalid number"));
+                0.0
+            }
+        }
+    }
+}
+
+/// `'c` / `"c` в числовом аргументе означает код символа.
+fn char_constant(arg: &str) -> Option<u32> {
+    let rest = arg.strip_prefix('\'').or_else(|| arg.strip_prefix('"'))?;
+    Some(rest.chars().next().map(|c| c as u32).unwrap_or(0))
+}
+
+/// Ошибка разбора целого; внутри — значение, которое все равно печатается.
+enum IntError {
+    /// Не число: частично разобранное значение (как bash).
+    Invalid(i64),
+    /// Не помещается в `i64`: `i64::MAX` или `i64::MIN` по знаку.
+    TooLarge(i64),
+}
+
+/// Разбирает целое как `printf`: десятичное, `0x..`, `0..` (восьмеричное) или `'c`.
+fn parse_int(arg: &str) -> Result<i64, IntError> {
+    if let Some(c) = char_constant(arg) {
+        return Ok(c as i64);
+    }
+
+    let s = arg.trim_start();
+    let (negative, s) = match s.as_bytes().first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let (radix, digits) = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        (16, hex)
+    } else if s.len() > 1 && s.starts_with('0') {
+        (8, &s[1..])
+    } else {
+        (10, s)
+    };
+
+    // Отрицательные числа копим со знаком, чтобы `i64::MIN` не переполнялся.
+    let mut value: Option<i64> = Some(0);
+    let mut valid = !digits.is_empty();
+    for c in digits.chars() {
+        let Some(d) = c.to_digit(radix) else {
+            valid = false;
+            break;
+        };
+        value = value
+            .and_then(|v| v.checked_mul(radix as i64))
+            .and_then(|v| {
+                if negative {
+                    v.checked_sub(d as i64)
+                } else {
+                    v.checked_add(d as i64)
+                }
+            });
+    }
+    match value {
+        Some(v) if valid => Ok(v),
+        Some(v) => Err(IntError::Invalid(v)),
+        None if !valid => Err(IntError::Invalid(0)),
+        None => Err(IntError::TooLarge(if negative {
+            i64::MIN
+        } else {
+            i64::MAX
+        })),
+    }
+}
+
+/// Дополняет тело до ширины поля.
+///
+/// `prefix` — знак или `0x`, перед которым (а не между ним и цифрами) ставятся пробелы,
+/// а нули при флаге `0` вставляются после него.
+fn pad(spec: &Spec, prefix: &str, body: &str, zero_allowed: bool) -> String {
+    let len = prefix.chars().count() + body.chars().count();
+    let width = spec.width.unwrap_or(0);
+    if len >= width {
+        return format!("{prefix}{body}");
+    }
+    let fill = width - len;
+    if spec.left {
+        format!("{prefix}{body}{}", " ".repeat(fill))
+    } else if spec.zero && zero_allowed {
+        format!("{prefix}{}{body}", "0".repeat(fill))
+    } else {
+        format!("{}{prefix}{body}", " ".repeat(fill))
+    }
+}
+
+fn sign_prefix(spec: &Spec, negative: bool) -> &'static str {
+    if negative {
+        "-"
+    } else if spec.plus {
+        "+"
+    } else if spec.space {
+        " "
+    } else {
+        ""
+    }
+}
+
+fn format_signed(spec: &Spec, value: i64) -> String {
+    let mut digits = value.unsigned_abs().to_string();
+    if let Some(p) = spec.precision {
+        if p == 0 && value == 0 {
+            digits.clear();
+        } else if digits.len() < p {
+            digits = format!("{}{digits}", "0".repeat(p - digits.len()));
+        }
+    }
+    let prefix = sign_prefix(spec, value < 0);
+    pad(spec, prefix, &digits, spec.precision.is_none())
+}
+
+fn format_unsigned(spec: &Spec, value: u64, conv: char) -> String {
+    let mut digits = match conv {
+        'o' => format!("{value:o}"),
+        'x' => format!("{value:x}"),
+        'X' => format!("{value:X}"),
+        _ => value.to_string(),
+    };
+    if let Some(p) = spec.precision {
+        if p == 0 && value == 0 {
+            digits.clear();
+        } else if digits.len() < p {
+            digits = format!("{}{digits}", "0".repeat(p - digits.len()));
+        }
+    }
+    let prefix = match conv {
+        'o' if spec.alt && !digits.starts_with('0') => "0",
+        'x' if spec.alt && value != 0 => "0x",
+        'X' if spec.alt && value != 0 => "0X",
+        _ => "",
+    };
+    pad(spec, prefix, &digits, spec.precision.is_none())
+}
+
+/// Форматирует `v` в экспоненциальной записи C: `1.500000e+00`.
+fn exp_notation(v: f64, precision: usize, upper: bool) -> String {
+    let s = format!("{v:.precision$e}");
+    let (mantissa, exp) = s.split_once('e').unwrap_or((&s, "0"));
+    let exp: i32 = exp.parse().unwrap_or(0);
+    let sign = if exp < 0 { '-' } else { '+' };
+    let e = if upper { 'E' } else { 'e' };
+    format!("{mantissa}{e}{sign}{:02}", exp.unsigned_abs())
+}
+
+/// Убирает незначащие нули дробной части (для `%g`).
+fn strip_fraction_zeros(s: &str) -> String {
+    let (number, exp) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], &s[i..]),
+        None => (s, ""),
+    };
+    if !number.contains('.') {
+        return s.to_string();
+    }
+    let trimmed = number.trim_end_matches('0').trim_end_matches('.');
+    format!("{trimmed}{exp}")
+}
+
+fn format_float(spec: &Spec, value: f64, conv: char) -> String {
+    let upper = conv.is_ascii_uppercase();
+    let precision = spec.precision.unwrap_or(6);
+
+    let magnitude = value.abs();
+    let body = if !magnitude.is_finite() {
+        let s = if magnitude.is_nan() { "nan" } else { "inf" };
+        if upper {
+            s.to_uppercase()
+        } else {
+            s.to_string()
+        }
+    } else {
+        match conv.to_ascii_lowercase() {
+            'e' => exp_notation(magnitude, precision, upper),
+            'g' => {
+                let p = precision.max(1);
+                let exp = if magnitude == 0.0 {
+                    0
+                } else {
+                    let s = format!("{magnitude:.prec$e}", prec = p - 1);
+                    s.split_once('e')
+                        .and_then(|(_, e)| e.parse::<i32>().ok())
+                        .unwrap_or(0)
+                };
+                let s = if exp < -4 || exp >= p as i32 {
+                    exp_notation(magnitude, p - 1, upper)
+                } else {
+                    let prec = (p as i32 - 1 - exp).max(0) as usize;
+                    format!("{magnitude:.prec$}")
+                };
+                if spec.alt {
+                    s
+                } else {
+                    strip_fraction_zeros(&s)
+                }
+            }
+            _ => {
+                let s = format!("{magnitude:.precision$}");
+                if spec.alt && precision == 0 {
+                    format!("{s}.")
+                } else {
+                    s
+                }
+            }
+        }
+    };
+
+    let prefix = sign_prefix(spec, value.is_sign_negative() && !value.is_nan());
+    pad(spec, prefix, &body, magnitude.is_finite())
+}
+
+fn format_str(spec: &Spec, s: &str) -> String {
+    let s: String = match spec.precision {
+        Some(p) => s.chars().take(p).collect(),
+        None => s.to_string(),
+    };
+    pad(spec, "", &s, false)
+}
+
+/// Наибольшая ширина и точность поля: строка под поле выделяется целиком,
+/// поэтому `%9999999999d` — ошибка, а не попытка занять всю память.
+const MAX_FIELD: usize = 1 << 20;
+
+/// Ошибка в строке формата: сообщение без префикса `printf: `.
+struct FormatError(String);
+
+/// Ширина или точность поля; `text` — как она записана (для сообщения).
+fn field_size(value: Option<usize>, text: &str, what: &str) -> Result<usize, FormatError> {
+    value
+        .filter(|&n| n <= MAX_FIELD)
+        .ok_or_else(|| FormatError(format!("{text}: invalid {what}")))
+}
+
+/// Один проход по строке формата.
+///
+/// Возвращает `true`, если встречен `\c` (или `\c` в аргументе `%b`) и вывод нужно прекратить.
+fn format_once(format: &str, args: &mut Args<'_>, out: &mut Vec<u8>) -> Result<bool, FormatError> {
+    let mut rest = format;
+    while !rest.is_empty() {
+        let Some(idx) = rest.find(['%', '\\']) else {
+            out.extend_from_slice(rest.as_bytes());
+            break;
+        };
+        out.extend_from_slice(&rest.as_bytes()[..idx]);
+        rest = &rest[idx..];
+
+        if rest.starts_with('\\') {
+            // Раскрываем ровно одну escape-последовательность.
+            let mut chars = rest.char_indices().skip(1);
+            let end = match chars.next() {
+                None => rest.len(),
+                Some((i, c)) => {
+                    let mut end = i + c.len_utf8();
+                    let (radix, max) = match c {
+                        'x' => (16, 2),
+                        'u' => (16, 4),
+                        'U' => (16, 8),
+                        '0'..='7' => (8, 2),
+                        _ => (0, 0),
+                    };
+                    for (j, d) in chars.take(max) {
+                        if radix == 0 || !d.is_digit(radix) {
+                            break;
+                        }
+                        end = j + d.len_utf8();
+                    }
+                    end
+                }
+            };
+            let escaped = expand_escapes(&rest[..end], OctalEscape::Bare);
+            out.extend_from_slice(&escaped.bytes);
+            if escaped.stop {
+                return Ok(true);
+            }
+            rest = &rest[end..];
+            continue;
+        }
+
+        // `%...`
+        let bytes = rest.as_bytes();
+        let mut i = 1;
+        if bytes.get(1) == Some(&b'%') {
+            out.push(b'%');
+            rest = &rest[2..];
+            continue;
+        }
+
+        let mut spec = Spec::default();
+        while let Some(&b) = bytes.get(i) {
+            match b {
+                b'-' => spec.left = true,
+                b'+' => spec.plus = true,
+                b' ' => spec.space = true,
+                b'0' => spec.zero = true,
+                b'#' => spec.alt = true,
+                _ => break,
+            }
+            i += 1;
+        }
+
+        if bytes.get(i) == Some(&b'*') {
+            let text = args.peek();
+            let w = args.next_int();
+            if w < 0 {
+                spec.left = true;
+            }
+            let width = usize::try_from(w.unsigned_abs()).ok();
+            spec.width = Some(field_size(width, text, "field width")?);
+            i += 1;
+        } else {
+            let start = i;
+            while bytes.get(i).is_some_and(u8::is_ascii_digit) {
+                i += 1;
+            }
+            if i > start {
+                let text = &rest[start..i];
+                spec.width = Some(field_size(text.parse().ok(), text, "field width")?);
+            }
+        }
+
+        if bytes.get(i) == Some(&b'.') {
+            i += 1;
+            if bytes.get(i) == Some(&b'*') {
+                let text = args.peek();
+                let p = args.next_int();
+                if p >= 0 {
+                    let precision = usize::try_from(p).ok();
+                    spec.precision = Some(field_size(precision, text, "precision")?);
+                }
+                i += 1;
+            } else {
+                let start = i;
+                while bytes.get(i).is_some_and(u8::is_ascii_digit) {
+                    i += 1;
+                }
+                let text = &rest[start..i];
+                spec.precision = match text {
+                    "" => Some(0),
+                    _ => Some(field_size(text.parse().ok(), text, "precision")?),
+                };
+            }
+        }
+
+        let Some(conv) = rest[i..].chars().next() else {
+            return Err(FormatError(format!("{rest}: invalid directive")));
+        };
+        let directive_end = i + conv.len_utf8();
+
+        let formatted = match conv {
+            's' => format_str(&spec, args.next().unwrap_or("")),
+            'b' => {
+                let escaped = expand_escapes(args.next().unwrap_or(""), OctalEscape::ZeroPrefixed);
+                let s = String::from_utf8_lossy(&escaped.bytes).to_string();
+                out.extend_from_slice(format_str(&spec, &s).as_bytes());
+                if escaped.stop {
+                    return Ok(true);
+                }
+                rest = &rest[directive_end..];
+                continue;
+            }
+            'c' => {
+                let c: String = args.next().unwrap_or("").chars().take(1).collect();
+                pad(&spec, "", &c, false)
+            }
+            'd' | 'i' => format_signed(&spec, args.next_int()),
+            'u' | 'o' | 'x' | 'X' => format_unsigned(&spec, args.next_int() as u64, conv),
+            'f' | 'F' | 'e' | 'E' | 'g' | 'G' => format_float(&spec, args.next_float(), conv),
+            _ => {
+                let directive = &rest[..directive_end];
+                return Err(FormatError(format!("{directive}: invalid directive")));
+            }
+        };
+        out.extend_from_slice(formatted.as_bytes());
+        rest = &rest[directive_end..];
+    }
+    Ok(false)
+}
+
+/// Форматированный вывод: `printf FORMAT [ARGUMENTS...]`.
+///
+/// Поддерживаются `%s %b %c %d %i %u %o %x %X %f %F %e %E %g %G %%`,
+/// флаги `- + пробел 0 #`, ширина и точность (в т.ч. `*`).
+/// Если аргументов больше, чем директив, формат применяется повторно.
+///
+/// Коды возврата:
+/// - 0: успех
+/// - 1: неверный числовой аргумент, директива формата, ширина или точность поля
+/// - 2: не передан формат
+pub(super) fn run_printf(args: &[String], io: &mut IoStreams<'_>) -> ShellResult<ShellControl> {
+    let Some((format, rest)) = args.split_first() else {
+        writeln!(io.stderr, "printf: usage: printf format [arguments]").map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(2));
+    };
+
+    let mut args = Args {
+        args: rest,
+        pos: 0,
+        errors: Vec::new(),
+    };
+    let mut out = Vec::new();
+    let mut exit_code = 0;
+
+    loop {
+        let before = args.pos;
+        match format_once(format, &mut args, &mut out) {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(FormatError(msg)) => {
+                io.stdout.write_all(&out).map_err(ShellError::Io)?;
+                writeln!(io.stderr, "printf: {msg}").map_err(ShellError::Io)?;
+                return Ok(ShellControl::Continue(1));
+            }
+        }
+        // Повторяем формат, пока остаются аргументы (и формат их потребляет).
+        if args.pos >= rest.len() || args.pos == before {
+            break;
+        }
+    }
+
+    io.stdout.write_all(&out).map_err(ShellError::Io)?;
+    for e in &args.errors {
+        writeln!(io.stderr, "printf: {e}").map_err(ShellError::Io)?;
+        exit_code = 1;
+    }
+    Ok(ShellControl::Continue(exit_code))
+}
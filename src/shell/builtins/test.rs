@@ -0,0 +1,367 @@
+//! `test`, `[` и `[[ ... ]]`: вычисление условных выражений.
+//!
+//! Все три формы используют один разборщик выражений; они отличаются набором
+//! логических операторов (`-a`/`-o` против `&&`/`||`) и тем, что `[[` дополнительно
+//! поддерживает `=~` (regex) и glob-шаблоны справа от `==`/`!=`. `test` и `[` —
+//! builtins, а `[[` — составная команда шелла: ее слова приходят без разбиения
+//! на поля и с признаком кавычек (`CondWord`).
+
+use regex::Regex;
+
+use super::super::types::{CondWord, IoStreams, ShellControl, ShellError, ShellResult};
+use super::BuiltinContext;
+use super::find::glob_match;
+
+/// Форма условной команды.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TestForm {
+    /// `test expr`.
+    Test,
+    /// `[ expr ]`.
+    Bracket,
+    /// `[[ expr ]]`.
+    Extended,
+}
+
+impl TestForm {
+    fn name(self) -> &'static str {
+        match self {
+            TestForm::Test => "test",
+            TestForm::Bracket => "[",
+            TestForm::Extended => "[[",
+        }
+    }
+}
+
+/// Ошибка вычисления: сообщение для stderr (код возврата 2).
+type EvalResult<T> = Result<T, String>;
+
+struct Evaluator<'a> {
+    words: &'a [CondWord],
+    /// Значения слов `words`.
+    texts: Vec<String>,
+    pos: usize,
+    form: TestForm,
+    ctx: BuiltinContext<'a>,
+}
+
+impl Evaluator<'_> {
+    /// Оператор на текущей позиции: слово, которое может быть оператором.
+    fn peek(&self) -> Option<&str> {
+        self.words
+            .get(self.pos)
+            .filter(|word| word.operator)
+            .map(|_| self.texts[self.pos].as_str())
+    }
+
+    fn remaining(&self) -> usize {
+        self.words.len() - self.pos
+    }
+
+    /// Номер следующего слова.
+    fn next(&mut self) -> EvalResult<usize> {
+        if self.pos == self.words.len() {
+            return Err("argument expected".to_string());
+        }
+        self.pos += 1;
+        Ok(self.pos - 1)
+    }
+
+    /// Слово `idx` — оператор `op`.
+    fn is_operator(&self, idx: usize, op: &str) -> bool {
+        self.words[idx].operator && self.texts[idx] == op
+    }
+
+    fn or_operator(&self) -> &'static str {
+        if self.form == TestForm::Extended {
+            "||"
+        } else {
+            "-o"
+        }
+    }
+
+    fn and_operator(&self) -> &'static str {
+        if self.form == TestForm::Extended {
+            "&&"
+        } else {
+            "-a"
+        }
+    }
+
+    fn parse_or(&mut self) -> EvalResult<bool> {
+        let mut value = self.parse_and()?;
+        while self.peek() == Some(self.or_operator()) {
+            self.pos += 1;
+            // Вычисляем обе части: у `test` нет побочных эффектов, а ошибки разбора
+            // должны обнаруживаться независимо от значения левой части.
+            let rhs = self.parse_and()?;
+            value = value || rhs;
+        }
+        Ok(value)
+    }
+
+    fn parse_and(&mut self) -> EvalResult<bool> {
+        let mut value = self.parse_not()?;
+        while self.peek() == Some(self.and_operator()) {
+            self.pos += 1;
+            let rhs = self.parse_not()?;
+            value = value && rhs;
+        }
+        Ok(value)
+    }
+
+    fn parse_not(&mut self) -> EvalResult<bool> {
+        // Одиночный `!` без операнда — просто непустая строка.
+        if self.peek() == Some("!") && self.remaining() > 1 {
+            self.pos += 1;
+            return Ok(!self.parse_not()?);
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> EvalResult<bool> {
+        let tok = self.next()?;
+
+        // Бинарный оператор имеет приоритет: `[ -f = -f ]` — сравнение строк.
+        if let Some(op) = self.peek()
+            && is_binary_operator(op, self.form)
+            && self.remaining() >= 2
+        {
+            let op = self.next()?;
+            let rhs = self.next()?;
+            return self.binary(tok, &self.texts[op], rhs);
+        }
+
+        if self.is_operator(tok, "(") && self.remaining() > 0 {
+            let value = self.parse_or()?;
+            return match self.next() {
+                Ok(close) if self.is_operator(close, ")") => Ok(value),
+                _ => Err("')' expected".to_string()),
+            };
+        }
+
+        if self.words[tok].operator && is_unary_operator(&self.texts[tok]) && self.remaining() > 0 {
+            let operand = self.next()?;
+            return Ok(self.unary(&self.texts[tok], &self.texts[operand]));
+        }
+
+        Ok(!self.texts[tok].is_empty())
+    }
+
+    fn unary(&self, op: &str, operand: &str) -> bool {
+        match op {
+            "-z" => operand.is_empty(),
+            "-n" => !operand.is_empty(),
+            "-L" | "-h" => self
+                .ctx
+                .fs
+                .symlink_metadata(&self.ctx.resolve(operand))
+                .is_ok_and(|m| m.is_symlink()),
+            _ => {
+                let Ok(meta) = self.ctx.fs.metadata(&self.ctx.resolve(operand)) else {
+                    return false;
+                };
+                match op {
+                    "-e" | "-a" => true,
+                    "-f" => meta.is_file(),
+                    "-d" => meta.is_dir(),
+                    // Права проверяются без учета владельца: `-r` истинно,
+                    // если чтение разрешено хоть кому-то.
+                    "-s" => meta.size() > 0,
+                    "-r" => meta.mode() & 0o444 != 0,
+                    "-w" => meta.mode() & 0o222 != 0,
+                    "-x" => meta.mode() & 0o111 != 0,
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    /// Бинарный оператор над словами `lhs` и `rhs` (номера слов).
+    fn binary(&self, lhs: usize, op: &str, rhs: usize) -> EvalResult<bool> {
+        let extended = self.form == TestForm::Extended;
+        let pattern = &self.words[rhs];
+        let (lhs, rhs) = (self.texts[lhs].as_str(), self.texts[rhs].as_str());
+        match op {
+            "=" | "==" if extended => Ok(glob_match(&glob_pattern(pattern), lhs, false)),
+            "!=" if extended => Ok(!glob_match(&glob_pattern(pattern), lhs, false)),
+            "=" | "==" => Ok(lhs == rhs),
+            "!=" => Ok(lhs != rhs),
+            "<" => Ok(lhs < rhs),
+            ">" => Ok(lhs > rhs),
+            "=~" => {
+                let re = Regex::new(&regex_pattern(pattern))
+                    .map_err(|e| format!("invalid regex: {e}"))?;
+                Ok(re.is_match(lhs))
+            }
+            "-nt" | "-ot" => {
+                let mtime = |p: &str| {
+                    self.ctx
+                        .fs
+                        .metadata(&self.ctx.resolve(p))
+                        .ok()
+                        .and_then(|m| m.modified())
+                };
+                let (l, r) = (mtime(lhs), mtime(rhs));
+                Ok(match op {
+                    // Как в bash: существующий файл новее несуществующего.
+                    "-nt" => match (l, r) {
+                        (Some(l), Some(r)) => l > r,
+                        (l, r) => l.is_some() && r.is_none(),
+                    },
+                    _ => match (l, r) {
+                        (Some(l), Some(r)) => l < r,
+                        (l, r) => l.is_none() && r.is_some(),
+                    },
+                })
+            }
+            "-ef" => {
+                let canon = |p: &str| self.ctx.fs.canonicalize(&self.ctx.resolve(p)).ok();
+                Ok(matches!((canon(lhs), canon(rhs)), (Some(l), Some(r)) if l == r))
+            }
+            _ => {
+                let l = parse_integer(lhs)?;
+                let r = parse_integer(rhs)?;
+                Ok(match op {
+                    "-eq" => l == r,
+                    "-ne" => l != r,
+                    "-lt" => l < r,
+                    "-le" => l <= r,
+                    "-gt" => l > r,
+                    _ => l >= r,
+                })
+            }
+        }
+    }
+}
+
+fn is_unary_operator(op: &str) -> bool {
+    matches!(
+        op,
+        "-e" | "-a" | "-f" | "-d" | "-r" | "-w" | "-x" | "-s" | "-L" | "-h" | "-z" | "-n"
+    )
+}
+
+fn is_binary_operator(op: &str, form: TestForm) -> bool {
+    match op {
+        "=" | "==" | "!=" | "<" | ">" | "-eq" | "-ne" | "-lt" | "-le" | "-gt" | "-ge" | "-nt"
+        | "-ot" | "-ef" => true,
+        "=~" => form == TestForm::Extended,
+        _ => false,
+    }
+}
+
+/// Glob-шаблон из слова: части в кавычках сравниваются буквально.
+fn glob_pattern(word: &CondWord) -> String {
+    let mut pattern = String::new();
+    for (text, quoted) in &word.parts {
+        for c in text.chars() {
+            if *quoted && matches!(c, '*' | '?' | '[' | ']' | '\\') {
+                pattern.push('\\');
+            }
+            pattern.push(c);
+        }
+    }
+    pattern
+}
+
+/// Регулярное выражение из слова: части в кавычках сравниваются буквально.
+fn regex_pattern(word: &CondWord) -> String {
+    word.parts
+        .iter()
+        .map(|(text, quoted)| {
+            if *quoted {
+                regex::escape(text)
+            } else {
+                text.clone()
+            }
+        })
+        .collect()
+}
+
+fn parse_integer(s: &str) -> EvalResult<i64> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("{s}: integer expression expected"))
+}
+
+/// Вычисляет условное выражение `test` или `[`.
+///
+/// Для `[` последний аргумент обязан быть `]`.
+/// Поддерживаются:
+/// - файловые проверки `-e -f -d -r -w -x -s -L`, сравнения `-nt -ot -ef`
+///   (пути разрешаются относительно рабочей директории шелла);
+/// - строки: `-z -n = != < >`, одиночный аргумент — «непустая строка»;
+/// - целые числа: `-eq -ne -lt -le -gt -ge`;
+/// - `!`, скобки `( )`, `-a`/`-o`.
+///
+/// Коды возврата:
+/// - 0: выражение истинно
+/// - 1: выражение ложно (в т.ч. пустое выражение)
+/// - 2: синтаксическая ошибка или нечисловой операнд
+pub(super) fn run_test(
+    args: &[String],
+    form: TestForm,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let args = match form {
+        TestForm::Bracket => match args.split_last() {
+            Some((last, rest)) if last == "]" => rest,
+            _ => {
+                writeln!(io.stderr, "[: missing ']'").map_err(ShellError::Io)?;
+                return Ok(ShellControl::Continue(2));
+            }
+        },
+        _ => args,
+    };
+    let words: Vec<CondWord> = args.iter().map(|arg| CondWord::plain(arg)).collect();
+    evaluate(&words, form, ctx, io)
+}
+
+/// Вычисляет выражение `[[ ... ]]` (слова между `[[` и `]]`).
+///
+/// Те же проверки, что у `test`, но вместо `-a`/`-o` — `&&`/`||`, а справа от
+/// `==`/`!=` стоит glob-шаблон, справа от `=~` — регулярное выражение (крейт
+/// `regex`); части шаблона в кавычках сравниваются буквально. Операторами
+/// считаются только слова без кавычек и подстановок.
+pub(crate) fn run_conditional(
+    words: &[CondWord],
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    evaluate(words, TestForm::Extended, ctx, io)
+}
+
+fn evaluate(
+    words: &[CondWord],
+    form: TestForm,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    if words.is_empty() {
+        return Ok(ShellControl::Continue(1));
+    }
+
+    let mut eval = Evaluator {
+        words,
+        texts: words.iter().map(CondWord::text).collect(),
+        pos: 0,
+        form,
+        ctx,
+    };
+    let result = eval
+        .parse_or()
+        .and_then(|value| match eval.words.get(eval.pos) {
+            None => Ok(value),
+            Some(_) => Err(format!("{}: unexpected argument", eval.texts[eval.pos])),
+        });
+    match result {
+        Ok(true) => Ok(ShellControl::Continue(0)),
+        Ok(false) => Ok(ShellControl::Continue(1)),
+        Err(msg) => {
+            writeln!(io.stderr, "{}: {msg}", form.name()).map_err(ShellError::Io)?;
+            Ok(ShellControl::Continue(2))
+        }
+    }
+}
@@ -0,0 +1,1161 @@
+//! Текстовые builtins: `head`, `tail`, `sort`, `uniq`, `cut`, `tr`, `tee`.
+//!
+//! Все команды работают в той же модели, что `cat`/`grep`: читают файлы-операнды,
+//! а без операндов (или с операндом `-`) — stdin пайплайна. `head` и `tail`
+//! читают stdin потоком, остальным он нужен целиком.
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::io::{BufRead, Read, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use clap::Parser;
+
+use super::super::limits::TIMEOUT_STATUS;
+use super::super::types::{IoStreams, ShellControl, ShellError, ShellResult};
+use super::super::vfs::FileSystem;
+use super::{BuiltinContext, io_error_message};
+
+/// Читает файлы-операнды (или stdin, если операндов нет) и возвращает их содержимое.
+///
+/// Ошибки чтения печатаются в stderr с префиксом команды; второй элемент результата
+/// сообщает, были ли такие ошибки.
+fn read_inputs(
+    cmd: &str,
+    files: &[String],
+    stdin: Option<&[u8]>,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<(Vec<Vec<u8>>, bool)> {
+    if files.is_empty() {
+        return Ok((vec![stdin.map(<[u8]>::to_vec).unwrap_or_default()], false));
+    }
+
+    let mut stdin = stdin;
+    let mut inputs = Vec::with_capacity(files.len());
+    let mut had_error = false;
+    for path in files {
+        if path == "-" {
+            inputs.push(stdin.take().map(<[u8]>::to_vec).unwrap_or_default());
+            continue;
+        }
+        match ctx.read(path) {
+            Ok(bytes) => inputs.push(bytes),
+            Err(e) => {
+                let msg = io_error_message(&e);
+                writeln!(io.stderr, "{cmd}: {path}: {msg}").map_err(ShellError::Io)?;
+                had_error = true;
+            }
+        }
+    }
+    Ok((inputs, had_error))
+}
+
+/// Вход `head`/`tail`: файл-операнд или stdin, который читается потоком.
+enum Source {
+    File(Vec<u8>),
+    Stdin,
+}
+
+impl Source {
+    /// Содержимое файла; для stdin — пусто.
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Source::File(bytes) => bytes,
+            Source::Stdin => &[],
+        }
+    }
+}
+
+/// Как `read_inputs`, но stdin не читается заранее: `head` берет из пайпа
+/// только нужные строки, `tail` держит в памяти только последние.
+fn open_inputs(
+    cmd: &str,
+    files: &[String],
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<(Vec<(String, Source)>, bool)> {
+    if files.is_empty() {
+        return Ok((vec![("-".to_string(), Source::Stdin)], false));
+    }
+    let mut inputs = Vec::with_capacity(files.len());
+    let mut had_error = false;
+    for path in files {
+        if path == "-" {
+            inputs.push(("standard input".to_string(), Source::Stdin));
+            continue;
+        }
+        match ctx.read(path) {
+            Ok(bytes) => inputs.push((path.clone(), Source::File(bytes))),
+            Err(e) => {
+                let msg = io_error_message(&e);
+                writeln!(io.stderr, "{cmd}: {path}: {msg}").map_err(ShellError::Io)?;
+                had_error = true;
+            }
+        }
+    }
+    Ok((inputs, had_error))
+}
+
+/// Разбирает аргументы clap-структуры; при ошибке печатает ее и возвращает `None`.
+fn parse_cli<T: Parser>(
+    cmd: &str,
+    args: impl IntoIterator<Item = String>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<Option<T>> {
+    let argv = std::iter::once(cmd.to_string()).chain(args);
+    match T::try_parse_from(argv) {
+        Ok(p) => Ok(Some(p)),
+        Err(e) => {
+            writeln!(io.stderr, "{cmd}: {e}").map_err(ShellError::Io)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Разворачивает устаревшую форму `-N` в `-n N` (для `head`/`tail`).
+fn expand_count_shorthand(args: &[String]) -> Vec<String> {
+    let mut out = Vec::with_capacity(args.len());
+    for (idx, arg) in args.iter().enumerate() {
+        let is_shorthand = arg.len() > 1
+            && arg.starts_with('-')
+            && arg[1..].chars().all(|c| c.is_ascii_digit())
+            && (idx == 0 || !matches!(args[idx - 1].as_str(), "-n" | "-c"));
+        if is_shorthand {
+            out.push("-n".to_string());
+            out.push(arg[1..].to_string());
+        } else {
+            out.push(arg.clone());
+        }
+    }
+    out
+}
+
+fn write_header(
+    io: &mut IoStreams<'_>,
+    name: &str,
+    first: &mut bool,
+    enabled: bool,
+) -> ShellResult<()> {
+    if !enabled {
+        return Ok(());
+    }
+    if !*first {
+        writeln!(io.stdout).map_err(ShellError::Io)?;
+    }
+    *first = false;
+    writeln!(io.stdout, "==> {name} <==").map_err(ShellError::Io)
+}
+
+fn exit_status(had_error: bool) -> ShellControl {
+    ShellControl::Continue(if had_error { 1 } else { 0 })
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "head", disable_help_flag = true, disable_version_flag = true)]
+struct HeadCli {
+    /// Print the first NUM lines; with a leading `-`, all but the last NUM lines.
+    #[arg(short = 'n', value_name = "NUM", allow_hyphen_values = true)]
+    lines: Option<String>,
+
+    /// Print the first NUM bytes; with a leading `-`, all but the last NUM bytes.
+    #[arg(short = 'c', value_name = "NUM", allow_hyphen_values = true)]
+    bytes: Option<String>,
+
+    /// Files to read. If omitted, reads stdin (pipeline input).
+    files: Vec<String>,
+}
+
+/// Количество для `head`: первые N либо "все, кроме последних N".
+enum HeadCount {
+    First(usize),
+    AllButLast(usize),
+}
+
+fn parse_head_count(s: &str) -> Option<HeadCount> {
+    match s.strip_prefix('-') {
+        Some(n) => n.parse().ok().map(HeadCount::AllButLast),
+        None => s.parse().ok().map(HeadCount::First),
+    }
+}
+
+/// Печатает начало файлов.
+///
+/// Флаги: `-n NUM` (по умолчанию 10), `-c NUM`, а также краткая форма `-NUM`.
+/// Для нескольких файлов печатаются заголовки `==> file <==`.
+///
+/// Коды возврата: 0 — успех, 1 — ошибка чтения/аргументов.
+pub(super) fn run_head(
+    args: &[String],
+    stdin: Option<&mut dyn BufRead>,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(cli) = parse_cli::<HeadCli>("head", expand_count_shorthand(args), io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+
+    let (by_bytes, raw) = match (&cli.bytes, &cli.lines) {
+        (Some(c), _) => (true, c.as_str()),
+        (None, Some(n)) => (false, n.as_str()),
+        (None, None) => (false, "10"),
+    };
+    let Some(count) = parse_head_count(raw) else {
+        let what = if by_bytes { "bytes" } else { "lines" };
+        writeln!(io.stderr, "head: invalid number of {what}: '{raw}'").map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(1));
+    };
+
+    let (inputs, had_error) = open_inputs("head", &cli.files, ctx, io)?;
+    let headers = cli.files.len() > 1;
+    let mut first = true;
+    let mut stdin = stdin;
+    for (name, source) in &inputs {
+        write_header(io, name, &mut first, headers)?;
+        let mut bytes: &[u8] = source.bytes();
+        let input: &mut dyn BufRead = match (source, stdin.as_deref_mut()) {
+            (Source::Stdin, Some(reader)) => reader,
+            _ => &mut bytes,
+        };
+        if by_bytes {
+            head_bytes(input, &count, io.stdout)
+        } else {
+            head_lines(input, &count, io.stdout)
+        }
+        .map_err(ShellError::Io)?;
+    }
+    Ok(exit_status(had_error))
+}
+
+/// Копирует из `input` первые строки (или все, кроме последних) и перестает
+/// читать, как только они получены.
+fn head_lines(
+    input: &mut dyn BufRead,
+    count: &HeadCount,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    match *count {
+        HeadCount::First(n) => {
+            let mut line = Vec::new();
+            for _ in 0..n {
+                line.clear();
+                if input.read_until(b'\n', &mut line)? == 0 {
+                    break;
+                }
+                out.write_all(&line)?;
+            }
+        }
+        HeadCount::AllButLast(n) => {
+            let mut held = VecDeque::new();
+            loop {
+                let mut line = Vec::new();
+                if input.read_until(b'\n', &mut line)? == 0 {
+                    break;
+                }
+                held.push_back(line);
+                if held.len() > n
+                    && let Some(line) = held.pop_front()
+                {
+                    out.write_all(&line)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Копирует из `input` первые байты (или все, кроме последних).
+fn head_bytes(
+    input: &mut dyn BufRead,
+    count: &HeadCount,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    match *count {
+        HeadCount::First(n) => {
+            std::io::copy(&mut input.take(n as u64), out)?;
+        }
+        HeadCount::AllButLast(n) => {
+            let mut data = Vec::new();
+            input.read_to_end(&mut data)?;
+            out.write_all(&data[..data.len().saturating_sub(n)])?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "tail", disable_help_flag = true, disable_version_flag = true)]
+struct TailCli {
+    /// Output the last NUM lines; with a leading `+`, output starting with line NUM.
+    #[arg(short = 'n', value_name = "NUM", allow_hyphen_values = true)]
+    lines: Option<String>,
+
+    /// Output the last NUM bytes; with a leading `+`, output starting with byte NUM.
+    #[arg(short = 'c', value_name = "NUM", allow_hyphen_values = true)]
+    bytes: Option<String>,
+
+    /// Output appended data as the file grows.
+    #[arg(short = 'f')]
+    follow: bool,
+
+    /// Files to read. If omitted, reads stdin (pipeline input).
+    files: Vec<String>,
+}
+
+/// Количество для `tail`: последние N либо "начиная с N-го".
+enum TailCount {
+    Last(usize),
+    From(usize),
+}
+
+fn parse_tail_count(s: &str) -> Option<TailCount> {
+    match s.strip_prefix('+') {
+        Some(n) => n.parse().ok().map(TailCount::From),
+        None => s
+            .strip_prefix('-')
+            .unwrap_or(s)
+            .parse()
+            .ok()
+            .map(TailCount::Last),
+    }
+}
+
+/// Размер, от которого `tail -c` начинает выбрасывать прочитанное начало.
+const TAIL_CHUNK: usize = 64 * 1024;
+
+/// Интервал опроса файла в режиме `tail -f`.
+const TAIL_FOLLOW_POLL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Печатает конец файлов.
+///
+/// Флаги: `-n NUM` (по умолчанию 10; `+NUM` — начиная со строки NUM), `-c NUM`,
+/// `-f` — после вывода следить за дописыванием в последний файл. Слежение
+/// прекращается, когда файл удален, запись в stdout завершилась ошибкой или
+/// наступил срок `timeout` (`BuiltinContext::deadline`).
+/// Для stdin `-f` игнорируется.
+///
+/// Коды возврата: 0 — успех, 1 — ошибка чтения/аргументов, 124 — `-f`
+/// прерван по сроку.
+pub(super) fn run_tail(
+    args: &[String],
+    stdin: Option<&mut dyn BufRead>,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(cli) = parse_cli::<TailCli>("tail", expand_count_shorthand(args), io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+
+    let (by_bytes, raw) = match (&cli.bytes, &cli.lines) {
+        (Some(c), _) => (true, c.as_str()),
+        (None, Some(n)) => (false, n.as_str()),
+        (None, None) => (false, "10"),
+    };
+    let Some(count) = parse_tail_count(raw) else {
+        let what = if by_bytes { "bytes" } else { "lines" };
+        writeln!(io.stderr, "tail: invalid number of {what}: '{raw}'").map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(1));
+    };
+
+    let (inputs, had_error) = open_inputs("tail", &cli.files, ctx, io)?;
+    let headers = cli.files.len() > 1;
+    let mut first = true;
+    let mut stdin = stdin;
+    for (name, source) in &inputs {
+        write_header(io, name, &mut first, headers)?;
+        let mut bytes: &[u8] = source.bytes();
+        let input: &mut dyn BufRead = match (source, stdin.as_deref_mut()) {
+            (Source::Stdin, Some(reader)) => reader,
+            _ => &mut bytes,
+        };
+        if by_bytes {
+            tail_bytes(input, &count, io.stdout)
+        } else {
+            tail_lines(input, &count, io.stdout)
+        }
+        .map_err(ShellError::Io)?;
+    }
+    io.stdout.flush().map_err(ShellError::Io)?;
+
+    if cli.follow {
+        let followed = cli
+            .files
+            .last()
+            .filter(|p| p.as_str() != "-")
+            .and_then(|p| inputs.last().filter(|(name, _)| name == p));
+        if let Some((name, source)) = followed {
+            let path = ctx.resolve(name);
+            let offset = source.bytes().len() as u64;
+            if follow_file(ctx.fs, &path, offset, ctx.deadline, io) {
+                return Ok(ShellControl::Continue(TIMEOUT_STATUS));
+            }
+        }
+    }
+
+    Ok(exit_status(had_error))
+}
+
+/// Копирует из `input` последние строки (в памяти — только они) или строки
+/// начиная с заданной.
+fn tail_lines(
+    input: &mut dyn BufRead,
+    count: &TailCount,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    match *count {
+        TailCount::Last(n) => {
+            let mut held = VecDeque::new();
+            loop {
+                let mut line = Vec::new();
+                if input.read_until(b'\n', &mut line)? == 0 {
+                    break;
+                }
+                held.push_back(line);
+                if held.len() > n {
+                    held.pop_front();
+                }
+            }
+            for line in held {
+                out.write_all(&line)?;
+            }
+        }
+        TailCount::From(n) => {
+            let mut line = Vec::new();
+            for _ in 1..n {
+                line.clear();
+                if input.read_until(b'\n', &mut line)? == 0 {
+                    return Ok(());
+                }
+            }
+            std::io::copy(input, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Копирует из `input` последние байты или байты начиная с заданного.
+fn tail_bytes(
+    input: &mut dyn BufRead,
+    count: &TailCount,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    match *count {
+        TailCount::Last(n) => {
+            let mut data = Vec::new();
+            loop {
+                let chunk = input.fill_buf()?;
+                if chunk.is_empty() {
+                    break;
+                }
+                let len = chunk.len();
+                data.extend_from_slice(chunk);
+                input.consume(len);
+                // Лишнее начало выбрасываем пачками, а не на каждом чтении.
+                if data.len() >= n.max(TAIL_CHUNK).saturating_mul(2) {
+                    data.drain(..data.len() - n);
+                }
+            }
+            out.write_all(&data[data.len().saturating_sub(n)..])?;
+        }
+        TailCount::From(n) => {
+            let skip = n.saturating_sub(1) as u64;
+            std::io::copy(&mut (&mut *input).take(skip), &mut std::io::sink())?;
+            std::io::copy(input, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Опрашивает файл через файловую систему шелла и печатает дописанные после
+/// `offset` данные до срока `deadline`; `true` — слежение прервано по сроку.
+///
+/// Размер берется из `metadata`, а читается только новый хвост (`read_from`);
+/// файл короче `offset` считается усеченным и читается сначала.
+fn follow_file(
+    fs: &dyn FileSystem,
+    path: &Path,
+    mut offset: u64,
+    deadline: Option<Instant>,
+    io: &mut IoStreams<'_>,
+) -> bool {
+    loop {
+        let poll = match deadline {
+            Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                Some(left) if !left.is_zero() => left.min(TAIL_FOLLOW_POLL),
+                _ => return true,
+            },
+            None => TAIL_FOLLOW_POLL,
+        };
+        std::thread::sleep(poll);
+        let Ok(len) = fs.metadata(path).map(|m| m.size()) else {
+            return false;
+        };
+        if len < offset {
+            // Файл усечен: начинаем сначала.
+            offset = 0;
+        }
+        if len == offset {
+            continue;
+        }
+        let Ok(added) = fs.read_from(path, offset) else {
+            return false;
+        };
+        offset += added.len() as u64;
+        if io.stdout.write_all(&added).is_err() || io.stdout.flush().is_err() {
+            return false;
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "sort", disable_help_flag = true, disable_version_flag = true)]
+struct SortCli {
+    /// Compare according to string numerical value.
+    #[arg(short = 'n')]
+    numeric: bool,
+
+    /// Reverse the result of comparisons.
+    #[arg(short = 'r')]
+    reverse: bool,
+
+    /// Output only the first of an equal run.
+    #[arg(short = 'u')]
+    unique: bool,
+
+    /// Fold lower case to upper case characters.
+    #[arg(short = 'f')]
+    ignore_case: bool,
+
+    /// Sort via a key: `N[,M]` (fields are 1-based), optionally followed by
+    /// the modifiers `n`, `r`, `f`, `b`.
+    #[arg(short = 'k', value_name = "KEYDEF")]
+    keys: Vec<String>,
+
+    /// Use SEP instead of blank-to-non-blank transition as field separator.
+    #[arg(short = 't', value_name = "SEP")]
+    separator: Option<char>,
+
+    /// Files to sort. If omitted, reads stdin (pipeline input).
+    files: Vec<String>,
+}
+
+/// Способ сравнения ключа: флаги `sort` или модификаторы самого ключа.
+#[derive(Debug, Clone, Copy, Default)]
+struct SortOrder {
+    numeric: bool,
+    reverse: bool,
+    ignore_case: bool,
+    /// Пропускать ведущие пробелы ключа (`b`).
+    skip_blanks: bool,
+}
+
+impl SortOrder {
+    fn compare(self, a: &str, b: &str) -> Ordering {
+        let (a, b) = if self.skip_blanks {
+            (a.trim_start(), b.trim_start())
+        } else {
+            (a, b)
+        };
+        let ord = if self.numeric {
+            leading_number(a)
+                .partial_cmp(&leading_number(b))
+                .unwrap_or(Ordering::Equal)
+        } else if self.ignore_case {
+            a.to_uppercase().cmp(&b.to_uppercase())
+        } else {
+            a.cmp(b)
+        };
+        if self.reverse { ord.reverse() } else { ord }
+    }
+}
+
+/// Ключ сортировки `-k N[,M]` (номера полей с 1, `M` включительно).
+#[derive(Debug, Clone, Copy)]
+struct SortKey {
+    start: usize,
+    end: Option<usize>,
+    /// Модификаторы ключа (`-k2,2n`); как в GNU sort, ключ с модификаторами
+    /// не наследует флаги `-n`, `-r`, `-f`.
+    order: Option<SortOrder>,
+}
+
+/// Разбирает `-k`: номер поля и модификаторы `n`, `r`, `f`, `b` после любого
+/// из номеров. Ошибка — сообщение для `sort: ...`.
+fn parse_sort_key(s: &str) -> Result<SortKey, String> {
+    let mut order = SortOrder::default();
+    let mut modified = false;
+    let mut field = |f: &str| -> Result<usize, String> {
+        let digits = f.find(|c: char| !c.is_ascii_digit()).unwrap_or(f.len());
+        let n = f[..digits]
+            .parse()
+            .ok()
+            .filter(|&n: &usize| n > 0)
+            .ok_or_else(|| format!("invalid key: '{s}'"))?;
+        for c in f[digits..].chars() {
+            match c {
+                'n' => order.numeric = true,
+                'r' => order.reverse = true,
+                'f' => order.ignore_case = true,
+                'b' => order.skip_blanks = true,
+                '.' => return Err(format!("{s}: character offsets in keys are not supported")),
+                _ => return Err(format!("{s}: unsupported key modifier '{c}'")),
+            }
+            modified = true;
+        }
+        Ok(n)
+    };
+    let (start, end) = match s.split_once(',') {
+        Some((start, end)) => (field(start)?, Some(field(end)?)),
+        None => (field(s)?, None),
+    };
+    Ok(SortKey {
+        start,
+        end,
+        order: modified.then_some(order),
+    })
+}
+
+/// Делит строку на поля: по `sep` либо по пробельным промежуткам.
+fn split_fields(line: &str, sep: Option<char>) -> Vec<&str> {
+    match sep {
+        Some(c) => line.split(c).collect(),
+        None => line.split_whitespace().collect(),
+    }
+}
+
+fn extract_key(line: &str, key: SortKey, sep: Option<char>) -> String {
+    let fields = split_fields(line, sep);
+    let start = key.start - 1;
+    if start >= fields.len() {
+        return String::new();
+    }
+    let end = key
+        .end
+        .unwrap_or(fields.len())
+        .min(fields.len())
+        .max(key.start);
+    let joiner = sep.map(String::from).unwrap_or_else(|| " ".to_string());
+    fields[start..end].join(&joiner)
+}
+
+/// Числовое значение начала строки (как `sort -n`): нечисловые строки равны 0.
+fn leading_number(s: &str) -> f64 {
+    let s = s.trim_start();
+    let mut end = 0;
+    let bytes = s.as_bytes();
+    if matches!(bytes.first(), Some(b'-' | b'+')) {
+        end = 1;
+    }
+    let mut seen_dot = false;
+    while let Some(&b) = bytes.get(end) {
+        if b.is_ascii_digit() {
+            end += 1;
+        } else if b == b'.' && !seen_dot {
+            seen_dot = true;
+            end += 1;
+        } else {
+            break;
+        }
+    }
+    s[..end].parse().unwrap_or(0.0)
+}
+
+/// Сортирует строки входа.
+///
+/// Флаги: `-n`, `-r`, `-u`, `-f`, `-k N[,M]` (можно несколько, с модификаторами
+/// ключа `n`, `r`, `f`, `b`: `-k2,2nr`), `-t SEP`.
+/// Строки с равными ключами упорядочиваются по всей строке (кроме режима `-u`,
+/// где из каждой группы равных ключей печатается первая).
+///
+/// Коды возврата: 0 — успех, 2 — ошибка чтения/аргументов.
+pub(super) fn run_sort(
+    args: &[String],
+    stdin: Option<&[u8]>,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(cli) = parse_cli::<SortCli>("sort", args.iter().cloned(), io)? else {
+        return Ok(ShellControl::Continue(2));
+    };
+
+    let mut keys = Vec::with_capacity(cli.keys.len());
+    for raw in &cli.keys {
+        match parse_sort_key(raw) {
+            Ok(k) => keys.push(k),
+            Err(msg) => {
+                writeln!(io.stderr, "sort: {msg}").map_err(ShellError::Io)?;
+                return Ok(ShellControl::Continue(2));
+            }
+        }
+    }
+
+    let (inputs, had_error) = read_inputs("sort", &cli.files, stdin, ctx, io)?;
+    if had_error {
+        return Ok(ShellControl::Continue(2));
+    }
+    let text: String = inputs
+        .iter()
+        .map(|i| {
+            let mut s = String::from_utf8_lossy(i).to_string();
+            if !s.is_empty() && !s.ends_with('\n') {
+                s.push('\n');
+            }
+            s
+        })
+        .collect();
+    let mut lines: Vec<&str> = text.lines().collect();
+
+    let global = SortOrder {
+        numeric: cli.numeric,
+        reverse: cli.reverse,
+        ignore_case: cli.ignore_case,
+        skip_blanks: false,
+    };
+    let compare_keys = |a: &str, b: &str| -> Ordering {
+        if keys.is_empty() {
+            return global.compare(a, b);
+        }
+        for key in &keys {
+            let ord = key.order.unwrap_or(global).compare(
+                &extract_key(a, *key, cli.separator),
+                &extract_key(b, *key, cli.separator),
+            );
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+        Ordering::Equal
+    };
+
+    lines.sort_by(|a, b| {
+        let ord = compare_keys(a, b);
+        if ord != Ordering::Equal || cli.unique {
+            return ord;
+        }
+        // Последнее сравнение — по всей строке, его разворачивает только `-r`.
+        if cli.reverse { b.cmp(a) } else { a.cmp(b) }
+    });
+    if cli.unique {
+        lines.dedup_by(|a, b| compare_keys(a, b) == Ordering::Equal);
+    }
+
+    for line in lines {
+        writeln!(io.stdout, "{line}").map_err(ShellError::Io)?;
+    }
+    Ok(ShellControl::Continue(0))
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "uniq", disable_help_flag = true, disable_version_flag = true)]
+struct UniqCli {
+    /// Prefix lines by the number of occurrences.
+    #[arg(short = 'c')]
+    count: bool,
+
+    /// Only print duplicate lines, one for each group.
+    #[arg(short = 'd')]
+    repeated: bool,
+
+    /// Only print unique lines.
+    #[arg(short = 'u')]
+    unique: bool,
+
+    /// Ignore differences in case when comparing.
+    #[arg(short = 'i')]
+    ignore_case: bool,
+
+    /// Input file. If omitted, reads stdin (pipeline input).
+    input: Option<String>,
+}
+
+/// Схлопывает подряд идущие одинаковые строки.
+///
+/// Флаги: `-c` (счетчик), `-d` (только повторяющиеся), `-u` (только уникальные), `-i`.
+///
+/// Коды возврата: 0 — успех, 1 — ошибка чтения/аргументов.
+pub(super) fn run_uniq(
+    args: &[String],
+    stdin: Option<&[u8]>,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(cli) = parse_cli::<UniqCli>("uniq", args.iter().cloned(), io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+    let files: Vec<String> = cli.input.iter().cloned().collect();
+    let (inputs, had_error) = read_inputs("uniq", &files, stdin, ctx, io)?;
+    if had_error {
+        return Ok(ShellControl::Continue(1));
+    }
+    let text = inputs
+        .first()
+        .map(|i| String::from_utf8_lossy(i).to_string())
+        .unwrap_or_default();
+
+    let same = |a: &str, b: &str| {
+        if cli.ignore_case {
+            a.to_lowercase() == b.to_lowercase()
+        } else {
+            a == b
+        }
+    };
+
+    let mut groups: Vec<(&str, usize)> = Vec::new();
+    for line in text.lines() {
+        match groups.last_mut() {
+            Some((prev, n)) if same(prev, line) => *n += 1,
+            _ => groups.push((line, 1)),
+        }
+    }
+
+    for (line, n) in groups {
+        if (cli.repeated && n < 2) || (cli.unique && n > 1) {
+            continue;
+        }
+        if cli.count {
+            writeln!(io.stdout, "{n:>7} {line}").map_err(ShellError::Io)?;
+        } else {
+            writeln!(io.stdout, "{line}").map_err(ShellError::Io)?;
+        }
+    }
+    Ok(ShellControl::Continue(0))
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "cut", disable_help_flag = true, disable_version_flag = true)]
+struct CutCli {
+    /// Use DELIM instead of TAB for field delimiter.
+    #[arg(short = 'd', value_name = "DELIM")]
+    delimiter: Option<char>,
+
+    /// Select only these fields.
+    #[arg(short = 'f', value_name = "LIST", allow_hyphen_values = true)]
+    fields: Option<String>,
+
+    /// Select only these characters.
+    #[arg(short = 'c', value_name = "LIST", allow_hyphen_values = true)]
+    chars: Option<String>,
+
+    /// Do not print lines not containing delimiters.
+    #[arg(short = 's')]
+    only_delimited: bool,
+
+    /// Files to read. If omitted, reads stdin (pipeline input).
+    files: Vec<String>,
+}
+
+/// Список диапазонов `cut` (`N`, `N-M`, `N-`, `-M`), номера с 1.
+struct CutList(Vec<(usize, usize)>);
+
+impl CutList {
+    fn parse(s: &str) -> Option<Self> {
+        let mut ranges = Vec::new();
+        for part in s.split(',') {
+            let range = match part.split_once('-') {
+                Some((a, b)) => {
+                    let start = if a.is_empty() { 1 } else { a.parse().ok()? };
+                    let end = if b.is_empty() {
+                        usize::MAX
+                    } else {
+                        b.parse().ok()?
+                    };
+                    (start, end)
+                }
+                None => {
+                    let n = part.parse().ok()?;
+                    (n, n)
+                }
+            };
+            if range.0 == 0 || range.0 > range.1 {
+                return None;
+            }
+            ranges.push(range);
+        }
+        Some(Self(ranges))
+    }
+
+    fn contains(&self, pos: usize) -> bool {
+        self.0.iter().any(|&(a, b)| a <= pos && pos <= b)
+    }
+}
+
+/// Вырезает поля (`-f` с разделителем `-d`, по умолчанию TAB) или символы (`-c`).
+///
+/// Коды возврата: 0 — успех, 1 — ошибка чтения/аргументов.
+pub(super) fn run_cut(
+    args: &[String],
+    stdin: Option<&[u8]>,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(cli) = parse_cli::<CutCli>("cut", args.iter().cloned(), io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+
+    let (by_fields, raw) = match (&cli.fields, &cli.chars) {
+        (Some(f), None) => (true, f),
+        (None, Some(c)) => (false, c),
+        _ => {
+            writeln!(io.stderr, "cut: you must specify exactly one of -f or -c")
+                .map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(1));
+        }
+    };
+    let Some(list) = CutList::parse(raw) else {
+        writeln!(io.stderr, "cut: invalid list: '{raw}'").map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(1));
+    };
+    let delim = cli.delimiter.unwrap_or('\t');
+
+    let (inputs, had_error) = read_inputs("cut", &cli.files, stdin, ctx, io)?;
+    for input in &inputs {
+        let text = String::from_utf8_lossy(input);
+        for line in text.lines() {
+            if by_fields {
+                if !line.contains(delim) {
+                    if !cli.only_delimited {
+                        writeln!(io.stdout, "{line}").map_err(ShellError::Io)?;
+                    }
+                    continue;
+                }
+                let selected: Vec<&str> = line
+                    .split(delim)
+                    .enumerate()
+                    .filter(|(i, _)| list.contains(i + 1))
+                    .map(|(_, f)| f)
+                    .collect();
+                writeln!(io.stdout, "{}", selected.join(&delim.to_string()))
+                    .map_err(ShellError::Io)?;
+            } else {
+                let selected: String = line
+                    .chars()
+                    .enumerate()
+                    .filter(|(i, _)| list.contains(i + 1))
+                    .map(|(_, c)| c)
+                    .collect();
+                writeln!(io.stdout, "{selected}").map_err(ShellError::Io)?;
+            }
+        }
+    }
+    Ok(exit_status(had_error))
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "tr", disable_help_flag = true, disable_version_flag = true)]
+struct TrCli {
+    /// Delete characters in SET1, do not translate.
+    #[arg(short = 'd')]
+    delete: bool,
+
+    /// Replace each sequence of a repeated character listed in the last SET with a single one.
+    #[arg(short = 's')]
+    squeeze: bool,
+
+    /// First character set.
+    set1: String,
+
+    /// Second character set.
+    set2: Option<String>,
+}
+
+type CharPredicate = fn(char) -> bool;
+
+/// Раскрывает множество `tr`: диапазоны `a-z`, классы `[:alpha:]`, escapes `\n`, `\t`, `\\`.
+fn expand_tr_set(set: &str) -> Result<Vec<char>, String> {
+    const CLASSES: &[(&str, CharPredicate)] = &[
+        ("alnum", |c| c.is_ascii_alphanumeric()),
+        ("alpha", |c| c.is_ascii_alphabetic()),
+        ("blank", |c| c == ' ' || c == '\t'),
+        ("cntrl", |c| c.is_ascii_control()),
+        ("digit", |c| c.is_ascii_digit()),
+        ("graph", |c| c.is_ascii_graphic()),
+        ("lower", |c| c.is_ascii_lowercase()),
+        ("print", |c| c.is_ascii_graphic() || c == ' '),
+        ("punct", |c| c.is_ascii_punctuation()),
+        ("space", |c| c.is_ascii_whitespace() || c == '\x0b'),
+        ("upper", |c| c.is_ascii_uppercase()),
+        ("xdigit", |c| c.is_ascii_hexdigit()),
+    ];
+
+    // Сначала раскрываем escapes, помечая классы как единые элементы.
+    enum Item {
+        Char(char),
+        Class(CharPredicate),
+    }
+    let mut items = Vec::new();
+    let mut rest = set;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("[:")
+            && let Some(end) = after.find(":]")
+        {
+            let name = &after[..end];
+            let Some((_, pred)) = CLASSES.iter().find(|(n, _)| *n == name) else {
+                return Err(format!("invalid character class '{name}'"));
+            };
+            items.push(Item::Class(*pred));
+            rest = &after[end + 2..];
+            continue;
+        }
+        if c == '\\' {
+            let mut chars = rest.chars();
+            let _ = chars.next();
+            let (ch, len) = match chars.next() {
+                Some('n') => ('\n', 2),
+                Some('t') => ('\t', 2),
+                Some('r') => ('\r', 2),
+                Some('\\') => ('\\', 2),
+                Some(other) => (other, 1 + other.len_utf8()),
+                None => ('\\', 1),
+            };
+            items.push(Item::Char(ch));
+            rest = &rest[len..];
+            continue;
+        }
+        items.push(Item::Char(c));
+        rest = &rest[c.len_utf8()..];
+    }
+
+    let mut out = Vec::new();
+    let mut idx = 0;
+    while idx < items.len() {
+        match &items[idx] {
+            Item::Class(pred) => {
+                out.extend((0u8..=127).map(char::from).filter(|&c| pred(c)));
+                idx += 1;
+            }
+            Item::Char(a) => {
+                if let (Some(Item::Char('-')), Some(Item::Char(b))) =
+                    (items.get(idx + 1), items.get(idx + 2))
+                {
+                    if a > b {
+                        return Err(format!("range-endpoints of '{a}-{b}' are in reverse order"));
+                    }
+                    out.extend(*a..=*b);
+                    idx += 3;
+                } else {
+                    out.push(*a);
+                    idx += 1;
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Заменяет, удаляет (`-d`) или схлопывает (`-s`) символы stdin.
+///
+/// Коды возврата: 0 — успех, 1 — ошибка аргументов.
+pub(super) fn run_tr(
+    args: &[String],
+    stdin: Option<&[u8]>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(cli) = parse_cli::<TrCli>("tr", args.iter().cloned(), io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+
+    let set1 = expand_tr_set(&cli.set1);
+    let set2 = cli.set2.as_deref().map(expand_tr_set).transpose();
+    let (set1, set2) = match (set1, set2) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(e), _) | (_, Err(e)) => {
+            writeln!(io.stderr, "tr: {e}").map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(1));
+        }
+    };
+
+    let translating = !cli.delete && set2.is_some();
+    if !cli.delete && !cli.squeeze && set2.is_none() {
+        writeln!(io.stderr, "tr: missing operand after '{}'", cli.set1).map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(1));
+    }
+    if translating && set2.as_ref().is_some_and(Vec::is_empty) && !set1.is_empty() {
+        writeln!(
+            io.stderr,
+            "tr: when not truncating set1, string2 must be non-empty"
+        )
+        .map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(1));
+    }
+
+    // Множество для схлопывания: последнее заданное.
+    let squeeze_set: &[char] = match (&set2, cli.delete) {
+        (Some(s2), _) => s2,
+        (None, false) => &set1,
+        (None, true) => &[],
+    };
+
+    let text = String::from_utf8_lossy(stdin.unwrap_or_default()).to_string();
+    let mut out = String::with_capacity(text.len());
+    let mut last: Option<char> = None;
+    for c in text.chars() {
+        let mapped = if cli.delete {
+            if set1.contains(&c) {
+                continue;
+            }
+            c
+        } else if let (true, Some(s2)) = (translating, &set2) {
+            // Последнее вхождение символа в SET1 определяет замену (как в GNU tr).
+            match set1.iter().rposition(|&x| x == c) {
+                Some(i) => *s2.get(i).or(s2.last()).unwrap_or(&c),
+                None => c,
+            }
+        } else {
+            c
+        };
+
+        if cli.squeeze && last == Some(mapped) && squeeze_set.contains(&mapped) {
+            continue;
+        }
+        last = Some(mapped);
+        out.push(mapped);
+    }
+    io.stdout
+        .write_all(out.as_bytes())
+        .map_err(ShellError::Io)?;
+    Ok(ShellControl::Continue(0))
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "tee", disable_help_flag = true, disable_version_flag = true)]
+struct TeeCli {
+    /// Append to the given files, do not overwrite.
+    #[arg(short = 'a')]
+    append: bool,
+
+    /// Files to write stdin to (in addition to stdout).
+    files: Vec<String>,
+}
+
+/// Копирует stdin в stdout и в каждый из файлов.
+///
+/// Флаг `-a` — дописывать в файлы вместо перезаписи.
+///
+/// Коды возврата: 0 — успех, 1 — ошибка записи хотя бы в один файл/аргументов.
+pub(super) fn run_tee(
+    args: &[String],
+    stdin: Option<&[u8]>,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(cli) = parse_cli::<TeeCli>("tee", args.iter().cloned(), io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+    let input = stdin.unwrap_or_default();
+
+    let mut had_error = false;
+    for path in &cli.files {
+        let result = ctx
+            .fs
+            .open_write(&ctx.resolve(path), cli.append)
+            .and_then(|mut f| f.write_all(input));
+        if let Err(e) = result {
+            let msg = io_error_message(&e);
+            writeln!(io.stderr, "tee: {path}: {msg}").map_err(ShellError::Io)?;
+            had_error = true;
+        }
+    }
+    io.stdout.write_all(input).map_err(ShellError::Io)?;
+    Ok(exit_status(had_error))
+}
@@ -0,0 +1,244 @@
+//! `xargs`: сборка командных строк из stdin.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use clap::Parser;
+
+use super::super::types::{IoStreams, RunResult, ShellControl, ShellError, ShellResult};
+use super::BuiltinContext;
+
+#[derive(Parser, Debug)]
+#[command(name = "xargs", disable_help_flag = true, disable_version_flag = true)]
+struct XargsCli {
+    /// Use at most MAX-ARGS arguments per command line.
+    #[arg(short = 'n', value_name = "MAX-ARGS")]
+    max_args: Option<usize>,
+
+    /// Input items are terminated by a null character instead of whitespace.
+    #[arg(short = '0')]
+    null: bool,
+
+    /// Replace occurrences of REPLACE in the initial arguments with names read from
+    /// standard input (one per line).
+    #[arg(short = 'I', value_name = "REPLACE")]
+    replace: Option<String>,
+
+    /// Run up to MAX-PROCS commands at a time (0 — all at once).
+    #[arg(short = 'P', value_name = "MAX-PROCS", default_value_t = 1)]
+    max_procs: usize,
+
+    /// Do not run the command if the input is empty.
+    #[arg(short = 'r')]
+    no_run_if_empty: bool,
+
+    /// Command and its initial arguments (default: echo).
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    command: Vec<String>,
+}
+
+/// Делит ввод на элементы по пробельным символам с учетом кавычек и `\`.
+fn split_items(input: &str) -> Result<Vec<String>, String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut started = false;
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if started {
+                    items.push(std::mem::take(&mut current));
+                    started = false;
+                }
+            }
+            '\'' | '"' => {
+                started = true;
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some('\n') | None => {
+                            let kind = if c == '\'' { "single" } else { "double" };
+                            return Err(format!("unmatched {kind} quote"));
+                        }
+                        Some(other) => current.push(other),
+                    }
+                }
+            }
+            '\\' => {
+                started = true;
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            _ => {
+                started = true;
+                current.push(c);
+            }
+        }
+    }
+    if started {
+        items.push(current);
+    }
+    Ok(items)
+}
+
+/// Строит и выполняет команды из элементов stdin.
+///
+/// Флаги: `-n N` (не более N элементов на команду), `-0` (элементы разделены `\0`),
+/// `-I REPL` (по команде на строку ввода, `REPL` в аргументах заменяется строкой),
+/// `-P N` (до N команд параллельно), `-r` (не запускать при пустом вводе).
+/// Команды запускаются по обычным правилам шелла (builtin или внешняя программа);
+/// вывод печатается в порядке команд, даже при `-P`.
+///
+/// Коды возврата (как в GNU xargs):
+/// - 0: все команды завершились успешно
+/// - 123: хотя бы одна команда завершилась с ненулевым кодом
+/// - 126: команда найдена, но не запускается
+/// - 127: команда не найдена
+/// - 1: ошибка аргументов или разбора ввода
+pub(super) fn run_xargs(
+    args: &[String],
+    stdin: Option<&[u8]>,
+    ctx: BuiltinContext<'_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let argv = std::iter::once("xargs".to_string()).chain(args.iter().cloned());
+    let cli = match XargsCli::try_parse_from(argv) {
+        Ok(c) => c,
+        Err(e) => {
+            writeln!(io.stderr, "xargs: {e}").map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(1));
+        }
+    };
+    if cli.max_args == Some(0) {
+        writeln!(io.stderr, "xargs: value for -n must be at least 1").map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(1));
+    }
+
+    let input = String::from_utf8_lossy(stdin.unwrap_or_default()).to_string();
+    let command = if cli.command.is_empty() {
+        vec!["echo".to_string()]
+    } else {
+        cli.command.clone()
+    };
+
+    let invocations: Vec<Vec<String>> = if let Some(repl) = &cli.replace {
+        let lines: Vec<&str> = if cli.null {
+            input.split('\0').filter(|l| !l.is_empty()).collect()
+        } else {
+            input
+                .lines()
+                .map(str::trim_start)
+                .filter(|l| !l.is_empty())
+                .collect()
+        };
+        lines
+            .into_iter()
+            .map(|line| {
+                command
+                    .iter()
+                    .map(|a| a.replace(repl.as_str(), line))
+                    .collect()
+            })
+            .collect()
+    } else {
+        let items = if cli.null {
+            Ok(input
+                .split('\0')
+                .filter(|i| !i.is_empty())
+                .map(str::to_string)
+                .collect())
+        } else {
+            split_items(&input)
+        };
+        let items = match items {
+            Ok(i) => i,
+            Err(msg) => {
+                writeln!(io.stderr, "xargs: {msg}").map_err(ShellError::Io)?;
+                return Ok(ShellControl::Continue(1));
+            }
+        };
+        if items.is_empty() {
+            if cli.no_run_if_empty {
+                Vec::new()
+            } else {
+                vec![command.clone()]
+            }
+        } else {
+            let chunk = cli.max_args.unwrap_or(items.len());
+            items
+                .chunks(chunk)
+                .map(|c| command.iter().cloned().chain(c.iter().cloned()).collect())
+                .collect()
+        }
+    };
+
+    let results = run_invocations(&invocations, cli.max_procs, ctx);
+
+    let mut exit_code = 0;
+    for (argv, result) in invocations.iter().zip(results) {
+        match result {
+            Ok(r) => {
+                io.stdout.write_all(&r.stdout).map_err(ShellError::Io)?;
+                io.stderr.write_all(&r.stderr).map_err(ShellError::Io)?;
+                if r.exit_code != 0 && exit_code == 0 {
+                    exit_code = 123;
+                }
+            }
+            Err(ShellError::Spawn(e)) => {
+                writeln!(io.stderr, "xargs: {e}").map_err(ShellError::Io)?;
+                exit_code = e.exit_code();
+            }
+            Err(ShellError::Process(msg)) => {
+                writeln!(io.stderr, "xargs: {}: {msg}", argv[0]).map_err(ShellError::Io)?;
+                exit_code = 127;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(ShellControl::Continue(exit_code))
+}
+
+/// Выполняет команды, не более `max_procs` одновременно; результаты — в исходном порядке.
+fn run_invocations(
+    invocations: &[Vec<String>],
+    max_procs: usize,
+    ctx: BuiltinContext<'_>,
+) -> Vec<ShellResult<RunResult>> {
+    let workers = if max_procs == 0 {
+        invocations.len()
+    } else {
+        max_procs.min(invocations.len())
+    };
+    let run = |argv: &Vec<String>| ctx.runner.run_command(&argv[0], &argv[1..], None);
+
+    if workers <= 1 {
+        return invocations.iter().map(run).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let slots: Vec<Mutex<Option<ShellResult<RunResult>>>> =
+        invocations.iter().map(|_| Mutex::new(None)).collect();
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                loop {
+                    let idx = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(argv) = invocations.get(idx) else {
+                        break;
+                    };
+                    let result = run(argv);
+                    *slots[idx].lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+                }
+            });
+        }
+    });
+    slots
+        .into_iter()
+        .map(|slot| {
+            slot.into_inner()
+                .unwrap_or_else(|e| e.into_inner())
+                .unwrap_or_else(|| Err(ShellError::Process("xargs worker panicked".to_string())))
+        })
+        .collect()
+}
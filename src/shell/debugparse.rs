@@ -0,0 +1,454 @@
+//! Отладочный вывод разбора строки: `--dump-tokens`, `--dump-ast`, `set -o debugparse`
+//! и `set -o debug-expand`.
+//!
+//! Подстановки выполняются только при запуске команды, поэтому и токены, и
+//! список команд печатаются до них: слово токена — список частей с позициями,
+//! слово команды — его текст без кавычек (`$X` как записано). Результат
+//! подстановок `set -o debug-expand` печатает, когда до команды доходит
+//! выполнение. Формат `pretty` — дерево с отступами, `json` — один объект на строку:
+//!
+//! ```text
+//! tokens:
+//!   0..4 word "echo" = literal "echo"
+//!   5..7 word "$X" = var X
+//! ast:
+//!   entry always
+//!     pipeline
+//!       simple 0..7 ["echo", "$X"]
+//! expand: simple 0..7 ["echo", "a", "b"]
+//! ```
+
+use std::fmt::{self, Write};
+
+use super::options::DumpFormat;
+use super::parser::{SpannedToken, Token};
+use super::types::{
+    Command, CommandList, CondWord, Connector, GroupKind, HereInput, ListEntry, Span, Stage,
+};
+use super::wordexp::WordPart;
+
+/// Токены строки в формате `format` (с переводом строки в конце).
+pub(crate) fn dump_tokens(tokens: &[SpannedToken], format: DumpFormat) -> String {
+    match format {
+        DumpFormat::Pretty => {
+            let mut out = String::from("tokens:\n");
+            for tok in tokens {
+                let _ = write!(
+                    out,
+                    "  {} {} {:?}",
+                    span_text(tok.span),
+                    token_kind(&tok.token),
+                    tok.token.to_string()
+                );
+                if let Token::Word(word) = &tok.token {
+                    let parts: Vec<_> = word.parts.iter().map(part_text).collect();
+                    let _ = write!(out, " = {}", parts.join(" + "));
+                }
+                out.push('\n');
+            }
+            out
+        }
+        DumpFormat::Json => {
+            let tokens = tokens.iter().map(token_json).collect();
+            format!("{}\n", Json::object([("tokens", Json::List(tokens))]))
+        }
+    }
+}
+
+/// Разобранный список команд в формате `format` (с переводом строки в конце).
+pub(crate) fn dump_ast(list: &CommandList, format: DumpFormat) -> String {
+    match format {
+        DumpFormat::Pretty => {
+            let mut out = String::from("ast:\n");
+            write_list(&mut out, list, 1);
+            out
+        }
+        DumpFormat::Json => format!("{}\n", Json::object([("ast", list_json(list))])),
+    }
+}
+
+/// Присваивание `name=value` после подстановок (с переводом строки в конце).
+pub(crate) fn dump_expanded_assignment(name: &str, value: &str, format: DumpFormat) -> String {
+    match format {
+        DumpFormat::Pretty => format!("expand: assign {name}={value:?}\n"),
+        DumpFormat::Json => {
+            let assignment = Json::object([
+                ("kind", Json::string("assign")),
+                ("name", Json::string(name)),
+                ("value", Json::string(value)),
+            ]);
+            format!("{}\n", Json::object([("expand", assignment)]))
+        }
+    }
+}
+
+/// Стадия пайплайна после подстановок: слова, которые получит команда, пути
+/// перенаправлений и here-input (с переводом строки в конце).
+pub(crate) fn dump_stage(stage: &Stage, format: DumpFormat) -> String {
+    let (kind, span, words, here_input, redirects) = match stage {
+        Stage::Simple(spec) => {
+            let words = std::iter::once(&spec.name).chain(&spec.args).cloned();
+            let words = if spec.name.is_empty() && spec.args.is_empty() {
+                Vec::new()
+            } else {
+                words.collect()
+            };
+            let here_input = spec.here_input.as_deref();
+            (
+                "simple",
+                Some(spec.span),
+                Some(words),
+                here_input,
+                &spec.redirects,
+            )
+        }
+        Stage::Group(group) => {
+            let here_input = group.here_input.as_deref();
+            (
+                group_name(group.kind),
+                None,
+                None,
+                here_input,
+                &group.redirects,
+            )
+        }
+        Stage::Conditional(conditional) => {
+            let words = conditional.words.iter().map(CondWord::text).collect();
+            (
+                "conditional",
+                None,
+                Some(words),
+                None,
+                &conditional.redirects,
+            )
+        }
+    };
+    match format {
+        DumpFormat::Pretty => {
+            let mut out = format!("expand: {kind}");
+            if let Some(span) = span {
+                let _ = write!(out, " {}", span_text(span));
+            }
+            if let Some(words) = words {
+                let _ = write!(out, " {words:?}");
+            }
+            out.push('\n');
+            for redirect in redirects {
+                let _ = writeln!(out, "  redirect {:?}", redirect.to_string());
+            }
+            if let Some(input) = here_input {
+                let _ = writeln!(out, "  here-input {input:?}");
+            }
+            out
+        }
+        DumpFormat::Json => {
+            let mut fields = vec![("kind", Json::string(kind))];
+            if let Some(span) = span {
+                fields.push(("span", span_json(span)));
+            }
+            if let Some(words) = words {
+                fields.push((
+                    "words",
+                    Json::List(words.iter().map(Json::string).collect()),
+                ));
+            }
+            let redirects = redirects.iter().map(Json::string).collect();
+            fields.push(("redirects", Json::List(redirects)));
+            fields.push(("here_input", here_input.map_or(Json::Null, Json::string)));
+            format!("{}\n", Json::object([("expand", Json::Object(fields))]))
+        }
+    }
+}
+
+fn token_kind(token: &Token) -> &'static str {
+    match token {
+        Token::Word(_) => "word",
+        Token::Pipe => "pipe",
+        Token::Operator(_) => "operator",
+        Token::RedirectWord { .. } | Token::Redirect(_) => "redirect",
+        Token::ProcessSubst { .. } => "process-subst",
+    }
+}
+
+fn connector_name(connector: Connector) -> &'static str {
+    match connector {
+        Connector::Always => "always",
+        Connector::IfSuccess => "if-success",
+        Connector::IfFailure => "if-failure",
+    }
+}
+
+fn group_name(kind: GroupKind) -> &'static str {
+    match kind {
+        GroupKind::Subshell => "subshell",
+        GroupKind::Brace => "brace",
+    }
+}
+
+/// Текст here-input до подстановок (у here-string — с переводом строки).
+fn here_input_text(input: &HereInput) -> String {
+    match input {
+        HereInput::Literal(body) => body.clone(),
+        HereInput::Heredoc(body) => body.text.clone(),
+        HereInput::HereString(word) => format!("{}\n", word.text),
+    }
+}
+
+/// Присваивания элемента: имя и значение до подстановок.
+fn assignments(entry: &ListEntry) -> impl Iterator<Item = (&str, &str)> {
+    entry
+        .assignments
+        .iter()
+        .map(|(name, word)| (name.as_str(), &word.text[name.len() + 1..]))
+}
+
+fn span_text(span: Span) -> String {
+    format!("{}..{}", span.start, span.end)
+}
+
+/// Часть слова: `literal "a"`, `var X`, `arith "1+1"`; в кавычках — с пометкой `(quoted)`.
+fn part_text(part: &WordPart) -> String {
+    let text = match part {
+        WordPart::Literal { text, .. } => format!("literal {text:?}"),
+        WordPart::Var { name, .. } => format!("var {name}"),
+        WordPart::Arith { expr, .. } => format!("arith {expr:?}"),
+    };
+    if part.quoted() {
+        format!("{text} (quoted)")
+    } else {
+        text
+    }
+}
+
+fn write_list(out: &mut String, list: &CommandList, depth: usize) {
+    let indent = "  ".repeat(depth);
+    for entry in &list.entries {
+        let _ = writeln!(out, "{indent}entry {}", connector_name(entry.connector));
+        for (name, value) in assignments(entry) {
+            let _ = writeln!(out, "{indent}  assign {name}={value:?}");
+        }
+        if let Some(pipeline) = &entry.pipeline {
+            let _ = writeln!(out, "{indent}  pipeline");
+            for command in &pipeline.commands {
+                write_command(out, command, depth + 2);
+            }
+        }
+    }
+}
+
+fn write_command(out: &mut String, command: &Command, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let (here_input, redirects) = match command {
+        Command::Simple(spec) => {
+            let words: Vec<_> = spec.words.iter().map(|word| &word.text).collect();
+            let _ = writeln!(out, "{indent}simple {} {words:?}", span_text(spec.span));
+            for subst in &spec.process_substs {
+                let op = if subst.output { ">(...)" } else { "<(...)" };
+                let _ = writeln!(out, "{indent}  process-subst word {} {op}", subst.word);
+                write_list(out, &subst.list, depth + 2);
+            }
+            (&spec.here_input, &spec.redirects)
+        }
+        Command::Group(group) => {
+            let _ = writeln!(
+                out,
+                "{indent}group {} {}",
+                span_text(group.span),
+                group_name(group.kind)
+            );
+            write_list(out, &group.body, depth + 1);
+            (&group.here_input, &group.redirects)
+        }
+        Command::Conditional(conditional) => {
+            let words: Vec<_> = conditional.words.iter().map(|word| &word.text).collect();
+            let _ = writeln!(
+                out,
+                "{indent}conditional {} {words:?}",
+                span_text(conditional.span)
+            );
+            (&None, &conditional.redirects)
+        }
+    };
+    for redirect in redirects {
+        let _ = writeln!(out, "{indent}  redirect {:?}", redirect.to_string());
+    }
+    if let Some(input) = here_input {
+        let _ = writeln!(out, "{indent}  here-input {:?}", here_input_text(input));
+    }
+}
+
+fn span_json(span: Span) -> Json {
+    Json::List(vec![Json::Number(span.start), Json::Number(span.end)])
+}
+
+fn token_json(tok: &SpannedToken) -> Json {
+    let mut fields = vec![
+        ("kind", Json::string(token_kind(&tok.token))),
+        ("text", Json::string(tok.token.to_string())),
+        ("span", span_json(tok.span)),
+    ];
+    if let Token::Word(word) = &tok.token {
+        let parts = word.parts.iter().map(part_json).collect();
+        fields.push(("parts", Json::List(parts)));
+    }
+    Json::Object(fields)
+}
+
+fn part_json(part: &WordPart) -> Json {
+    let (kind, key, value) = match part {
+        WordPart::Literal { text, .. } => ("literal", "text", text),
+        WordPart::Var { name, .. } => ("var", "name", name),
+        WordPart::Arith { expr, .. } => ("arith", "expr", expr),
+    };
+    Json::object([
+        ("kind", Json::string(kind)),
+        (key, Json::string(value)),
+        ("quoted", Json::Bool(part.quoted())),
+        ("span", span_json(part.span())),
+    ])
+}
+
+fn list_json(list: &CommandList) -> Json {
+    let entries = list
+        .entries
+        .iter()
+        .map(|entry| {
+            let assignments = assignments(entry)
+                .map(|(name, value)| {
+                    Json::object([("name", Json::string(name)), ("value", Json::string(value))])
+                })
+                .collect();
+            let pipeline = entry.pipeline.as_ref().map_or(Json::Null, |pipeline| {
+                let commands = pipeline.commands.iter().map(command_json).collect();
+                Json::object([("commands", Json::List(commands))])
+            });
+            Json::object([
+                ("connector", Json::string(connector_name(entry.connector))),
+                ("assignments", Json::List(assignments)),
+                ("pipeline", pipeline),
+            ])
+        })
+        .collect();
+    Json::object([("entries", Json::List(entries))])
+}
+
+fn command_json(command: &Command) -> Json {
+    let (mut fields, here_input, redirects) = match command {
+        Command::Simple(spec) => {
+            let words = spec.words.iter().map(Json::string).collect();
+            let substs = spec
+                .process_substs
+                .iter()
+                .map(|subst| {
+                    Json::object([
+                        ("word", Json::Number(subst.word)),
+                        ("output", Json::Bool(subst.output)),
+                        ("list", list_json(&subst.list)),
+                    ])
+                })
+                .collect();
+            let fields = vec![
+                ("kind", Json::string("simple")),
+                ("span", span_json(spec.span)),
+                ("words", Json::List(words)),
+                ("process_substs", Json::List(substs)),
+            ];
+            (fields, &spec.here_input, &spec.redirects)
+        }
+        Command::Group(group) => {
+            let fields = vec![
+                ("kind", Json::string(group_name(group.kind))),
+                ("span", span_json(group.span)),
+                ("body", list_json(&group.body)),
+            ];
+            (fields, &group.here_input, &group.redirects)
+        }
+        Command::Conditional(conditional) => {
+            let words = conditional.words.iter().map(Json::string).collect();
+            let fields = vec![
+                ("kind", Json::string("conditional")),
+                ("span", span_json(conditional.span)),
+                ("words", Json::List(words)),
+            ];
+            (fields, &None, &conditional.redirects)
+        }
+    };
+    let redirects = redirects.iter().map(Json::string).collect();
+    fields.push(("redirects", Json::List(redirects)));
+    fields.push((
+        "here_input",
+        here_input
+            .as_ref()
+            .map_or(Json::Null, |input| Json::string(here_input_text(input))),
+    ));
+    Json::Object(fields)
+}
+
+/// Значение JSON для отладочного вывода (ключи объекта — в порядке добавления).
+enum Json {
+    Null,
+    Bool(bool),
+    Number(usize),
+    String(String),
+    List(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn string(value: impl ToString) -> Json {
+        Json::String(value.to_string())
+    }
+
+    fn object<const N: usize>(fields: [(&'static str, Json); N]) -> Json {
+        Json::Object(fields.into())
+    }
+}
+
+/// Строка JSON в кавычках с экранированием.
+fn write_json_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if u32::from(c) < 0x20 => write!(f, "\\u{:04x}", u32::from(c))?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            Json::Number(n) => write!(f, "{n}"),
+            Json::String(s) => write_json_string(f, s),
+            Json::List(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(fields) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_json_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
@@ -0,0 +1,408 @@
+//! Встраиваемый API: интерпретатор как библиотека.
+//!
+//! `Shell` хранит состояние между вызовами `eval` (переменные, рабочую
+//! директорию, алиасы, опции `set`), как одна сессия REPL. Скрипт выполняется
+//! построчно по тем же правилам, что ввод REPL, но без истории и подстановок `!`;
+//! stdin команд пуст, стартовые файлы не читаются.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::executor::{ProcessExecutor, SharedExecutor, StdProcessExecutor};
+use super::types::{IoStreams, ShellControl, ShellError, ShellResult};
+use super::vfs::{FileSystem, OsFs};
+use super::{ListInput, ShellState, run_lines};
+
+/// Builtin приложения: получает вызов, возвращает код возврата.
+pub(crate) type BuiltinFn = Arc<dyn Fn(&mut BuiltinCall<'_>) -> io::Result<i32> + Send + Sync>;
+
+/// Builtins приложения по именам; общие для всех копий состояния шелла.
+pub(crate) type CustomBuiltins = Arc<BTreeMap<String, BuiltinFn>>;
+
+/// Вызов builtin, заданного приложением через `ShellBuilder::builtin`.
+#[non_exhaustive]
+pub struct BuiltinCall<'a> {
+    /// Аргументы команды (без имени).
+    pub args: &'a [String],
+    /// Весь stdin команды (пайп, перенаправление, here-document); иначе пусто.
+    pub stdin: &'a [u8],
+    /// Переменные шелла.
+    pub env: &'a HashMap<String, String>,
+    /// Рабочая директория шелла.
+    pub cwd: &'a Path,
+    /// Файловая система шелла (`ShellBuilder::filesystem`).
+    pub fs: &'a dyn FileSystem,
+    /// stdout команды: следующая стадия пайплайна, перенаправление или вывод `eval`.
+    pub stdout: &'a mut dyn Write,
+    /// stderr команды (с учетом `2>`).
+    pub stderr: &'a mut dyn Write,
+}
+
+/// Выполняет builtin приложения; ошибка записи — ошибка строки (код 1).
+pub(crate) fn run_custom_builtin(
+    builtin: &BuiltinFn,
+    args: &[String],
+    stdin: &[u8],
+    state: &ShellState,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let mut call = BuiltinCall {
+        args,
+        stdin,
+        env: &state.env,
+        cwd: &state.cwd,
+        fs: state.fs.as_ref(),
+        stdout: &mut *io.stdout,
+        stderr: &mut *io.stderr,
+    };
+    let code = builtin(&mut call).map_err(ShellError::Io)?;
+    Ok(ShellControl::Continue(code))
+}
+
+/// Код завершения скрипта.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Status {
+    /// Код последней команды (или аргумент `exit`).
+    pub code: i32,
+    /// Скрипт завершился командой `exit` (или ошибкой под `set -e`).
+    pub exited: bool,
+}
+
+impl Status {
+    /// Код равен 0.
+    pub fn success(&self) -> bool {
+        self.code == 0
+    }
+}
+
+/// Результат `Shell::eval`: код, захваченный вывод и измененные переменные.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Output {
+    /// Код последней команды и признак `exit`.
+    pub status: Status,
+    /// Все, что скрипт вывел в stdout.
+    pub stdout: Vec<u8>,
+    /// Все, что скрипт вывел в stderr, включая ошибки строк.
+    pub stderr: Vec<u8>,
+    /// Переменные, которые скрипт задал или изменил, с новыми значениями.
+    pub vars: BTreeMap<String, String>,
+}
+
+impl Output {
+    /// stdout как текст (невалидный UTF-8 заменяется на `U+FFFD`).
+    pub fn stdout_text(&self) -> String {
+        String::from_utf8_lossy(&self.stdout).into_owned()
+    }
+
+    /// stderr как текст (невалидный UTF-8 заменяется на `U+FFFD`).
+    pub fn stderr_text(&self) -> String {
+        String::from_utf8_lossy(&self.stderr).into_owned()
+    }
+}
+
+/// Интерпретатор для выполнения скриптов из программы.
+///
+/// ```
+/// use se_cli::Shell;
+///
+/// let mut shell = Shell::builder().env("NAME", "world").build();
+/// let out = shell.eval("GREETING=\"hello $NAME\"\necho $GREETING | tr a-z A-Z");
+/// assert!(out.status.success());
+/// assert_eq!(out.stdout_text(), "HELLO WORLD\n");
+/// assert_eq!(out.vars["GREETING"], "hello world");
+/// assert_eq!(shell.var("GREETING"), Some("hello world"));
+/// ```
+pub struct Shell {
+    state: ShellState,
+    executor: SharedExecutor,
+}
+
+impl Default for Shell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shell {
+    /// Шелл с окружением и рабочей директорией текущего процесса.
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    /// Настройка окружения, рабочей директории и builtins шелла.
+    pub fn builder() -> ShellBuilder {
+        ShellBuilder {
+            env: std::env::vars().collect(),
+            cwd: None,
+            builtins: BTreeMap::new(),
+            fs: Arc::new(OsFs),
+            timeout: None,
+            executor: Arc::new(StdProcessExecutor::new()),
+        }
+    }
+
+    /// Выполняет скрипт `script` (строки через `\n`) с захватом вывода.
+    ///
+    /// Ошибки строк (синтаксис, неизвестная команда) попадают в `stderr` и код,
+    /// как в REPL: выполнение продолжается со следующей строки.
+    ///
+    /// ```
+    /// let mut shell = se_cli::Shell::new();
+    /// let out = shell.eval("echo a; nope_command_xyz");
+    /// assert_eq!(out.stdout_text(), "a\n");
+    /// assert_eq!(out.status.code, 127);
+    /// assert!(out.stderr_text().starts_with("nope_command_xyz: command not found"));
+    /// ```
+    pub fn eval(&mut self, script: &str) -> Output {
+        let before = self.state.env.clone();
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let status = match self.eval_to(script, &mut stdout, &mut stderr) {
+            Ok(status) => status,
+            // Чтение строк из `&str` и запись в `Vec` не падают.
+            Err(e) => {
+                let _ = writeln!(stderr, "{}", ShellError::Io(e));
+                Status {
+                    code: 1,
+                    exited: false,
+                }
+            }
+        };
+
+        let vars = self
+            .state
+            .env
+            .iter()
+            .filter(|&(name, value)| before.get(name) != Some(value))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        Output {
+            status,
+            stdout,
+            stderr,
+            vars,
+        }
+    }
+
+    /// Выполняет скрипт `script`, записывая вывод команд в `stdout` и `stderr` по
+    /// мере выполнения.
+    ///
+    /// Ошибка записи в поток прерывает скрипт и возвращается как `Err`.
+    ///
+    /// ```
+    /// let mut shell = se_cli::Shell::new();
+    /// let mut out = Vec::new();
+    /// let status = shell.eval_to("echo one\nexit 3\necho two", &mut out, std::io::sink())?;
+    /// assert_eq!((status.code, status.exited), (3, true));
+    /// assert_eq!(out, b"one\n");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn eval_to<O: Write, E: Write>(
+        &mut self,
+        script: &str,
+        mut stdout: O,
+        mut stderr: E,
+    ) -> io::Result<Status> {
+        let mut io = IoStreams {
+            stdout: &mut stdout,
+            stderr: &mut stderr,
+        };
+        let lines = script.lines().map(|line| Ok(line.to_string()));
+        let control = match run_lines(
+            &self.executor,
+            &mut self.state,
+            lines,
+            Some("eval"),
+            &mut ListInput::Consumed,
+            &mut io,
+        ) {
+            Ok(control) => control,
+            Err(ShellError::Io(e)) => return Err(e),
+            Err(e) => {
+                writeln!(io.stderr, "{e}")?;
+                ShellControl::Continue(1)
+            }
+        };
+        io.stdout.flush()?;
+        io.stderr.flush()?;
+        Ok(match control {
+            ShellControl::Continue(code) => Status {
+                code,
+                exited: false,
+            },
+            ShellControl::Exit(code) => Status { code, exited: true },
+        })
+    }
+
+    /// Значение переменной шелла.
+    pub fn var(&self, name: &str) -> Option<&str> {
+        self.state.env.get(name).map(String::as_str)
+    }
+
+    /// Задает переменную шелла (она видна и внешним программам).
+    pub fn set_var(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.state.env.insert(name.into(), value.into());
+    }
+
+    /// Удаляет переменную шелла; возвращает ее значение.
+    pub fn remove_var(&mut self, name: &str) -> Option<String> {
+        self.state.env.remove(name)
+    }
+
+    /// Все переменные шелла (в произвольном порядке).
+    pub fn vars(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.state
+            .env
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Рабочая директория шелла (меняется командой `cd`).
+    pub fn cwd(&self) -> &Path {
+        &self.state.cwd
+    }
+}
+
+/// Параметры нового `Shell`; создается через `Shell::builder`.
+///
+/// По умолчанию — окружение и рабочая директория текущего процесса. Методы
+/// окружения повторяют `std::process::Command`.
+///
+/// ```
+/// use std::io::{Read, Write};
+///
+/// let mut shell = se_cli::Shell::builder()
+///     .env_clear()
+///     .env("LANG", "C")
+///     .current_dir(std::env::temp_dir())
+///     .builtin("shout", |call| {
+///         let mut input = String::new();
+///         call.stdin.read_to_string(&mut input)?;
+///         write!(call.stdout, "{}", input.to_uppercase())?;
+///         Ok(0)
+///     })
+///     .build();
+/// let out = shell.eval("echo quiet | shout");
+/// assert_eq!(out.stdout_text(), "QUIET\n");
+/// assert_eq!(shell.var("LANG"), Some("C"));
+/// assert_eq!(shell.var("HOME"), None);
+/// ```
+pub struct ShellBuilder {
+    env: HashMap<String, String>,
+    cwd: Option<PathBuf>,
+    builtins: BTreeMap<String, BuiltinFn>,
+    fs: Arc<dyn FileSystem>,
+    timeout: Option<Duration>,
+    executor: SharedExecutor,
+}
+
+impl ShellBuilder {
+    /// Задает переменную окружения.
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Задает несколько переменных окружения.
+    pub fn envs<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.env
+            .extend(vars.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Удаляет переменную окружения.
+    pub fn env_remove(mut self, key: &str) -> Self {
+        self.env.remove(key);
+        self
+    }
+
+    /// Удаляет все переменные (в том числе `PATH`: внешние программы
+    /// запускаются только по пути с `/`).
+    pub fn env_clear(mut self) -> Self {
+        self.env.clear();
+        self
+    }
+
+    /// Рабочая директория шелла; относительный путь — от начальной директории
+    /// файловой системы (для `OsFs` — директории процесса).
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(dir.into());
+        self
+    }
+
+    /// Добавляет builtin `name`.
+    ///
+    /// Он заменяет одноименную builtin-утилиту (`echo`, `grep`, ...) и программу
+    /// из `PATH`, но не команды, меняющие состояние шелла (`cd`, `set`, `alias`,
+    /// `source`, `type`, ...). Работает в пайплайнах, группах, `xargs`; виден
+    /// `type` и `which`.
+    pub fn builtin<F>(mut self, name: impl Into<String>, builtin: F) -> Self
+    where
+        F: Fn(&mut BuiltinCall<'_>) -> io::Result<i32> + Send + Sync + 'static,
+    {
+        self.builtins.insert(name.into(), Arc::new(builtin));
+        self
+    }
+
+    /// Файловая система для builtins, перенаправлений, `cd` и `source`
+    /// (по умолчанию `vfs::OsFs`); внешние программы работают с файловой системой ОС.
+    pub fn filesystem(mut self, fs: impl FileSystem + 'static) -> Self {
+        self.fs = Arc::new(fs);
+        self
+    }
+
+    /// Исполнитель внешних программ (по умолчанию `executor::StdProcessExecutor`):
+    /// например, `mock::MockExecutor`, чтобы скрипт не запускал процессов.
+    /// `Arc` можно оставить у себя — так мок отдает записанные запуски.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use se_cli::mock::{MockExecutor, MockResponse};
+    ///
+    /// let mock = Arc::new(MockExecutor::new());
+    /// mock.respond("git", MockResponse::stdout("main\n"));
+    /// let mut shell = se_cli::Shell::builder().executor(mock.clone()).build();
+    ///
+    /// let out = shell.eval("git branch --show-current | tr a-z A-Z");
+    /// assert_eq!(out.stdout_text(), "MAIN\n");
+    /// assert_eq!(mock.calls()[0].argv, ["git", "branch", "--show-current"]);
+    /// ```
+    pub fn executor(mut self, executor: Arc<dyn ProcessExecutor>) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// Срок для внешних программ каждого пайплайна (как `se-cli --timeout`):
+    /// не завершившиеся за `timeout` программы убиваются, код пайплайна — 124.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Создает шелл.
+    pub fn build(self) -> Shell {
+        let start = self.fs.current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let cwd = match self.cwd {
+            Some(dir) => start.join(dir),
+            None => start,
+        };
+        let mut state = ShellState::new(self.env, cwd);
+        state.builtins = Arc::new(self.builtins);
+        state.fs = self.fs;
+        state.options.timeout = self.timeout;
+        Shell {
+            state,
+            executor: self.executor,
+        }
+    }
+}
@@ -1,61 +1,657 @@
-//! Запуск внешних команд.
+//! Запуск внешних команд и поиск программ в `PATH`.
+//!
+//! Публичная часть — трейт `ProcessExecutor` и типы его аргументов: через него
+//! приложение подменяет запуск программ (`ShellBuilder::executor`, `run_with_executor`).
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use se_cli::executor::{
+//!     ProcessCommand, ProcessExecutor, ProcessInput, ProcessOutput, RunResult, StdProcessExecutor,
+//! };
+//!
+//! /// Печатает каждый запуск и передает его настоящему исполнителю.
+//! struct Logged(StdProcessExecutor);
+//!
+//! impl ProcessExecutor for Logged {
+//!     fn run(
+//!         &self,
+//!         command: ProcessCommand<'_>,
+//!         stdin: ProcessInput,
+//!         stdout: ProcessOutput,
+//!     ) -> std::io::Result<RunResult> {
+//!         eprintln!("exec {} {:?}", command.program.display(), command.args);
+//!         self.0.run(command, stdin, stdout)
+//!     }
+//! }
+//!
+//! std::process::exit(se_cli::run_with_executor(Arc::new(Logged(StdProcessExecutor::new()))));
+//! ```
 
-use std::collections::HashMap;
-use std::io::Write;
-use std::process::{Command, Stdio};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use super::types::{RunResult, ShellError, ShellResult};
+use super::builtins::io_error_message;
+use super::types::{ShellError, ShellResult, SpawnError, SpawnErrorKind};
+
+pub use super::limits::{ProcessLimits, Resource, TIMEOUT_STATUS};
+pub use super::types::RunResult;
+
+/// Исполнитель, общий для потоков пайплайна и подстановок процессов.
+pub(crate) type SharedExecutor = Arc<dyn ProcessExecutor>;
+
+/// Внешняя программа для запуска.
+pub struct ProcessCommand<'a> {
+    /// Путь к программе (найденный в `PATH` через `ProcessExecutor::find_programs`).
+    pub program: &'a Path,
+    /// Аргументы без имени программы.
+    pub args: &'a [String],
+    /// Полное окружение процесса (окружение шелла, а не процесса шелла).
+    pub env: &'a HashMap<String, String>,
+    /// Рабочая директория процесса.
+    pub cwd: &'a Path,
+    /// Дескрипторы, которые процесс наследует (unix; пути `/dev/fd/N`
+    /// подстановок процессов).
+    pub inherit_fds: &'a [i32],
+    /// Лимиты ресурсов (`ulimit`) и срок выполнения (`timeout`).
+    pub limits: &'a ProcessLimits,
+}
+
+/// stdin внешней программы.
+#[non_exhaustive]
+pub enum ProcessInput {
+    /// stdin шелла.
+    Inherit,
+    /// Пустой ввод.
+    Null,
+    /// Готовые данные (пайп, перенаправление, here-document).
+    Bytes(Vec<u8>),
+    /// Выход предыдущей стадии пайплайна.
+    Pipe(PipeReader),
+}
+
+/// stdout внешней программы.
+#[non_exhaustive]
+pub enum ProcessOutput {
+    /// Захватить в `RunResult::stdout`.
+    Capture,
+    /// Писать во вход следующей стадии пайплайна (`RunResult::stdout` пуст).
+    Pipe(PipeWriter),
+}
+
+/// Конец pipe'а для чтения (`ProcessInput::Pipe`); передается процессу как
+/// `Stdio` или читается как `Read`.
+#[derive(Debug)]
+pub struct PipeReader(pub(crate) os_pipe::PipeReader);
+
+/// Конец pipe'а для записи (`ProcessOutput::Pipe`); передается процессу как
+/// `Stdio` или пишется как `Write`. Читающая сторона видит EOF, когда закрыты
+/// все копии.
+#[derive(Debug)]
+pub struct PipeWriter(pub(crate) os_pipe::PipeWriter);
+
+/// Создает pipe: например, чтобы вызвать `ProcessExecutor::run` с
+/// `ProcessInput::Pipe` из своего кода.
+pub fn pipe() -> std::io::Result<(PipeReader, PipeWriter)> {
+    let (reader, writer) = os_pipe::pipe()?;
+    Ok((PipeReader(reader), PipeWriter(writer)))
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for PipeWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl From<PipeReader> for Stdio {
+    fn from(reader: PipeReader) -> Stdio {
+        Stdio::from(reader.0)
+    }
+}
+
+impl From<PipeWriter> for Stdio {
+    fn from(writer: PipeWriter) -> Stdio {
+        Stdio::from(writer.0)
+    }
+}
+
+/// Запуск внешних программ.
+///
+/// Через этот трейт шелл запускает все внешние команды: одиночные, стадии
+/// пайплайна и вложенные (`xargs`, `find -exec`). Реализации: `StdProcessExecutor`
+/// (настоящие процессы) и `mock::MockExecutor` (ответы из памяти, для тестов и
+/// встраивания).
+pub trait ProcessExecutor: Send + Sync {
+    /// Все программы `name` (имя без `/`) в каталогах `PATH` по порядку.
+    fn find_programs(&self, name: &str, env: &HashMap<String, String>, cwd: &Path) -> Vec<PathBuf> {
+        search_path(name, env, cwd).collect()
+    }
+
+    /// Можно ли запустить программу `path` (проверка записей таблицы `hash`).
+    fn is_program(&self, path: &Path) -> bool {
+        is_executable(path)
+    }
+
+    /// Запускает программу и ждет ее завершения. stderr программы всегда
+    /// захватывается в `RunResult::stderr`.
+    ///
+    /// Программа, не завершившаяся к `command.limits.deadline`, убивается
+    /// (вместе с запущенными ею процессами); код — `TIMEOUT_STATUS`.
+    ///
+    /// Ошибка — программу не удалось запустить: шелл печатает
+    /// `prog: command not found` (статус 127) для `NotFound` и
+    /// `prog: <ошибка>` (статус 126) для остальных.
+    fn run(
+        &self,
+        command: ProcessCommand<'_>,
+        stdin: ProcessInput,
+        stdout: ProcessOutput,
+    ) -> std::io::Result<RunResult>;
+}
 
 /// Исполнитель внешних процессов через `std::process::Command`.
-pub(crate) struct StdProcessExecutor;
+#[derive(Debug, Default)]
+pub struct StdProcessExecutor;
 
 impl StdProcessExecutor {
     /// Создает новый исполнитель внешних команд.
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         Self
     }
+}
+
+/// Запускает `command` через `executor`; ошибка запуска — `ShellError::Spawn`.
+pub(crate) fn run_program(
+    executor: &dyn ProcessExecutor,
+    command: ProcessCommand<'_>,
+    stdin: ProcessInput,
+    stdout: ProcessOutput,
+) -> ShellResult<RunResult> {
+    let program = command.program;
+    executor
+        .run(command, stdin, stdout)
+        .map_err(|e| spawn_error(program, &e))
+}
 
-    /// Запускает внешнюю команду и возвращает ее stdout/stderr и код возврата.
-    pub(crate) fn run_external(
+/// Запускает `command` через `executor`, передавая ее stdout в `stdout` по мере
+/// вывода, а не после завершения: вывод не копится в памяти, а если `stdout`
+/// закрыт (следующая стадия пайплайна дочитала), программа получает SIGPIPE.
+/// Ошибка записи в `stdout` возвращается после завершения программы.
+pub(crate) fn run_program_streaming(
+    executor: &dyn ProcessExecutor,
+    command: ProcessCommand<'_>,
+    stdin: ProcessInput,
+    stdout: &mut dyn Write,
+) -> ShellResult<RunResult> {
+    let (mut reader, writer) = pipe().map_err(ShellError::Io)?;
+    std::thread::scope(|scope| {
+        let running =
+            scope.spawn(move || run_program(executor, command, stdin, ProcessOutput::Pipe(writer)));
+        let copied = std::io::copy(&mut reader, stdout);
+        drop(reader);
+        let result = running
+            .join()
+            .map_err(|_| ShellError::Process("program runner panicked".to_string()))?;
+        copied.map_err(ShellError::Io)?;
+        result
+    })
+}
+
+impl ProcessExecutor for StdProcessExecutor {
+    fn run(
         &self,
-        program: &str,
-        args: &[String],
-        env: &HashMap<String, String>,
-        stdin: Option<&[u8]>,
-    ) -> ShellResult<RunResult> {
+        command: ProcessCommand<'_>,
+        stdin: ProcessInput,
+        stdout: ProcessOutput,
+    ) -> std::io::Result<RunResult> {
         // Очищаем env и передаем ровно то окружение, которое хранит ShellState.
         // Так тесты и поведение шелла остаются детерминированными.
-        let mut cmd = Command::new(program);
-        cmd.args(args);
+        let mut cmd = Command::new(command.program);
+        cmd.args(command.args);
         cmd.env_clear();
-        cmd.envs(env);
-        if stdin.is_some() {
-            cmd.stdin(Stdio::piped());
-        } else {
-            cmd.stdin(Stdio::inherit());
-        }
-        cmd.stdout(Stdio::piped());
+        cmd.envs(command.env);
+        cmd.current_dir(command.cwd);
+        keep_fds_open(&mut cmd, command.inherit_fds);
+        apply_limits(&mut cmd, command.limits);
+        let mut input = None;
+        cmd.stdin(match stdin {
+            ProcessInput::Inherit => Stdio::inherit(),
+            ProcessInput::Null => Stdio::null(),
+            ProcessInput::Bytes(data) => {
+                input = Some(data);
+                Stdio::piped()
+            }
+            ProcessInput::Pipe(reader) => Stdio::from(reader),
+        });
+        cmd.stdout(match stdout {
+            ProcessOutput::Capture => Stdio::piped(),
+            ProcessOutput::Pipe(writer) => Stdio::from(writer),
+        });
         cmd.stderr(Stdio::piped());
 
-        let mut child = cmd.spawn().map_err(|e| {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                ShellError::Process(format!("command not found: {program}"))
-            } else {
-                ShellError::Process(format!("failed to spawn {program}: {e}"))
-            }
-        })?;
+        let spawned = cmd.spawn();
+        // Концы pipe'ов, переданные процессу, закрываем в шелле сразу: иначе
+        // следующая стадия не увидит EOF.
+        drop(cmd);
+        let mut child = spawned?;
 
-        if let (Some(input), Some(mut child_stdin)) = (stdin, child.stdin.take()) {
-            child_stdin.write_all(input).map_err(ShellError::Io)?;
-        }
+        // Данные для stdin пишем из отдельного потока: процесс может не читать
+        // их целиком или писать вывод, не дочитав ввод.
+        let stdin_writer = match (input, child.stdin.take()) {
+            (Some(data), Some(mut child_stdin)) => Some(std::thread::spawn(move || {
+                let _ = child_stdin.write_all(&data);
+            })),
+            _ => None,
+        };
 
-        let output = child.wait_with_output().map_err(ShellError::Io)?;
+        let (output, timed_out) = match command.limits.deadline {
+            Some(deadline) => wait_with_deadline(child, deadline),
+            None => child.wait_with_output().map(|output| (output, false)),
+        }?;
+        if let Some(handle) = stdin_writer {
+            let _ = handle.join();
+        }
 
-        let exit_code = output.status.code().unwrap_or(1);
         Ok(RunResult {
-            exit_code,
+            exit_code: if timed_out {
+                TIMEOUT_STATUS
+            } else {
+                exit_code(output.status)
+            },
             stdout: output.stdout,
             stderr: output.stderr,
         })
     }
 }
+
+/// Ждет процесс не дольше `deadline`, читая его вывод; `true` — процесс убит по сроку.
+///
+/// С ограничением срока процесс запускается в своей группе процессов (см.
+/// `apply_limits`), и `SIGKILL` получает вся группа: программы, запущенные
+/// убитой, не держат pipe'ы открытыми.
+fn wait_with_deadline(mut child: Child, deadline: Instant) -> std::io::Result<(Output, bool)> {
+    fn read_all(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<Vec<u8>> {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    }
+    let stdout = read_all(child.stdout.take());
+    let stderr = read_all(child.stderr.take());
+
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            kill_group(&mut child);
+            timed_out = true;
+            break child.wait()?;
+        }
+        std::thread::sleep((deadline - now).min(Duration::from_millis(10)));
+    };
+    let output = Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    };
+    Ok((output, timed_out))
+}
+
+#[cfg(unix)]
+fn kill_group(child: &mut Child) {
+    // SAFETY: `kill` с отрицательным pid посылает сигнал группе процесса, который
+    // еще не дождались (pid не переиспользован).
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// Код возврата завершившегося процесса; смерть от сигнала `N` — `128 + N`.
+pub(crate) fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
+
+/// Ошибка запуска программы по пути `program`.
+pub(crate) fn spawn_error(program: &Path, e: &std::io::Error) -> ShellError {
+    let kind = if e.kind() == std::io::ErrorKind::NotFound {
+        SpawnErrorKind::NotFound
+    } else if program.is_dir() {
+        SpawnErrorKind::NotExecutable("Is a directory".to_string())
+    } else {
+        SpawnErrorKind::NotExecutable(io_error_message(e))
+    };
+    ShellError::Spawn(SpawnError::new(program.display().to_string(), kind))
+}
+
+/// Ошибка для команды, которой нет ни среди builtins, ни в `PATH`. Если в `PATH`
+/// есть файл без права на выполнение — `Permission denied` (статус 126), как в bash.
+pub(crate) fn not_found_error(name: &str, env: &HashMap<String, String>, cwd: &Path) -> ShellError {
+    let denied = path_var(env)
+        .split(':')
+        .map(|dir| cwd.join(dir).join(name))
+        .find(|path| path.is_file());
+    let kind = match denied {
+        Some(_) => SpawnErrorKind::NotExecutable("Permission denied".to_string()),
+        None => SpawnErrorKind::NotFound,
+    };
+    ShellError::Spawn(SpawnError::new(name, kind))
+}
+
+/// Имена исполняемых файлов во всех каталогах `PATH` (для подсказок).
+pub(crate) fn path_executables(env: &HashMap<String, String>, cwd: &Path) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    for dir in path_var(env).split(':') {
+        let Ok(entries) = std::fs::read_dir(cwd.join(dir)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if let Ok(name) = entry.file_name().into_string()
+                && is_executable(&entry.path())
+            {
+                names.insert(name);
+            }
+        }
+    }
+    names
+}
+
+/// Самые похожие на `name` имена из `candidates` (по расстоянию Дамерау —
+/// Левенштейна: вставка, удаление, замена, перестановка соседних символов).
+///
+/// Подходят имена на расстоянии не больше трети длины `name` (от 1 до 2) и
+/// меньше длины самого имени; возвращаются до трех ближайших по алфавиту.
+/// Имена без букв и цифр (`.`, `[`) не предлагаются: на опечатку `g` они
+/// похожи только длиной.
+pub(crate) fn closest_names<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<String> {
+    let limit = (name.chars().count() / 3).clamp(1, 2);
+    let mut best: Option<(usize, BTreeSet<&str>)> = None;
+    for candidate in candidates {
+        let len = candidate.chars().count();
+        if candidate == name
+            || len.abs_diff(name.chars().count()) > limit
+            || !candidate.contains(char::is_alphanumeric)
+        {
+            continue;
+        }
+        let distance = edit_distance(name, candidate);
+        if distance > limit || distance >= len {
+            continue;
+        }
+        match &mut best {
+            Some((d, names)) if *d == distance => {
+                names.insert(candidate);
+            }
+            Some((d, _)) if *d < distance => {}
+            _ => best = Some((distance, BTreeSet::from([candidate]))),
+        }
+    }
+    best.map_or_else(Vec::new, |(_, names)| {
+        names.into_iter().take(3).map(str::to_string).collect()
+    })
+}
+
+/// Расстояние Дамерау — Левенштейна (вариант без повторного редактирования
+/// переставленных символов).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // rows[i][j] — расстояние между a[..i] и b[..j].
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut d = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d = d.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = d;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Найденная программа в таблице `hash`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HashEntry {
+    pub(crate) path: PathBuf,
+    /// Сколько раз команда запускалась по этому пути.
+    pub(crate) hits: usize,
+}
+
+/// Таблица найденных в `PATH` программ (как `hash` в bash).
+///
+/// Записи действительны для значения `PATH`, при котором они найдены: после
+/// смены `PATH` таблица очищается. Запись, файл которой исчез, ищется заново.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CommandHash {
+    /// `PATH`, для которого заполнена таблица.
+    path_var: String,
+    entries: BTreeMap<String, HashEntry>,
+}
+
+impl CommandHash {
+    /// Путь к программе `name`: имя с `/` — путь от `cwd` (без поиска), иначе
+    /// запись таблицы или первая программа из `PATH` (ищет `executor`).
+    pub(crate) fn lookup(
+        &self,
+        name: &str,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+        executor: &dyn ProcessExecutor,
+    ) -> Option<PathBuf> {
+        if name.contains('/') {
+            return Some(cwd.join(name));
+        }
+        match self.get(name, env) {
+            Some(entry) if executor.is_program(&entry.path) => Some(entry.path.clone()),
+            _ => executor.find_programs(name, env, cwd).into_iter().next(),
+        }
+    }
+
+    /// Находит программу для запуска и запоминает ее путь (счетчик запусков
+    /// растет на 1). Пути с `/` в таблицу не попадают.
+    pub(crate) fn resolve(
+        &mut self,
+        name: &str,
+        env: &HashMap<String, String>,
+        cwd: &Path,
+        executor: &dyn ProcessExecutor,
+    ) -> Option<PathBuf> {
+        let path = self.lookup(name, env, cwd, executor)?;
+        if !name.contains('/') {
+            self.remember(name, path.clone(), env).hits += 1;
+        }
+        Some(path)
+    }
+
+    /// Добавляет запись (с нулевым счетчиком, если пути еще не было).
+    pub(crate) fn remember(
+        &mut self,
+        name: &str,
+        path: PathBuf,
+        env: &HashMap<String, String>,
+    ) -> &mut HashEntry {
+        self.sync(env);
+        let entry = self
+            .entries
+            .entry(name.to_string())
+            .or_insert_with(|| HashEntry {
+                path: path.clone(),
+                hits: 0,
+            });
+        if entry.path != path {
+            *entry = HashEntry { path, hits: 0 };
+        }
+        entry
+    }
+
+    /// Запись таблицы для `name`, если `PATH` с тех пор не менялся.
+    pub(crate) fn get(&self, name: &str, env: &HashMap<String, String>) -> Option<&HashEntry> {
+        if self.path_var != path_var(env) {
+            return None;
+        }
+        self.entries.get(name)
+    }
+
+    /// Удаляет запись; `false`, если ее не было.
+    pub(crate) fn forget(&mut self, name: &str, env: &HashMap<String, String>) -> bool {
+        self.sync(env);
+        self.entries.remove(name).is_some()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Записи по алфавиту имен (пусто, если `PATH` сменился).
+    pub(crate) fn iter<'a>(
+        &'a self,
+        env: &HashMap<String, String>,
+    ) -> impl Iterator<Item = (&'a String, &'a HashEntry)> {
+        let valid = self.path_var == path_var(env);
+        self.entries.iter().filter(move |_| valid)
+    }
+
+    /// Очищает таблицу, если `PATH` сменился.
+    fn sync(&mut self, env: &HashMap<String, String>) {
+        let current = path_var(env);
+        if self.path_var != current {
+            self.path_var = current.to_string();
+            self.entries.clear();
+        }
+    }
+}
+
+fn path_var(env: &HashMap<String, String>) -> &str {
+    env.get("PATH").map_or("", String::as_str)
+}
+
+/// Все исполняемые файлы `name` в каталогах `PATH` по порядку. Пустой элемент
+/// `PATH` и относительные каталоги считаются от `cwd`.
+fn search_path<'a>(
+    name: &'a str,
+    env: &'a HashMap<String, String>,
+    cwd: &'a Path,
+) -> impl Iterator<Item = PathBuf> + 'a {
+    path_var(env)
+        .split(':')
+        .map(move |dir| cwd.join(dir).join(name))
+        .filter(|path| is_executable(path))
+}
+
+/// Обычный файл, который можно запустить (unix: есть бит `x`).
+fn is_executable(path: &Path) -> bool {
+    let Ok(meta) = std::fs::metadata(path) else {
+        return false;
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        meta.is_file() && meta.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        meta.is_file()
+    }
+}
+
+/// Снимает `FD_CLOEXEC` с `fds` в дочернем процессе, чтобы они пережили `exec`.
+///
+/// В родителе флаг остается: дескрипторы не утекают в другие процессы,
+/// запускаемые параллельно (стадии пайплайна).
+#[cfg(unix)]
+fn keep_fds_open(cmd: &mut Command, fds: &[i32]) {
+    use std::os::unix::process::CommandExt;
+
+    if fds.is_empty() {
+        return;
+    }
+    let fds = fds.to_vec();
+    // SAFETY: между fork и exec вызывается только `fcntl` (async-signal-safe),
+    // замыкание не выделяет память.
+    unsafe {
+        cmd.pre_exec(move || {
+            for &fd in &fds {
+                if libc::fcntl(fd, libc::F_SETFD, 0) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn keep_fds_open(_cmd: &mut Command, _fds: &[i32]) {}
+
+/// Применяет `ulimit` к дочернему процессу перед `exec`; со сроком выполнения
+/// процесс получает свою группу, чтобы по сроку убить и его потомков.
+#[cfg(unix)]
+fn apply_limits(cmd: &mut Command, limits: &ProcessLimits) {
+    use std::os::unix::process::CommandExt;
+
+    if limits.deadline.is_some() {
+        cmd.process_group(0);
+    }
+    let rlimits = limits.rlimits();
+    if rlimits.is_empty() {
+        return;
+    }
+    // SAFETY: между fork и exec вызывается только `setrlimit` (async-signal-safe),
+    // замыкание не выделяет память.
+    unsafe {
+        cmd.pre_exec(move || {
+            for (resource, limit) in &rlimits {
+                if libc::setrlimit(*resource, limit) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn apply_limits(_cmd: &mut Command, _limits: &ProcessLimits) {}
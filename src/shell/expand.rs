@@ -0,0 +1,348 @@
+//! Подстановки в словах команды непосредственно перед ее запуском.
+//!
+//! Парсер оставляет в списке команд слова как они записаны (`wordexp::Word`),
+//! а исполнитель раскрывает каждую стадию пайплайна и каждое присваивание,
+//! только когда до них доходит выполнение, над текущим состоянием шелла. Поэтому
+//! присваивания, `cd` и `$((i++))` предыдущих команд строки видны следующим
+//! (`cd /; echo $PWD`), а невыполненные команды ничего не раскрывают и не
+//! меняют (`false && echo $((i+=1))`).
+//!
+//! Порядок в слове как в bash: фигурные скобки и тильда (`wordexp`), затем
+//! `$NAME` и `$((...))`, затем разбиение результатов вне кавычек на поля по
+//! `IFS`. Ошибки подстановок — `ShellError::Parse` с местом в строке.
+
+use std::collections::HashMap;
+
+use super::ShellState;
+use super::arith::{self, ArithVars};
+use super::parser::{ParseError, ParseErrorKind, substitute_arith_vars};
+use super::types::{
+    Command, CommandSpec, CondWord, ConditionalStage, GroupStage, HereInput, Redirect, ShellError,
+    ShellResult, Span, Stage,
+};
+use super::wordexp::{self, Word, WordPart};
+
+/// Раскрывает стадию пайплайна: слова, цели перенаправлений и here-input.
+///
+/// Тело группы не раскрывается: его команды раскрываются по мере выполнения.
+pub(super) fn expand_command(state: &mut ShellState, command: Command) -> ShellResult<Stage> {
+    match command {
+        Command::Simple(simple) => {
+            let mut words = Vec::new();
+            let mut process_substs = simple.process_substs;
+            let mut substs = process_substs.iter_mut().peekable();
+            for (i, word) in simple.words.iter().enumerate() {
+                match substs.next_if(|subst| subst.word == i) {
+                    // Путь `/dev/fd/N` подставит исполнитель.
+                    Some(subst) => {
+                        subst.word = words.len();
+                        words.push(String::new());
+                    }
+                    None => words.extend(expand_fields(state, word)?),
+                }
+            }
+            let redirects = expand_redirects(state, &simple.redirects)?;
+            let here_input = expand_here_input(state, simple.here_input.as_ref())?;
+            // Слова, раскрывшиеся в пустоту (`$EMPTY`), дают команду с пустым
+            // именем: исполнитель только откроет ее перенаправления.
+            let name = if words.is_empty() {
+                String::new()
+            } else {
+                words.remove(0)
+            };
+            Ok(Stage::Simple(CommandSpec {
+                span: simple.span,
+                name,
+                args: words,
+                here_input,
+                redirects,
+                process_substs,
+            }))
+        }
+        Command::Group(group) => Ok(Stage::Group(GroupStage {
+            kind: group.kind,
+            here_input: expand_here_input(state, group.here_input.as_ref())?,
+            redirects: expand_redirects(state, &group.redirects)?,
+            body: group.body,
+        })),
+        Command::Conditional(conditional) => {
+            let words = conditional
+                .words
+                .iter()
+                .map(|word| expand_cond_word(state, word))
+                .collect::<ShellResult<_>>()?;
+            Ok(Stage::Conditional(ConditionalStage {
+                words,
+                redirects: expand_redirects(state, &conditional.redirects)?,
+            }))
+        }
+    }
+}
+
+/// Слово `[[ ]]`: тильда и подстановки без фигурных скобок и разбиения на поля;
+/// у каждой части остается признак кавычек.
+fn expand_cond_word(state: &mut ShellState, word: &Word) -> ShellResult<CondWord> {
+    let expanded = wordexp::expand_tilde(word, &state.env);
+    let mut parts = Vec::with_capacity(expanded.parts.len());
+    for part in &expanded.parts {
+        parts.push(match part {
+            WordPart::Literal { text, quoted, .. } => (text.clone(), *quoted),
+            WordPart::Var { name, quoted, span } => (lookup_var(state, name, *span)?, *quoted),
+            WordPart::Arith { expr, quoted, span } => (expand_arith(state, expr, *span)?, *quoted),
+        });
+    }
+    Ok(CondWord {
+        parts,
+        operator: word.is_plain(),
+    })
+}
+
+/// Значение присваивания `NAME=value` (`word` — слово целиком): тильда после
+/// `=` и `:`, подстановки без разбиения на поля (`X=$Y` — всегда одно значение).
+pub(super) fn expand_assignment(
+    state: &mut ShellState,
+    name: &str,
+    word: &Word,
+) -> ShellResult<String> {
+    Ok(expand_joined(state, word)?[name.len() + 1..].to_string())
+}
+
+fn expand_here_input(
+    state: &mut ShellState,
+    input: Option<&HereInput>,
+) -> ShellResult<Option<String>> {
+    Ok(match input {
+        None => None,
+        Some(HereInput::Literal(body)) => Some(body.clone()),
+        Some(HereInput::Heredoc(body)) => Some(expand_joined(state, body)?),
+        Some(HereInput::HereString(word)) => Some(format!("{}\n", expand_joined(state, word)?)),
+    })
+}
+
+fn expand_redirects(
+    state: &mut ShellState,
+    redirects: &[Redirect<Word>],
+) -> ShellResult<Vec<Redirect>> {
+    redirects
+        .iter()
+        .map(|redirect| {
+            Ok(match redirect {
+                Redirect::Input(word) => Redirect::Input(expand_path(state, word)?),
+                Redirect::Output { fd, path, append } => Redirect::Output {
+                    fd: *fd,
+                    path: expand_path(state, path)?,
+                    append: *append,
+                },
+                Redirect::OutputBoth { path, append } => Redirect::OutputBoth {
+                    path: expand_path(state, path)?,
+                    append: *append,
+                },
+                Redirect::Duplicate { fd, target } => Redirect::Duplicate {
+                    fd: *fd,
+                    target: *target,
+                },
+            })
+        })
+        .collect()
+}
+
+/// Все раскрытия слова команды: фигурные скобки, тильда, подстановки и
+/// разбиение на поля по `IFS` (по умолчанию — пробел, таб, перевод строки).
+fn expand_fields(state: &mut ShellState, word: &Word) -> ShellResult<Vec<String>> {
+    let ifs = state
+        .env
+        .get("IFS")
+        .cloned()
+        .unwrap_or_else(|| " \t\n".to_string());
+    let mut fields = Vec::new();
+    for word in wordexp::expand_braces(word) {
+        let word = wordexp::expand_tilde(&word, &state.env);
+        fields.extend(expand_word(state, &word, Some(&ifs))?);
+    }
+    Ok(fields)
+}
+
+/// Тильда и подстановки без разбиения: результат — одна строка.
+fn expand_joined(state: &mut ShellState, word: &Word) -> ShellResult<String> {
+    let word = wordexp::expand_tilde(word, &state.env);
+    Ok(expand_word(state, &word, None)?.concat())
+}
+
+/// Цель перенаправления: после раскрытий должно остаться ровно одно слово.
+fn expand_path(state: &mut ShellState, word: &Word) -> ShellResult<String> {
+    let mut fields = expand_fields(state, word)?;
+    match fields.len() {
+        1 => Ok(fields.remove(0)),
+        _ => Err(ShellError::Parse(ParseError::new(
+            ParseErrorKind::AmbiguousRedirect(word.text.clone()),
+            word.span,
+        ))),
+    }
+}
+
+/// Выполняет подстановки в слове; `ifs` — разделители полей для частей вне
+/// кавычек (`None` — не делить).
+fn expand_word(state: &mut ShellState, word: &Word, ifs: Option<&str>) -> ShellResult<Vec<String>> {
+    let mut fields = Fields::new();
+    for part in &word.parts {
+        let (value, quoted) = match part {
+            WordPart::Literal { text, quoted, .. } => {
+                fields.push_text(text, *quoted);
+                continue;
+            }
+            WordPart::Var { name, quoted, span } => (lookup_var(state, name, *span)?, *quoted),
+            WordPart::Arith { expr, quoted, span } => (expand_arith(state, expr, *span)?, *quoted),
+        };
+        match ifs {
+            Some(ifs) if !quoted => fields.push_split(&value, ifs),
+            _ => fields.push_text(&value, quoted),
+        }
+    }
+    Ok(fields.finish())
+}
+
+/// Значение `$NAME` (`$1`…`$9` и `${10}` — позиционные параметры, `NAME[N]` —
+/// элемент, см. `lookup_element`): незаданная переменная — пустая строка, а при `set -u` — ошибка в месте подстановки `span`.
+fn lookup_var(state: &ShellState, name: &str, span: Span) -> ShellResult<String> {
+    let value = match name.split_once('[') {
+        Some((base, index)) => lookup_element(state, base, index.trim_end_matches(']')),
+        None => name
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| state.positional.get(n.checked_sub(1)?))
+            .or_else(|| state.env.get(name))
+            .cloned(),
+    };
+    match value {
+        Some(value) => Ok(value),
+        None if state.options.nounset => Err(ShellError::Parse(ParseError::new(
+            ParseErrorKind::UnsetVariable(name.to_string()),
+            span,
+        ))),
+        None => Ok(String::new()),
+    }
+}
+
+/// Элемент `${NAME[index]}`: `PIPESTATUS` — массив кодов через пробел, остальные
+/// переменные — массивы из одного значения (как в bash). `@` и `*` — все элементы
+/// через пробел.
+fn lookup_element(state: &ShellState, base: &str, index: &str) -> Option<String> {
+    let value = state.env.get(base)?;
+    let items: Vec<&str> = if base == "PIPESTATUS" {
+        value.split(' ').collect()
+    } else {
+        vec![value]
+    };
+    match index {
+        "@" | "*" => Some(items.join(" ")),
+        n => items
+            .get(n.parse::<usize>().ok()?)
+            .map(|item| item.to_string()),
+    }
+}
+
+/// Вычисляет `$((expr))`: сначала подставляет `$NAME` внутри выражения, затем считает.
+///
+/// Присваивания внутри выражения применяются к состоянию шелла (с проверками
+/// `ShellState::apply_assignments`). Ошибка указывает на `span` подстановки.
+/// Имена без `$` по-прежнему считаются 0, даже при `set -u`.
+fn expand_arith(state: &mut ShellState, expr: &str, span: Span) -> ShellResult<String> {
+    let substituted = substitute_arith_vars(expr, |name| lookup_var(state, name, span))?;
+    let mut vars = RecordingVars {
+        env: &state.env,
+        assigned: Vec::new(),
+    };
+    let value = arith::evaluate(&substituted, &mut vars);
+    let assigned = vars.assigned;
+    state.apply_assignments(&assigned)?;
+    value
+        .map(|value| value.to_string())
+        .map_err(|e| ShellError::Parse(ParseError::new(ParseErrorKind::Arithmetic(e), span)))
+}
+
+/// Переменные для `$((...))`: присваивания копятся поверх окружения и
+/// применяются к шеллу после вычисления.
+struct RecordingVars<'a> {
+    env: &'a HashMap<String, String>,
+    assigned: Vec<(String, String)>,
+}
+
+impl ArithVars for RecordingVars<'_> {
+    fn get(&self, name: &str) -> Option<&str> {
+        self.assigned
+            .iter()
+            .rev()
+            .find(|(assigned, _)| assigned == name)
+            .map(|(_, value)| value.as_str())
+            .or_else(|| self.env.get(name).map(String::as_str))
+    }
+
+    fn set(&mut self, name: &str, value: String) {
+        self.assigned.push((name.to_string(), value));
+    }
+}
+
+/// Сборщик полей при подстановке в слово.
+///
+/// Результаты подстановок вне кавычек делятся на поля по символам `IFS`:
+/// пробельные символы `IFS` схлопываются и обрезаются по краям, остальные
+/// разделяют поля по одному (`a::b` при `IFS=:` — три поля). Слово, от
+/// которого после подстановок ничего не осталось (`$EMPTY` вне кавычек),
+/// не дает ни одного поля.
+struct Fields {
+    fields: Vec<String>,
+    current: String,
+    /// Текущее поле существует (даже если пустое: `""`, `a$EMPTY`).
+    started: bool,
+    /// Последним был пробельный разделитель `IFS`, закрывший поле.
+    after_space: bool,
+}
+
+impl Fields {
+    fn new() -> Self {
+        Fields {
+            fields: Vec::new(),
+            current: String::new(),
+            started: false,
+            after_space: false,
+        }
+    }
+
+    fn push_text(&mut self, text: &str, quoted: bool) {
+        self.current.push_str(text);
+        self.started |= quoted || !text.is_empty();
+        self.after_space = false;
+    }
+
+    fn push_split(&mut self, value: &str, ifs: &str) {
+        for c in value.chars() {
+            if !ifs.contains(c) {
+                self.current.push(c);
+                self.started = true;
+                self.after_space = false;
+            } else if matches!(c, ' ' | '\t' | '\n') {
+                if self.started {
+                    self.end_field();
+                    self.after_space = true;
+                }
+            } else {
+                if self.started || !self.after_space {
+                    self.end_field();
+                }
+                self.after_space = false;
+            }
+        }
+    }
+
+    fn end_field(&mut self) {
+        self.fields.push(std::mem::take(&mut self.current));
+        self.started = false;
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        if self.started {
+            self.end_field();
+        }
+        self.fields
+    }
+}
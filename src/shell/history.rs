@@ -0,0 +1,286 @@
+//! История команд: список введенных строк со временем и подстановки `!`.
+//!
+//! В историю попадают строки, прочитанные REPL (не строки `source` и стартового
+//! файла), уже после подстановок истории. Настройки берутся из переменных шелла:
+//! - `HISTSIZE` — сколько записей хранить (по умолчанию 500, `0` — не вести
+//!   историю, отрицательное — без ограничения);
+//! - `HISTCONTROL` — через `:`: `ignorespace` (не записывать строки, которые
+//!   начинаются с пробела), `ignoredups` (не записывать повтор предыдущей
+//!   строки), `ignoreboth` — оба;
+//! - `HISTFILE` — файл истории (читается и записывается интерактивным шеллом).
+//!
+//! Записи нумеруются с 1; при вытеснении старых записей номера остальных не меняются.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Размер истории, если `HISTSIZE` не задан.
+const DEFAULT_SIZE: usize = 500;
+
+/// Запись истории.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HistoryEntry {
+    pub(crate) line: String,
+    pub(crate) time: SystemTime,
+}
+
+/// Список введенных строк.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct History {
+    entries: VecDeque<HistoryEntry>,
+    /// Сколько записей вытеснено с начала (номер первой записи — `base + 1`).
+    base: usize,
+}
+
+impl History {
+    /// Записывает строку с учетом `HISTCONTROL` и `HISTSIZE` из `env`.
+    pub(crate) fn record(&mut self, line: &str, time: SystemTime, env: &HashMap<String, String>) {
+        let control = env.get("HISTCONTROL").map_or("", String::as_str);
+        let ignore = |what: &str| {
+            control
+                .split(':')
+                .any(|item| item == what || item == "ignoreboth")
+        };
+        if line.trim().is_empty()
+            || (ignore("ignorespace") && line.starts_with([' ', '\t']))
+            || (ignore("ignoredups") && self.entries.back().is_some_and(|e| e.line == line))
+        {
+            return;
+        }
+        self.entries.push_back(HistoryEntry {
+            line: line.to_string(),
+            time,
+        });
+        self.truncate(size_limit(env));
+    }
+
+    /// Оставляет не больше `limit` последних записей (`None` — без ограничения).
+    fn truncate(&mut self, limit: Option<usize>) {
+        if let Some(limit) = limit {
+            while self.entries.len() > limit {
+                self.entries.pop_front();
+                self.base += 1;
+            }
+        }
+    }
+
+    /// Записи с их номерами, от старых к новым.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (usize, &HistoryEntry)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| (self.base + i + 1, entry))
+    }
+
+    /// Строка с номером `number`.
+    fn get(&self, number: usize) -> Option<&str> {
+        let index = number.checked_sub(self.base + 1)?;
+        self.entries.get(index).map(|e| e.line.as_str())
+    }
+
+    /// `n`-я с конца строка (1 — последняя).
+    fn back(&self, n: usize) -> Option<&str> {
+        let index = self.entries.len().checked_sub(n)?;
+        self.entries.get(index).map(|e| e.line.as_str())
+    }
+
+    /// Номер записи по аргументу `history -d`: положительный — номер,
+    /// отрицательный — отсчет с конца (`-1` — последняя).
+    pub(crate) fn resolve(&self, offset: i64) -> Option<usize> {
+        let number = if offset < 0 {
+            (self.base + self.entries.len() + 1).checked_sub(offset.unsigned_abs() as usize)?
+        } else {
+            usize::try_from(offset).ok()?
+        };
+        self.get(number).map(|_| number)
+    }
+
+    /// Удаляет запись с номером `number`; следующие записи сдвигаются на ее место.
+    pub(crate) fn delete(&mut self, number: usize) -> bool {
+        match number.checked_sub(self.base + 1) {
+            Some(index) => self.entries.remove(index).is_some(),
+            None => false,
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.base = 0;
+    }
+
+    /// Добавляет записи из текста файла истории: строка `#SECONDS` задает время
+    /// следующей записи (как пишет bash при `HISTTIMEFORMAT`).
+    pub(crate) fn load(&mut self, text: &str, env: &HashMap<String, String>) {
+        let mut time = None;
+        for line in text.lines() {
+            if let Some(secs) = line.strip_prefix('#')
+                && let Ok(secs) = secs.parse::<u64>()
+            {
+                time = Some(UNIX_EPOCH + Duration::from_secs(secs));
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            self.entries.push_back(HistoryEntry {
+                line: line.to_string(),
+                time: time.take().unwrap_or(UNIX_EPOCH),
+            });
+        }
+        self.truncate(size_limit(env));
+    }
+
+    /// Текст файла истории: перед каждой записью — строка `#SECONDS`.
+    pub(crate) fn to_file_text(&self) -> String {
+        let mut text = String::new();
+        for entry in &self.entries {
+            let secs = entry
+                .time
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            text.push_str(&format!("#{secs}\n{}\n", entry.line));
+        }
+        text
+    }
+}
+
+/// Ограничение размера из `HISTSIZE`.
+fn size_limit(env: &HashMap<String, String>) -> Option<usize> {
+    match env.get("HISTSIZE").map(|size| size.trim().parse::<i64>()) {
+        Some(Ok(size)) if size < 0 => None,
+        Some(Ok(size)) => Some(size as usize),
+        _ => Some(DEFAULT_SIZE),
+    }
+}
+
+/// Ошибка подстановки истории; строка не выполняется.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum HistoryError {
+    /// `!n`, `!prefix`: такой записи нет.
+    EventNotFound(String),
+    /// `^old^new`: в предыдущей строке нет `old`.
+    SubstitutionFailed(String),
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::EventNotFound(event) => write!(f, "{event}: event not found"),
+            HistoryError::SubstitutionFailed(event) => {
+                write!(f, "{event}: substitution failed")
+            }
+        }
+    }
+}
+
+/// Подстановки истории в строке, как в bash (до разбора строки):
+/// - `!!` — предыдущая строка;
+/// - `!n` — строка с номером `n`, `!-n` — `n`-я с конца;
+/// - `!prefix` — последняя строка, которая начинается с `prefix`;
+/// - `^old^new[^]` в начале строки — предыдущая строка, где первое вхождение
+///   `old` заменено на `new`.
+///
+/// Внутри одинарных кавычек и после `\` подстановок нет; `!` перед пробелом,
+/// `=`, `(`, концом строки и (в двойных кавычках) `"` — обычный символ.
+/// Возвращает `None`, если подстановок не было.
+pub(crate) fn expand(line: &str, history: &History) -> Result<Option<String>, HistoryError> {
+    if let Some(rest) = line.strip_prefix('^') {
+        return quick_substitution(line, rest, history).map(Some);
+    }
+
+    let mut out = String::with_capacity(line.len());
+    let mut changed = false;
+    let mut in_single = false;
+    let mut in_double = false;
+    let mut chars = line.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' if !in_double => in_single = !in_single,
+            '"' if !in_single => in_double = !in_double,
+            '\\' if !in_single => {
+                out.push(c);
+                if let Some((_, next)) = chars.next() {
+                    out.push(next);
+                }
+                continue;
+            }
+            '!' if !in_single => {
+                let rest = &line[i + 1..];
+                let literal = match rest.chars().next() {
+                    None => true,
+                    Some(next) => {
+                        next.is_whitespace()
+                            || matches!(next, '=' | '(')
+                            || (in_double && next == '"')
+                    }
+                };
+                if !literal {
+                    let event = event_spec(rest);
+                    out.push_str(resolve_event(event, history)?);
+                    changed = true;
+                    for _ in event.chars() {
+                        chars.next();
+                    }
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push(c);
+    }
+    Ok(changed.then_some(out))
+}
+
+/// Текст события после `!`: `!`, число (`-n`) или префикс до пробела, `:`,
+/// кавычки или оператора шелла.
+fn event_spec(rest: &str) -> &str {
+    if rest.starts_with('!') {
+        return &rest[..1];
+    }
+    let digits = rest.strip_prefix('-').unwrap_or(rest);
+    let len = if digits.starts_with(|c: char| c.is_ascii_digit()) {
+        rest.len() - digits.len()
+            + digits
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(digits.len())
+    } else {
+        rest.find(|c: char| c.is_whitespace() || ":;&|<>()'\"".contains(c))
+            .unwrap_or(rest.len())
+    };
+    &rest[..len]
+}
+
+/// Строка истории для события `event` (текст после `!`).
+fn resolve_event<'h>(event: &str, history: &'h History) -> Result<&'h str, HistoryError> {
+    let found = if event == "!" {
+        history.back(1)
+    } else if let Some(n) = event.strip_prefix('-').and_then(|n| n.parse().ok()) {
+        history.back(n)
+    } else if let Ok(n) = event.parse() {
+        history.get(n)
+    } else {
+        history
+            .entries
+            .iter()
+            .rev()
+            .map(|e| e.line.as_str())
+            .find(|line| line.starts_with(event))
+    };
+    found.ok_or_else(|| HistoryError::EventNotFound(format!("!{event}")))
+}
+
+/// `^old^new[^]`: `rest` — строка без первого `^`.
+fn quick_substitution(line: &str, rest: &str, history: &History) -> Result<String, HistoryError> {
+    let failed = || HistoryError::SubstitutionFailed(line.to_string());
+    let (old, new) = rest.split_once('^').ok_or_else(failed)?;
+    let new = new.strip_suffix('^').unwrap_or(new);
+    let previous = history
+        .back(1)
+        .ok_or_else(|| HistoryError::EventNotFound(line.to_string()))?;
+    if old.is_empty() || !previous.contains(old) {
+        return Err(failed());
+    }
+    Ok(previous.replacen(old, new, 1))
+}
@@ -0,0 +1,174 @@
+//! Ограничения внешних программ: ресурсы (`ulimit`) и срок выполнения
+//! (`timeout`, `--timeout`).
+//!
+//! Ограничения хранит `ShellState.limits` и передает исполнителю в
+//! `ProcessCommand::limits`: `StdProcessExecutor` применяет rlimits в дочернем
+//! процессе перед `exec`, а после срока убивает процесс вместе с его группой
+//! процессов (код `TIMEOUT_STATUS`). Из builtins срок соблюдает только
+//! ожидающий данных `tail -f` (`BuiltinContext::deadline`, тот же код);
+//! остальные builtins и сам шелл ограничения не касаются.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Код команды, убитой по истечении срока (как у `timeout` из coreutils).
+pub const TIMEOUT_STATUS: i32 = 124;
+
+/// Ресурс, который ограничивает `ulimit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Resource {
+    /// `-t`: процессорное время, секунды (`RLIMIT_CPU`).
+    Cpu,
+    /// `-v`: виртуальная память, Кбайт (`RLIMIT_AS`).
+    Memory,
+    /// `-n`: открытые файлы (`RLIMIT_NOFILE`).
+    OpenFiles,
+    /// `-u`: процессы пользователя (`RLIMIT_NPROC`).
+    Processes,
+}
+
+impl Resource {
+    /// Ресурсы в порядке вывода `ulimit -a`.
+    pub(crate) const ALL: [Resource; 4] = [
+        Resource::Cpu,
+        Resource::OpenFiles,
+        Resource::Processes,
+        Resource::Memory,
+    ];
+
+    /// Ресурс для флага `ulimit -X`.
+    pub(crate) fn from_flag(flag: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.flag() == flag)
+    }
+
+    pub(crate) fn flag(self) -> char {
+        match self {
+            Resource::Cpu => 't',
+            Resource::Memory => 'v',
+            Resource::OpenFiles => 'n',
+            Resource::Processes => 'u',
+        }
+    }
+
+    /// Название для `ulimit -a` и сообщений об ошибках.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Resource::Cpu => "cpu time",
+            Resource::Memory => "virtual memory",
+            Resource::OpenFiles => "open files",
+            Resource::Processes => "max user processes",
+        }
+    }
+
+    /// Единица значения для `ulimit -a` (`None` — штуки).
+    pub(crate) fn unit(self) -> Option<&'static str> {
+        match self {
+            Resource::Cpu => Some("seconds"),
+            Resource::Memory => Some("kbytes"),
+            Resource::OpenFiles | Resource::Processes => None,
+        }
+    }
+
+    /// Сколько единиц rlimit в единице `ulimit` (память задается в Кбайт).
+    fn scale(self) -> u64 {
+        match self {
+            Resource::Memory => 1024,
+            _ => 1,
+        }
+    }
+
+    /// Текущие мягкий и жесткий лимиты процесса шелла в единицах `ulimit`
+    /// (`None` — unlimited).
+    #[cfg(unix)]
+    pub(crate) fn current(self) -> std::io::Result<(Option<u64>, Option<u64>)> {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        // SAFETY: `limit` — корректный указатель на `rlimit`.
+        if unsafe { libc::getrlimit(self.raw(), &mut limit) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let value = |raw: libc::rlim_t| (raw != libc::RLIM_INFINITY).then(|| raw / self.scale());
+        Ok((value(limit.rlim_cur), value(limit.rlim_max)))
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn current(self) -> std::io::Result<(Option<u64>, Option<u64>)> {
+        Ok((None, None))
+    }
+
+    #[cfg(unix)]
+    fn raw(self) -> RawResource {
+        match self {
+            Resource::Cpu => libc::RLIMIT_CPU,
+            Resource::Memory => libc::RLIMIT_AS,
+            Resource::OpenFiles => libc::RLIMIT_NOFILE,
+            Resource::Processes => libc::RLIMIT_NPROC,
+        }
+    }
+}
+
+/// Тип номера ресурса в `getrlimit`/`setrlimit`.
+#[cfg(all(unix, target_os = "linux", target_env = "gnu"))]
+type RawResource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type RawResource = libc::c_int;
+
+/// Ограничения, с которыми запускаются внешние программы.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessLimits {
+    /// Лимиты `ulimit` в его единицах; `None` — unlimited. Ресурсы без записи
+    /// наследуются от процесса шелла.
+    pub resources: BTreeMap<Resource, Option<u64>>,
+    /// Срок, после которого процесс и его группа убиваются (`timeout`, `--timeout`).
+    pub deadline: Option<Instant>,
+}
+
+impl ProcessLimits {
+    /// Срок не позже, чем через `timeout` (вложенный `timeout` не продлевает
+    /// внешний); нулевой `timeout` срок не задает.
+    pub(crate) fn shorten_deadline(&mut self, timeout: Duration) {
+        if timeout.is_zero() {
+            return;
+        }
+        let Some(deadline) = Instant::now().checked_add(timeout) else {
+            return;
+        };
+        self.deadline = Some(self.deadline.map_or(deadline, |d| d.min(deadline)));
+    }
+
+    /// Лимиты для `setrlimit` в дочернем процессе: значения в байтах/штуках,
+    /// мягкий и жесткий лимит одинаковы, как у `ulimit` без `-S`/`-H`.
+    #[cfg(unix)]
+    pub(crate) fn rlimits(&self) -> Vec<(RawResource, libc::rlimit)> {
+        self.resources
+            .iter()
+            .map(|(&resource, &value)| {
+                let raw = value.map_or(libc::RLIM_INFINITY, |v| v.saturating_mul(resource.scale()));
+                let limit = libc::rlimit {
+                    rlim_cur: raw,
+                    rlim_max: raw,
+                };
+                (resource.raw(), limit)
+            })
+            .collect()
+    }
+}
+
+/// Разбирает срок `timeout`: число (можно дробное) с суффиксом `s`, `m`, `h`
+/// или `d` (по умолчанию секунды), например `1.5`, `30s`, `2m`.
+pub(crate) fn parse_duration(text: &str) -> Option<Duration> {
+    let (number, unit) = match text.char_indices().last()? {
+        (i, 's') => (&text[..i], 1.0),
+        (i, 'm') => (&text[..i], 60.0),
+        (i, 'h') => (&text[..i], 3600.0),
+        (i, 'd') => (&text[..i], 86400.0),
+        _ => (text, 1.0),
+    };
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    let seconds = number.parse::<f64>().ok()? * unit;
+    Duration::try_from_secs_f64(seconds).ok()
+}
@@ -0,0 +1,175 @@
+//! Исполнитель внешних программ в памяти.
+//!
+//! `MockExecutor` не запускает процессов: программа — это ответ, заданный
+//! заранее (`respond`) или функцией от запуска (`handle`). Каждый запуск
+//! записывается (`calls`) вместе с аргументами, окружением, рабочей директорией,
+//! stdin и лимитами, поэтому поведение внешних команд можно проверять без `sh`/`cmd`.
+//!
+//! Программы мока «лежат» в каталоге `MOCK_BIN`: такие пути видят `type`,
+//! `which` и `hash`.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::executor::{ProcessCommand, ProcessExecutor, ProcessInput, ProcessOutput};
+use super::limits::ProcessLimits;
+use super::types::RunResult;
+
+/// Каталог программ мока.
+pub const MOCK_BIN: &str = "/mock/bin";
+
+/// Ответ программы мока.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockResponse {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub exit_code: i32,
+}
+
+impl MockResponse {
+    /// Успешный ответ с выводом `text`.
+    pub fn stdout(text: impl Into<Vec<u8>>) -> Self {
+        Self {
+            stdout: text.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Тот же ответ с сообщением `text` в stderr.
+    pub fn with_stderr(mut self, text: impl Into<Vec<u8>>) -> Self {
+        self.stderr = text.into();
+        self
+    }
+
+    /// Тот же ответ с кодом возврата `code`.
+    pub fn with_exit_code(mut self, code: i32) -> Self {
+        self.exit_code = code;
+        self
+    }
+}
+
+/// Запуск программы, записанный моком.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockCall {
+    /// Путь, по которому шелл запустил программу.
+    pub program: PathBuf,
+    /// Имя программы и аргументы.
+    pub argv: Vec<String>,
+    pub env: HashMap<String, String>,
+    pub cwd: PathBuf,
+    /// Весь ввод программы (пусто, если stdin не перенаправлен).
+    pub stdin: Vec<u8>,
+    /// Лимиты `ulimit` и срок `timeout`, с которыми шелл запустил программу
+    /// (мок их не применяет).
+    pub limits: ProcessLimits,
+}
+
+type Handler = Arc<dyn Fn(&MockCall) -> MockResponse + Send + Sync>;
+
+/// Исполнитель, который отвечает на запуски из памяти и записывает их.
+#[derive(Default)]
+pub struct MockExecutor {
+    programs: Mutex<HashMap<String, Handler>>,
+    calls: Mutex<Vec<MockCall>>,
+}
+
+impl MockExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Программа `name` на каждый запуск отвечает `response`.
+    pub fn respond(&self, name: &str, response: MockResponse) {
+        self.handle(name, move |_| response.clone());
+    }
+
+    /// Программа `name` отвечает результатом `handler` (например, выводит
+    /// свой stdin или аргументы).
+    pub fn handle(
+        &self,
+        name: &str,
+        handler: impl Fn(&MockCall) -> MockResponse + Send + Sync + 'static,
+    ) {
+        lock(&self.programs).insert(name.to_string(), Arc::new(handler));
+    }
+
+    /// Запуски по порядку (стадии пайплайна — в порядке завершения).
+    pub fn calls(&self) -> Vec<MockCall> {
+        lock(&self.calls).clone()
+    }
+
+    fn handler(&self, path: &Path) -> Option<Handler> {
+        let name = path.file_name()?.to_str()?;
+        lock(&self.programs).get(name).cloned()
+    }
+}
+
+impl ProcessExecutor for MockExecutor {
+    fn find_programs(
+        &self,
+        name: &str,
+        _env: &HashMap<String, String>,
+        _cwd: &Path,
+    ) -> Vec<PathBuf> {
+        let path = Path::new(MOCK_BIN).join(name);
+        self.handler(&path).map(|_| path).into_iter().collect()
+    }
+
+    fn is_program(&self, path: &Path) -> bool {
+        path.parent() == Some(Path::new(MOCK_BIN)) && self.handler(path).is_some()
+    }
+
+    fn run(
+        &self,
+        command: ProcessCommand<'_>,
+        stdin: ProcessInput,
+        stdout: ProcessOutput,
+    ) -> io::Result<RunResult> {
+        let Some(handler) = self.handler(command.program) else {
+            return Err(io::ErrorKind::NotFound.into());
+        };
+        let input = match stdin {
+            ProcessInput::Inherit | ProcessInput::Null => Vec::new(),
+            ProcessInput::Bytes(data) => data,
+            ProcessInput::Pipe(mut reader) => {
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data)?;
+                data
+            }
+        };
+        let name = command.program.file_name().unwrap_or_default();
+        let call = MockCall {
+            program: command.program.to_path_buf(),
+            argv: std::iter::once(name.to_string_lossy().into_owned())
+                .chain(command.args.iter().cloned())
+                .collect(),
+            env: command.env.clone(),
+            cwd: command.cwd.to_path_buf(),
+            stdin: input,
+            limits: command.limits.clone(),
+        };
+        let response = handler(&call);
+        lock(&self.calls).push(call);
+
+        let stdout = match stdout {
+            ProcessOutput::Capture => response.stdout,
+            ProcessOutput::Pipe(mut writer) => {
+                // Следующая стадия могла завершиться, не дочитав: как SIGPIPE, молча.
+                let _ = writer.write_all(&response.stdout);
+                Vec::new()
+            }
+        };
+        Ok(RunResult {
+            exit_code: response.exit_code,
+            stdout,
+            stderr: response.stderr,
+        })
+    }
+}
+
+/// Захватывает мьютекс, даже если другой поток паниковал с ним.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
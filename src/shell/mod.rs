@@ -1,141 +1,854 @@
 //! Исполнение команд и цикл REPL.
 
+mod arith;
 mod builtins;
-mod executor;
+mod debugparse;
+mod embed;
+pub mod executor;
+mod expand;
+mod history;
+mod limits;
+pub mod mock;
+mod options;
 mod parser;
+mod procsubst;
+mod redirect;
+mod restrict;
+mod state_builtins;
 mod types;
+pub mod vfs;
+mod wordexp;
 
 #[cfg(test)]
 mod tests;
 
+use std::cell::Cell;
 use std::collections::HashMap;
-use std::io::BufRead;
 use std::io::Read;
 use std::io::Write;
-use std::process::Stdio;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
-use builtins::Builtin;
-use executor::StdProcessExecutor;
-use parser::parse_line;
-use types::{CommandSpec, IoStreams, Pipeline, ShellControl, ShellError, ShellResult};
+use builtins::{BUILTIN_NAMES, Builtin, BuiltinContext};
+use embed::CustomBuiltins;
+pub use embed::{BuiltinCall, Output, Shell, ShellBuilder, Status};
+use executor::{
+    CommandHash, ProcessCommand, ProcessExecutor, ProcessInput, ProcessOutput, SharedExecutor,
+};
+use history::History;
+use limits::ProcessLimits;
+use options::ShellOptions;
+pub(crate) use options::Startup;
+use parser::{HeredocSpec, ParseErrorKind, parse_line_with_heredocs, pending_heredocs};
+use procsubst::RunningSubsts;
+use redirect::Redirections;
+use restrict::{Restrictions, RootedFs};
+use state_builtins::{STATE_BUILTIN_NAMES, StateBuiltin};
+use types::{
+    CommandList, CommandRunner, CommandSpec, ConditionalStage, Connector, GroupKind, GroupStage,
+    IoStreams, Pipeline, Redirect, RunResult, ShellControl, ShellError, ShellResult, SpawnError,
+    SpawnErrorKind, Stage,
+};
+use vfs::{FileSystem, OsFs, SharedFileSystem};
 
 /// Состояние интерпретатора.
 ///
-/// Содержит набор переменных окружения, которые будут передаваться внешним процессам.
+/// Содержит набор переменных окружения, которые будут передаваться внешним процессам,
+/// рабочую директорию, опции шелла, алиасы, позиционные параметры, историю,
+/// таблицу найденных программ, builtins приложения, файловую систему,
+/// ограничения (`--restricted`) и лимиты внешних программ (`ulimit`, `timeout`).
+#[derive(Clone)]
 struct ShellState {
     env: HashMap<String, String>,
+    cwd: PathBuf,
+    options: ShellOptions,
+    /// Алиасы `alias name=value`; раскрываются при разборе строки.
+    aliases: HashMap<String, String>,
+    /// Позиционные параметры `$1`…`$9` (аргументы `source FILE args...`).
+    positional: Vec<String>,
+    /// Строки, введенные в REPL.
+    history: History,
+    /// Найденные в `PATH` программы (`hash`).
+    commands: CommandHash,
+    /// Builtins, заданные приложением (`ShellBuilder::builtin`).
+    builtins: CustomBuiltins,
+    /// Файловая система builtins, перенаправлений, `cd` и `source`.
+    fs: SharedFileSystem,
+    /// Ограниченный режим; `None` — ограничений нет.
+    restrictions: Option<Arc<Restrictions>>,
+    /// Лимиты ресурсов и срок выполнения внешних программ.
+    limits: ProcessLimits,
 }
 
 impl ShellState {
-    /// Инициализирует состояние окружением текущего процесса.
+    /// Состояние с окружением `env` и рабочей директорией `cwd` (файловая система — ОС).
+    fn new(env: HashMap<String, String>, cwd: PathBuf) -> Self {
+        Self {
+            env,
+            cwd,
+            options: ShellOptions::default(),
+            aliases: HashMap::new(),
+            positional: Vec::new(),
+            history: History::default(),
+            commands: CommandHash::default(),
+            builtins: CustomBuiltins::default(),
+            fs: Arc::new(OsFs),
+            restrictions: None,
+            limits: ProcessLimits::default(),
+        }
+    }
+
+    /// Инициализирует состояние окружением и рабочей директорией текущего процесса.
     fn new_from_process_env() -> Self {
-        let mut env = HashMap::new();
-        for (k, v) in std::env::vars() {
-            env.insert(k, v);
+        let cwd = OsFs.current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        Self::new(std::env::vars().collect(), cwd)
+    }
+
+    /// Встроенная ли команда `name` (state builtin, builtin приложения или утилита).
+    fn is_builtin(&self, name: &str) -> bool {
+        StateBuiltin::from_name(name).is_some()
+            || self.builtins.contains_key(name)
+            || Builtin::from_name(name).is_some()
+    }
+
+    /// Читает ли команда `name args` stdin (внешние программы и builtins
+    /// приложения — всегда).
+    fn reads_stdin(&self, name: &str, args: &[String]) -> bool {
+        if let Some(builtin) = StateBuiltin::from_name(name) {
+            return builtin.reads_stdin();
         }
-        Self { env }
+        self.builtins.contains_key(name)
+            || Builtin::from_name(name).is_none_or(|builtin| builtin.reads_stdin(args))
+    }
+
+    /// Файл истории из `HISTFILE` (относительный путь — от рабочей директории).
+    fn history_file(&self) -> Option<PathBuf> {
+        self.env
+            .get("HISTFILE")
+            .filter(|path| !path.is_empty())
+            .map(|path| self.cwd.join(path))
+    }
+
+    /// Запоминает коды стадий последнего пайплайна в `PIPESTATUS` через пробел;
+    /// `${PIPESTATUS[N]}` делит значение на элементы при подстановке.
+    fn set_pipestatus(&mut self, codes: &[i32]) {
+        let codes: Vec<_> = codes.iter().map(i32::to_string).collect();
+        self.env.insert("PIPESTATUS".to_string(), codes.join(" "));
+    }
+
+    /// Выполняет `f` со сроком для внешних программ не позже, чем через `timeout`;
+    /// после `f` прежний срок восстанавливается.
+    fn with_timeout<T>(&mut self, timeout: Duration, f: impl FnOnce(&mut Self) -> T) -> T {
+        let saved = self.limits.deadline;
+        self.limits.shorten_deadline(timeout);
+        let result = f(self);
+        self.limits.deadline = saved;
+        result
+    }
+
+    /// Строка трассировки `set -x`: `PS4` (по умолчанию `+ `) и слова `words`
+    /// (уже в кавычках, см. `trace_quote`) через пробел. `None`, если трассировка
+    /// выключена.
+    fn trace_line(&self, words: impl IntoIterator<Item = String>) -> Option<String> {
+        if !self.options.xtrace {
+            return None;
+        }
+        let mut line = self.env.get("PS4").map_or("+ ", String::as_str).to_string();
+        line.push_str(&words.into_iter().collect::<Vec<_>>().join(" "));
+        line.push('\n');
+        Some(line)
+    }
+
+    /// Запуск вложенных команд из builtins (`xargs`, `find -exec`) в этом состоянии.
+    fn command_runner<'a>(&'a self, executor: &'a dyn ProcessExecutor) -> ShellCommandRunner<'a> {
+        ShellCommandRunner {
+            executor,
+            env: &self.env,
+            cwd: &self.cwd,
+            fs: self.fs.as_ref(),
+            builtins: &self.builtins,
+            commands: &self.commands,
+            restrictions: self.restrictions.as_deref(),
+            limits: &self.limits,
+        }
+    }
+
+    /// Путь к внешней программе `name`; запоминается в таблице `hash`.
+    fn resolve_command(
+        &mut self,
+        name: &str,
+        executor: &dyn ProcessExecutor,
+    ) -> ShellResult<PathBuf> {
+        self.commands
+            .resolve(name, &self.env, &self.cwd, executor)
+            .ok_or_else(|| executor::not_found_error(name, &self.env, &self.cwd))
+    }
+
+    /// Ошибка запуска команды `name` для пользователя: с именем, как оно записано
+    /// в команде, и (если команда не найдена) похожими именами builtins, алиасов
+    /// и программ из `PATH`.
+    fn spawn_failure(&self, name: &str, mut error: SpawnError) -> SpawnError {
+        error.name = name.to_string();
+        if error.kind == SpawnErrorKind::NotFound && !name.contains('/') {
+            let programs = executor::path_executables(&self.env, &self.cwd);
+            let candidates = BUILTIN_NAMES
+                .iter()
+                .chain(STATE_BUILTIN_NAMES)
+                .copied()
+                .chain(self.builtins.keys().map(String::as_str))
+                .chain(self.aliases.keys().map(String::as_str))
+                .chain(programs.iter().map(String::as_str));
+            error.suggestions = executor::closest_names(name, candidates);
+        }
+        error
     }
 
     /// Применяет список присваиваний `NAME=value` к окружению интерпретатора.
-    fn apply_assignments(&mut self, assignments: &[(String, String)]) {
+    ///
+    /// В ограниченном режиме присваивание защищенной переменной (`PATH`, ...)
+    /// отклоняется целиком, до применения остальных.
+    fn apply_assignments(&mut self, assignments: &[(String, String)]) -> ShellResult<()> {
+        if let Some(restrictions) = &self.restrictions {
+            restrictions.check_assignments(assignments)?;
+        }
         for (k, v) in assignments {
             self.env.insert(k.clone(), v.clone());
         }
+        Ok(())
+    }
+}
+
+/// Запуск вложенных команд из builtins по обычным правилам шелла.
+///
+/// Builtin (в том числе builtin приложения) выполняется в текущем процессе с
+/// захватом вывода, иначе команда запускается как внешняя программа с окружением шелла.
+struct ShellCommandRunner<'a> {
+    executor: &'a dyn ProcessExecutor,
+    env: &'a HashMap<String, String>,
+    cwd: &'a Path,
+    fs: &'a dyn FileSystem,
+    builtins: &'a CustomBuiltins,
+    /// Таблица `hash` шелла (только для поиска: вложенные запуски ее не меняют).
+    commands: &'a CommandHash,
+    /// Ограниченный режим шелла.
+    restrictions: Option<&'a Restrictions>,
+    /// Лимиты внешних программ (`ulimit`, `timeout`).
+    limits: &'a ProcessLimits,
+}
+
+impl CommandRunner for ShellCommandRunner<'_> {
+    fn run_command(
+        &self,
+        name: &str,
+        args: &[String],
+        stdin: Option<&[u8]>,
+    ) -> ShellResult<RunResult> {
+        if let Some(builtin) = self.builtins.get(name) {
+            let mut stdout = Vec::new();
+            let mut stderr = Vec::new();
+            let mut call = BuiltinCall {
+                args,
+                stdin: stdin.unwrap_or_default(),
+                env: self.env,
+                cwd: self.cwd,
+                fs: self.fs,
+                stdout: &mut stdout,
+                stderr: &mut stderr,
+            };
+            let exit_code = builtin(&mut call).map_err(ShellError::Io)?;
+            return Ok(RunResult {
+                exit_code,
+                stdout,
+                stderr,
+            });
+        }
+        if let Some(builtin) = Builtin::from_name(name) {
+            let mut stdout = Vec::new();
+            let mut stderr = Vec::new();
+            let control = {
+                let mut io = IoStreams {
+                    stdout: &mut stdout,
+                    stderr: &mut stderr,
+                };
+                let ctx = BuiltinContext {
+                    cwd: self.cwd,
+                    fs: self.fs,
+                    runner: self,
+                    deadline: self.limits.deadline,
+                };
+                let mut stdin = stdin;
+                let stdin = stdin.as_mut().map(|data| data as &mut dyn BufRead);
+                builtins::run_builtin_with_input(builtin, args, stdin, ctx, &mut io)?
+            };
+            let exit_code = match control {
+                ShellControl::Continue(code) | ShellControl::Exit(code) => code,
+            };
+            return Ok(RunResult {
+                exit_code,
+                stdout,
+                stderr,
+            });
+        }
+
+        if let Some(restrictions) = self.restrictions {
+            restrictions.check_program(name)?;
+        }
+        let program = self
+            .commands
+            .lookup(name, self.env, self.cwd, self.executor)
+            .ok_or_else(|| executor::not_found_error(name, self.env, self.cwd))?;
+        let command = ProcessCommand {
+            program: &program,
+            args,
+            env: self.env,
+            cwd: self.cwd,
+            inherit_fds: &[],
+            limits: self.limits,
+        };
+        // Вложенная команда не должна читать ввод REPL: без данных stdin пустой.
+        let stdin = ProcessInput::Bytes(stdin.unwrap_or_default().to_vec());
+        executor::run_program(self.executor, command, stdin, ProcessOutput::Capture)
     }
 }
 
-/// Запускает REPL поверх заданных потоков ввода/вывода.
-pub(crate) fn run_repl<R: std::io::Read, W1: std::io::Write, W2: std::io::Write>(
+/// Запускает REPL поверх заданных потоков ввода/вывода с параметрами `startup`;
+/// внешние программы запускает `executor` (`StdProcessExecutor` или, например,
+/// `mock::MockExecutor`).
+///
+/// Перед чтением ввода выполняет стартовый файл (см. `Startup::startup_file`);
+/// `exit` в нем завершает шелл. Интерактивный шелл после стартового файла читает
+/// историю из `HISTFILE` (по умолчанию `~/.se_cli_history`) и записывает ее
+/// туда при выходе.
+pub(crate) fn run_repl_with_executor<R: std::io::Read, W1: std::io::Write, W2: std::io::Write>(
     input: R,
     mut output: W1,
     mut error: W2,
+    startup: Startup,
+    executor: SharedExecutor,
 ) -> i32 {
     let mut state = ShellState::new_from_process_env();
-    let executor = StdProcessExecutor::new();
+    state.options = startup.options;
+    state.options.histexpand = startup.interactive;
+    if !startup.interactive {
+        state.options.timeout = startup.timeout;
+    }
+    state.restrictions = startup
+        .restricted
+        .clone()
+        .map(|restrictions| Arc::new(restrictions.resolve(&state.cwd)));
+    if let Some(restrictions) = &state.restrictions {
+        state.fs = Arc::new(RootedFs::new(state.fs.clone(), restrictions.root.clone()));
+    }
     let mut io = IoStreams {
         stdout: &mut output,
         stderr: &mut error,
     };
 
-    let reader = std::io::BufReader::new(input);
-    for line in reader.lines() {
-        let line = match line {
-            Ok(l) => l,
+    if startup.interactive
+        && !state.env.contains_key("HISTFILE")
+        && let Some(home) = state.env.get("HOME")
+    {
+        let path = Path::new(home).join(".se_cli_history");
+        let path = path.to_string_lossy().into_owned();
+        state.env.insert("HISTFILE".to_string(), path);
+    }
+
+    if let Some(path) = startup.startup_file(&state.env) {
+        match std::fs::File::open(state.cwd.join(&path)) {
+            Ok(file) => {
+                let lines = std::io::BufReader::new(file).lines();
+                let origin = path.to_string_lossy();
+                match run_lines(
+                    &executor,
+                    &mut state,
+                    lines,
+                    Some(&origin),
+                    &mut ListInput::Inherit,
+                    &mut io,
+                ) {
+                    Ok(ShellControl::Continue(_)) => {}
+                    Ok(ShellControl::Exit(code)) => return code,
+                    Err(e) => {
+                        let _ = writeln!(io.stderr, "{e}");
+                        return 1;
+                    }
+                }
+            }
             Err(e) => {
-                let _ = writeln!(io.stderr, "I/O error: {e}");
-                return 1;
+                let _ = writeln!(
+                    io.stderr,
+                    "se-cli: {}: {}",
+                    path.display(),
+                    builtins::io_error_message(&e)
+                );
             }
+        }
+    }
+
+    if startup.interactive
+        && let Some(path) = state.history_file()
+        && let Ok(text) = std::fs::read_to_string(path)
+    {
+        state.history.load(&text, &state.env);
+    }
+
+    let reader = std::io::BufReader::new(input);
+    let code = match run_lines(
+        &executor,
+        &mut state,
+        reader.lines(),
+        None,
+        &mut ListInput::Inherit,
+        &mut io,
+    ) {
+        // На этом этапе не ведём глобальный "$?": конец ввода — код 0.
+        Ok(ShellControl::Continue(_)) => 0,
+        Ok(ShellControl::Exit(code)) => code,
+        Err(e) => {
+            let _ = writeln!(io.stderr, "{e}");
+            1
+        }
+    };
+
+    if startup.interactive
+        && let Some(path) = state.history_file()
+        && let Err(e) = std::fs::write(&path, state.history.to_file_text())
+    {
+        let _ = writeln!(
+            io.stderr,
+            "se-cli: {}: {}",
+            path.display(),
+            builtins::io_error_message(&e)
+        );
+    }
+    code
+}
+
+/// Выполняет строки `lines` по одной, как REPL: ошибка строки печатается в
+/// stderr, и выполнение продолжается со следующей строки.
+///
+/// `origin` — имя файла (для `source` и стартового файла): ошибки печатаются
+/// как `FILE: line N: ...`. Строки ввода REPL (`origin` — `None`) записываются
+/// в историю, а при `set -H` (по умолчанию в интерактивном шелле) сначала
+/// проходят подстановки истории (`!!`, `^old^new`; результат печатается в stderr). Возвращает `Exit` после `exit` или ошибки под
+/// `set -e` (синтаксис — код 2, иначе 1), иначе `Continue` с кодом последней
+/// строки. Ошибка чтения строк возвращается как `ShellError::Io`.
+fn run_lines<I>(
+    executor: &SharedExecutor,
+    state: &mut ShellState,
+    lines: I,
+    origin: Option<&str>,
+    input: &mut ListInput,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl>
+where
+    I: Iterator<Item = std::io::Result<String>>,
+{
+    let line_no = Cell::new(0);
+    let mut lines = lines.inspect(|_| line_no.set(line_no.get() + 1));
+    let mut last = 0;
+    while let Some(line) = lines.next() {
+        let line = line.map_err(ShellError::Io)?;
+        let number = line_no.get();
+        let line = if origin.is_none() {
+            let expanded = if state.options.histexpand {
+                history::expand(&line, &state.history)
+            } else {
+                Ok(None)
+            };
+            let line = match expanded {
+                Ok(None) => line,
+                Ok(Some(expanded)) => {
+                    writeln!(io.stderr, "{expanded}").map_err(ShellError::Io)?;
+                    expanded
+                }
+                Err(e) => {
+                    writeln!(io.stderr, "{e}").map_err(ShellError::Io)?;
+                    last = 1;
+                    continue;
+                }
+            };
+            state
+                .history
+                .record(line.trim_end(), SystemTime::now(), &state.env);
+            line
+        } else {
+            line
         };
 
-        match run_single_line(&executor, &mut state, &line, &mut io) {
-            Ok(ShellControl::Continue(_code)) => {
-                // На этом этапе не ведём глобальный "$?".
-            }
-            Ok(ShellControl::Exit(code)) => return code,
+        // Тела here-documents — следующие строки ввода, по порядку операторов `<<`.
+        let mut bodies = Vec::new();
+        for spec in pending_heredocs(line.trim()) {
+            bodies.push(read_heredoc_body(&mut lines, &spec, io).map_err(ShellError::Io)?);
+        }
+
+        match run_single_line(executor, state, &line, &bodies, input, io) {
+            Ok(ShellControl::Continue(code)) => last = code,
+            Ok(ShellControl::Exit(code)) => return Ok(ShellControl::Exit(code)),
             Err(e) => {
-                let _ = writeln!(io.stderr, "{e}");
+                match origin {
+                    Some(origin) => writeln!(io.stderr, "{origin}: line {number}: {e}"),
+                    None => writeln!(io.stderr, "{e}"),
+                }
+                .map_err(ShellError::Io)?;
+                last = match e {
+                    ShellError::Parse(e) if !matches!(e.kind, ParseErrorKind::UnsetVariable(_)) => {
+                        2
+                    }
+                    _ => 1,
+                };
+                // `set -e`: ошибка строки завершает шелл.
+                if state.options.errexit {
+                    return Ok(ShellControl::Exit(last));
+                }
             }
         }
     }
+    Ok(ShellControl::Continue(last))
+}
 
-    0
+/// Читает тело here-document до строки-терминатора (сам терминатор не входит).
+///
+/// Для `<<-` ведущие табуляции срезаются и у строк тела, и у терминатора.
+/// Если ввод закончился раньше терминатора, печатает предупреждение (как bash)
+/// и возвращает прочитанное.
+fn read_heredoc_body<I>(
+    lines: &mut I,
+    spec: &HeredocSpec,
+    io: &mut IoStreams<'_>,
+) -> std::io::Result<String>
+where
+    I: Iterator<Item = std::io::Result<String>>,
+{
+    let mut body = String::new();
+    for line in lines.by_ref() {
+        let line = line?;
+        let line = if spec.strip_tabs {
+            line.trim_start_matches('\t')
+        } else {
+            line.as_str()
+        };
+        if line == spec.delimiter {
+            return Ok(body);
+        }
+        body.push_str(line);
+        body.push('\n');
+    }
+    writeln!(
+        io.stderr,
+        "warning: here-document delimited by end-of-file (wanted `{}`)",
+        spec.delimiter
+    )?;
+    Ok(body)
 }
 
-/// Обрабатывает одну строку ввода: trim → parse → apply env → execute.
+/// Обрабатывает одну строку ввода: trim → parse → execute.
 ///
+/// `heredoc_bodies` — уже прочитанные тела here-documents этой строки,
+/// `input` — вход команд строки. С опциями `dump_tokens` / `dump_ast` перед выполнением печатает в stderr
+/// токены и разобранный список команд.
 /// Возвращает управляющее действие (продолжить или выйти) либо ошибку,
-/// которую REPL напечатает в stderr.
+/// которую REPL напечатает в stderr; ошибки подстановок, которые возникают
+/// уже при выполнении, указывают место в этой строке.
 fn run_single_line(
-    executor: &StdProcessExecutor,
+    executor: &SharedExecutor,
     state: &mut ShellState,
     line: &str,
+    heredoc_bodies: &[String],
+    input: &mut ListInput,
     io: &mut IoStreams<'_>,
 ) -> ShellResult<ShellControl> {
-    // Выполняет одну введенную строку: trim -> parse -> apply env -> builtin/external.
+    // Выполняет одну введенную строку: trim -> parse -> список команд.
     let trimmed = line.trim();
     if trimmed.is_empty() {
         return Ok(ShellControl::Continue(0));
     }
 
-    let parsed = parse_line(trimmed, &state.env).map_err(ShellError::Parse)?;
-    state.apply_assignments(&parsed.assignments);
+    let options = state.options;
+    if options.dump_tokens
+        && let Ok(tokens) = parser::tokenize(trimmed)
+    {
+        let dump = debugparse::dump_tokens(&tokens, options.dump_format);
+        io.stderr
+            .write_all(dump.as_bytes())
+            .map_err(ShellError::Io)?;
+    }
+    let list = parse_line_with_heredocs(trimmed, heredoc_bodies, &state.aliases)
+        .map_err(ShellError::Parse)?;
+    if options.dump_ast {
+        let dump = debugparse::dump_ast(&list, options.dump_format);
+        io.stderr
+            .write_all(dump.as_bytes())
+            .map_err(ShellError::Io)?;
+    }
 
-    let Some(pipeline) = parsed.pipeline else {
-        return Ok(ShellControl::Continue(0));
-    };
+    run_list(executor, state, list, input, io).map_err(|e| match e {
+        ShellError::Parse(mut e) if e.source.is_empty() => {
+            e.source = trimmed.into();
+            ShellError::Parse(e)
+        }
+        e => e,
+    })
+}
+
+/// Слово для трассировки `set -x`: в одинарных кавычках, если в нем есть
+/// пробелы или спецсимволы шелла (как печатает bash).
+fn trace_quote(word: &str) -> String {
+    let plain = |c: char| c.is_alphanumeric() || "_-./=:,+@%^".contains(c);
+    if !word.is_empty() && word.chars().all(plain) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
 
-    run_pipeline(executor, state, pipeline, io)
+/// Вход списка команд (строки, группы или стадии пайплайна).
+///
+/// Данные из пайпа или перенаправления достаются первой команде, которая читает
+/// stdin; следующие получают пустой ввод (как при общем дескрипторе в bash).
+/// Вход стадии пайплайна builtins читают потоком (`ListInput::reader`): `head`
+/// дочитывает только нужные строки, остаток достается следующей команде.
+enum ListInput {
+    /// Вход не перенаправлен: внешние команды наследуют stdin шелла.
+    Inherit,
+    /// Данные еще не прочитаны.
+    Pending(Vec<u8>),
+    /// Выход предыдущей стадии пайплайна, читаемый по мере надобности.
+    Pipe(BufReader<os_pipe::PipeReader>),
+    /// Данные уже достались другой команде.
+    Consumed,
+}
+
+impl ListInput {
+    /// Возвращает stdin для команды целиком; `reads_stdin` — читает ли она его.
+    ///
+    /// Пайп читается до конца; ошибка чтения считается концом входа.
+    fn take(&mut self, reads_stdin: bool) -> Option<Vec<u8>> {
+        match self {
+            ListInput::Inherit => None,
+            _ if !reads_stdin => None,
+            ListInput::Pending(data) => {
+                let data = std::mem::take(data);
+                *self = ListInput::Consumed;
+                Some(data)
+            }
+            ListInput::Pipe(reader) => {
+                let mut data = Vec::new();
+                let _ = reader.read_to_end(&mut data);
+                Some(data)
+            }
+            ListInput::Consumed => Some(Vec::new()),
+        }
+    }
+
+    /// Возвращает stdin для builtin в виде потока: пайп не читается заранее.
+    fn reader(&mut self, reads_stdin: bool) -> Option<Box<dyn BufRead + '_>> {
+        match self {
+            ListInput::Inherit => None,
+            _ if !reads_stdin => None,
+            ListInput::Pipe(reader) => Some(Box::new(reader)),
+            ListInput::Pending(data) => {
+                let data = std::mem::take(data);
+                *self = ListInput::Consumed;
+                Some(Box::new(std::io::Cursor::new(data)))
+            }
+            ListInput::Consumed => Some(Box::new(std::io::empty())),
+        }
+    }
+}
+
+/// Выполняет список команд с учетом `;`, `&&` и `||`.
+///
+/// Присваивания-префиксы элемента раскрываются и применяются к состоянию по
+/// одному слева направо, только если элемент выполняется. Внутренняя ошибка запуска (`ShellError::Process`) печатается в
+/// stderr и считается кодом 127, чтобы `cmd || fallback` продолжал работать
+/// (`command not found` и т.п. печатает сама команда, см. `ShellState::spawn_failure`). Возвращает код последней
+/// выполненной команды.
+///
+/// При `set -e` ненулевой код элемента завершает шелл (`ShellControl::Exit`),
+/// если после элемента не идет `&&` или `||`: код проверяемой команды — не ошибка.
+fn run_list(
+    executor: &SharedExecutor,
+    state: &mut ShellState,
+    list: CommandList,
+    input: &mut ListInput,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let mut last = 0;
+    let mut entries = list.entries.into_iter().peekable();
+    while let Some(entry) = entries.next() {
+        let run = match entry.connector {
+            Connector::Always => true,
+            Connector::IfSuccess => last == 0,
+            Connector::IfFailure => last != 0,
+        };
+        if !run {
+            continue;
+        }
+
+        for (name, word) in &entry.assignments {
+            let value = expand::expand_assignment(state, name, word)?;
+            if state.options.dump_expanded {
+                let dump =
+                    debugparse::dump_expanded_assignment(name, &value, state.options.dump_format);
+                io.stderr
+                    .write_all(dump.as_bytes())
+                    .map_err(ShellError::Io)?;
+            }
+            if let Some(trace) = state.trace_line([format!("{name}={}", trace_quote(&value))]) {
+                io.stderr
+                    .write_all(trace.as_bytes())
+                    .map_err(ShellError::Io)?;
+            }
+            state.apply_assignments(&[(name.clone(), value)])?;
+        }
+        let Some(pipeline) = entry.pipeline else {
+            last = 0;
+            continue;
+        };
+        match run_pipeline(executor, state, pipeline, input, io) {
+            Ok(ShellControl::Continue(code)) => last = code,
+            Ok(ShellControl::Exit(code)) => return Ok(ShellControl::Exit(code)),
+            Err(e @ ShellError::Process(_)) => {
+                writeln!(io.stderr, "{e}").map_err(ShellError::Io)?;
+                last = 127;
+                state.set_pipestatus(&[last]);
+            }
+            Err(e) => return Err(e),
+        }
+
+        let checked = entries
+            .peek()
+            .is_some_and(|next| next.connector != Connector::Always);
+        if state.options.errexit && last != 0 && !checked {
+            return Ok(ShellControl::Exit(last));
+        }
+    }
+    Ok(ShellControl::Continue(last))
 }
 
 /// Выполняет распарсенный pipeline.
 ///
+/// Одиночная команда раскрывается (см. `expand`) и выполняется в текущем
+/// состоянии. Стадии настоящего пайпа раскрываются каждая в своей копии
+/// состояния, как в подоболочках bash: `echo $((i++)) | cat` не меняет `i`.
+///
 /// `stdout` конвейера — это stdout последней команды. `stderr` каждой команды
-/// выводится напрямую в `io.stderr` (не является частью пайпа).
+/// выводится напрямую в `io.stderr` (не является частью пайпа). Коды стадий
+/// попадают в `PIPESTATUS`; код пайплайна — код последней стадии, а при
+/// `set -o pipefail` — последний ненулевой код (0, если все стадии успешны).
+///
+/// С общим сроком (`--timeout`) внешние программы пайплайна, не завершившиеся
+/// за этот срок, убиваются (код 124); срок отсчитывается от начала пайплайна.
 fn run_pipeline(
-    executor: &StdProcessExecutor,
+    executor: &SharedExecutor,
     state: &mut ShellState,
     pipeline: Pipeline,
+    input: &mut ListInput,
     io: &mut IoStreams<'_>,
 ) -> ShellResult<ShellControl> {
+    if let Some(timeout) = state.options.timeout
+        && state.limits.deadline.is_none()
+    {
+        return state.with_timeout(timeout, |state| {
+            run_pipeline(executor, state, pipeline, input, io)
+        });
+    }
     if pipeline.commands.len() == 1 {
-        return run_single_command(
-            executor,
-            state,
-            pipeline.commands.into_iter().next().unwrap(),
-            io,
-        );
+        let command = pipeline.commands.into_iter().next().unwrap();
+        let stage = expand::expand_command(state, command)?;
+        dump_expanded(state, &stage, io)?;
+        return run_stage(executor, state, stage, input, io);
+    }
+
+    let mut stages = Vec::with_capacity(pipeline.commands.len());
+    for command in pipeline.commands {
+        let mut stage_state = state.clone();
+        let stage = expand::expand_command(&mut stage_state, command)?;
+        dump_expanded(state, &stage, io)?;
+        stages.push((stage_state, stage));
+    }
+    if let Some(restrictions) = &state.restrictions {
+        for (stage_state, stage) in &stages {
+            restrictions.check_command(stage, stage_state)?;
+        }
     }
 
     // `exit` внутри пайпа считаем ошибкой: не завершаем REPL внезапно.
-    if pipeline.commands.iter().any(|c| c.name == "exit") {
+    if stages
+        .iter()
+        .any(|(_, stage)| matches!(stage, Stage::Simple(spec) if spec.name == "exit"))
+    {
         writeln!(io.stderr, "exit: cannot be used in pipeline").map_err(ShellError::Io)?;
         return Ok(ShellControl::Continue(2));
     }
 
-    run_pipeline_with_os_pipes(state, pipeline, io)
+    let first_reads = match &stages[0].1 {
+        Stage::Simple(spec) => state.reads_stdin(&spec.name, &spec.args),
+        Stage::Group(_) => true,
+        Stage::Conditional(_) => false,
+    };
+    let first_input = input.take(first_reads);
+    let codes = run_pipeline_with_os_pipes(executor, stages, first_input, io)?;
+    state.set_pipestatus(&codes);
+    let code = if state.options.pipefail {
+        codes.iter().rev().find(|&&code| code != 0)
+    } else {
+        codes.last()
+    };
+    Ok(ShellControl::Continue(code.copied().unwrap_or(0)))
+}
+
+/// `set -o debug-expand`: печатает в stderr стадию после подстановок.
+fn dump_expanded(state: &ShellState, stage: &Stage, io: &mut IoStreams<'_>) -> ShellResult<()> {
+    if state.options.dump_expanded {
+        let dump = debugparse::dump_stage(stage, state.options.dump_format);
+        io.stderr
+            .write_all(dump.as_bytes())
+            .map_err(ShellError::Io)?;
+    }
+    Ok(())
 }
 
+/// Выполняет раскрытую команду в текущем состоянии как пайплайн из одной
+/// стадии: с проверкой `--restricted` и записью кода в `PIPESTATUS`.
+fn run_stage(
+    executor: &SharedExecutor,
+    state: &mut ShellState,
+    stage: Stage,
+    input: &mut ListInput,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    if let Some(restrictions) = &state.restrictions {
+        restrictions.check_command(&stage, state)?;
+    }
+    let control = run_command(executor, state, stage, input, io)?;
+    if let ShellControl::Continue(code) = control {
+        state.set_pipestatus(&[code]);
+    }
+    Ok(control)
+}
+
+/// Выполняет команду пайплайна: простую команду, группу или `[[ ]]`.
+fn run_command(
+    executor: &SharedExecutor,
+    state: &mut ShellState,
+    stage: Stage,
+    input: &mut ListInput,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    match stage {
+        Stage::Simple(spec) => run_simple_command(executor, state, spec, input, io),
+        Stage::Group(group) => run_group(executor, state, group, input, io),
+        Stage::Conditional(conditional) => run_conditional(executor, state, conditional, io),
+    }
+}
+
+/// Код стадии пайплайна, писавшей в закрытый pipe (как смерть от SIGPIPE, 128 + 13).
+const BROKEN_PIPE_STATUS: i32 = 141;
+
 struct StageResult {
     exit_code: i32,
     stderr: Vec<u8>,
@@ -144,16 +857,18 @@ struct StageResult {
 /// Выполняет пайплайн через реальные OS-pipe'ы.
 ///
 /// Стадии запускаются параллельно, чтобы избежать блокировок при заполнении буферов.
+/// Каждая стадия работает со своей копией состояния шелла (`stages`).
+/// `first_input` — stdin первой стадии (вход группы, в которой стоит пайплайн).
+/// Возвращает коды стадий по порядку.
 fn run_pipeline_with_os_pipes(
-    state: &ShellState,
-    pipeline: Pipeline,
+    executor: &SharedExecutor,
+    stages: Vec<(ShellState, Stage)>,
+    first_input: Option<Vec<u8>>,
     io: &mut IoStreams<'_>,
-) -> ShellResult<ShellControl> {
-    let n = pipeline.commands.len();
+) -> ShellResult<Vec<i32>> {
+    let n = stages.len();
     debug_assert!(n >= 2);
 
-    let env = Arc::new(state.env.clone());
-
     // Между стадиями: N-1 pipe'ов stdout->stdin.
     let mut readers: Vec<Option<os_pipe::PipeReader>> = Vec::with_capacity(n - 1);
     let mut writers: Vec<Option<os_pipe::PipeWriter>> = Vec::with_capacity(n - 1);
@@ -167,14 +882,22 @@ fn run_pipeline_with_os_pipes(
     let (mut final_out_reader, final_out_writer) = os_pipe::pipe().map_err(ShellError::Io)?;
     let mut final_out_writer = Some(final_out_writer);
 
+    let mut first_input = first_input;
     let mut handles = Vec::with_capacity(n);
 
-    for (idx, command) in pipeline.commands.into_iter().enumerate() {
-        let stdin_pipe = if idx == 0 {
+    for (idx, (state, stage)) in stages.into_iter().enumerate() {
+        // Here-document/here-string заменяет вход из предыдущей стадии.
+        let here_input = match &stage {
+            Stage::Simple(spec) => spec.here_input.is_some(),
+            Stage::Group(group) => group.here_input.is_some(),
+            Stage::Conditional(_) => false,
+        };
+        let stdin_pipe = if idx == 0 || here_input {
             None
         } else {
             readers[idx - 1].take()
         };
+        let input = first_input.take();
         let stdout_pipe = if idx + 1 == n {
             final_out_writer
                 .take()
@@ -185,97 +908,27 @@ fn run_pipeline_with_os_pipes(
                 .expect("writer for stage taken exactly once")
         };
 
-        let env = Arc::clone(&env);
+        let executor = Arc::clone(executor);
         handles.push(std::thread::spawn(move || -> ShellResult<StageResult> {
-            if let Some(builtin) = Builtin::from_name(&command.name) {
-                // Builtin запускаем в потоке. stdin читаем из pipe целиком.
-                let input = if let Some(mut r) = stdin_pipe {
-                    let mut buf = Vec::new();
-                    r.read_to_end(&mut buf).map_err(ShellError::Io)?;
-                    Some(buf)
-                } else {
-                    None
-                };
-
-                let mut out = Vec::new();
-                let mut err = Vec::new();
+            match stage {
+                Stage::Simple(spec)
+                    if spec.redirects.is_empty()
+                        && spec.process_substs.is_empty()
+                        && runs_external(&spec, &state, executor.as_ref()) =>
                 {
-                    let mut local_io = IoStreams {
-                        stdout: &mut out,
-                        stderr: &mut err,
-                    };
-                    let control = builtins::run_builtin_with_input(
-                        builtin,
-                        &command.args,
-                        input.as_deref(),
-                        &mut local_io,
-                    )?;
-                    let exit_code = match control {
-                        ShellControl::Continue(code) => code,
-                        ShellControl::Exit(code) => code,
-                    };
-
-                    // stdout builtin'а — в stdout pipe.
-                    let mut w = stdout_pipe;
-                    w.write_all(&out).map_err(ShellError::Io)?;
-                    drop(w);
-
-                    return Ok(StageResult {
-                        exit_code,
-                        stderr: err,
-                    });
-                }
-            }
-
-            // External stage.
-            let mut cmd = std::process::Command::new(&command.name);
-            cmd.args(&command.args);
-            cmd.env_clear();
-            cmd.envs(env.iter());
-
-            if let Some(r) = stdin_pipe {
-                cmd.stdin(Stdio::from(r));
-            } else {
-                // В первом элементе пайплайна stdin пока не поддерживаем (нет редиректов),
-                // чтобы REPL-ввод не смешивался с stdin команды.
-                cmd.stdin(Stdio::null());
-            }
-            cmd.stdout(Stdio::from(stdout_pipe));
-            cmd.stderr(Stdio::piped());
-
-            let mut child = cmd.spawn().map_err(|e| {
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    ShellError::Process(format!("command not found: {}", command.name))
-                } else {
-                    ShellError::Process(format!("failed to spawn {}: {e}", command.name))
+                    run_external_stage(
+                        executor.as_ref(),
+                        spec,
+                        &state,
+                        stdin_pipe,
+                        input,
+                        stdout_pipe,
+                    )
                 }
-            })?;
-
-            let mut child_stderr = child
-                .stderr
-                .take()
-                .ok_or_else(|| ShellError::Process("failed to capture stderr".to_string()))?;
-
-            let stderr_handle = std::thread::spawn(move || -> std::io::Result<Vec<u8>> {
-                let mut buf = Vec::new();
-                child_stderr.read_to_end(&mut buf)?;
-                Ok(buf)
-            });
-
-            let status = child.wait().map_err(ShellError::Io)?;
-            let exit_code = status.code().unwrap_or(1);
-
-            let stderr = match stderr_handle.join() {
-                Ok(Ok(buf)) => buf,
-                Ok(Err(e)) => return Err(ShellError::Io(e)),
-                Err(_) => {
-                    return Err(ShellError::Process(
-                        "stderr reader thread panicked".to_string(),
-                    ));
+                stage => {
+                    run_buffered_stage(&executor, stage, state, stdin_pipe, input, stdout_pipe)
                 }
-            };
-
-            Ok(StageResult { exit_code, stderr })
+            }
         }));
     }
 
@@ -301,26 +954,409 @@ fn run_pipeline_with_os_pipes(
     }
     io.stdout.write_all(&final_stdout).map_err(ShellError::Io)?;
 
-    let last_exit = results.last().map(|r| r.exit_code).unwrap_or(0);
-    Ok(ShellControl::Continue(last_exit))
+    Ok(results.iter().map(|r| r.exit_code).collect())
+}
+
+/// Запускает ли команда внешнюю программу (с учетом префикса `command`).
+fn runs_external(spec: &CommandSpec, state: &ShellState, executor: &dyn ProcessExecutor) -> bool {
+    let mut target = spec.clone();
+    let bypass_builtins = strip_command_prefix(&mut target);
+    let name = target.name.as_str();
+    !name.is_empty()
+        && StateBuiltin::from_name(name).is_none()
+        && (!state.is_builtin(name)
+            || (bypass_builtins
+                && state
+                    .commands
+                    .lookup(name, &state.env, &state.cwd, executor)
+                    .is_some()))
 }
 
-fn run_single_command(
-    executor: &StdProcessExecutor,
+/// Стадия пайпа, исполняемая в шелле: builtin, группа или команда с перенаправлениями.
+///
+/// Builtins читают stdin из pipe потоком, остальные команды — целиком. Когда
+/// стадия завершается, pipe закрывается: предыдущая стадия, которая еще пишет
+/// (`yes | head -n 1`), получает SIGPIPE. Стадия, писавшая в закрытый pipe,
+/// завершается с кодом `BROKEN_PIPE_STATUS`.
+///
+/// Стадия работает с копией состояния: изменения (в т.ч. `cd` и присваивания
+/// в группах) не видны шеллу, `exit` завершает только стадию.
+fn run_buffered_stage(
+    executor: &SharedExecutor,
+    stage: Stage,
+    mut state: ShellState,
+    stdin_pipe: Option<os_pipe::PipeReader>,
+    input: Option<Vec<u8>>,
+    stdout_pipe: os_pipe::PipeWriter,
+) -> ShellResult<StageResult> {
+    // В первой стадии без входа stdin пуст, чтобы REPL-ввод не смешивался с stdin команды.
+    let mut input = match (stdin_pipe, input) {
+        (Some(reader), _) => ListInput::Pipe(BufReader::new(reader)),
+        (None, Some(data)) => ListInput::Pending(data),
+        (None, None) => ListInput::Consumed,
+    };
+
+    let mut out = stdout_pipe;
+    let mut err = Vec::new();
+    let control = {
+        let mut local_io = IoStreams {
+            stdout: &mut out,
+            stderr: &mut err,
+        };
+        match run_command(executor, &mut state, stage, &mut input, &mut local_io) {
+            Err(ShellError::Io(e)) if e.kind() == std::io::ErrorKind::BrokenPipe => {
+                ShellControl::Continue(BROKEN_PIPE_STATUS)
+            }
+            result => result?,
+        }
+    };
+    drop(out);
+
+    let (ShellControl::Continue(exit_code) | ShellControl::Exit(exit_code)) = control;
+    Ok(StageResult {
+        exit_code,
+        stderr: err,
+    })
+}
+
+/// Стадия пайпа — внешняя программа, подключенная к pipe'ам напрямую.
+fn run_external_stage(
+    executor: &dyn ProcessExecutor,
+    mut command: CommandSpec,
+    state: &ShellState,
+    stdin_pipe: Option<os_pipe::PipeReader>,
+    input: Option<Vec<u8>>,
+    stdout_pipe: os_pipe::PipeWriter,
+) -> ShellResult<StageResult> {
+    let input = command.here_input.take().map(String::into_bytes).or(input);
+    let words = std::iter::once(&command.name).chain(&command.args);
+    let trace = state.trace_line(words.map(|w| trace_quote(w)));
+    strip_command_prefix(&mut command);
+    // Трассировка `set -x` идет в stderr стадии перед выводом команды.
+    let mut stderr = trace.map(String::into_bytes).unwrap_or_default();
+
+    let stdin = match (stdin_pipe, input) {
+        (Some(r), _) => ProcessInput::Pipe(executor::PipeReader(r)),
+        (None, Some(data)) => ProcessInput::Bytes(data),
+        // В первом элементе пайплайна без входа stdin пуст,
+        // чтобы REPL-ввод не смешивался с stdin команды.
+        (None, None) => ProcessInput::Null,
+    };
+    let result = match state
+        .commands
+        .lookup(&command.name, &state.env, &state.cwd, executor)
+    {
+        Some(program) => {
+            let process = ProcessCommand {
+                program: &program,
+                args: &command.args,
+                env: &state.env,
+                cwd: &state.cwd,
+                inherit_fds: &[],
+                limits: &state.limits,
+            };
+            executor::run_program(
+                executor,
+                process,
+                stdin,
+                ProcessOutput::Pipe(executor::PipeWriter(stdout_pipe)),
+            )
+        }
+        None => Err(executor::not_found_error(
+            &command.name,
+            &state.env,
+            &state.cwd,
+        )),
+    };
+    match result {
+        Ok(result) => {
+            stderr.extend(result.stderr);
+            Ok(StageResult {
+                exit_code: result.exit_code,
+                stderr,
+            })
+        }
+        Err(ShellError::Spawn(e)) => {
+            let e = state.spawn_failure(&command.name, e);
+            writeln!(stderr, "{e}").map_err(ShellError::Io)?;
+            Ok(StageResult {
+                exit_code: e.exit_code(),
+                stderr,
+            })
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Открывает перенаправления команды; при ошибке печатает ее (код возврата 1).
+fn open_redirections(
+    redirects: &[Redirect],
+    state: &ShellState,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<Option<Redirections>> {
+    match Redirections::open(redirects, &state.cwd, state.fs.as_ref()) {
+        Ok(r) => Ok(Some(r)),
+        Err(msg) => {
+            writeln!(io.stderr, "{msg}").map_err(ShellError::Io)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Выполняет простую команду: state builtin, builtin или внешнюю программу
+/// (путь ищется в `PATH` и запоминается в таблице `hash`).
+///
+/// `command NAME args...` выполняет `NAME`, но builtin-утилита (`grep`, `cat`, ...)
+/// уступает одноименной программе из `PATH`; алиасы к `NAME` не применяются уже
+/// при разборе (он не стоит на месте имени команды).
+///
+/// Собственный вход команды (`< file`, here-document) заменяет вход `input`.
+/// Подстановки процессов запускаются перед командой и завершаются после нее.
+fn run_simple_command(
+    executor: &SharedExecutor,
     state: &mut ShellState,
-    command: CommandSpec,
+    mut command: CommandSpec,
+    input: &mut ListInput,
     io: &mut IoStreams<'_>,
 ) -> ShellResult<ShellControl> {
-    if let Some(builtin) = Builtin::from_name(&command.name) {
-        return builtins::run_builtin(builtin, &command.args, io);
+    let Some(mut redirections) = open_redirections(&command.redirects, state, io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+    // Все слова раскрылись в пустоту (`$EMPTY > file`): только перенаправления.
+    if command.name.is_empty() && !command.process_substs.iter().any(|s| s.word == 0) {
+        return Ok(ShellControl::Continue(0));
     }
+    let own_input = redirections
+        .stdin
+        .take()
+        .or_else(|| command.here_input.take().map(String::into_bytes));
 
-    let result = executor.run_external(&command.name, &command.args, &state.env, None)?;
-    io.stdout
-        .write_all(&result.stdout)
-        .map_err(ShellError::Io)?;
-    io.stderr
-        .write_all(&result.stderr)
-        .map_err(ShellError::Io)?;
-    Ok(ShellControl::Continue(result.exit_code))
+    let substs = if command.process_substs.is_empty() {
+        None
+    } else {
+        let mut words: Vec<String> = std::iter::once(std::mem::take(&mut command.name))
+            .chain(std::mem::take(&mut command.args))
+            .collect();
+        let substs = std::mem::take(&mut command.process_substs);
+        let running = RunningSubsts::start(substs, executor, state, &mut words)?;
+        command.name = words.remove(0);
+        command.args = words;
+        Some(running)
+    };
+    let inherit_fds = substs.as_ref().map(RunningSubsts::fds).unwrap_or_default();
+    // Трассировка — до перенаправлений команды, как в bash.
+    let words = std::iter::once(&command.name).chain(&command.args);
+    if let Some(trace) = state.trace_line(words.map(|w| trace_quote(w))) {
+        io.stderr
+            .write_all(trace.as_bytes())
+            .map_err(ShellError::Io)?;
+    }
+
+    let bypass_builtins = strip_command_prefix(&mut command);
+
+    let result = redirections.apply(io, |io| {
+        let utility = state.builtins.contains_key(&command.name)
+            || Builtin::from_name(&command.name).is_some();
+        let external = if bypass_builtins && utility {
+            state
+                .commands
+                .resolve(&command.name, &state.env, &state.cwd, executor.as_ref())
+        } else {
+            None
+        };
+        if external.is_none()
+            && let Some(builtin) = StateBuiltin::from_name(&command.name)
+        {
+            // Собственный вход (`source file < in`) заменяет вход списка.
+            let mut own_input = own_input.map(ListInput::Pending);
+            let ctx = state_builtins::StateContext {
+                executor,
+                input: own_input.as_mut().unwrap_or(input),
+            };
+            let restrictions = state.restrictions.clone();
+            let snapshot = restrictions.as_ref().map(|_| Restrictions::snapshot(state));
+            let control = state_builtins::run_state_builtin(
+                builtin,
+                &command.name,
+                &command.args,
+                state,
+                ctx,
+                io,
+            )?;
+            if let (Some(restrictions), Some(snapshot)) = (restrictions, snapshot) {
+                restrictions.verify(state, snapshot)?;
+            }
+            return Ok(control);
+        }
+
+        if external.is_none()
+            && let Some(builtin) = state.builtins.get(&command.name)
+        {
+            let stdin = own_input.or_else(|| input.take(true)).unwrap_or_default();
+            return embed::run_custom_builtin(builtin, &command.args, &stdin, state, io);
+        }
+
+        if external.is_none()
+            && let Some(builtin) = Builtin::from_name(&command.name)
+        {
+            let mut own_input = own_input.map(ListInput::Pending);
+            let mut stdin = own_input
+                .as_mut()
+                .unwrap_or(input)
+                .reader(builtin.reads_stdin(&command.args));
+            let runner = state.command_runner(executor.as_ref());
+            let ctx = BuiltinContext {
+                cwd: &state.cwd,
+                fs: state.fs.as_ref(),
+                runner: &runner,
+                deadline: state.limits.deadline,
+            };
+            return builtins::run_builtin_with_input(
+                builtin,
+                &command.args,
+                stdin.as_mut().map(|reader| reader as &mut dyn BufRead),
+                ctx,
+                io,
+            );
+        }
+
+        let program = match external {
+            Some(path) => Ok(path),
+            None => state.resolve_command(&command.name, executor.as_ref()),
+        };
+        let stdin = own_input
+            .or_else(|| input.take(true))
+            .map_or(ProcessInput::Inherit, ProcessInput::Bytes);
+        let result = program.and_then(|program| {
+            let process = ProcessCommand {
+                program: &program,
+                args: &command.args,
+                env: &state.env,
+                cwd: &state.cwd,
+                inherit_fds: &inherit_fds,
+                limits: &state.limits,
+            };
+            executor::run_program_streaming(executor.as_ref(), process, stdin, io.stdout)
+        });
+        let result = match result {
+            Ok(result) => result,
+            Err(ShellError::Spawn(e)) => {
+                let e = state.spawn_failure(&command.name, e);
+                writeln!(io.stderr, "{e}").map_err(ShellError::Io)?;
+                return Ok(ShellControl::Continue(e.exit_code()));
+            }
+            Err(e) => return Err(e),
+        };
+        io.stderr
+            .write_all(&result.stderr)
+            .map_err(ShellError::Io)?;
+        Ok(ShellControl::Continue(result.exit_code))
+    });
+
+    // Вывод подстановок идет в исходные потоки: как в bash, подстановка
+    // раскрывается до применения перенаправлений команды.
+    if let Some(substs) = substs {
+        substs.finish(io)?;
+    }
+    result
+}
+
+/// Снимает префиксы `command [--]` перед именем команды; `true`, если они были.
+///
+/// `command` без имени или с опцией (`-v`, `-V`) остается builtin `command`.
+fn strip_command_prefix(command: &mut CommandSpec) -> bool {
+    let mut stripped = false;
+    while command.name == "command" {
+        let skip = match command.args.first().map(String::as_str) {
+            Some("--") => 1,
+            Some(arg) if !arg.starts_with('-') => 0,
+            _ => break,
+        };
+        if command.args.len() <= skip {
+            break;
+        }
+        command.args.drain(..skip);
+        command.name = command.args.remove(0);
+        stripped = true;
+    }
+    stripped
+}
+
+/// Выполняет группу `( ... )` или `{ ...; }`.
+///
+/// Подоболочка работает с копией состояния: присваивания, `cd` и `exit` внутри нее
+/// не влияют на шелл. Группа в фигурных скобках выполняется в текущем состоянии.
+/// Перенаправления группы действуют на все ее команды.
+fn run_group(
+    executor: &SharedExecutor,
+    state: &mut ShellState,
+    group: GroupStage,
+    input: &mut ListInput,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(mut redirections) = open_redirections(&group.redirects, state, io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+    let own_input = redirections
+        .stdin
+        .take()
+        .or_else(|| group.here_input.map(String::into_bytes));
+    let mut group_input;
+    let input = match own_input {
+        Some(data) => {
+            group_input = ListInput::Pending(data);
+            &mut group_input
+        }
+        None => input,
+    };
+
+    redirections.apply(io, |io| match group.kind {
+        GroupKind::Subshell => {
+            let mut sub_state = state.clone();
+            let control = run_list(executor, &mut sub_state, group.body, input, io)?;
+            let (ShellControl::Continue(code) | ShellControl::Exit(code)) = control;
+            Ok(ShellControl::Continue(code))
+        }
+        GroupKind::Brace => run_list(executor, state, group.body, input, io),
+    })
+}
+
+/// Выполняет `[[ expr ]]`: перенаправления действуют на сообщения об ошибках
+/// выражения (`[[ a =~ ( ]] 2>/dev/null`).
+fn run_conditional(
+    executor: &SharedExecutor,
+    state: &mut ShellState,
+    conditional: ConditionalStage,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some(redirections) = open_redirections(&conditional.redirects, state, io)? else {
+        return Ok(ShellControl::Continue(1));
+    };
+    // Слова без кавычек (в т.ч. операторы `||`, `==`) печатаются как записаны.
+    let words = conditional.words.iter().map(|word| {
+        if word.operator {
+            word.text()
+        } else {
+            trace_quote(&word.text())
+        }
+    });
+    let words = std::iter::once("[[".to_string())
+        .chain(words)
+        .chain(std::iter::once("]]".to_string()));
+    if let Some(trace) = state.trace_line(words) {
+        io.stderr
+            .write_all(trace.as_bytes())
+            .map_err(ShellError::Io)?;
+    }
+
+    let runner = state.command_runner(executor.as_ref());
+    let ctx = BuiltinContext {
+        cwd: &state.cwd,
+        fs: state.fs.as_ref(),
+        runner: &runner,
+        deadline: state.limits.deadline,
+    };
+    redirections.apply(io, |io| {
+        builtins::run_conditional(&conditional.words, ctx, io)
+    })
 }
@@ -0,0 +1,233 @@
+//! Опции шелла: флаги командной строки и `set -o NAME` / `set +o NAME`.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{Parser, ValueEnum};
+
+use super::limits::parse_duration;
+use super::restrict::Restrictions;
+
+/// Формат отладочного вывода разбора.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum DumpFormat {
+    /// Indented tree.
+    #[default]
+    Pretty,
+    /// One JSON object per line.
+    Json,
+}
+
+/// Опции, влияющие на выполнение строк.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ShellOptions {
+    /// Печатать токены строки в stderr перед выполнением.
+    pub(crate) dump_tokens: bool,
+    /// Печатать разобранный список команд (слова до подстановок).
+    pub(crate) dump_ast: bool,
+    /// `set -o debug-expand`: печатать каждую команду и присваивание после
+    /// подстановок, перед запуском.
+    pub(crate) dump_expanded: bool,
+    pub(crate) dump_format: DumpFormat,
+    /// `set -e`: ненулевой код команды завершает шелл.
+    pub(crate) errexit: bool,
+    /// `set -u`: подстановка незаданной переменной — ошибка.
+    pub(crate) nounset: bool,
+    /// `set -o pipefail`: код пайплайна — последний ненулевой код стадии.
+    pub(crate) pipefail: bool,
+    /// `set -x`: перед выполнением команды печатать ее слова в stderr после `PS4`.
+    pub(crate) xtrace: bool,
+    /// `set -H`: подстановки истории (`!!`, `^old^new`) в строках ввода; по
+    /// умолчанию включены только в интерактивном шелле.
+    pub(crate) histexpand: bool,
+    /// Срок выполнения внешних программ каждого пайплайна (`--timeout` в
+    /// неинтерактивном шелле, `ShellBuilder::timeout`).
+    pub(crate) timeout: Option<Duration>,
+}
+
+/// Имена опций для `set -o` в порядке вывода.
+pub(crate) const OPTION_NAMES: &[&str] = &[
+    "debug-expand",
+    "debugparse",
+    "errexit",
+    "histexpand",
+    "nounset",
+    "pipefail",
+    "xtrace",
+];
+
+impl ShellOptions {
+    /// Имя опции для однобуквенного флага `set -e` / `set +x`.
+    pub(crate) fn short_name(flag: char) -> Option<&'static str> {
+        match flag {
+            'e' => Some("errexit"),
+            'H' => Some("histexpand"),
+            'u' => Some("nounset"),
+            'x' => Some("xtrace"),
+            _ => None,
+        }
+    }
+
+    /// Значение опции `set -o NAME`; `None` — нет такой опции.
+    pub(crate) fn get(&self, name: &str) -> Option<bool> {
+        match name {
+            "debug-expand" => Some(self.dump_expanded),
+            "debugparse" => Some(self.dump_tokens && self.dump_ast),
+            "errexit" => Some(self.errexit),
+            "histexpand" => Some(self.histexpand),
+            "nounset" => Some(self.nounset),
+            "pipefail" => Some(self.pipefail),
+            "xtrace" => Some(self.xtrace),
+            _ => None,
+        }
+    }
+
+    /// Включает или выключает опцию; возвращает `false`, если такой опции нет.
+    pub(crate) fn set(&mut self, name: &str, on: bool) -> bool {
+        match name {
+            "debug-expand" => self.dump_expanded = on,
+            "debugparse" => {
+                self.dump_tokens = on;
+                self.dump_ast = on;
+            }
+            "errexit" => self.errexit = on,
+            "histexpand" => self.histexpand = on,
+            "nounset" => self.nounset = on,
+            "pipefail" => self.pipefail = on,
+            "xtrace" => self.xtrace = on,
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Стартовый файл интерактивного шелла.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) enum RcFile {
+    /// `~/.se_clirc`, если он есть.
+    #[default]
+    Default,
+    /// `--rcfile FILE`.
+    Path(PathBuf),
+    /// `--norc`.
+    None,
+}
+
+/// Параметры запуска интерпретатора.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Startup {
+    pub(crate) options: ShellOptions,
+    pub(crate) rcfile: RcFile,
+    /// Интерактивный режим (`-i` или ввод с терминала): при запуске выполняется
+    /// rc-файл, иначе — файл из `$SE_CLI_ENV`.
+    pub(crate) interactive: bool,
+    /// Ограниченный режим (`--restricted`); корень может быть относительным.
+    pub(crate) restricted: Option<Restrictions>,
+    /// `--timeout DURATION`: действует, только если шелл неинтерактивный.
+    pub(crate) timeout: Option<Duration>,
+}
+
+impl Startup {
+    /// Разбирает аргументы командной строки интерпретатора (первый — имя программы).
+    pub(crate) fn from_args<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let cli = Cli::try_parse_from(args)?;
+        let rcfile = match (cli.norc, cli.rcfile) {
+            (true, _) => RcFile::None,
+            (false, Some(path)) => RcFile::Path(path),
+            (false, None) => RcFile::Default,
+        };
+        Ok(Startup {
+            options: ShellOptions {
+                dump_tokens: cli.dump_tokens,
+                dump_ast: cli.dump_ast,
+                dump_format: cli.dump_format,
+                ..ShellOptions::default()
+            },
+            rcfile,
+            interactive: cli.interactive,
+            restricted: cli.restricted.then(|| Restrictions {
+                root: cli.restricted_root.unwrap_or_else(|| PathBuf::from(".")),
+                allowed: cli.allow.into_iter().collect(),
+            }),
+            timeout: cli.timeout,
+        })
+    }
+
+    /// Файл, который шелл выполняет перед чтением ввода (`env` — окружение шелла).
+    ///
+    /// Интерактивный шелл выполняет `--rcfile FILE` или `$HOME/.se_clirc`, если он
+    /// есть (`--norc` — ничего). Неинтерактивный — файл из `$SE_CLI_ENV`, если
+    /// переменная задана и не пуста.
+    pub(crate) fn startup_file(&self, env: &HashMap<String, String>) -> Option<PathBuf> {
+        if !self.interactive {
+            return env
+                .get("SE_CLI_ENV")
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from);
+        }
+        match &self.rcfile {
+            RcFile::Default => {
+                let path = PathBuf::from(env.get("HOME")?).join(".se_clirc");
+                path.is_file().then_some(path)
+            }
+            RcFile::Path(path) => Some(path.clone()),
+            RcFile::None => None,
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "se-cli", about = "Simple command line interpreter")]
+struct Cli {
+    /// Run as an interactive shell (read the rc file) even if stdin is not a terminal.
+    #[arg(short, long)]
+    interactive: bool,
+
+    /// Run FILE instead of ~/.se_clirc when the shell is interactive.
+    #[arg(long, value_name = "FILE")]
+    rcfile: Option<PathBuf>,
+
+    /// Do not run any rc file at startup.
+    #[arg(long)]
+    norc: bool,
+
+    /// Print the tokens of each line to stderr before running it.
+    #[arg(long)]
+    dump_tokens: bool,
+
+    /// Print the parsed command list (words before expansion) to stderr before running it.
+    /// `set -o debug-expand` prints each command after expansion.
+    #[arg(long)]
+    dump_ast: bool,
+
+    /// Format of --dump-tokens, --dump-ast, `set -o debugparse` and `set -o debug-expand` output.
+    #[arg(long, value_enum, default_value_t = DumpFormat::Pretty)]
+    dump_format: DumpFormat,
+
+    /// Restricted mode: no `/` in command names, no output redirections, no changes
+    /// to PATH, SHELL, ENV or SE_CLI_ENV, no `cd` outside the root, only allowed programs.
+    #[arg(short, long)]
+    restricted: bool,
+
+    /// Directory `cd` may not leave in restricted mode (default: the current directory).
+    #[arg(long, value_name = "DIR", requires = "restricted")]
+    restricted_root: Option<PathBuf>,
+
+    /// External program that may run in restricted mode (repeatable).
+    #[arg(long, value_name = "NAME", requires = "restricted")]
+    allow: Vec<String>,
+
+    /// Kill external programs of a pipeline still running after DURATION (e.g. `30s`, `2m`)
+    /// with status 124; only for a non-interactive shell.
+    #[arg(long, value_name = "DURATION", value_parser = parse_timeout)]
+    timeout: Option<Duration>,
+}
+
+fn parse_timeout(text: &str) -> Result<Duration, String> {
+    parse_duration(text).ok_or_else(|| format!("invalid time interval '{text}'"))
+}
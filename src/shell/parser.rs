@@ -1,286 +1,1030 @@
 //! Разбор командной строки.
 //!
+//! Строка режется на токены и разбирается в список команд целиком, но слова
+//! остаются как записаны (`wordexp::Word`): подстановки выполняет `expand`
+//! перед запуском каждой команды. Результат подстановки никогда не разбирается
+//! как синтаксис (`X="a|b"; echo $X` печатает `a|b`, а не запускает пайп). У
+//! токенов, слов и их частей есть байтовые позиции в строке (`Span`) — по ним
+//! ошибки (в том числе ошибки подстановок) показывают место с `^`.
+//!
 //! Поддерживает:
 //! - разделение на аргументы по пробелам
 //! - одинарные и двойные кавычки (кавычки убираются)
-//! - присваивания окружения `NAME=value` (в начале строки, в любом количестве)
-//! - подстановки `$NAME` (в обычном режиме и в двойных кавычках)
-//! - пайпы `|` (вне кавычек)
+//! - присваивания окружения `NAME=value` (в начале команды, в любом количестве)
+//! - раскрытие фигурных скобок `{a,b}`, `{1..10}` и тильды `~` (см. `wordexp`)
+//! - подстановки `$NAME` и позиционных параметров `$1`…`$9` (в обычном режиме
+//!   и в двойных кавычках)
+//! - комментарии `# ...` (с `#` в начале слова до конца строки)
+//! - арифметические подстановки `$((выражение))`
+//! - разбиение результатов подстановок вне кавычек на поля по `IFS`
+//! - пайпы `|` (вне кавычек и вне `[[ ... ]]` / `(( ... ))`)
+//! - списки команд `;`, `&&`, `||`
+//! - подоболочки `( ... )` и группы `{ ...; }`
+//! - перенаправления `<`, `>`, `>>`, `2>`, `2>>`, `&>`, `&>>`, `2>&1`, `>&2`
+//! - подстановки процессов `<(list)` и `>(list)`
+//! - here-documents `<<DELIM`, `<<-DELIM` и here-strings `<<<word`
+//! - алиасы: первое слово команды заменяется токенами текста алиаса
 
 use std::fmt;
 
 use std::collections::HashMap;
 
-use super::types::{CommandSpec, Pipeline};
+use super::arith::ArithError;
+use super::types::{
+    Command, CommandList, ConditionalCommand, Connector, GroupKind, GroupSpec, HereInput,
+    ListEntry, Pipeline, ProcessSubst, Redirect, SimpleCommand, Span,
+};
+use super::wordexp::Word;
 
-/// Результат парсинга одной строки.
+/// Вид ошибки парсинга.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct ParsedLine {
-    pub(crate) assignments: Vec<(String, String)>,
-    pub(crate) pipeline: Option<Pipeline>,
-}
-
-/// Ошибка парсинга.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) enum ParseError {
+pub(crate) enum ParseErrorKind {
     /// В строке есть незакрытая кавычка.
     UnclosedQuote(char),
     /// Пайп встречен там, где ожидается команда.
     EmptyPipelineSegment,
+    /// Нет закрывающих `))` у `$((`.
+    UnterminatedArithmetic,
+    /// Ошибка в арифметической подстановке `$((...))`.
+    Arithmetic(ArithError),
+    /// После оператора (`<<`, `<<<`, `>`, `<`) нет слова.
+    MissingRedirectWord(&'static str),
+    /// Токен в неожиданном месте (например `)` без `(`).
+    UnexpectedToken(String),
+    /// Строка закончилась там, где ожидается продолжение (после `&&`, внутри `( ... )`).
+    UnexpectedEnd,
+    /// Цель перенаправления после подстановок — не ровно одно слово.
+    AmbiguousRedirect(String),
+    /// `set -u`: подстановка незаданной переменной.
+    UnsetVariable(String),
 }
 
-impl fmt::Display for ParseError {
+impl fmt::Display for ParseErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ParseError::UnclosedQuote(q) => write!(f, "unclosed quote: {q}"),
-            ParseError::EmptyPipelineSegment => write!(f, "empty pipeline segment"),
+            ParseErrorKind::UnclosedQuote(q) => write!(f, "unclosed quote: {q}"),
+            ParseErrorKind::EmptyPipelineSegment => write!(f, "empty pipeline segment"),
+            ParseErrorKind::UnterminatedArithmetic => write!(f, "unterminated $(("),
+            ParseErrorKind::Arithmetic(e) => write!(f, "{e}"),
+            ParseErrorKind::MissingRedirectWord(op) => write!(f, "missing word after `{op}`"),
+            ParseErrorKind::UnexpectedToken(tok) => write!(f, "unexpected token `{tok}`"),
+            ParseErrorKind::UnexpectedEnd => write!(f, "unexpected end of line"),
+            ParseErrorKind::AmbiguousRedirect(word) => write!(f, "{word}: ambiguous redirect"),
+            ParseErrorKind::UnsetVariable(name) => write!(f, "{name}: unbound variable"),
         }
     }
 }
 
-impl std::error::Error for ParseError {}
-
-/// Парсит одну строку пользовательского ввода.
+/// Ошибка парсинга с местом в строке.
 ///
-/// `base_env` используется для подстановок `$NAME`. Присваивания `NAME=value`
-/// в начале строки влияют на подстановки далее по этой же строке.
-pub(crate) fn parse_line(
-    line: &str,
-    base_env: &HashMap<String, String>,
-) -> Result<ParsedLine, ParseError> {
-    let expanded = expand_line(line, base_env)?;
-    let tokens = tokenize_with_pipes_and_quotes(&expanded)?;
+/// `Display` печатает сообщение, строку с `^` под ошибочным местом, ожидаемые
+/// токены и подсказку:
+///
+/// ```text
+/// unclosed quote: " at column 6
+///   echo "hello
+///        ^^^^^^
+///   hint: did you forget a closing `"` opened at column 6?
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ParseError {
+    pub(crate) kind: ParseErrorKind,
+    /// Место ошибки (пустой диапазон — позиция между символами, например конец строки).
+    pub(crate) span: Span,
+    /// Что ожидалось на месте ошибки: токены в обратных кавычках или описание (`command`).
+    pub(crate) expected: &'static [&'static str],
+    /// Где открыта незакрытая конструкция (кавычка, `$((`, `(`, `{`).
+    pub(crate) opened_at: Option<Span>,
+    /// Разбираемая строка; пустая, пока ошибка не дошла до `parse_line_with_heredocs`
+    /// (ошибки подстановок — до `run_single_line`).
+    pub(crate) source: Box<str>,
+}
 
-    let (assignments, tokens) = split_assignments_prefix(tokens);
+impl ParseError {
+    pub(crate) fn new(kind: ParseErrorKind, span: Span) -> Self {
+        ParseError {
+            kind,
+            span,
+            expected: &[],
+            opened_at: None,
+            source: Box::default(),
+        }
+    }
 
-    if tokens.is_empty() {
-        return Ok(ParsedLine {
-            assignments,
-            pipeline: None,
-        });
+    fn with_expected(mut self, expected: &'static [&'static str]) -> Self {
+        self.expected = expected;
+        self
     }
 
-    let pipeline = parse_pipeline(tokens)?;
-    Ok(ParsedLine {
-        assignments,
-        pipeline: Some(pipeline),
-    })
+    fn with_opened_at(mut self, opened_at: Span) -> Self {
+        self.opened_at = Some(opened_at);
+        self
+    }
+
+    /// Номер колонки (с 1, в символах) для байтовой позиции `pos` строки.
+    pub(crate) fn column(&self, pos: usize) -> usize {
+        let pos = pos.min(self.source.len());
+        self.source
+            .get(..pos)
+            .map_or(pos, |prefix| prefix.chars().count())
+            + 1
+    }
+
+    /// Подсказка, как исправить строку.
+    pub(crate) fn hint(&self) -> Option<String> {
+        let opened = self
+            .opened_at
+            .map(|span| (self.source.get(span.start..span.end).unwrap_or(""), span));
+        match (&self.kind, opened) {
+            (ParseErrorKind::UnclosedQuote(q), Some((_, span))) => Some(format!(
+                "did you forget a closing `{q}` opened at column {}?",
+                self.column(span.start)
+            )),
+            (ParseErrorKind::UnterminatedArithmetic, Some((_, span))) => Some(format!(
+                "did you forget `))` for `$((` opened at column {}?",
+                self.column(span.start)
+            )),
+            (_, Some(("{", span))) => Some(format!(
+                "did you forget `; }}` for `{{` opened at column {}? `}}` closes a group only at the start of a command",
+                self.column(span.start)
+            )),
+            (_, Some(("[[", span))) => Some(format!(
+                "did you forget `]]` for `[[` opened at column {}?",
+                self.column(span.start)
+            )),
+            (_, Some((open, span))) => Some(format!(
+                "did you forget a closing `)` for `{open}` opened at column {}?",
+                self.column(span.start)
+            )),
+            (ParseErrorKind::UnexpectedToken(tok), None) if tok == "&" => {
+                Some("background jobs (`&`) are not supported".to_string())
+            }
+            (ParseErrorKind::AmbiguousRedirect(word), None) => {
+                Some(format!("quote the word to keep it whole: \"{word}\""))
+            }
+            _ => None,
+        }
+    }
 }
 
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if self.source.is_empty() {
+            return Ok(());
+        }
+        let column = self.column(self.span.start);
+        let width = self
+            .source
+            .get(self.span.start..self.span.end)
+            .map_or(0, |s| s.chars().count())
+            .max(1);
+        write!(
+            f,
+            " at column {column}\n  {}\n  {}{}",
+            self.source,
+            " ".repeat(column - 1),
+            "^".repeat(width)
+        )?;
+        if !self.expected.is_empty() {
+            write!(f, "\n  expected: {}", self.expected.join(", "))?;
+        }
+        if let Some(hint) = self.hint() {
+            write!(f, "\n  hint: {hint}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Here-document, тело которого REPL должен дочитать после строки с командой.
 #[derive(Debug, Clone, PartialEq, Eq)]
-enum Token {
-    Word(String),
-    Pipe,
+pub(crate) struct HeredocSpec {
+    /// Строка-терминатор (кавычки сняты).
+    pub(crate) delimiter: String,
+    /// `<<-`: у строк тела и терминатора срезаются ведущие табуляции.
+    pub(crate) strip_tabs: bool,
 }
 
-type Assignments = Vec<(String, String)>;
-type Tokens = Vec<Token>;
+/// Возвращает here-documents строки в порядке появления.
+///
+/// Если строка не разбирается, возвращает пустой список: ошибку сообщит `parse_line`.
+pub(crate) fn pending_heredocs(line: &str) -> Vec<HeredocSpec> {
+    let Ok(tokens) = tokenize_with_pipes_and_quotes(line, 0) else {
+        return Vec::new();
+    };
+    tokens
+        .into_iter()
+        .filter_map(|tok| match tok.token {
+            Token::RedirectWord {
+                op: RedirectOp::Heredoc { strip_tabs },
+                word,
+            } => Some(HeredocSpec {
+                delimiter: word.text,
+                strip_tabs,
+            }),
+            _ => None,
+        })
+        .collect()
+}
 
-fn parse_pipeline(tokens: Vec<Token>) -> Result<Pipeline, ParseError> {
-    let mut commands = Vec::new();
-    let mut current: Vec<String> = Vec::new();
+/// Парсит одну строку пользовательского ввода без алиасов; тела
+/// here-documents считаются пустыми.
+#[cfg(test)]
+pub(crate) fn parse_line(line: &str) -> Result<CommandList, ParseError> {
+    parse_line_with_heredocs(line, &[], &HashMap::new())
+}
 
+/// Парсит строку вместе с телами ее here-documents (`bodies` — в порядке `<<`).
+///
+/// Возвращает список команд строки (пустой для пустой строки) со словами до
+/// подстановок. Тело с терминатором без кавычек становится словом с частями
+/// `$NAME` и `$((...))` (`HereInput::Heredoc`); с терминатором в кавычках
+/// (`<<'EOF'`) остается как есть. `aliases` — таблица алиасов (см. `expand_aliases`).
+pub(crate) fn parse_line_with_heredocs(
+    line: &str,
+    bodies: &[String],
+    aliases: &HashMap<String, String>,
+) -> Result<CommandList, ParseError> {
+    parse_tokens(line, bodies, aliases).map_err(|mut e| {
+        e.source = line.into();
+        e
+    })
+}
+
+/// Токены строки (для отладочного вывода `--dump-tokens`).
+pub(crate) fn tokenize(line: &str) -> Result<Vec<SpannedToken>, ParseError> {
+    tokenize_with_pipes_and_quotes(line, 0).map_err(|mut e| {
+        e.source = line.into();
+        e
+    })
+}
+
+fn parse_tokens(
+    line: &str,
+    bodies: &[String],
+    aliases: &HashMap<String, String>,
+) -> Result<CommandList, ParseError> {
+    let tokens = expand_aliases(tokenize_with_pipes_and_quotes(line, 0)?, aliases)?;
+
+    let mut parser = ListParser {
+        tokens,
+        pos: 0,
+        end: line.len(),
+        bodies: bodies.iter(),
+        aliases,
+    };
+    let list = parser.parse_list(None)?;
+    if parser.peek().is_some() {
+        return Err(parser.unexpected(&[]));
+    }
+    Ok(list)
+}
+
+/// Раскрывает алиасы: слово в позиции имени команды (в начале строки, после
+/// `|`, `;`, `&&`, `||`, `(`, `{` и присваиваний), записанное без кавычек и
+/// подстановок, заменяется токенами текста алиаса.
+///
+/// Как в bash:
+/// - результат снова проверяется (`alias ll='ls -l'` и `alias ls='ls -F'` дают
+///   `ls -F -l`), но алиас не раскрывается внутри собственного текста —
+///   `alias ls='ls -F'` не зацикливается;
+/// - если текст алиаса заканчивается пробелом, проверяется и следующее слово
+///   (`alias sudo='sudo '`).
+///
+/// Токены из алиаса получают место его имени в строке.
+fn expand_aliases(tokens: Tokens, aliases: &HashMap<String, String>) -> Result<Tokens, ParseError> {
+    if aliases.is_empty() {
+        return Ok(tokens);
+    }
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut check = true;
+    push_expanded(tokens, aliases, &mut Vec::new(), &mut check, &mut out)?;
+    Ok(out)
+}
+
+/// Шаг `expand_aliases`: `active` — алиасы, внутри текста которых идет разбор;
+/// `check` — следующее слово нужно проверить на алиас.
+fn push_expanded(
+    tokens: Tokens,
+    aliases: &HashMap<String, String>,
+    active: &mut Vec<String>,
+    check: &mut bool,
+    out: &mut Tokens,
+) -> Result<(), ParseError> {
     for tok in tokens {
-        match tok {
-            Token::Word(w) => current.push(w),
-            Token::Pipe => {
-                if current.is_empty() {
-                    return Err(ParseError::EmptyPipelineSegment);
-                }
-                let name = current.remove(0);
-                let args = current;
-                commands.push(CommandSpec { name, args });
-                current = Vec::new();
+        if *check
+            && let Token::Word(word) = &tok.token
+            && word.is_plain()
+            && !active.contains(&word.text)
+            && let Some(value) = aliases.get(&word.text)
+        {
+            let span = tok.span;
+            let mut inner = tokenize_with_pipes_and_quotes(value, 0).map_err(|mut e| {
+                e.span = span;
+                e.opened_at = None;
+                e
+            })?;
+            for inner_tok in &mut inner {
+                relocate(inner_tok, span);
             }
+            active.push(word.text.clone());
+            push_expanded(inner, aliases, active, check, out)?;
+            active.pop();
+            if value.ends_with([' ', '\t']) {
+                *check = true;
+            }
+            continue;
         }
+        *check = match &tok.token {
+            Token::Pipe => true,
+            Token::Operator(op) => *op != Operator::RParen,
+            Token::Word(word) => {
+                word.is_keyword("{") || (*check && word.assignment_name().is_some())
+            }
+            Token::RedirectWord { .. } | Token::Redirect(_) => *check,
+            Token::ProcessSubst { .. } => false,
+        };
+        out.push(tok);
     }
+    Ok(())
+}
 
-    if current.is_empty() {
-        return Err(ParseError::EmptyPipelineSegment);
+/// Переносит токен из текста алиаса на место имени алиаса `span`.
+fn relocate(tok: &mut SpannedToken, span: Span) {
+    tok.span = span;
+    match &mut tok.token {
+        Token::Word(word) | Token::RedirectWord { word, .. } => word.relocate(span),
+        Token::Pipe | Token::Operator(_) | Token::Redirect(_) | Token::ProcessSubst { .. } => {}
     }
-    let name = current.remove(0);
-    let args = current;
-    commands.push(CommandSpec { name, args });
+}
 
-    Ok(Pipeline { commands })
+/// Управляющие операторы.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Operator {
+    /// `;`
+    Semi,
+    /// `&&`
+    AndIf,
+    /// `||`
+    OrIf,
+    /// `&` (фоновые задачи не поддерживаются — только для сообщения об ошибке).
+    Amp,
+    /// `(`
+    LParen,
+    /// `)`
+    RParen,
 }
 
-/// Пытается распарсить токен как присваивание окружения `NAME=value`.
-///
-/// Возвращает `None`, если токен не является присваиванием или имя переменной невалидно.
-fn parse_assignment(token: &str) -> Option<(String, String)> {
-    let (name, value) = token.split_once('=')?;
-    if name.is_empty() {
-        return None;
+impl Operator {
+    fn as_str(self) -> &'static str {
+        match self {
+            Operator::Semi => ";",
+            Operator::AndIf => "&&",
+            Operator::OrIf => "||",
+            Operator::Amp => "&",
+            Operator::LParen => "(",
+            Operator::RParen => ")",
+        }
     }
+}
 
-    let mut chars = name.chars();
-    let first = chars.next()?;
-    if !(first == '_' || first.is_ascii_alphabetic()) {
-        return None;
-    }
-    if !chars.all(|c| c == '_' || c.is_ascii_alphanumeric()) {
-        return None;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Token {
+    Word(Word),
+    Pipe,
+    Operator(Operator),
+    /// Оператор со словом-операндом: `> file`, `< file`, `<<EOF`, `<<<word`.
+    RedirectWord {
+        op: RedirectOp,
+        word: Word,
+    },
+    /// `2>&1`, `>&2` (без слова).
+    Redirect(Redirect<Word>),
+    /// `<(list)` / `>(list)` (текст списка без внешних скобок).
+    ProcessSubst {
+        output: bool,
+        body: String,
+    },
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "{}", w.text),
+            Token::Pipe => write!(f, "|"),
+            Token::Operator(op) => write!(f, "{}", op.as_str()),
+            Token::RedirectWord { op, word } => write!(f, "{}{}", op.operator(), word.text),
+            Token::Redirect(r) => write!(f, "{r}"),
+            Token::ProcessSubst {
+                output: false,
+                body,
+            } => write!(f, "<({body})"),
+            Token::ProcessSubst { output: true, body } => write!(f, ">({body})"),
+        }
     }
+}
+
+/// Токен и его место в строке.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SpannedToken {
+    pub(crate) token: Token,
+    pub(crate) span: Span,
+}
+
+type Tokens = Vec<SpannedToken>;
 
-    Some((name.to_string(), value.to_string()))
+/// Чем заканчивается вложенный список.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListEnd {
+    /// `)` подоболочки.
+    Paren,
+    /// `}` группы.
+    Brace,
 }
 
-/// Выполняет подстановки `$NAME` по строке, сохраняя кавычки.
+/// Рекурсивный спуск по токенам строки.
 ///
-/// Подстановка выполняется:
-/// - в обычном режиме и в двойных кавычках
-/// - не выполняется в одинарных кавычках
+/// ```text
+/// list     := entry ((';' | '&&' | '||') entry)* ';'?
+/// entry    := assignment* pipeline?
+/// pipeline := command ('|' command)*
+/// command  := '(' list ')' io* | '{' list '}' io* | (word | io)+
+/// ```
 ///
-/// Присваивания `NAME=value` в начале строки влияют на подстановки дальше
-/// в этой же строке (обрабатываются слева направо).
-fn expand_line(input: &str, base_env: &HashMap<String, String>) -> Result<String, ParseError> {
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-    enum Mode {
-        Normal,
-        InSingleQuote,
-        InDoubleQuote,
+/// Слова попадают в список команд без подстановок.
+struct ListParser<'a> {
+    tokens: Tokens,
+    pos: usize,
+    /// Позиция конца разбираемого текста (для ошибок «неожиданный конец»).
+    end: usize,
+    bodies: std::slice::Iter<'a, String>,
+    /// Алиасы для списков подстановок процессов.
+    aliases: &'a HashMap<String, String>,
+}
+
+impl ListParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|tok| &tok.token)
     }
 
-    let mut out = String::new();
-    let mut env: HashMap<String, String> = base_env.clone();
-    let mut in_assignment_prefix = true;
+    /// Место следующего токена (или конец строки).
+    fn peek_span(&self) -> Span {
+        self.tokens
+            .get(self.pos)
+            .map_or(Span::new(self.end, self.end), |tok| tok.span)
+    }
 
-    // Для распознавания присваиваний нужен текущий "word" без кавычек.
-    let mut current_assignment_word = String::new();
-    let mut assignment_word_started = false;
+    /// Место последнего разобранного токена.
+    fn last_span(&self) -> Span {
+        self.tokens[self.pos - 1].span
+    }
 
-    let mut mode = Mode::Normal;
+    /// Ошибка для следующего токена, который здесь не ожидался.
+    fn unexpected(&self, expected: &'static [&'static str]) -> ParseError {
+        let kind = match self.peek() {
+            Some(tok) => ParseErrorKind::UnexpectedToken(tok.to_string()),
+            None => ParseErrorKind::UnexpectedEnd,
+        };
+        ParseError::new(kind, self.peek_span()).with_expected(expected)
+    }
 
-    let mut chars = input.chars().peekable();
+    fn at_list_end(&self, end: Option<ListEnd>) -> bool {
+        match (self.peek(), end) {
+            (None, _) => true,
+            (Some(Token::Operator(Operator::RParen)), Some(ListEnd::Paren)) => true,
+            (Some(Token::Word(w)), Some(ListEnd::Brace)) => w.is_keyword("}"),
+            _ => false,
+        }
+    }
 
-    let finish_assignment_word =
-        |env: &mut HashMap<String, String>,
-         in_assignment_prefix: &mut bool,
-         current_assignment_word: &mut String,
-         assignment_word_started: &mut bool| {
-            if !*assignment_word_started {
-                return;
+    fn parse_list(&mut self, end: Option<ListEnd>) -> Result<CommandList, ParseError> {
+        let mut entries = Vec::new();
+        let mut connector = Connector::Always;
+        loop {
+            if self.at_list_end(end) {
+                if connector != Connector::Always {
+                    return Err(self.unexpected(&["command"]));
+                }
+                break;
             }
-            let word = std::mem::take(current_assignment_word);
-            *assignment_word_started = false;
+            entries.push(self.parse_entry(connector)?);
 
-            if *in_assignment_prefix {
-                if let Some((k, v)) = parse_assignment(&word) {
-                    env.insert(k, v);
-                } else {
-                    *in_assignment_prefix = false;
-                }
+            connector = match self.peek() {
+                Some(Token::Operator(Operator::Semi)) => Connector::Always,
+                Some(Token::Operator(Operator::AndIf)) => Connector::IfSuccess,
+                Some(Token::Operator(Operator::OrIf)) => Connector::IfFailure,
+                Some(Token::Operator(Operator::Amp)) => return Err(self.unexpected(&[])),
+                _ => break,
+            };
+            self.pos += 1;
+        }
+
+        if end.is_some() && entries.is_empty() {
+            return Err(self.unexpected(&["command"]));
+        }
+        Ok(CommandList { entries })
+    }
+
+    fn parse_entry(&mut self, connector: Connector) -> Result<ListEntry, ParseError> {
+        let mut assignments = Vec::new();
+        while let Some(Token::Word(w)) = self.peek()
+            && let Some(name) = w.assignment_name()
+        {
+            assignments.push((name.to_string(), w.clone()));
+            self.pos += 1;
+        }
+
+        let pipeline = if self.at_separator() {
+            None
+        } else {
+            Some(self.parse_pipeline()?)
+        };
+        if assignments.is_empty() && pipeline.is_none() {
+            return Err(self.unexpected(&["command"]));
+        }
+        Ok(ListEntry {
+            connector,
+            assignments,
+            pipeline,
+        })
+    }
+
+    /// Следующий токен завершает элемент списка (`;`, `&&`, `||`, `)`, `}` или конец).
+    fn at_separator(&self) -> bool {
+        match self.peek() {
+            None => true,
+            Some(Token::Operator(op)) => *op != Operator::LParen,
+            Some(Token::Word(w)) => w.is_keyword("}"),
+            _ => false,
+        }
+    }
+
+    fn parse_pipeline(&mut self) -> Result<Pipeline, ParseError> {
+        let mut commands = vec![self.parse_command()?];
+        while let Some(Token::Pipe) = self.peek() {
+            self.pos += 1;
+            commands.push(self.parse_command()?);
+        }
+        Ok(Pipeline { commands })
+    }
+
+    fn parse_command(&mut self) -> Result<Command, ParseError> {
+        let open = self.peek_span();
+        match self.peek() {
+            Some(Token::Operator(Operator::LParen)) => {
+                self.pos += 1;
+                self.parse_group(GroupKind::Subshell, open)
             }
+            Some(Token::Word(w)) if w.is_keyword("{") => {
+                self.pos += 1;
+                self.parse_group(GroupKind::Brace, open)
+            }
+            _ => self.parse_simple(),
+        }
+    }
+
+    /// Разбирает тело группы; `open` — место открывающей `(` или `{`.
+    fn parse_group(&mut self, kind: GroupKind, open: Span) -> Result<Command, ParseError> {
+        let (end, expected): (_, &'static [&'static str]) = match kind {
+            GroupKind::Subshell => (ListEnd::Paren, &["`)`", "`;`", "`&&`", "`||`"]),
+            GroupKind::Brace => (ListEnd::Brace, &["`}`", "`;`", "`&&`", "`||`"]),
         };
-    while let Some(ch) = chars.next() {
-        match mode {
-            Mode::Normal => match ch {
-                ' ' | '\t' => {
-                    finish_assignment_word(
-                        &mut env,
-                        &mut in_assignment_prefix,
-                        &mut current_assignment_word,
-                        &mut assignment_word_started,
-                    );
-
-                    out.push(ch);
-                    while matches!(chars.peek(), Some(' ' | '\t')) {
-                        let _ = chars.next();
-                        out.push(ch);
-                    }
-                }
-                '|' => {
-                    finish_assignment_word(
-                        &mut env,
-                        &mut in_assignment_prefix,
-                        &mut current_assignment_word,
-                        &mut assignment_word_started,
-                    );
-                    in_assignment_prefix = false;
-                    out.push('|');
-                }
-                '\'' => {
-                    mode = Mode::InSingleQuote;
-                    out.push('\'');
-                    assignment_word_started = true;
-                }
-                '"' => {
-                    mode = Mode::InDoubleQuote;
-                    out.push('"');
-                    assignment_word_started = true;
+        let body = self.parse_list(Some(end))?;
+        if !self.at_list_end(Some(end)) || self.peek().is_none() {
+            return Err(self.unexpected(expected).with_opened_at(open));
+        }
+        self.pos += 1;
+
+        let mut here_input = None;
+        let mut redirects = Vec::new();
+        while self.parse_io(&mut here_input, &mut redirects)? {}
+        Ok(Command::Group(GroupSpec {
+            kind,
+            span: open.to(self.last_span()),
+            body,
+            here_input,
+            redirects,
+        }))
+    }
+
+    /// Разбирает перенаправление или here-input, если он следующий. Возвращает, был ли он.
+    fn parse_io(
+        &mut self,
+        here_input: &mut Option<HereInput>,
+        redirects: &mut Vec<Redirect<Word>>,
+    ) -> Result<bool, ParseError> {
+        let span = self.peek_span();
+        let (op, word) = match self.peek() {
+            Some(Token::Redirect(r)) => {
+                redirects.push(r.clone());
+                self.pos += 1;
+                return Ok(true);
+            }
+            Some(Token::RedirectWord { op, word }) => (*op, word.clone()),
+            _ => return Ok(false),
+        };
+        self.pos += 1;
+
+        let input = match op {
+            RedirectOp::Heredoc { .. } if word.is_quoted() => {
+                HereInput::Literal(self.bodies.next().cloned().unwrap_or_default())
+            }
+            RedirectOp::Heredoc { .. } => {
+                let body = self.bodies.next().map(String::as_str).unwrap_or("");
+                HereInput::Heredoc(heredoc_word(body, span)?)
+            }
+            RedirectOp::HereString => HereInput::HereString(word),
+            RedirectOp::Input => {
+                redirects.push(Redirect::Input(word));
+                return Ok(true);
+            }
+            RedirectOp::Output { fd, append } => {
+                redirects.push(Redirect::Output {
+                    fd,
+                    path: word,
+                    append,
+                });
+                return Ok(true);
+            }
+            RedirectOp::OutputBoth { append } => {
+                redirects.push(Redirect::OutputBoth { path: word, append });
+                return Ok(true);
+            }
+        };
+        // Как в bash: при нескольких источниках действует последний.
+        *here_input = Some(input);
+        Ok(true)
+    }
+
+    fn parse_simple(&mut self) -> Result<Command, ParseError> {
+        let start = self.peek_span();
+        let mut words = Vec::new();
+        let mut here_input = None;
+        let mut redirects = Vec::new();
+        let mut process_substs = Vec::new();
+        loop {
+            let span = self.peek_span();
+            match self.peek() {
+                Some(Token::Word(w)) => {
+                    words.push(w.clone());
+                    self.pos += 1;
                 }
-                '$' => {
-                    if let Some(name) = try_read_var_name(&mut chars) {
-                        let val = env.get(&name).map(|s| s.as_str()).unwrap_or("");
-                        out.push_str(val);
-                        current_assignment_word.push_str(val);
-                        assignment_word_started = true;
-                    } else {
-                        out.push('$');
-                        current_assignment_word.push('$');
-                        assignment_word_started = true;
-                    }
+                Some(Token::ProcessSubst { output, body }) => {
+                    let (output, body) = (*output, body.clone());
+                    self.pos += 1;
+                    process_substs.push(ProcessSubst {
+                        word: words.len(),
+                        output,
+                        // Текст списка начинается после `<(`.
+                        list: self.parse_nested(&body, span.start + 2)?,
+                    });
+                    // Путь `/dev/fd/N` подставит исполнитель.
+                    words.push(Word::default());
                 }
                 _ => {
-                    out.push(ch);
-                    current_assignment_word.push(ch);
-                    assignment_word_started = true;
+                    if !self.parse_io(&mut here_input, &mut redirects)? {
+                        break;
+                    }
                 }
-            },
-            Mode::InSingleQuote => {
-                if ch == '\'' {
-                    mode = Mode::Normal;
-                    out.push('\'');
-                } else {
-                    out.push(ch);
-                    current_assignment_word.push(ch);
-                    assignment_word_started = true;
+            }
+        }
+
+        if words.is_empty() {
+            // Команды из одних перенаправлений (`> file`) не поддерживаются.
+            return Err(match self.peek() {
+                None | Some(Token::Pipe) => {
+                    ParseError::new(ParseErrorKind::EmptyPipelineSegment, self.peek_span())
+                        .with_expected(&["command"])
                 }
+                Some(_) => self.unexpected(&["command"]),
+            });
+        }
+        if words[0].is_keyword("[[") {
+            return self.conditional(words, redirects);
+        }
+        Ok(Command::Simple(SimpleCommand {
+            span: start.to(self.last_span()),
+            words,
+            here_input,
+            redirects,
+            process_substs,
+        }))
+    }
+
+    /// Собирает `[[ expr ]]` из слов простой команды: `]]` должно быть последним
+    /// словом, а выражение — непустым. Here-input после `]]` не нужен выражению
+    /// и отбрасывается.
+    fn conditional(
+        &self,
+        mut words: Vec<Word>,
+        redirects: Vec<Redirect<Word>>,
+    ) -> Result<Command, ParseError> {
+        let open = words[0].span;
+        let Some(close) = words.iter().position(|word| word.is_keyword("]]")) else {
+            return Err(ParseError::new(
+                ParseErrorKind::UnexpectedEnd,
+                Span::new(self.peek_span().start, self.peek_span().start),
+            )
+            .with_expected(&["`]]`"])
+            .with_opened_at(open));
+        };
+        if close == 1 {
+            return Err(ParseError::new(
+                ParseErrorKind::UnexpectedToken("]]".into()),
+                words[1].span,
+            )
+            .with_expected(&["expression"]));
+        }
+        if let Some(extra) = words.get(close + 1) {
+            return Err(ParseError::new(
+                ParseErrorKind::UnexpectedToken(extra.text.clone()),
+                extra.span,
+            )
+            .with_expected(&["`&&`", "`||`", "`;`", "`|`"]));
+        }
+        words.truncate(close);
+        words.remove(0);
+        Ok(Command::Conditional(ConditionalCommand {
+            span: open.to(self.last_span()),
+            words,
+            redirects,
+        }))
+    }
+
+    /// Разбирает список команд подстановки процесса (`offset` — начало текста в строке).
+    fn parse_nested(&mut self, text: &str, offset: usize) -> Result<CommandList, ParseError> {
+        let mut nested = ListParser {
+            tokens: expand_aliases(tokenize_with_pipes_and_quotes(text, offset)?, self.aliases)?,
+            pos: 0,
+            end: offset + text.len(),
+            bodies: [].iter(),
+            aliases: self.aliases,
+        };
+        let list = nested.parse_list(None)?;
+        if nested.peek().is_some() {
+            return Err(nested.unexpected(&[]));
+        }
+        Ok(list)
+    }
+}
+
+/// Тело here-document `<<EOF` как слово: `$NAME` и `$((...))` — его части,
+/// остальное — текст в кавычках (кавычки в теле — обычные символы, поля не
+/// делятся). Экранирование `\$`, `\\`, `` \` `` снимается здесь.
+///
+/// Все части получают место оператора `<<` (`span`): на него указывают ошибки подстановок.
+fn heredoc_word(body: &str, span: Span) -> Result<Word, ParseError> {
+    let mut word = Word::default();
+    let mut chars = Cursor::new(body, 0);
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if matches!(chars.peek(), Some('$' | '\\' | '`')) => {
+                word.push_char(chars.next().unwrap_or('\\'), true, span);
             }
-            Mode::InDoubleQuote => {
-                if ch == '"' {
-                    mode = Mode::Normal;
-                    out.push('"');
-                } else if ch == '$' {
-                    if let Some(name) = try_read_var_name(&mut chars) {
-                        let val = env.get(&name).map(|s| s.as_str()).unwrap_or("");
-                        out.push_str(val);
-                        current_assignment_word.push_str(val);
-                        assignment_word_started = true;
-                    } else {
-                        out.push('$');
-                        current_assignment_word.push('$');
-                        assignment_word_started = true;
-                    }
+            '$' => {
+                let expr =
+                    try_read_arith_expr(&mut chars).map_err(|e| ParseError::new(e.kind, span))?;
+                if let Some(expr) = expr {
+                    word.push_arith(expr, true, span);
+                } else if let Some(name) = try_read_var_name(&mut chars) {
+                    word.push_var(name, true, span);
                 } else {
-                    out.push(ch);
-                    current_assignment_word.push(ch);
-                    assignment_word_started = true;
+                    word.push_char('$', true, span);
                 }
             }
+            _ => word.push_char(c, true, span),
         }
     }
+    Ok(word)
+}
 
-    match mode {
-        Mode::Normal => {
-            finish_assignment_word(
-                &mut env,
-                &mut in_assignment_prefix,
-                &mut current_assignment_word,
-                &mut assignment_word_started,
-            );
-            Ok(out)
+/// Оператор, которому нужно следующее слово (цель перенаправления, here-document, here-string).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RedirectOp {
+    Heredoc { strip_tabs: bool },
+    HereString,
+    Input,
+    Output { fd: u32, append: bool },
+    OutputBoth { append: bool },
+}
+
+impl RedirectOp {
+    fn operator(self) -> &'static str {
+        match self {
+            RedirectOp::Heredoc { strip_tabs: false } => "<<",
+            RedirectOp::Heredoc { strip_tabs: true } => "<<-",
+            RedirectOp::HereString => "<<<",
+            RedirectOp::Input => "<",
+            RedirectOp::Output {
+                fd: 2,
+                append: false,
+            } => "2>",
+            RedirectOp::Output {
+                fd: 2,
+                append: true,
+            } => "2>>",
+            RedirectOp::Output { append: false, .. } => ">",
+            RedirectOp::Output { append: true, .. } => ">>",
+            RedirectOp::OutputBoth { append: false } => "&>",
+            RedirectOp::OutputBoth { append: true } => "&>>",
+        }
+    }
+}
+
+/// Посимвольное чтение текста с байтовыми позициями.
+///
+/// `offset` — где текст начинается в исходной строке (у тела `<(...)` — после `<(`).
+#[derive(Clone)]
+struct Cursor<'a> {
+    src: &'a str,
+    pos: usize,
+    offset: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(src: &'a str, offset: usize) -> Self {
+        Cursor {
+            src,
+            pos: 0,
+            offset,
+        }
+    }
+
+    /// Позиция следующего символа в исходной строке.
+    fn location(&self) -> usize {
+        self.offset + self.pos
+    }
+
+    /// Позиция конца текста в исходной строке.
+    fn end(&self) -> usize {
+        self.offset + self.src.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn next_if(&mut self, f: impl FnOnce(char) -> bool) -> Option<char> {
+        let c = self.peek().filter(|c| f(*c))?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn next_if_eq(&mut self, expected: char) -> Option<char> {
+        self.next_if(|c| c == expected)
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        self.next_if(|_| true)
+    }
+}
+
+/// Накопитель токенов для `tokenize_with_pipes_and_quotes`.
+struct Tokenizer {
+    tokens: Tokens,
+    current: Word,
+    token_started: bool,
+    /// Оператор, ждущий слово, и его место.
+    pending: Option<(RedirectOp, Span)>,
+    // Внутри `[[ ... ]]` и `(( ... ))` символы `|`, `&`, `<`, `>`, `(`, `)` — часть
+    // операндов, а не операторы. Здесь хранится ожидаемый закрывающий токен.
+    group_close: Option<&'static str>,
+}
+
+impl Tokenizer {
+    /// Следующее слово — имя команды (в начале строки, после `|`, `;`, `&&`, `||`, `(`, `{`).
+    fn at_command_start(&self) -> bool {
+        match self.tokens.last().map(|tok| &tok.token) {
+            None | Some(Token::Pipe) => true,
+            Some(Token::Operator(op)) => *op != Operator::RParen,
+            Some(Token::Word(w)) => w.is_keyword("{"),
+            _ => false,
+        }
+    }
+
+    /// Спецсимволы шелла сейчас обычные символы: внутри `[[ ]]`, `(( ))` или `((...))`.
+    fn literal_operators(&self) -> bool {
+        self.group_close.is_some()
+            || (self.current.text.starts_with("((") && self.at_command_start())
+    }
+
+    /// Текущее слово занимает байты до `end`; первое обращение задает его начало `start`.
+    fn extend_word(&mut self, start: usize, end: usize) {
+        if !self.token_started {
+            self.current.span.start = start;
+            self.token_started = true;
+        }
+        self.current.span.end = end;
+    }
+
+    fn finish_word(&mut self) {
+        if !self.token_started {
+            return;
+        }
+        let word = std::mem::take(&mut self.current);
+        self.token_started = false;
+
+        if let Some((op, op_span)) = self.pending.take() {
+            let span = op_span.to(word.span);
+            self.tokens.push(SpannedToken {
+                token: Token::RedirectWord { op, word },
+                span,
+            });
+            return;
+        }
+
+        let at_command_start = self.at_command_start();
+        let text = word.text.as_str();
+        match self.group_close {
+            Some(close) if text.ends_with(close) => self.group_close = None,
+            Some(_) => {}
+            None if at_command_start && text == "[[" => self.group_close = Some("]]"),
+            None if at_command_start && text.starts_with("((") && !text.ends_with("))") => {
+                self.group_close = Some("))")
+            }
+            None => {}
+        }
+        let span = word.span;
+        self.tokens.push(SpannedToken {
+            token: Token::Word(word),
+            span,
+        });
+    }
+
+    /// Ошибка: оператор перенаправления остался без слова.
+    fn check_no_pending(&self) -> Result<(), ParseError> {
+        match self.pending {
+            Some((op, span)) => Err(ParseError::new(
+                ParseErrorKind::MissingRedirectWord(op.operator()),
+                span,
+            )
+            .with_expected(&["word"])),
+            None => Ok(()),
         }
-        Mode::InSingleQuote => Err(ParseError::UnclosedQuote('\'')),
-        Mode::InDoubleQuote => Err(ParseError::UnclosedQuote('"')),
+    }
+
+    /// Завершает текущее слово и добавляет оператор; перед оператором не должно
+    /// быть незавершенного перенаправления.
+    fn push_operator(&mut self, token: Token, span: Span) -> Result<(), ParseError> {
+        self.finish_word();
+        self.check_no_pending()?;
+        self.tokens.push(SpannedToken { token, span });
+        Ok(())
+    }
+
+    /// Начинает перенаправление, которому нужно следующее слово.
+    fn start_redirect(&mut self, redirect: RedirectOp, span: Span) -> Result<(), ParseError> {
+        self.finish_word();
+        self.check_no_pending()?;
+        self.pending = Some((redirect, span));
+        Ok(())
+    }
+
+    fn push_char(&mut self, ch: char, quoted: bool, start: usize) {
+        let span = Span::new(start, start + ch.len_utf8());
+        self.current.push_char(ch, quoted, span);
+        self.extend_word(span.start, span.end);
+    }
+
+    /// Разбирает то, что идет после `$` (`start` — позиция `$`): `$((expr))`,
+    /// `$NAME` или просто `$`.
+    fn push_dollar(
+        &mut self,
+        chars: &mut Cursor<'_>,
+        quoted: bool,
+        start: usize,
+    ) -> Result<(), ParseError> {
+        let expr = try_read_arith_expr(chars).map_err(|e| {
+            let open = Span::new(start, start + 3);
+            ParseError::new(e.kind, open.to(Span::new(e.span.end, e.span.end))).with_opened_at(open)
+        })?;
+        let span = Span::new(start, chars.location());
+        if let Some(expr) = expr {
+            self.current.push_arith(expr, quoted, span);
+        } else if let Some(name) = try_read_var_name(chars) {
+            let span = Span::new(start, chars.location());
+            self.current.push_var(name, quoted, span);
+        } else {
+            self.current.push_char('$', quoted, span);
+        }
+        self.extend_word(start, chars.location());
+        Ok(())
     }
 }
 
-/// Превращает строку (уже после expand) в токены с учетом кавычек и `|`.
+/// Превращает строку в токены с учетом кавычек, операторов
+/// (`|`, `;`, `&&`, `||`, `(`, `)`) и перенаправлений.
 ///
-/// Кавычки удаляются (quote removal), как описано в архитектуре.
-fn tokenize_with_pipes_and_quotes(input: &str) -> Result<Tokens, ParseError> {
+/// Кавычки удаляются (quote removal), как описано в архитектуре; подстановки
+/// `$NAME` и `$((...))` остаются частями слов и выполняются парсером.
+/// `offset` — где `input` начинается в исходной строке (для позиций токенов).
+fn tokenize_with_pipes_and_quotes(input: &str, offset: usize) -> Result<Tokens, ParseError> {
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     enum Mode {
         Normal,
@@ -288,112 +1032,319 @@ fn tokenize_with_pipes_and_quotes(input: &str) -> Result<Tokens, ParseError> {
         InDoubleQuote,
     }
 
-    let mut tokens: Tokens = Vec::new();
-    let mut current = String::new();
+    let mut t = Tokenizer {
+        tokens: Vec::new(),
+        current: Word::default(),
+        token_started: false,
+        pending: None,
+        group_close: None,
+    };
     let mut mode = Mode::Normal;
-    let mut token_started = false;
+    // Место открывающей кавычки (для ошибки о незакрытой кавычке).
+    let mut quote_start = 0;
 
-    let mut chars = input.chars().peekable();
-    while let Some(ch) = chars.next() {
+    let mut chars = Cursor::new(input, offset);
+    loop {
+        let start = chars.location();
+        let Some(ch) = chars.next() else {
+            break;
+        };
+        let op_span = |chars: &Cursor<'_>| Span::new(start, chars.location());
         match mode {
             Mode::Normal => match ch {
+                // Комментарий: `#` в начале слова (не внутри `[[ ]]` / `(( ))`).
+                '#' if !t.token_started && t.group_close.is_none() => break,
                 ' ' | '\t' => {
-                    if token_started {
-                        tokens.push(Token::Word(std::mem::take(&mut current)));
-                        token_started = false;
-                    }
+                    t.finish_word();
                     while matches!(chars.peek(), Some(' ' | '\t')) {
                         let _ = chars.next();
                     }
                 }
+                '\'' | '"' => {
+                    mode = if ch == '\'' {
+                        Mode::InSingleQuote
+                    } else {
+                        Mode::InDoubleQuote
+                    };
+                    quote_start = start;
+                    t.current.open_quote(chars.location());
+                    t.extend_word(start, chars.location());
+                }
+                '$' => t.push_dollar(&mut chars, false, start)?,
+                ';' => {
+                    t.push_operator(Token::Operator(Operator::Semi), op_span(&chars))?;
+                    t.group_close = None;
+                }
+                '|' | '&' | '<' | '>' | '(' | ')' if t.literal_operators() => {
+                    t.push_char(ch, false, start)
+                }
+                '<' | '>' if chars.next_if_eq('(').is_some() => {
+                    let open = op_span(&chars);
+                    let body = read_process_subst_body(&mut chars).map_err(|e| {
+                        ParseError::new(e.kind, open.to(e.span))
+                            .with_expected(&["`)`"])
+                            .with_opened_at(open)
+                    })?;
+                    t.push_operator(
+                        Token::ProcessSubst {
+                            output: ch == '>',
+                            body,
+                        },
+                        op_span(&chars),
+                    )?;
+                }
                 '|' => {
-                    if token_started {
-                        tokens.push(Token::Word(std::mem::take(&mut current)));
-                        token_started = false;
+                    let token = if chars.next_if_eq('|').is_some() {
+                        Token::Operator(Operator::OrIf)
+                    } else {
+                        Token::Pipe
+                    };
+                    t.push_operator(token, op_span(&chars))?;
+                }
+                '&' => {
+                    if chars.next_if_eq('&').is_some() {
+                        t.push_operator(Token::Operator(Operator::AndIf), op_span(&chars))?;
+                    } else if chars.next_if_eq('>').is_some() {
+                        let append = chars.next_if_eq('>').is_some();
+                        t.start_redirect(RedirectOp::OutputBoth { append }, op_span(&chars))?;
+                    } else {
+                        t.push_operator(Token::Operator(Operator::Amp), op_span(&chars))?;
                     }
-                    tokens.push(Token::Pipe);
                 }
-                '\'' => {
-                    mode = Mode::InSingleQuote;
-                    token_started = true;
+                '<' => {
+                    let redirect = if chars.next_if_eq('<').is_none() {
+                        RedirectOp::Input
+                    } else if chars.next_if_eq('<').is_some() {
+                        RedirectOp::HereString
+                    } else if chars.next_if_eq('-').is_some() {
+                        RedirectOp::Heredoc { strip_tabs: true }
+                    } else {
+                        RedirectOp::Heredoc { strip_tabs: false }
+                    };
+                    t.start_redirect(redirect, op_span(&chars))?;
+                }
+                '>' => {
+                    // `2>`: номер дескриптора — слово из одной цифры прямо перед `>`.
+                    let mut start = start;
+                    let fd = if t.current.is_keyword("2") { 2 } else { 1 };
+                    if t.current.is_keyword("1") || t.current.is_keyword("2") {
+                        start = t.current.span.start;
+                        t.current = Word::default();
+                        t.token_started = false;
+                    }
+                    let op_span = |chars: &Cursor<'_>| Span::new(start, chars.location());
+                    if chars.next_if_eq('&').is_some() {
+                        let target = match chars.next_if(|c| c == '1' || c == '2') {
+                            Some('2') => 2,
+                            Some(_) => 1,
+                            None => {
+                                return Err(ParseError::new(
+                                    ParseErrorKind::MissingRedirectWord(">&"),
+                                    op_span(&chars),
+                                )
+                                .with_expected(&["`1`", "`2`"]));
+                            }
+                        };
+                        t.push_operator(
+                            Token::Redirect(Redirect::Duplicate { fd, target }),
+                            op_span(&chars),
+                        )?;
+                    } else {
+                        let append = chars.next_if_eq('>').is_some();
+                        // `>|` (запись поверх файла при noclobber) — то же, что `>`.
+                        if !append {
+                            let _ = chars.next_if_eq('|');
+                        }
+                        t.start_redirect(RedirectOp::Output { fd, append }, op_span(&chars))?;
+                    }
                 }
-                '"' => {
-                    mode = Mode::InDoubleQuote;
-                    token_started = true;
+                '(' if !t.token_started && t.at_command_start() && chars.peek() == Some('(') => {
+                    // `((expr))` в начале команды — арифметическая команда, не подоболочка.
+                    t.push_char(ch, false, start);
                 }
-                _ => {
-                    current.push(ch);
-                    token_started = true;
+                // Скобка внутри слова (`$(`, `a(b)`) — обычный символ.
+                '(' if t.token_started => t.push_char(ch, false, start),
+                ')' if t.current.text.matches('(').count()
+                    > t.current.text.matches(')').count() =>
+                {
+                    t.push_char(ch, false, start)
                 }
+                '(' => t.push_operator(Token::Operator(Operator::LParen), op_span(&chars))?,
+                ')' => t.push_operator(Token::Operator(Operator::RParen), op_span(&chars))?,
+                _ => t.push_char(ch, false, start),
             },
             Mode::InSingleQuote => {
                 if ch == '\'' {
                     mode = Mode::Normal;
+                    t.extend_word(start, chars.location());
                 } else {
-                    current.push(ch);
-                    token_started = true;
+                    t.push_char(ch, true, start);
                 }
             }
-            Mode::InDoubleQuote => {
-                if ch == '"' {
+            Mode::InDoubleQuote => match ch {
+                '"' => {
                     mode = Mode::Normal;
-                } else {
-                    current.push(ch);
-                    token_started = true;
+                    t.extend_word(start, chars.location());
                 }
-            }
+                '$' => t.push_dollar(&mut chars, true, start)?,
+                _ => t.push_char(ch, true, start),
+            },
         }
     }
 
+    let unclosed = |q: char| {
+        let open = Span::new(quote_start, quote_start + 1);
+        Err(ParseError::new(
+            ParseErrorKind::UnclosedQuote(q),
+            open.to(Span::new(chars.end(), chars.end())),
+        )
+        .with_expected(if q == '"' { &["`\"`"] } else { &["`'`"] })
+        .with_opened_at(open))
+    };
     match mode {
         Mode::Normal => {
-            if token_started {
-                tokens.push(Token::Word(current));
-            }
-            Ok(tokens)
+            t.finish_word();
+            t.check_no_pending()?;
+            Ok(t.tokens)
         }
-        Mode::InSingleQuote => Err(ParseError::UnclosedQuote('\'')),
-        Mode::InDoubleQuote => Err(ParseError::UnclosedQuote('"')),
+        Mode::InSingleQuote => unclosed('\''),
+        Mode::InDoubleQuote => unclosed('"'),
     }
 }
 
-fn split_assignments_prefix(tokens: Tokens) -> (Assignments, Tokens) {
-    let mut assignments = Vec::new();
-    let mut idx = 0;
-    while idx < tokens.len() {
-        match &tokens[idx] {
-            Token::Word(w) => {
-                if let Some((k, v)) = parse_assignment(w) {
-                    assignments.push((k, v));
-                    idx += 1;
-                    continue;
-                }
-                break;
-            }
-            Token::Pipe => break,
+/// Читает текст `<(...)` / `>(...)` до парной `)` (скобки в кавычках не считаются).
+///
+/// Ошибка `UnexpectedEnd` указывает на конец текста.
+fn read_process_subst_body(chars: &mut Cursor<'_>) -> Result<String, ParseError> {
+    let mut body = String::new();
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    for c in chars.by_ref() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '\'' | '"' => quote = Some(c),
+                '(' => depth += 1,
+                ')' if depth == 0 => return Ok(body),
+                ')' => depth -= 1,
+                _ => {}
+            },
         }
+        body.push(c);
     }
-
-    (assignments, tokens.into_iter().skip(idx).collect())
+    let end = chars.end();
+    Err(ParseError::new(
+        ParseErrorKind::UnexpectedEnd,
+        Span::new(end, end),
+    ))
 }
 
-fn try_read_var_name<I>(chars: &mut std::iter::Peekable<I>) -> Option<String>
-where
-    I: Iterator<Item = char>,
-{
-    let first = chars.peek().copied()?;
+fn try_read_var_name(chars: &mut Cursor<'_>) -> Option<String> {
+    let first = chars.peek()?;
+    if first == '{' {
+        return try_read_braced_var(chars);
+    }
+    // Позиционный параметр — одна цифра: `$10` — это `$1` и `0`.
+    if let Some(digit) = chars.next_if(|c| matches!(c, '1'..='9')) {
+        return Some(digit.to_string());
+    }
     if !(first == '_' || first.is_ascii_alphabetic()) {
         return None;
     }
 
     let mut name = String::new();
-    name.push(chars.next().unwrap());
-    while let Some(c) = chars.peek().copied() {
-        if c == '_' || c.is_ascii_alphanumeric() {
-            name.push(chars.next().unwrap());
-        } else {
-            break;
+    while let Some(c) = chars.next_if(|c| c == '_' || c.is_ascii_alphanumeric()) {
+        name.push(c);
+    }
+    Some(name)
+}
+
+/// `${NAME}`, `${10}` или элемент `${NAME[N]}` / `${NAME[@]}` (после `$`).
+///
+/// Возвращает имя вместе с индексом (`PIPESTATUS[1]`); другие формы `${...}`
+/// не поддерживаются, и тогда `$` остается обычным символом.
+fn try_read_braced_var(chars: &mut Cursor<'_>) -> Option<String> {
+    let mut lookahead = chars.clone();
+    lookahead.next_if_eq('{')?;
+    let mut name = String::new();
+    while let Some(c) = lookahead.next_if(|c| c == '_' || c.is_ascii_alphanumeric()) {
+        name.push(c);
+    }
+    let valid = match name.chars().next() {
+        Some(c) if c.is_ascii_digit() => name.chars().all(|c| c.is_ascii_digit()),
+        Some(_) => true,
+        None => false,
+    };
+    if !valid {
+        return None;
+    }
+    if lookahead.next_if_eq('[').is_some() {
+        let mut index = String::new();
+        while let Some(c) = lookahead.next_if(|c| c != ']' && c != '}') {
+            index.push(c);
+        }
+        lookahead.next_if_eq(']')?;
+        if !(index == "@" || index == "*" || index.parse::<usize>().is_ok()) {
+            return None;
         }
+        name = format!("{name}[{index}]");
     }
+    lookahead.next_if_eq('}')?;
+    *chars = lookahead;
     Some(name)
 }
+
+/// Если дальше идет `((`, читает выражение до парных `))` (после `$`).
+///
+/// Возвращает `Ok(None)`, если это не арифметическая подстановка. Ошибка
+/// `UnterminatedArithmetic` указывает на конец текста.
+fn try_read_arith_expr(chars: &mut Cursor<'_>) -> Result<Option<String>, ParseError> {
+    let mut lookahead = chars.clone();
+    if lookahead.next() != Some('(') || lookahead.next() != Some('(') {
+        return Ok(None);
+    }
+    *chars = lookahead;
+
+    let mut expr = String::new();
+    let mut depth = 0usize;
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                if chars.next() == Some(')') {
+                    return Ok(Some(expr));
+                }
+                break;
+            }
+            ')' => depth -= 1,
+            _ => {}
+        }
+        expr.push(c);
+    }
+    let end = chars.end();
+    Err(ParseError::new(
+        ParseErrorKind::UnterminatedArithmetic,
+        Span::new(end, end),
+    ))
+}
+
+/// Заменяет `$NAME` в выражении `$((...))` значениями `lookup` (до вычисления
+/// выражения; имена без `$` вычислитель читает сам).
+pub(crate) fn substitute_arith_vars<E>(
+    expr: &str,
+    mut lookup: impl FnMut(&str) -> Result<String, E>,
+) -> Result<String, E> {
+    let mut substituted = String::new();
+    let mut chars = Cursor::new(expr, 0);
+    while let Some(c) = chars.next() {
+        if c == '$'
+            && let Some(name) = try_read_var_name(&mut chars)
+        {
+            substituted.push_str(&lookup(&name)?);
+        } else {
+            substituted.push(c);
+        }
+    }
+    Ok(substituted)
+}
@@ -0,0 +1,163 @@
+//! Подстановка процессов `<(list)` и `>(list)`.
+//!
+//! Для каждой подстановки создается pipe (`os_pipe`), а слово команды заменяется
+//! путем `/dev/fd/N` к концу pipe, который остается у шелла. Внутренний список
+//! команд выполняется в отдельном потоке над копией состояния — одновременно
+//! с внешней командой. Когда внешняя команда завершилась, шелл закрывает свои
+//! концы pipe'ов (для `>(list)` это означает EOF на входе списка) и дожидается
+//! внутренних команд; их вывод печатается после вывода внешней команды.
+
+use std::io::{Read, Write};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use super::executor::SharedExecutor;
+use super::types::{CommandList, IoStreams, ProcessSubst, ShellControl, ShellError, ShellResult};
+use super::{ListInput, ShellState, run_list};
+
+/// Конец pipe'а, который держит шелл, пока работает внешняя команда.
+enum PipeEnd {
+    Reader(os_pipe::PipeReader),
+    Writer(os_pipe::PipeWriter),
+}
+
+/// Вывод внутреннего списка, который печатается после внешней команды.
+#[derive(Default)]
+struct SubstOutput {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+/// Запущенные подстановки процессов одной команды.
+pub(crate) struct RunningSubsts {
+    ends: Vec<PipeEnd>,
+    handles: Vec<JoinHandle<SubstOutput>>,
+}
+
+impl RunningSubsts {
+    /// Запускает подстановки и записывает пути `/dev/fd/N` в `words`
+    /// (слово 0 — имя команды).
+    pub(crate) fn start(
+        substs: Vec<ProcessSubst>,
+        executor: &SharedExecutor,
+        state: &ShellState,
+        words: &mut [String],
+    ) -> ShellResult<Self> {
+        let mut running = RunningSubsts {
+            ends: Vec::new(),
+            handles: Vec::new(),
+        };
+        for subst in substs {
+            let (reader, writer) = os_pipe::pipe().map_err(ShellError::Io)?;
+            let state = state.clone();
+            let executor = Arc::clone(executor);
+            let (end, handle) = if subst.output {
+                // `>(list)`: команда пишет в pipe, список читает его целиком.
+                let handle = std::thread::spawn(move || {
+                    let mut reader = reader;
+                    let mut data = Vec::new();
+                    let _ = reader.read_to_end(&mut data);
+                    drop(reader);
+                    let mut output = SubstOutput::default();
+                    run_subst_list(
+                        &executor,
+                        state,
+                        subst.list,
+                        ListInput::Pending(data),
+                        &mut output.stdout,
+                        &mut output.stderr,
+                    );
+                    output
+                });
+                (PipeEnd::Writer(writer), handle)
+            } else {
+                // `<(list)`: список пишет в pipe, команда читает из него.
+                let handle = std::thread::spawn(move || {
+                    let mut writer = writer;
+                    let mut output = SubstOutput::default();
+                    run_subst_list(
+                        &executor,
+                        state,
+                        subst.list,
+                        ListInput::Consumed,
+                        &mut writer,
+                        &mut output.stderr,
+                    );
+                    output
+                });
+                (PipeEnd::Reader(reader), handle)
+            };
+            words[subst.word] = fd_path(&end)?;
+            running.ends.push(end);
+            running.handles.push(handle);
+        }
+        Ok(running)
+    }
+
+    /// Дескрипторы, которые должна унаследовать внешняя команда.
+    pub(crate) fn fds(&self) -> Vec<i32> {
+        self.ends.iter().filter_map(raw_fd).collect()
+    }
+
+    /// Закрывает концы pipe'ов шелла, дожидается внутренних списков и печатает
+    /// их вывод.
+    pub(crate) fn finish(self, io: &mut IoStreams<'_>) -> ShellResult<()> {
+        drop(self.ends);
+        for handle in self.handles {
+            let output = handle
+                .join()
+                .map_err(|_| ShellError::Process("process substitution panicked".to_string()))?;
+            io.stdout
+                .write_all(&output.stdout)
+                .map_err(ShellError::Io)?;
+            io.stderr
+                .write_all(&output.stderr)
+                .map_err(ShellError::Io)?;
+        }
+        Ok(())
+    }
+}
+
+/// Выполняет список подстановки; ошибки печатаются в `stderr` списка.
+fn run_subst_list(
+    executor: &SharedExecutor,
+    mut state: ShellState,
+    list: CommandList,
+    mut input: ListInput,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) {
+    let mut io = IoStreams { stdout, stderr };
+    match run_list(executor, &mut state, list, &mut input, &mut io) {
+        Ok(ShellControl::Continue(_) | ShellControl::Exit(_)) => {}
+        // Внешняя команда закрыла pipe, не дочитав: как SIGPIPE в bash, молча.
+        Err(ShellError::Io(e)) if e.kind() == std::io::ErrorKind::BrokenPipe => {}
+        Err(e) => {
+            let _ = writeln!(io.stderr, "{e}");
+        }
+    }
+}
+
+#[cfg(unix)]
+fn raw_fd(end: &PipeEnd) -> Option<i32> {
+    use std::os::fd::AsRawFd;
+
+    Some(match end {
+        PipeEnd::Reader(r) => r.as_raw_fd(),
+        PipeEnd::Writer(w) => w.as_raw_fd(),
+    })
+}
+
+#[cfg(not(unix))]
+fn raw_fd(_end: &PipeEnd) -> Option<i32> {
+    None
+}
+
+fn fd_path(end: &PipeEnd) -> ShellResult<String> {
+    match raw_fd(end) {
+        Some(fd) => Ok(format!("/dev/fd/{fd}")),
+        None => Err(ShellError::Process(
+            "process substitution is not supported on this platform".to_string(),
+        )),
+    }
+}
@@ -0,0 +1,153 @@
+//! Перенаправления ввода/вывода команд и групп (`<`, `>`, `>>`, `2>&1`, ...).
+//!
+//! Перенаправления применяются к `IoStreams`: stdout и stderr команды заменяются
+//! писателями, которые направляют вывод в файл или в другой поток. Порядок
+//! перенаправлений важен, как в bash: `> out 2>&1` пишет оба потока в файл,
+//! а `2>&1 > out` — только stdout.
+
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::Path;
+
+use super::builtins::io_error_message;
+use super::types::{IoStreams, Redirect};
+use super::vfs::FileSystem;
+
+/// Куда пишет дескриптор.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Stdout,
+    Stderr,
+    File(usize),
+}
+
+/// Открытые перенаправления команды.
+pub(crate) struct Redirections {
+    /// Содержимое файла из `< path` (stdin команды).
+    pub(crate) stdin: Option<Vec<u8>>,
+    stdout: Target,
+    stderr: Target,
+    files: Vec<RefCell<Box<dyn Write + Send>>>,
+}
+
+impl Redirections {
+    /// Открывает файлы перенаправлений в `fs` (пути — относительно `cwd`).
+    ///
+    /// Ошибка — сообщение вида `path: No such file or directory`.
+    pub(crate) fn open(
+        redirects: &[Redirect],
+        cwd: &Path,
+        fs: &dyn FileSystem,
+    ) -> Result<Self, String> {
+        let mut r = Redirections {
+            stdin: None,
+            stdout: Target::Stdout,
+            stderr: Target::Stderr,
+            files: Vec::new(),
+        };
+        let error = |path: &str, e: std::io::Error| format!("{path}: {}", io_error_message(&e));
+        for redirect in redirects {
+            match redirect {
+                Redirect::Input(path) => {
+                    let data = fs.read(&cwd.join(path)).map_err(|e| error(path, e))?;
+                    r.stdin = Some(data);
+                }
+                Redirect::Output { fd, path, append } => {
+                    let target = r
+                        .open_output(fs, &cwd.join(path), *append)
+                        .map_err(|e| error(path, e))?;
+                    *r.slot(*fd) = target;
+                }
+                Redirect::OutputBoth { path, append } => {
+                    let target = r
+                        .open_output(fs, &cwd.join(path), *append)
+                        .map_err(|e| error(path, e))?;
+                    r.stdout = target;
+                    r.stderr = target;
+                }
+                Redirect::Duplicate { fd, target } => {
+                    let target = *r.slot(*target);
+                    *r.slot(*fd) = target;
+                }
+            }
+        }
+        Ok(r)
+    }
+
+    fn open_output(
+        &mut self,
+        fs: &dyn FileSystem,
+        path: &Path,
+        append: bool,
+    ) -> std::io::Result<Target> {
+        let file = fs.open_write(path, append)?;
+        self.files.push(RefCell::new(file));
+        Ok(Target::File(self.files.len() - 1))
+    }
+
+    fn slot(&mut self, fd: u32) -> &mut Target {
+        if fd == 2 {
+            &mut self.stderr
+        } else {
+            &mut self.stdout
+        }
+    }
+
+    /// Выполняет `f` с потоками `io`, измененными перенаправлениями.
+    pub(crate) fn apply<T>(
+        &self,
+        io: &mut IoStreams<'_>,
+        f: impl FnOnce(&mut IoStreams<'_>) -> T,
+    ) -> T {
+        if self.stdout == Target::Stdout && self.stderr == Target::Stderr {
+            return f(io);
+        }
+        let stdout: RefCell<&mut dyn Write> = RefCell::new(&mut *io.stdout);
+        let stderr: RefCell<&mut dyn Write> = RefCell::new(&mut *io.stderr);
+        let mut out = Route {
+            target: self.stdout,
+            stdout: &stdout,
+            stderr: &stderr,
+            files: &self.files,
+        };
+        let mut err = Route {
+            target: self.stderr,
+            stdout: &stdout,
+            stderr: &stderr,
+            files: &self.files,
+        };
+        f(&mut IoStreams {
+            stdout: &mut out,
+            stderr: &mut err,
+        })
+    }
+}
+
+/// Писатель, направляющий вывод в исходный поток или файл.
+///
+/// Оба потока команды могут указывать на один и тот же исходный поток (`2>&1`),
+/// поэтому исходные потоки разделяются через `RefCell`.
+struct Route<'r, 'w> {
+    target: Target,
+    stdout: &'r RefCell<&'w mut dyn Write>,
+    stderr: &'r RefCell<&'w mut dyn Write>,
+    files: &'r [RefCell<Box<dyn Write + Send>>],
+}
+
+impl Write for Route<'_, '_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.target {
+            Target::Stdout => self.stdout.borrow_mut().write(buf),
+            Target::Stderr => self.stderr.borrow_mut().write(buf),
+            Target::File(i) => self.files[i].borrow_mut().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.target {
+            Target::Stdout => self.stdout.borrow_mut().flush(),
+            Target::Stderr => self.stderr.borrow_mut().flush(),
+            Target::File(i) => self.files[i].borrow_mut().flush(),
+        }
+    }
+}
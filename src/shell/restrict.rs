@@ -0,0 +1,252 @@
+//! Ограниченный режим (`se-cli --restricted`).
+//!
+//! Ограничения проверяются в одном месте — при диспетчеризации команд, а не в
+//! каждом builtin:
+//! - перед запуском пайплайна каждая стадия (уже после подстановок) проверяется
+//!   `Restrictions::check_command`:
+//!   имя команды со `/`, внешняя программа не из списка разрешенных и
+//!   перенаправление вывода в файл отклоняются;
+//! - присваивания `NAME=value` (в том числе из `$((...))`) не могут менять
+//!   `PATH`, `SHELL`, `ENV` и `SE_CLI_ENV` (см. `ShellState::apply_assignments`);
+//! - state builtins (`cd`, `let`, `((`, ...) выполняются над снимком рабочей
+//!   директории и переменных: если после команды рабочая директория вышла за
+//!   разрешенный корень или изменилась защищенная переменная, состояние
+//!   восстанавливается (`Restrictions::verify`).
+//!
+//! Нарушение — ошибка `ShellError::Restricted`: строка прерывается с кодом 1.
+//!
+//! Builtins, которые сами меняют файлы (`tee`, `cp`, `mv`, `rm`, `mkdir`,
+//! `touch`, `history -w`), работают через файловую систему `RootedFs`: запись
+//! за пределами корня — ошибка команды (`rm: /etc/x: restricted: outside of /srv`),
+//! как любая другая ошибка ввода-вывода.
+
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::ShellState;
+use super::types::{Redirect, ShellError, ShellResult, Stage};
+use super::vfs::{FileSystem, Metadata, SharedFileSystem, normalize};
+
+/// Переменные, которые нельзя менять в ограниченном режиме.
+pub(crate) const PROTECTED_VARS: &[&str] = &["ENV", "PATH", "SE_CLI_ENV", "SHELL"];
+
+/// Параметры ограниченного режима.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Restrictions {
+    /// Директория, за пределы которой нельзя выйти `cd` (относительный путь —
+    /// от рабочей директории при запуске, см. `Restrictions::resolve`).
+    pub(crate) root: PathBuf,
+    /// Внешние программы, которые разрешено запускать (по имени).
+    pub(crate) allowed: BTreeSet<String>,
+}
+
+/// Рабочая директория и окружение до выполнения state builtin.
+pub(crate) struct Snapshot {
+    cwd: PathBuf,
+    env: HashMap<String, String>,
+}
+
+impl Restrictions {
+    /// Делает корень абсолютным относительно `cwd` и сокращает в нем `.` и `..`.
+    pub(crate) fn resolve(mut self, cwd: &Path) -> Self {
+        self.root = normalize(&cwd.join(&self.root));
+        self
+    }
+
+    /// Проверяет команду пайплайна перед запуском.
+    ///
+    /// Тело группы проверяется при его выполнении, здесь — только ее перенаправления
+    /// (как и у `[[ ]]`).
+    pub(super) fn check_command(&self, stage: &Stage, state: &ShellState) -> ShellResult<()> {
+        match stage {
+            Stage::Simple(spec) => {
+                self.check_redirects(&spec.redirects)?;
+                let mut target = spec.clone();
+                let bypass_builtins = super::strip_command_prefix(&mut target);
+                let name = target.name.as_str();
+                let utility =
+                    state.builtins.contains_key(name) || super::Builtin::from_name(name).is_some();
+                if name.is_empty() || (state.is_builtin(name) && !(bypass_builtins && utility)) {
+                    return Ok(());
+                }
+                self.check_program(name)
+            }
+            Stage::Group(group) => self.check_redirects(&group.redirects),
+            Stage::Conditional(conditional) => self.check_redirects(&conditional.redirects),
+        }
+    }
+
+    /// Можно ли запустить внешнюю программу `name` (в том числе из `xargs` и `find -exec`).
+    pub(crate) fn check_program(&self, name: &str) -> ShellResult<()> {
+        if name.contains('/') {
+            return Err(ShellError::Restricted(format!(
+                "{name}: restricted: cannot specify `/' in command names"
+            )));
+        }
+        if !self.allowed.contains(name) {
+            return Err(ShellError::Restricted(format!(
+                "{name}: restricted: command is not allowed"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Отклоняет присваивание защищенной переменной.
+    pub(crate) fn check_assignments(&self, assignments: &[(String, String)]) -> ShellResult<()> {
+        match assignments
+            .iter()
+            .find(|(name, _)| PROTECTED_VARS.contains(&name.as_str()))
+        {
+            Some((name, _)) => Err(protected(name)),
+            None => Ok(()),
+        }
+    }
+
+    /// Снимок состояния перед state builtin.
+    pub(super) fn snapshot(state: &ShellState) -> Snapshot {
+        Snapshot {
+            cwd: state.cwd.clone(),
+            env: state.env.clone(),
+        }
+    }
+
+    /// Проверяет состояние после state builtin; при нарушении восстанавливает снимок.
+    pub(super) fn verify(&self, state: &mut ShellState, before: Snapshot) -> ShellResult<()> {
+        let error = if state.cwd != before.cwd && !state.cwd.starts_with(&self.root) {
+            Some(ShellError::Restricted(format!(
+                "cd: {}: restricted: outside of {}",
+                state.cwd.display(),
+                self.root.display()
+            )))
+        } else {
+            PROTECTED_VARS
+                .iter()
+                .find(|name| state.env.get(**name) != before.env.get(**name))
+                .map(|name| protected(name))
+        };
+        match error {
+            Some(e) => {
+                state.cwd = before.cwd;
+                state.env = before.env;
+                Err(e)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn check_redirects(&self, redirects: &[Redirect]) -> ShellResult<()> {
+        for redirect in redirects {
+            if let Redirect::Output { path, .. } | Redirect::OutputBoth { path, .. } = redirect {
+                return Err(ShellError::Restricted(format!(
+                    "{path}: restricted: cannot redirect output"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn protected(name: &str) -> ShellError {
+    ShellError::Restricted(format!("{name}: restricted: cannot change variable"))
+}
+
+/// Файловая система ограниченного режима: чтение без ограничений, изменения —
+/// только под корнем `root`.
+pub(crate) struct RootedFs {
+    inner: SharedFileSystem,
+    root: PathBuf,
+}
+
+impl RootedFs {
+    pub(crate) fn new(inner: SharedFileSystem, root: PathBuf) -> Self {
+        Self { inner, root }
+    }
+
+    /// Разрешено ли менять `path`; сам корень удалить или заменить нельзя.
+    fn check(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        if path.starts_with(&self.root) && path != self.root {
+            return Ok(());
+        }
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("restricted: outside of {}", self.root.display()),
+        ))
+    }
+}
+
+impl FileSystem for RootedFs {
+    fn current_dir(&self) -> io::Result<PathBuf> {
+        self.inner.current_dir()
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.inner.metadata(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.inner.symlink_metadata(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.inner.canonicalize(path)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.inner.read(path)
+    }
+
+    fn read_from(&self, path: &Path, offset: u64) -> io::Result<Vec<u8>> {
+        self.inner.read_from(path, offset)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<String>> {
+        self.inner.read_dir(path)
+    }
+
+    fn open_write(&self, path: &Path, append: bool) -> io::Result<Box<dyn Write + Send>> {
+        self.check(path)?;
+        self.inner.open_write(path, append)
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        self.check(path)?;
+        self.inner.create_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        if self.inner.metadata(path).is_ok_and(|m| m.is_dir()) {
+            return Ok(());
+        }
+        self.check(path)?;
+        self.inner.create_dir_all(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.check(path)?;
+        self.inner.remove_file(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.check(path)?;
+        self.inner.remove_dir_all(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.check(from)?;
+        self.check(to)?;
+        self.inner.rename(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.check(to)?;
+        self.inner.copy(from, to)
+    }
+
+    fn set_times(&self, path: &Path, accessed: SystemTime, modified: SystemTime) -> io::Result<()> {
+        self.check(path)?;
+        self.inner.set_times(path, accessed, modified)
+    }
+}
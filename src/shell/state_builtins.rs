@@ -0,0 +1,1005 @@
+//! Встроенные команды, которым нужно состояние шелла (`let`, `(( ... ))`, `cd`,
+//! `set`, `alias`, `unalias`, `source`, `history`, `type`, `which`, `command`, `hash`,
+//! `timeout`, `ulimit`).
+//!
+//! В отличие от `builtins`, им нужен `&mut ShellState`, поэтому их вызывает
+//! исполнитель напрямую. В пайплайне стадия получает копию состояния, которая
+//! после выполнения отбрасывается (как в bash, где стадии пайпа — подоболочки).
+
+use std::io::BufRead;
+use std::path::PathBuf;
+
+use super::arith;
+use super::builtins::{format_timestamp, io_error_message};
+use super::executor::{ProcessExecutor, SharedExecutor};
+use super::limits::{Resource, TIMEOUT_STATUS, parse_duration};
+use super::options::{OPTION_NAMES, ShellOptions};
+use super::types::{CommandSpec, IoStreams, ShellControl, ShellError, ShellResult, Span, Stage};
+use super::vfs::normalize;
+use super::{ListInput, ShellState, run_lines, run_stage};
+
+/// Имена команд `StateBuiltin` (кроме `((`) для подсказок.
+pub(crate) const STATE_BUILTIN_NAMES: &[&str] = &[
+    "let", "cd", "set", "alias", "unalias", "source", ".", "history", "type", "which", "command",
+    "hash", "timeout", "ulimit",
+];
+
+/// Команды, которым нужен доступ к состоянию шелла.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StateBuiltin {
+    /// `let expr...`
+    Let,
+    /// `(( expr ))`
+    Arithmetic,
+    /// `cd [dir]`
+    Cd,
+    /// `set [-o|+o [name]]...`
+    Set,
+    /// `alias [-p] [name[=value]]...`
+    Alias,
+    /// `unalias [-a] name...`
+    Unalias,
+    /// `source FILE [args...]`, `. FILE [args...]`
+    Source,
+    /// `history [N] | -c | -d OFFSET | -w [FILE]`
+    History,
+    /// `type [-atpP] name...`
+    Type,
+    /// `which [-a] name...`
+    Which,
+    /// `command -v|-V name...` (`command NAME args...` выполняет исполнитель)
+    Command,
+    /// `hash [-r] [-d|-t] [name...]`
+    Hash,
+    /// `timeout DURATION command [args...]`
+    Timeout,
+    /// `ulimit [-a] [-tvnu [limit]]...`
+    Ulimit,
+}
+
+impl StateBuiltin {
+    /// Возвращает команду по имени (первому слову команды).
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "let" => Some(StateBuiltin::Let),
+            "cd" => Some(StateBuiltin::Cd),
+            "set" => Some(StateBuiltin::Set),
+            "alias" => Some(StateBuiltin::Alias),
+            "unalias" => Some(StateBuiltin::Unalias),
+            "source" | "." => Some(StateBuiltin::Source),
+            "history" => Some(StateBuiltin::History),
+            "type" => Some(StateBuiltin::Type),
+            "which" => Some(StateBuiltin::Which),
+            "command" => Some(StateBuiltin::Command),
+            "hash" => Some(StateBuiltin::Hash),
+            "timeout" => Some(StateBuiltin::Timeout),
+            "ulimit" => Some(StateBuiltin::Ulimit),
+            _ if name.starts_with("((") => Some(StateBuiltin::Arithmetic),
+            _ => None,
+        }
+    }
+
+    /// Читает ли команда stdin: `source` (его читают команды файла), `command`
+    /// и `timeout` (его читает запущенная команда).
+    pub(crate) fn reads_stdin(self) -> bool {
+        matches!(
+            self,
+            StateBuiltin::Source | StateBuiltin::Command | StateBuiltin::Timeout
+        )
+    }
+}
+
+/// Что нужно командам, которые сами выполняют команды шелла (`source`).
+pub(crate) struct StateContext<'a, 'i> {
+    pub(crate) executor: &'a SharedExecutor,
+    /// Вход команды (пайп, перенаправление или stdin шелла).
+    pub(crate) input: &'i mut ListInput,
+}
+
+/// Выполняет команду над состоянием шелла.
+///
+/// `name` — первое слово команды (для `((` оно может содержать и часть выражения).
+pub(crate) fn run_state_builtin(
+    builtin: StateBuiltin,
+    name: &str,
+    args: &[String],
+    state: &mut ShellState,
+    ctx: StateContext<'_, '_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    match builtin {
+        StateBuiltin::Let => run_let(args, state, io),
+        StateBuiltin::Arithmetic => run_arithmetic_command(name, args, state, io),
+        StateBuiltin::Cd => run_cd(args, state, io),
+        StateBuiltin::Set => run_set(args, state, io),
+        StateBuiltin::Alias => run_alias(args, state, io),
+        StateBuiltin::Unalias => run_unalias(args, state, io),
+        StateBuiltin::Source => run_source(args, state, ctx, io),
+        StateBuiltin::History => run_history(args, state, io),
+        StateBuiltin::Type => run_type(args, state, ctx.executor.as_ref(), io),
+        StateBuiltin::Which => run_which(args, state, ctx.executor.as_ref(), io),
+        StateBuiltin::Command => run_command_query(args, state, ctx.executor.as_ref(), io),
+        StateBuiltin::Hash => run_hash(args, state, ctx.executor.as_ref(), io),
+        StateBuiltin::Timeout => run_timeout(args, state, ctx, io),
+        StateBuiltin::Ulimit => run_ulimit(args, state, io),
+    }
+}
+
+/// Вычисляет каждый аргумент как арифметическое выражение.
+///
+/// Коды возврата:
+/// - 0: значение последнего выражения не равно 0
+/// - 1: значение последнего выражения равно 0 или ошибка вычисления
+/// - 2: нет аргументов
+fn run_let(
+    args: &[String],
+    state: &mut ShellState,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    if args.is_empty() {
+        writeln!(io.stderr, "let: expression expected").map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(2));
+    }
+    let mut last = 0;
+    for expr in args {
+        match arith::evaluate(expr, &mut state.env) {
+            Ok(value) => last = value,
+            Err(e) => {
+                writeln!(io.stderr, "let: {e}").map_err(ShellError::Io)?;
+                return Ok(ShellControl::Continue(1));
+            }
+        }
+    }
+    Ok(ShellControl::Continue(if last != 0 { 0 } else { 1 }))
+}
+
+/// `(( expr ))`: слова команды склеиваются через пробел, внешние `((`/`))` снимаются.
+///
+/// Коды возврата:
+/// - 0: значение выражения не равно 0
+/// - 1: значение равно 0 или ошибка вычисления
+/// - 2: нет закрывающих `))`
+fn run_arithmetic_command(
+    name: &str,
+    args: &[String],
+    state: &mut ShellState,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let text = std::iter::once(name)
+        .chain(args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let Some(expr) = text
+        .strip_prefix("((")
+        .and_then(|rest| rest.strip_suffix("))"))
+    else {
+        writeln!(io.stderr, "((: missing '))'").map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(2));
+    };
+    match arith::evaluate(expr, &mut state.env) {
+        Ok(value) => Ok(ShellControl::Continue(if value != 0 { 0 } else { 1 })),
+        Err(e) => {
+            writeln!(io.stderr, "((: {e}").map_err(ShellError::Io)?;
+            Ok(ShellControl::Continue(1))
+        }
+    }
+}
+
+/// Меняет рабочую директорию шелла.
+///
+/// Без аргумента переходит в `$HOME`, `cd -` — в `$OLDPWD` (и печатает ее).
+/// Обновляет `PWD` и `OLDPWD`. Путь сохраняется без разрешения символических ссылок
+/// (как `cd -L` в bash), но `.` и `..` сокращаются.
+///
+/// Коды возврата:
+/// - 0: директория изменена
+/// - 1: директория не существует, не задан `HOME`/`OLDPWD` или лишние аргументы
+fn run_cd(
+    args: &[String],
+    state: &mut ShellState,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let (target, print) = match args {
+        [] => match state.env.get("HOME") {
+            Some(home) => (home.clone(), false),
+            None => {
+                writeln!(io.stderr, "cd: HOME not set").map_err(ShellError::Io)?;
+                return Ok(ShellControl::Continue(1));
+            }
+        },
+        [dash] if dash == "-" => match state.env.get("OLDPWD") {
+            Some(old) => (old.clone(), true),
+            None => {
+                writeln!(io.stderr, "cd: OLDPWD not set").map_err(ShellError::Io)?;
+                return Ok(ShellControl::Continue(1));
+            }
+        },
+        [dir] => (dir.clone(), false),
+        _ => {
+            writeln!(io.stderr, "cd: too many arguments").map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(1));
+        }
+    };
+
+    let new_cwd = normalize(&state.cwd.join(&target));
+    match state.fs.metadata(&new_cwd) {
+        Ok(meta) if meta.is_dir() => {}
+        Ok(_) => {
+            writeln!(io.stderr, "cd: {target}: Not a directory").map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(1));
+        }
+        Err(e) => {
+            writeln!(io.stderr, "cd: {target}: {}", io_error_message(&e))
+                .map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(1));
+        }
+    }
+
+    let old = std::mem::replace(&mut state.cwd, new_cwd);
+    state
+        .env
+        .insert("OLDPWD".to_string(), old.to_string_lossy().into_owned());
+    state
+        .env
+        .insert("PWD".to_string(), state.cwd.to_string_lossy().into_owned());
+    if print {
+        writeln!(io.stdout, "{}", state.cwd.display()).map_err(ShellError::Io)?;
+    }
+    Ok(ShellControl::Continue(0))
+}
+
+/// Меняет опции шелла или печатает их.
+///
+/// - `set -o NAME` / `set +o NAME` — включает / выключает опцию (`errexit`,
+///   `nounset`, `pipefail`, `xtrace`, `debugparse`);
+/// - `set -e`, `-u`, `-x` (и `+e`, `+u`, `+x`, в том числе слитно: `-eux`) — то же
+///   для `errexit`, `nounset`, `xtrace`;
+/// - `set -o` печатает опции как `name on|off`, `set +o` — командами `set ±o name`;
+/// - без аргументов печатает переменные `NAME=value` по алфавиту.
+///
+/// Коды возврата:
+/// - 0: успех
+/// - 2: неизвестная опция или аргумент
+fn run_set(
+    args: &[String],
+    state: &mut ShellState,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    if args.is_empty() {
+        let mut vars: Vec<_> = state.env.iter().collect();
+        vars.sort();
+        for (name, value) in vars {
+            writeln!(io.stdout, "{name}={value}").map_err(ShellError::Io)?;
+        }
+        return Ok(ShellControl::Continue(0));
+    }
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (on, flags) = match arg.split_at_checked(1) {
+            Some(("-", flags)) if !flags.is_empty() => (true, flags),
+            Some(("+", flags)) if !flags.is_empty() => (false, flags),
+            _ => {
+                writeln!(io.stderr, "set: {arg}: invalid option").map_err(ShellError::Io)?;
+                return Ok(ShellControl::Continue(2));
+            }
+        };
+        if flags != "o" {
+            for flag in flags.chars() {
+                let Some(name) = ShellOptions::short_name(flag) else {
+                    writeln!(io.stderr, "set: {}{flag}: invalid option", &arg[..1])
+                        .map_err(ShellError::Io)?;
+                    return Ok(ShellControl::Continue(2));
+                };
+                state.options.set(name, on);
+            }
+            continue;
+        }
+        let Some(name) = args.next() else {
+            for name in OPTION_NAMES {
+                let value = state.options.get(name).unwrap_or(false);
+                if on {
+                    let value = if value { "on" } else { "off" };
+                    writeln!(io.stdout, "{name:<15}\t{value}").map_err(ShellError::Io)?;
+                } else {
+                    let sign = if value { '-' } else { '+' };
+                    writeln!(io.stdout, "set {sign}o {name}").map_err(ShellError::Io)?;
+                }
+            }
+            break;
+        };
+        if !state.options.set(name, on) {
+            writeln!(io.stderr, "set: {name}: invalid option name").map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(2));
+        }
+    }
+    Ok(ShellControl::Continue(0))
+}
+
+/// Задает или печатает алиасы.
+///
+/// - `alias name=value` — задает алиас (значение — остаток аргумента после `=`);
+/// - `alias name` — печатает его как `alias name='value'`;
+/// - без аргументов или с `-p` — печатает все алиасы по алфавиту.
+///
+/// Алиасы раскрываются при разборе следующих строк (см. `parser::expand_aliases`).
+///
+/// Коды возврата:
+/// - 0: успех
+/// - 1: алиас не найден или недопустимое имя (остальные аргументы обрабатываются)
+fn run_alias(
+    args: &[String],
+    state: &mut ShellState,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let args = match args.first() {
+        Some(flag) if flag == "-p" => &args[1..],
+        _ => args,
+    };
+    if args.is_empty() {
+        let mut aliases: Vec<_> = state.aliases.iter().collect();
+        aliases.sort();
+        for (name, value) in aliases {
+            writeln!(io.stdout, "{}", alias_definition(name, value)).map_err(ShellError::Io)?;
+        }
+        return Ok(ShellControl::Continue(0));
+    }
+
+    let mut code = 0;
+    for arg in args {
+        match arg.split_once('=') {
+            Some((name, value)) if is_alias_name(name) => {
+                state.aliases.insert(name.to_string(), value.to_string());
+            }
+            Some((name, _)) => {
+                writeln!(io.stderr, "alias: `{name}': invalid alias name")
+                    .map_err(ShellError::Io)?;
+                code = 1;
+            }
+            None => match state.aliases.get(arg) {
+                Some(value) => {
+                    writeln!(io.stdout, "{}", alias_definition(arg, value))
+                        .map_err(ShellError::Io)?;
+                }
+                None => {
+                    writeln!(io.stderr, "alias: {arg}: not found").map_err(ShellError::Io)?;
+                    code = 1;
+                }
+            },
+        }
+    }
+    Ok(ShellControl::Continue(code))
+}
+
+/// Удаляет алиасы; `unalias -a` удаляет все.
+///
+/// Коды возврата:
+/// - 0: успех
+/// - 1: алиас не найден (остальные удаляются)
+/// - 2: нет аргументов
+fn run_unalias(
+    args: &[String],
+    state: &mut ShellState,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    match args.first().map(String::as_str) {
+        None => {
+            writeln!(io.stderr, "unalias: usage: unalias [-a] name [name ...]")
+                .map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(2));
+        }
+        Some("-a") => {
+            state.aliases.clear();
+            return Ok(ShellControl::Continue(0));
+        }
+        Some(_) => {}
+    }
+    let mut code = 0;
+    for name in args {
+        if state.aliases.remove(name).is_none() {
+            writeln!(io.stderr, "unalias: {name}: not found").map_err(ShellError::Io)?;
+            code = 1;
+        }
+    }
+    Ok(ShellControl::Continue(code))
+}
+
+/// Выполняет строки файла в текущем состоянии шелла: присваивания, `cd`,
+/// алиасы и опции остаются после него. Относительный путь — от рабочей
+/// директории шелла.
+///
+/// Аргументы после имени файла на время выполнения становятся позиционными
+/// параметрами `$1`…`$9`. Ошибки строк печатаются как `FILE: line N: ...`;
+/// `exit` в файле завершает шелл.
+///
+/// Коды возврата:
+/// - код последней строки файла
+/// - 1: файл не открывается
+/// - 2: нет имени файла
+fn run_source(
+    args: &[String],
+    state: &mut ShellState,
+    ctx: StateContext<'_, '_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let Some((path, params)) = args.split_first() else {
+        writeln!(io.stderr, "source: filename argument required").map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(2));
+    };
+    let text = match state.fs.read(&state.cwd.join(path)) {
+        Ok(text) => text,
+        Err(e) => {
+            writeln!(io.stderr, "source: {path}: {}", io_error_message(&e))
+                .map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(1));
+        }
+    };
+
+    let saved =
+        (!params.is_empty()).then(|| std::mem::replace(&mut state.positional, params.to_vec()));
+    let lines = std::io::Cursor::new(text).lines();
+    let result = run_lines(ctx.executor, state, lines, Some(path), ctx.input, io);
+    if let Some(saved) = saved {
+        state.positional = saved;
+    }
+    result
+}
+
+/// Выполняет команду со сроком: `timeout DURATION COMMAND [ARG]...`.
+///
+/// Внешние программы команды (вместе с запущенными ими процессами), не
+/// завершившиеся за `DURATION`, убиваются. Срок — число с суффиксом `s`, `m`,
+/// `h` или `d` (см. `limits::parse_duration`), `0` — без срока; вложенный
+/// `timeout` не продлевает внешний. Builtins выполняются в шелле, и срок
+/// действует только на программы, которые они запускают (`xargs`, `find -exec`).
+///
+/// Коды возврата:
+/// - код команды
+/// - 124: срок истек
+/// - 125: неверный срок или нет команды
+fn run_timeout(
+    args: &[String],
+    state: &mut ShellState,
+    ctx: StateContext<'_, '_>,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let args = args.strip_prefix(&["--".to_string()]).unwrap_or(args);
+    let [duration, name, rest @ ..] = args else {
+        writeln!(io.stderr, "timeout: missing operand").map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(TIMEOUT_STATUS + 1));
+    };
+    let Some(timeout) = parse_duration(duration) else {
+        writeln!(io.stderr, "timeout: invalid time interval '{duration}'")
+            .map_err(ShellError::Io)?;
+        return Ok(ShellControl::Continue(TIMEOUT_STATUS + 1));
+    };
+    // Команда проходит обычную диспетчеризацию (в том числе проверки `--restricted`).
+    let stage = Stage::Simple(CommandSpec {
+        span: Span::default(),
+        name: name.clone(),
+        args: rest.to_vec(),
+        here_input: None,
+        redirects: Vec::new(),
+        process_substs: Vec::new(),
+    });
+    state.with_timeout(timeout, |state| {
+        run_stage(ctx.executor, state, stage, ctx.input, io)
+    })
+}
+
+/// Лимиты ресурсов для внешних программ (см. `limits::Resource`).
+///
+/// - без аргументов или `-a` — печатает все лимиты;
+/// - `-t`, `-v`, `-n`, `-u` — печатает лимит (для нескольких — с названиями);
+/// - `-X LIMIT` — задает лимит (`N` или `unlimited`) запускаемым программам,
+///   мягкий и жесткий сразу; сам шелл не ограничивается. Пар может быть
+///   несколько: `ulimit -t 10 -n 64`.
+///
+/// Пока лимит не задан, программы наследуют лимит процесса шелла; выше его
+/// жесткого лимита значение задать нельзя.
+///
+/// Коды возврата:
+/// - 0: успех
+/// - 1: неверное число или лимит выше жесткого
+/// - 2: неизвестная опция
+fn run_ulimit(
+    args: &[String],
+    state: &mut ShellState,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let usage = "ulimit [-a] [-tvnu [limit]]";
+    let mut all = args.is_empty();
+    let mut queries = Vec::new();
+    let mut changes = Vec::new();
+    let mut rest = args.iter().peekable();
+    while let Some(arg) = rest.next() {
+        let Some(letters) = arg.strip_prefix('-').filter(|l| !l.is_empty()) else {
+            writeln!(io.stderr, "ulimit: {arg}: invalid number").map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(1));
+        };
+        let mut flags = letters.chars().peekable();
+        while let Some(flag) = flags.next() {
+            if flag == 'a' {
+                all = true;
+                continue;
+            }
+            let Some(resource) = Resource::from_flag(flag) else {
+                return invalid_option("ulimit", &format!("-{flag}"), usage, io);
+            };
+            // Значение может идти только после последнего флага группы.
+            match rest.next_if(|value| flags.peek().is_none() && !value.starts_with('-')) {
+                Some(value) => changes.push((resource, value)),
+                None => queries.push(resource),
+            }
+        }
+    }
+
+    for (resource, value) in changes {
+        let limit = match value.as_str() {
+            "unlimited" => None,
+            _ => match value.parse::<u64>() {
+                Ok(n) => Some(n),
+                Err(_) => {
+                    writeln!(io.stderr, "ulimit: {value}: invalid number")
+                        .map_err(ShellError::Io)?;
+                    return Ok(ShellControl::Continue(1));
+                }
+            },
+        };
+        let hard = resource.current().map_err(ShellError::Io)?.1;
+        if let Some(hard) = hard
+            && limit.is_none_or(|limit| limit > hard)
+        {
+            writeln!(
+                io.stderr,
+                "ulimit: {}: cannot modify limit: Operation not permitted",
+                resource.name()
+            )
+            .map_err(ShellError::Io)?;
+            return Ok(ShellControl::Continue(1));
+        }
+        state.limits.resources.insert(resource, limit);
+    }
+
+    let labeled = all || queries.len() > 1;
+    let shown = if all { Resource::ALL.to_vec() } else { queries };
+    for resource in shown {
+        let limit = match state.limits.resources.get(&resource) {
+            Some(&limit) => limit,
+            None => resource.current().map_err(ShellError::Io)?.0,
+        };
+        let value = limit.map_or("unlimited".to_string(), |n| n.to_string());
+        if labeled {
+            let unit = match resource.unit() {
+                Some(unit) => format!("({unit}, -{}) ", resource.flag()),
+                None => format!("(-{}) ", resource.flag()),
+            };
+            writeln!(io.stdout, "{:<20} {unit:>20}{value}", resource.name())
+                .map_err(ShellError::Io)?;
+        } else {
+            writeln!(io.stdout, "{value}").map_err(ShellError::Io)?;
+        }
+    }
+    Ok(ShellControl::Continue(0))
+}
+
+/// Печатает или меняет историю команд.
+///
+/// - без аргументов — все записи как `  N  line`, `history N` — последние `N`;
+///   если задан непустой `HISTTIMEFORMAT`, перед строкой печатается время записи
+///   `YYYY-MM-DD HH:MM:SS` (UTC; сам формат не разбирается);
+/// - `-c` — очищает историю;
+/// - `-d OFFSET` — удаляет запись с номером `OFFSET` (отрицательный — с конца);
+/// - `-w [FILE]` — записывает историю в `FILE` или `$HISTFILE`.
+///
+/// Коды возврата:
+/// - 0: успех
+/// - 1: неверный номер, нет `HISTFILE` или ошибка записи файла
+/// - 2: неизвестная опция
+fn run_history(
+    args: &[String],
+    state: &mut ShellState,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    match args.first().map(String::as_str) {
+        Some("-c") => {
+            state.history.clear();
+            Ok(ShellControl::Continue(0))
+        }
+        Some("-d") => {
+            let offset = args.get(1).map_or("", String::as_str);
+            match offset.parse().ok().and_then(|n| state.history.resolve(n)) {
+                Some(number) => {
+                    state.history.delete(number);
+                    Ok(ShellControl::Continue(0))
+                }
+                None => {
+                    writeln!(
+                        io.stderr,
+                        "history: {offset}: history position out of range"
+                    )
+                    .map_err(ShellError::Io)?;
+                    Ok(ShellControl::Continue(1))
+                }
+            }
+        }
+        Some("-w") => {
+            let path = match args.get(1) {
+                Some(file) => state.cwd.join(file),
+                None => match state.history_file() {
+                    Some(path) => path,
+                    None => {
+                        writeln!(io.stderr, "history: HISTFILE not set").map_err(ShellError::Io)?;
+                        return Ok(ShellControl::Continue(1));
+                    }
+                },
+            };
+            let written = state
+                .fs
+                .open_write(&path, false)
+                .and_then(|mut file| file.write_all(state.history.to_file_text().as_bytes()));
+            if let Err(e) = written {
+                writeln!(
+                    io.stderr,
+                    "history: {}: {}",
+                    path.display(),
+                    io_error_message(&e)
+                )
+                .map_err(ShellError::Io)?;
+                return Ok(ShellControl::Continue(1));
+            }
+            Ok(ShellControl::Continue(0))
+        }
+        Some(flag) if flag.starts_with('-') && flag.len() > 1 => {
+            writeln!(io.stderr, "history: {flag}: invalid option").map_err(ShellError::Io)?;
+            Ok(ShellControl::Continue(2))
+        }
+        count => {
+            let count = match count.map(str::parse::<usize>) {
+                None => usize::MAX,
+                Some(Ok(count)) => count,
+                Some(Err(_)) => {
+                    let arg = &args[0];
+                    writeln!(io.stderr, "history: {arg}: numeric argument required")
+                        .map_err(ShellError::Io)?;
+                    return Ok(ShellControl::Continue(1));
+                }
+            };
+            let with_time = state
+                .env
+                .get("HISTTIMEFORMAT")
+                .is_some_and(|f| !f.is_empty());
+            let entries: Vec<_> = state.history.iter().collect();
+            let skip = entries.len().saturating_sub(count);
+            for (number, entry) in &entries[skip..] {
+                if with_time {
+                    let time = format_timestamp(entry.time);
+                    writeln!(io.stdout, "{number:>5}  {time} {}", entry.line)
+                } else {
+                    writeln!(io.stdout, "{number:>5}  {}", entry.line)
+                }
+                .map_err(ShellError::Io)?;
+            }
+            Ok(ShellControl::Continue(0))
+        }
+    }
+}
+
+/// Чем является имя команды.
+enum CommandKind {
+    Alias(String),
+    Builtin,
+    /// Программа из таблицы `hash`.
+    Hashed(PathBuf),
+    File(PathBuf),
+}
+
+/// Значения имени `name` в порядке, в котором их ищет шелл: алиас, builtin,
+/// программа (из таблицы `hash` или `PATH`; ищет `executor`).
+///
+/// `all` — все значения, в т.ч. каждый подходящий файл из `PATH`; иначе только
+/// первое. `files_only` — только программы (`type -P`).
+fn describe(
+    name: &str,
+    state: &ShellState,
+    executor: &dyn ProcessExecutor,
+    all: bool,
+    files_only: bool,
+) -> Vec<CommandKind> {
+    let mut kinds = Vec::new();
+    if !files_only {
+        if let Some(value) = state.aliases.get(name) {
+            kinds.push(CommandKind::Alias(value.clone()));
+        }
+        if state.is_builtin(name) {
+            kinds.push(CommandKind::Builtin);
+        }
+    }
+    if name.contains('/') {
+        let path = state.cwd.join(name);
+        if executor.is_program(&path) {
+            kinds.push(CommandKind::File(path));
+        }
+    } else if let Some(entry) = state.commands.get(name, &state.env)
+        && !all
+        && executor.is_program(&entry.path)
+    {
+        kinds.push(CommandKind::Hashed(entry.path.clone()));
+    } else {
+        let programs = executor.find_programs(name, &state.env, &state.cwd);
+        kinds.extend(programs.into_iter().map(CommandKind::File));
+    }
+    if !all {
+        kinds.truncate(1);
+    }
+    kinds
+}
+
+/// Ведущие однобуквенные флаги из `allowed` (можно слитно: `-at`; `--` —
+/// конец флагов). Возвращает флаги и остальные аргументы; ошибка — неизвестная
+/// опция.
+fn split_flags<'a>(args: &'a [String], allowed: &str) -> Result<(String, &'a [String]), String> {
+    let mut flags = String::new();
+    for (i, arg) in args.iter().enumerate() {
+        if arg == "--" {
+            return Ok((flags, &args[i + 1..]));
+        }
+        let Some(letters) = arg.strip_prefix('-').filter(|l| !l.is_empty()) else {
+            return Ok((flags, &args[i..]));
+        };
+        if let Some(bad) = letters.chars().find(|c| !allowed.contains(*c)) {
+            return Err(format!("-{bad}"));
+        }
+        flags.push_str(letters);
+    }
+    Ok((flags, &[]))
+}
+
+/// Печатает ошибку неизвестной опции; код возврата 2.
+fn invalid_option(
+    name: &str,
+    flag: &str,
+    usage: &str,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    writeln!(io.stderr, "{name}: {flag}: invalid option").map_err(ShellError::Io)?;
+    writeln!(io.stderr, "{name}: usage: {usage}").map_err(ShellError::Io)?;
+    Ok(ShellControl::Continue(2))
+}
+
+/// Печатает, чем является каждое имя:
+/// `name is aliased to `value'`, `name is a shell builtin`, `name is /path`
+/// (`name is hashed (/path)`, если путь взят из таблицы `hash`).
+///
+/// - `-a` — все значения, включая каждый файл из `PATH`;
+/// - `-t` — одно слово: `alias`, `builtin` или `file`;
+/// - `-p` — путь, если имя — программа (иначе ничего);
+/// - `-P` — путь программы из `PATH`, даже если имя — алиас или builtin.
+///
+/// Коды возврата:
+/// - 0: все имена найдены
+/// - 1: какое-то имя не найдено
+/// - 2: неизвестная опция
+fn run_type(
+    args: &[String],
+    state: &mut ShellState,
+    executor: &dyn ProcessExecutor,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let (flags, names) = match split_flags(args, "atpP") {
+        Ok(parsed) => parsed,
+        Err(flag) => return invalid_option("type", &flag, "type [-atpP] name [name ...]", io),
+    };
+    let paths_only = flags.contains(['p', 'P']);
+    let mut code = 0;
+    for name in names {
+        let kinds = describe(
+            name,
+            state,
+            executor,
+            flags.contains('a'),
+            flags.contains('P'),
+        );
+        if kinds.is_empty() {
+            if !flags.contains('t') && !paths_only {
+                writeln!(io.stderr, "type: {name}: not found").map_err(ShellError::Io)?;
+            }
+            code = 1;
+        }
+        for kind in kinds {
+            let line = match (kind, flags.contains('t')) {
+                (CommandKind::Alias(_), true) => "alias".to_string(),
+                (CommandKind::Builtin, true) => "builtin".to_string(),
+                (CommandKind::Hashed(_) | CommandKind::File(_), true) => "file".to_string(),
+                (CommandKind::Hashed(path) | CommandKind::File(path), false) if paths_only => {
+                    path.display().to_string()
+                }
+                (_, false) if paths_only => continue,
+                (kind, false) => type_description(name, &kind),
+            };
+            writeln!(io.stdout, "{line}").map_err(ShellError::Io)?;
+        }
+    }
+    Ok(ShellControl::Continue(code))
+}
+
+/// Строка `type` для значения имени.
+fn type_description(name: &str, kind: &CommandKind) -> String {
+    match kind {
+        CommandKind::Alias(value) => format!("{name} is aliased to `{value}'"),
+        CommandKind::Builtin => format!("{name} is a shell builtin"),
+        CommandKind::Hashed(path) => format!("{name} is hashed ({})", path.display()),
+        CommandKind::File(path) => format!("{name} is {}", path.display()),
+    }
+}
+
+/// Печатает, что выполнит каждое имя: путь программы, `name: aliased to value`
+/// или `name: shell built-in command`; `-a` — все значения.
+///
+/// Коды возврата:
+/// - 0: все имена найдены
+/// - 1: какое-то имя не найдено
+/// - 2: неизвестная опция
+fn run_which(
+    args: &[String],
+    state: &mut ShellState,
+    executor: &dyn ProcessExecutor,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let (flags, names) = match split_flags(args, "a") {
+        Ok(parsed) => parsed,
+        Err(flag) => return invalid_option("which", &flag, "which [-a] name [name ...]", io),
+    };
+    let mut code = 0;
+    for name in names {
+        let kinds = describe(name, state, executor, flags.contains('a'), false);
+        if kinds.is_empty() {
+            let path = state.env.get("PATH").map_or("", String::as_str);
+            writeln!(io.stderr, "which: no {name} in ({path})").map_err(ShellError::Io)?;
+            code = 1;
+        }
+        for kind in kinds {
+            match kind {
+                CommandKind::Alias(value) => writeln!(io.stdout, "{name}: aliased to {value}"),
+                CommandKind::Builtin => writeln!(io.stdout, "{name}: shell built-in command"),
+                CommandKind::Hashed(path) | CommandKind::File(path) => {
+                    writeln!(io.stdout, "{}", path.display())
+                }
+            }
+            .map_err(ShellError::Io)?;
+        }
+    }
+    Ok(ShellControl::Continue(code))
+}
+
+/// `command -v name...` печатает, что выполнит имя: путь программы, имя builtin
+/// или определение алиаса; `command -V` — описание как у `type`. Без имени
+/// команда ничего не делает (`command NAME args...` выполняет исполнитель).
+///
+/// Коды возврата:
+/// - 0: все имена найдены
+/// - 1: какое-то имя не найдено
+/// - 2: неизвестная опция
+fn run_command_query(
+    args: &[String],
+    state: &mut ShellState,
+    executor: &dyn ProcessExecutor,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let usage = "command [-v|-V] name [arg ...]";
+    let (flags, names) = match split_flags(args, "vV") {
+        Ok(parsed) => parsed,
+        Err(flag) => return invalid_option("command", &flag, usage, io),
+    };
+    let verbose = flags.contains('V');
+    let mut code = 0;
+    for name in names {
+        let Some(kind) = describe(name, state, executor, false, false).pop() else {
+            if verbose {
+                writeln!(io.stderr, "command: {name}: not found").map_err(ShellError::Io)?;
+            }
+            code = 1;
+            continue;
+        };
+        let line = match kind {
+            kind if verbose => type_description(name, &kind),
+            CommandKind::Alias(value) => alias_definition(name, &value),
+            CommandKind::Builtin => name.clone(),
+            CommandKind::Hashed(path) | CommandKind::File(path) => path.display().to_string(),
+        };
+        writeln!(io.stdout, "{line}").map_err(ShellError::Io)?;
+    }
+    Ok(ShellControl::Continue(code))
+}
+
+/// Таблица найденных программ (см. `executor::CommandHash`).
+///
+/// - без аргументов — печатает таблицу (`hits`, путь);
+/// - `name...` — ищет программы в `PATH` и запоминает их (builtins пропускаются);
+/// - `-r` — очищает таблицу (затем обрабатываются имена, если они есть);
+/// - `-d name...` — удаляет записи;
+/// - `-t name...` — печатает пути из таблицы (для нескольких имен — `name\tpath`).
+///
+/// Коды возврата:
+/// - 0: успех
+/// - 1: имя не найдено
+/// - 2: неизвестная опция
+fn run_hash(
+    args: &[String],
+    state: &mut ShellState,
+    executor: &dyn ProcessExecutor,
+    io: &mut IoStreams<'_>,
+) -> ShellResult<ShellControl> {
+    let usage = "hash [-r] [-d|-t] [name ...]";
+    let (flags, names) = match split_flags(args, "rdt") {
+        Ok(parsed) => parsed,
+        Err(flag) => return invalid_option("hash", &flag, usage, io),
+    };
+    if flags.contains('r') {
+        state.commands.clear();
+    }
+    if names.is_empty() {
+        if flags.is_empty() {
+            let mut entries = state.commands.iter(&state.env).peekable();
+            if entries.peek().is_none() {
+                writeln!(io.stdout, "hash: hash table empty").map_err(ShellError::Io)?;
+            } else {
+                writeln!(io.stdout, "hits\tcommand").map_err(ShellError::Io)?;
+                for (_, entry) in entries {
+                    writeln!(io.stdout, "{:>4}\t{}", entry.hits, entry.path.display())
+                        .map_err(ShellError::Io)?;
+                }
+            }
+        }
+        return Ok(ShellControl::Continue(0));
+    }
+
+    let mut code = 0;
+    for name in names {
+        let found = if flags.contains('d') {
+            state.commands.forget(name, &state.env)
+        } else if flags.contains('t') {
+            match state.commands.get(name, &state.env) {
+                Some(entry) if names.len() == 1 => {
+                    writeln!(io.stdout, "{}", entry.path.display()).map_err(ShellError::Io)?;
+                    true
+                }
+                Some(entry) => {
+                    writeln!(io.stdout, "{name}\t{}", entry.path.display())
+                        .map_err(ShellError::Io)?;
+                    true
+                }
+                None => false,
+            }
+        } else if state.is_builtin(name) {
+            true
+        } else {
+            let programs = executor.find_programs(name, &state.env, &state.cwd);
+            match programs.into_iter().next() {
+                Some(path) if !name.contains('/') => {
+                    state.commands.remember(name, path, &state.env);
+                    true
+                }
+                _ => false,
+            }
+        };
+        if !found {
+            writeln!(io.stderr, "hash: {name}: not found").map_err(ShellError::Io)?;
+            code = 1;
+        }
+    }
+    Ok(ShellControl::Continue(code))
+}
+
+/// Имя алиаса: непустое, без пробелов, кавычек, `/`, `$`, `=` и операторов шелла.
+fn is_alias_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name
+            .chars()
+            .any(|c| c.is_whitespace() || "/$`=\\'\"|&;()<>".contains(c))
+}
+
+/// Определение алиаса в виде команды: `alias name='value'`.
+fn alias_definition(name: &str, value: &str) -> String {
+    format!("alias {name}='{}'", value.replace('\'', "'\\''"))
+}
@@ -0,0 +1,105 @@
+//! Тесты алиасов: раскрытие при разборе и builtins `alias` / `unalias`.
+
+use std::collections::HashMap;
+
+use super::super::parser::parse_line_with_heredocs;
+use super::super::types::{Command, CommandList};
+use super::repl::run_with_input;
+
+fn aliases(defs: &[(&str, &str)]) -> HashMap<String, String> {
+    defs.iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Слова простых команд списка (имя и аргументы) по порядку.
+fn words(list: &CommandList) -> Vec<Vec<String>> {
+    list.entries
+        .iter()
+        .flat_map(|entry| &entry.pipeline)
+        .flat_map(|pipeline| &pipeline.commands)
+        .map(|command| match command {
+            Command::Simple(spec) => spec.words.iter().map(|word| word.text.clone()).collect(),
+            Command::Group(_) => vec!["<group>".to_string()],
+            Command::Conditional(_) => vec!["<conditional>".to_string()],
+        })
+        .collect()
+}
+
+fn parse(line: &str, defs: &[(&str, &str)]) -> Vec<Vec<String>> {
+    words(&parse_line_with_heredocs(line, &[], &aliases(defs)).unwrap())
+}
+
+#[test]
+fn alias_expands_only_in_command_position() {
+    let defs = [("g", "grep -i -n"), ("ll", "ls -l | head")];
+    assert_eq!(
+        parse("g x g; X=1 g y && echo g | ll", &defs),
+        vec![
+            vec!["grep", "-i", "-n", "x", "g"],
+            vec!["grep", "-i", "-n", "y"],
+            vec!["echo", "g"],
+            vec!["ls", "-l"],
+            vec!["head"],
+        ]
+    );
+    // Слово в кавычках или с подстановкой — не алиас.
+    assert_eq!(
+        parse("'g' a; \"g\" b", &defs),
+        vec![vec!["g", "a"], vec!["g", "b"]]
+    );
+}
+
+#[test]
+fn alias_expansion_is_recursive_without_loops() {
+    let defs = [("ll", "ls -l"), ("ls", "ls -F"), ("a", "b"), ("b", "a")];
+    assert_eq!(
+        parse("ll x; ls; a", &defs),
+        vec![vec!["ls", "-F", "-l", "x"], vec!["ls", "-F"], vec!["a"]]
+    );
+}
+
+#[test]
+fn trailing_space_expands_next_word() {
+    let defs = [("sudo", "sudo "), ("nice", "nice"), ("ll", "ls -l")];
+    assert_eq!(
+        parse("sudo ll; nice ll", &defs),
+        vec![vec!["sudo", "ls", "-l"], vec!["nice", "ll"]]
+    );
+}
+
+#[test]
+fn alias_builtins_define_list_and_remove() {
+    let (code, out, err) = run_with_input(
+        "alias g='grep -i -n' q=\"echo 'a  b'\"\nalias\nalias g\nprintf 'A\\nb\\n' | g B\nq\nunalias g\nalias g\nalias\nexit\n",
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(
+        out,
+        "alias g='grep -i -n'\nalias q='echo '\\''a  b'\\'''\nalias g='grep -i -n'\n2:b\na  b\nalias q='echo '\\''a  b'\\'''\n"
+    );
+    assert_eq!(err, "alias: g: not found\n");
+}
+
+#[test]
+fn alias_builtins_report_errors() {
+    let (code, out, err) = run_with_input(
+        "alias a/b=x\nunalias nope\nunalias\nalias x=y z=w\nunalias -a\nalias\nexit\n",
+    );
+    assert_eq!(code, 0);
+    assert_eq!(out, "");
+    assert!(
+        err.contains("alias: `a/b': invalid alias name"),
+        "stderr: {err}"
+    );
+    assert!(err.contains("unalias: nope: not found"), "stderr: {err}");
+    assert!(err.contains("unalias: usage:"), "stderr: {err}");
+}
+
+#[test]
+fn alias_defined_on_a_line_applies_from_the_next_line() {
+    let (code, out, err) = run_with_input("alias hi='echo hello'; hi\nhi there\nexit\n");
+    assert_eq!(code, 0);
+    assert_eq!(out, "hello there\n");
+    assert!(err.contains("hi"), "stderr: {err}");
+}
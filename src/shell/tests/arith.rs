@@ -0,0 +1,82 @@
+//! Unit-тесты для арифметического вычислителя.
+
+use std::collections::HashMap;
+
+use super::super::arith::evaluate;
+
+fn eval(expr: &str) -> i64 {
+    evaluate(expr, &mut HashMap::new()).unwrap()
+}
+
+fn eval_err(expr: &str) -> String {
+    evaluate(expr, &mut HashMap::new()).unwrap_err().message
+}
+
+#[test]
+fn respects_precedence_and_associativity() {
+    assert_eq!(eval("1 + 2 * 3"), 7);
+    assert_eq!(eval("(1 + 2) * 3"), 9);
+    assert_eq!(eval("10 - 4 - 3"), 3);
+    assert_eq!(eval("2 ** 3 ** 2"), 512);
+    assert_eq!(eval("-2 ** 2"), 4);
+    assert_eq!(eval("1 << 4 | 1"), 17);
+    assert_eq!(eval("6 & 3 ^ 1"), 3);
+    assert_eq!(eval("1 + 2 == 3 && 4 > 5 || 1"), 1);
+    assert_eq!(eval("!0 + ~0"), 0);
+    assert_eq!(eval("0 ? 1 : 2 ? 3 : 4"), 3);
+    assert_eq!(eval("1, 2, 3"), 3);
+}
+
+#[test]
+fn parses_hex_and_octal_literals() {
+    assert_eq!(eval("0x1F"), 31);
+    assert_eq!(eval("010"), 8);
+    assert_eq!(eval_err("09"), "invalid number: 09");
+}
+
+#[test]
+fn reads_and_assigns_variables_without_dollar() {
+    let mut vars: HashMap<String, String> = HashMap::new();
+    vars.insert("i".to_string(), "5".to_string());
+    vars.insert("expr".to_string(), "i * 2".to_string());
+
+    assert_eq!(evaluate("i += 1", &mut vars).unwrap(), 6);
+    assert_eq!(vars["i"], "6");
+    assert_eq!(evaluate("i++", &mut vars).unwrap(), 6);
+    assert_eq!(evaluate("--i", &mut vars).unwrap(), 6);
+    assert_eq!(evaluate("expr + 1", &mut vars).unwrap(), 13);
+    assert_eq!(evaluate("unset_var + 1", &mut vars).unwrap(), 1);
+    assert_eq!(evaluate("a = b = 3", &mut vars).unwrap(), 3);
+    assert_eq!(vars["a"], "3");
+    assert_eq!(vars["b"], "3");
+}
+
+#[test]
+fn logical_and_conditional_operators_short_circuit() {
+    let mut vars: HashMap<String, String> = HashMap::new();
+    assert_eq!(evaluate("0 && (x = 1)", &mut vars).unwrap(), 0);
+    assert_eq!(evaluate("1 || (x = 1)", &mut vars).unwrap(), 1);
+    assert_eq!(evaluate("1 ? (y = 2) : (x = 3)", &mut vars).unwrap(), 2);
+    assert!(!vars.contains_key("x"));
+    assert_eq!(vars["y"], "2");
+}
+
+#[test]
+fn reports_runtime_and_syntax_errors() {
+    assert_eq!(eval_err("1 / 0"), "division by 0");
+    assert_eq!(eval_err("5 % 0"), "division by 0");
+    assert_eq!(eval_err("9223372036854775807 + 1"), "integer overflow");
+    assert_eq!(eval_err("2 ** 64"), "integer overflow");
+    assert_eq!(eval_err("2 ** -1"), "exponent less than 0");
+    assert_eq!(eval_err("1 +"), "syntax error: operand expected");
+    assert_eq!(eval_err("(1"), "syntax error: operand expected");
+    assert_eq!(eval_err("1 2"), "syntax error: unexpected `2`");
+    assert_eq!(eval_err("1 @ 2"), "syntax error: invalid character `@`");
+
+    let mut vars: HashMap<String, String> = HashMap::new();
+    vars.insert("a".to_string(), "a".to_string());
+    assert_eq!(
+        evaluate("a", &mut vars).unwrap_err().message,
+        "expression recursion level exceeded"
+    );
+}
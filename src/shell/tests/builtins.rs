@@ -1,11 +1,22 @@
 //! Unit-тесты для builtin-команд.
 
-use std::io::Write;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
 
-use super::super::builtins::{Builtin, run_builtin, run_builtin_with_input};
+use super::super::ShellCommandRunner;
+use super::super::builtins::{Builtin, BuiltinContext, run_builtin_with_input};
+use super::super::executor::{CommandHash, StdProcessExecutor};
 use super::super::types::{IoStreams, ShellControl};
-
-fn run(builtin: Builtin, args: &[&str]) -> (ShellControl, String, String) {
+use super::super::vfs::OsFs;
+
+/// Запускает builtin с рабочей директорией `cwd` и окружением текущего процесса.
+pub(super) fn run_in_dir(
+    cwd: &Path,
+    builtin: Builtin,
+    args: &[&str],
+    stdin: Option<&[u8]>,
+) -> (ShellControl, String, String) {
     let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
     let mut out = Vec::new();
     let mut err = Vec::new();
@@ -14,7 +25,27 @@ fn run(builtin: Builtin, args: &[&str]) -> (ShellControl, String, String) {
         stderr: &mut err,
     };
 
-    let control = run_builtin(builtin, &args, &mut io).unwrap();
+    let executor = StdProcessExecutor::new();
+    let env: HashMap<String, String> = std::env::vars().collect();
+    let runner = ShellCommandRunner {
+        executor: &executor,
+        env: &env,
+        cwd,
+        fs: &OsFs,
+        builtins: &Default::default(),
+        commands: &CommandHash::default(),
+        restrictions: None,
+        limits: &Default::default(),
+    };
+    let ctx = BuiltinContext {
+        cwd,
+        fs: &OsFs,
+        runner: &runner,
+        deadline: None,
+    };
+    let mut stdin = stdin;
+    let stdin = stdin.as_mut().map(|data| data as &mut dyn BufRead);
+    let control = run_builtin_with_input(builtin, &args, stdin, ctx, &mut io).unwrap();
     (
         control,
         String::from_utf8_lossy(&out).to_string(),
@@ -22,21 +53,18 @@ fn run(builtin: Builtin, args: &[&str]) -> (ShellControl, String, String) {
     )
 }
 
-fn run_with_stdin(builtin: Builtin, args: &[&str], stdin: &[u8]) -> (ShellControl, String, String) {
-    let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
-    let mut out = Vec::new();
-    let mut err = Vec::new();
-    let mut io = IoStreams {
-        stdout: &mut out,
-        stderr: &mut err,
-    };
+pub(super) fn run(builtin: Builtin, args: &[&str]) -> (ShellControl, String, String) {
+    let cwd = std::env::current_dir().unwrap();
+    run_in_dir(&cwd, builtin, args, None)
+}
 
-    let control = run_builtin_with_input(builtin, &args, Some(stdin), &mut io).unwrap();
-    (
-        control,
-        String::from_utf8_lossy(&out).to_string(),
-        String::from_utf8_lossy(&err).to_string(),
-    )
+pub(super) fn run_with_stdin(
+    builtin: Builtin,
+    args: &[&str],
+    stdin: &[u8],
+) -> (ShellControl, String, String) {
+    let cwd = std::env::current_dir().unwrap();
+    run_in_dir(&cwd, builtin, args, Some(stdin))
 }
 
 #[test]
@@ -210,6 +238,29 @@ fn grep_after_context_overlaps_do_not_duplicate_lines() {
     assert!(err.is_empty());
 }
 
+#[test]
+fn grep_line_numbers_mark_matches_and_context() {
+    let (control, out, err) = run_with_stdin(
+        Builtin::Grep,
+        &["-n", "-A", "1", "MATCH"],
+        b"a\nMATCH\nc\nd\n",
+    );
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "2:MATCH\n3-c\n");
+    assert!(err.is_empty());
+}
+
+#[test]
+fn grep_max_count_stops_after_matches_and_their_context() {
+    let input = b"a1\nb\na2\nc\na3\n";
+    let (control, out, _e) = run_with_stdin(Builtin::Grep, &["-m", "2", "-A", "1", "a"], input);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "a1\nb\na2\nc\n");
+    let (control, out, _e) = run_with_stdin(Builtin::Grep, &["-m", "0", "a"], input);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert_eq!(out, "");
+}
+
 #[test]
 fn grep_invalid_regex_is_error() {
     let mut tmp = tempfile::NamedTempFile::new().unwrap();
@@ -260,3 +311,219 @@ fn grep_multiple_files_returns_error_code_if_any_file_missing() {
     assert!(out.contains(&format!("{path}:MATCH\n")));
     assert!(err.starts_with("grep:"));
 }
+
+#[test]
+fn cat_number_lines_continues_across_inputs() {
+    let mut tmp = tempfile::NamedTempFile::new().unwrap();
+    tmp.write_all(b"b\n\nc\n").unwrap();
+    let path = tmp.path().to_string_lossy().to_string();
+
+    let (control, out, err) = run_with_stdin(Builtin::Cat, &["-n", "-", &path], b"a\n");
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "     1\ta\n     2\tb\n     3\t\n     4\tc\n");
+    assert!(err.is_empty());
+}
+
+#[test]
+fn cat_number_nonblank_skips_empty_lines() {
+    let (control, out, _err) = run_with_stdin(Builtin::Cat, &["-b"], b"a\n\nb\n");
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "     1\ta\n\n     2\tb\n");
+}
+
+#[test]
+fn cat_squeeze_blank_collapses_repeated_empty_lines() {
+    let (control, out, _err) = run_with_stdin(Builtin::Cat, &["-s"], b"a\n\n\n\nb\n");
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "a\n\nb\n");
+}
+
+#[test]
+fn cat_show_all_marks_tabs_ends_and_control_chars() {
+    let (control, out, _err) = run_with_stdin(Builtin::Cat, &["-A"], b"a\tb\x01\x7f\xe9\n");
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "a^Ib^A^?M-i$\n");
+}
+
+#[test]
+fn cat_dash_reads_stdin_only_once() {
+    let (control, out, _err) = run_with_stdin(Builtin::Cat, &["-", "-"], b"x\n");
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "x\n");
+}
+
+#[test]
+fn cat_unknown_flag_is_error() {
+    let (control, out, err) = run(Builtin::Cat, &["-z"]);
+    assert_eq!(control, ShellControl::Continue(2));
+    assert!(out.is_empty());
+    assert!(err.starts_with("cat:"));
+}
+
+#[test]
+fn echo_n_suppresses_newline() {
+    let (control, out, _err) = run(Builtin::Echo, &["-n", "hi"]);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "hi");
+}
+
+#[test]
+fn echo_e_interprets_escapes() {
+    let (_control, out, _err) = run(Builtin::Echo, &["-e", "a\\tb\\n\\x41\\0101"]);
+    assert_eq!(out, "a\tb\nAA\n");
+}
+
+#[test]
+fn echo_e_backslash_c_stops_output() {
+    let (_control, out, _err) = run(Builtin::Echo, &["-e", "one\\ctwo", "three"]);
+    assert_eq!(out, "one");
+}
+
+#[test]
+fn echo_without_e_keeps_backslashes_and_non_option_dashes() {
+    let (_control, out, _err) = run(Builtin::Echo, &["-x", "a\\nb"]);
+    assert_eq!(out, "-x a\\nb\n");
+
+    let (_control, out, _err) = run(Builtin::Echo, &["-eE", "a\\nb"]);
+    assert_eq!(out, "a\\nb\n");
+}
+
+#[test]
+fn printf_without_format_is_usage_error() {
+    let (control, out, err) = run(Builtin::Printf, &[]);
+    assert_eq!(control, ShellControl::Continue(2));
+    assert!(out.is_empty());
+    assert!(err.contains("usage"));
+}
+
+#[test]
+fn printf_formats_strings_and_integers() {
+    let (control, out, err) = run(
+        Builtin::Printf,
+        &[
+            "[%5s|%-5s|%.2s] %d %+i %05d %x %X %#o %%\\n",
+            "ab",
+            "cd",
+            "xyz",
+            "42",
+            "7",
+            "-42",
+            "255",
+            "255",
+            "8",
+        ],
+    );
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "[   ab|cd   |xy] 42 +7 -0042 ff FF 010 %\n");
+    assert!(err.is_empty());
+}
+
+#[test]
+fn printf_formats_floats_and_chars() {
+    let (control, out, _err) = run(
+        Builtin::Printf,
+        &[
+            "%.2f %e %8.3E %g %g %c\\n",
+            "3.14159",
+            "1500",
+            "0.00123",
+            "0.0001",
+            "1e10",
+            "xyz",
+        ],
+    );
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "3.14 1.500000e+03 1.230E-03 0.0001 1e+10 x\n");
+}
+
+#[test]
+fn printf_reuses_format_for_extra_arguments() {
+    let (control, out, _err) = run(Builtin::Printf, &["%s=%d\\n", "a", "1", "b", "2", "c"]);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "a=1\nb=2\nc=0\n");
+}
+
+#[test]
+fn printf_b_expands_escapes_in_argument() {
+    let (_control, out, _err) = run(Builtin::Printf, &["%s|%b|\\n", "a\\tb", "a\\tb"]);
+    assert_eq!(out, "a\\tb|a\tb|\n");
+}
+
+#[test]
+fn printf_star_width_and_numeric_forms() {
+    let (_control, out, _err) = run(
+        Builtin::Printf,
+        &["%*d|%d|%d|%d\\n", "4", "7", "0x1f", "010", "'A"],
+    );
+    assert_eq!(out, "   7|31|8|65\n");
+}
+
+#[test]
+fn printf_invalid_number_reports_error_and_prints_zero() {
+    let (control, out, err) = run(Builtin::Printf, &["%d\\n", "abc"]);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert_eq!(out, "0\n");
+    assert!(err.contains("printf: abc: invalid number"));
+}
+
+#[test]
+fn printf_clamps_integers_that_overflow() {
+    let (control, out, err) = run(
+        Builtin::Printf,
+        &[
+            "%d %d %d\\n",
+            "99999999999999999999",
+            "-99999999999999999999",
+            "-9223372036854775808",
+        ],
+    );
+    assert_eq!(control, ShellControl::Continue(1));
+    assert_eq!(
+        out,
+        "9223372036854775807 -9223372036854775808 -9223372036854775808\n"
+    );
+    assert_eq!(
+        err,
+        "printf: 99999999999999999999: Result too large\nprintf: -99999999999999999999: Result too large\n"
+    );
+}
+
+#[test]
+fn printf_invalid_directive_is_error() {
+    let (control, _out, err) = run(Builtin::Printf, &["%z"]);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(err.contains("invalid directive"));
+}
+
+#[test]
+fn printf_rejects_huge_width_and_precision() {
+    for (args, msg) in [
+        (
+            &["%*d", "99999999999", "1"][..],
+            "99999999999: invalid field width",
+        ),
+        (
+            &["%*d", "-99999999999", "1"][..],
+            "-99999999999: invalid field width",
+        ),
+        (
+            &["a%9999999999d", "1"][..],
+            "9999999999: invalid field width",
+        ),
+        (
+            &["%.99999999999f", "1"][..],
+            "99999999999: invalid precision",
+        ),
+        (
+            &["%.*s", "99999999999999999999", "x"][..],
+            "99999999999999999999: invalid precision",
+        ),
+    ] {
+        let (control, _out, err) = run(Builtin::Printf, args);
+        assert_eq!(control, ShellControl::Continue(1), "{args:?}");
+        assert_eq!(err, format!("printf: {msg}\n"), "{args:?}");
+    }
+    let (control, out, _err) = run(Builtin::Printf, &["%*d|%.*s", "5", "1", "2", "abc"]);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "    1|ab");
+}
@@ -0,0 +1,187 @@
+//! Unit-тесты для `test`, `[` и `[[ ... ]]`.
+
+use super::super::builtins::Builtin;
+use super::super::types::ShellControl;
+use super::builtins::run_in_dir;
+use super::repl::run_with_input;
+
+fn status_in(cwd: &std::path::Path, builtin: Builtin, args: &[&str]) -> i32 {
+    match run_in_dir(cwd, builtin, args, None).0 {
+        ShellControl::Continue(code) | ShellControl::Exit(code) => code,
+    }
+}
+
+fn status(builtin: Builtin, args: &[&str]) -> i32 {
+    status_in(&std::env::current_dir().unwrap(), builtin, args)
+}
+
+#[test]
+fn test_string_operators() {
+    assert_eq!(status(Builtin::Test, &["-z", ""]), 0);
+    assert_eq!(status(Builtin::Test, &["-n", ""]), 1);
+    assert_eq!(status(Builtin::Test, &["abc"]), 0);
+    assert_eq!(status(Builtin::Test, &[""]), 1);
+    assert_eq!(status(Builtin::Test, &[]), 1);
+    assert_eq!(status(Builtin::Test, &["a", "=", "a"]), 0);
+    assert_eq!(status(Builtin::Test, &["a", "!=", "a"]), 1);
+    assert_eq!(status(Builtin::Test, &["abc", "<", "abd"]), 0);
+    assert_eq!(status(Builtin::Test, &["abc", ">", "abd"]), 1);
+    // Бинарный оператор важнее унарного: это сравнение строк `-n` и `-n`.
+    assert_eq!(status(Builtin::Test, &["-n", "=", "-n"]), 0);
+}
+
+#[test]
+fn test_integer_comparisons_and_errors() {
+    assert_eq!(status(Builtin::Test, &["3", "-lt", "10"]), 0);
+    assert_eq!(status(Builtin::Test, &["3", "-ge", "10"]), 1);
+    assert_eq!(status(Builtin::Test, &["-5", "-eq", " -5"]), 0);
+
+    let (control, _out, err) = run_in_dir(
+        &std::env::current_dir().unwrap(),
+        Builtin::Test,
+        &["x", "-eq", "1"],
+        None,
+    );
+    assert_eq!(control, ShellControl::Continue(2));
+    assert_eq!(err, "test: x: integer expression expected\n");
+}
+
+#[test]
+fn test_logical_operators_and_parentheses() {
+    assert_eq!(status(Builtin::Test, &["!", "-z", "x"]), 0);
+    assert_eq!(status(Builtin::Test, &["a", "-a", ""]), 1);
+    assert_eq!(status(Builtin::Test, &["a", "-o", ""]), 0);
+    // `-a` связывает сильнее `-o`.
+    assert_eq!(status(Builtin::Test, &["a", "-o", "", "-a", ""]), 0);
+    assert_eq!(
+        status(Builtin::Test, &["(", "a", "-o", "", ")", "-a", ""]),
+        1
+    );
+    assert_eq!(status(Builtin::Test, &["(", "a"]), 2);
+}
+
+#[test]
+fn test_file_operators_resolve_against_shell_cwd() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("empty"), b"").unwrap();
+    std::fs::write(dir.path().join("full"), b"data").unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+
+    let cwd = dir.path();
+    assert_eq!(status_in(cwd, Builtin::Test, &["-e", "empty"]), 0);
+    assert_eq!(status_in(cwd, Builtin::Test, &["-e", "missing"]), 1);
+    assert_eq!(status_in(cwd, Builtin::Test, &["-f", "full"]), 0);
+    assert_eq!(status_in(cwd, Builtin::Test, &["-f", "sub"]), 1);
+    assert_eq!(status_in(cwd, Builtin::Test, &["-d", "sub"]), 0);
+    assert_eq!(status_in(cwd, Builtin::Test, &["-s", "empty"]), 1);
+    assert_eq!(status_in(cwd, Builtin::Test, &["-s", "full"]), 0);
+    assert_eq!(status_in(cwd, Builtin::Test, &["-r", "full"]), 0);
+    assert_eq!(status_in(cwd, Builtin::Test, &["-w", "full"]), 0);
+    assert_eq!(
+        status_in(cwd, Builtin::Test, &["full", "-nt", "missing"]),
+        0
+    );
+    assert_eq!(
+        status_in(cwd, Builtin::Test, &["missing", "-ot", "full"]),
+        0
+    );
+    assert_eq!(status_in(cwd, Builtin::Test, &["full", "-ef", "./full"]), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_executable_bit() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("script");
+    std::fs::write(&script, b"#!/bin/sh\n").unwrap();
+    assert_eq!(status_in(dir.path(), Builtin::Test, &["-x", "script"]), 1);
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    assert_eq!(status_in(dir.path(), Builtin::Test, &["-x", "script"]), 0);
+}
+
+#[test]
+fn bracket_requires_closing_bracket() {
+    assert_eq!(status(Builtin::Bracket, &["a", "=", "a", "]"]), 0);
+
+    let (control, _out, err) = run_in_dir(
+        &std::env::current_dir().unwrap(),
+        Builtin::Bracket,
+        &["a", "=", "a"],
+        None,
+    );
+    assert_eq!(control, ShellControl::Continue(2));
+    assert_eq!(err, "[: missing ']'\n");
+}
+
+/// Коды `[[ ... ]]` для строк `lines` (каждая — отдельная команда).
+fn conditional_statuses(lines: &[&str]) -> Vec<i32> {
+    let script: String = lines
+        .iter()
+        .map(|line| format!("{line}\necho $PIPESTATUS\n"))
+        .collect();
+    let (_code, out, err) = run_with_input(&script);
+    assert_eq!(err, "");
+    out.lines().map(|line| line.parse().unwrap()).collect()
+}
+
+#[test]
+fn double_bracket_supports_regex_globs_and_logical_operators() {
+    assert_eq!(
+        conditional_statuses(&[
+            r"[[ v1.2.3 =~ ^v[0-9]+\. ]]",
+            "[[ abc =~ ^b ]]",
+            "[[ main.rs == *.rs ]]",
+            "[[ main.rs != *.rs ]]",
+            "[[ '' || -n x ]]",
+            "[[ x && -z x ]]",
+            "[[ a =~ ( ]] 2>/dev/null",
+        ]),
+        [0, 1, 0, 1, 0, 1, 2]
+    );
+    // `=~` доступен только в `[[`.
+    assert_eq!(status(Builtin::Bracket, &["a", "=~", "a", "]"]), 2);
+}
+
+#[test]
+fn double_bracket_keeps_empty_and_unset_words() {
+    assert_eq!(
+        conditional_statuses(&[
+            "X=; [[ -n $X ]]",
+            "[[ -n $UNSET ]]",
+            "[[ -z $UNSET ]]",
+            "X=; [[ $X = \"\" && -d src ]]",
+            "X='a b'; [[ $X == 'a b' ]]",
+            // Значение `$OP` — операнд, а не оператор: это проверка непустой строки.
+            "OP=-z; [[ $OP ]]",
+        ]),
+        [1, 1, 0, 0, 0, 0]
+    );
+}
+
+#[test]
+fn double_bracket_quoted_patterns_match_literally() {
+    assert_eq!(
+        conditional_statuses(&[
+            "[[ ab == \"a*\" ]]",
+            "[[ 'a*' == \"a*\" ]]",
+            "p='a*'; [[ ab == $p ]]",
+            "p='a*'; [[ ab == \"$p\" ]]",
+            "[[ ab == a'?' ]]",
+            "[[ a.c =~ \"a.c\" ]]",
+            "[[ abc =~ \"a.c\" ]]",
+            "[[ abc =~ ^a\".\"c ]]",
+        ]),
+        [1, 0, 0, 1, 1, 0, 1, 1]
+    );
+}
+
+#[test]
+fn double_bracket_syntax_errors_are_parse_errors() {
+    let (_code, out, err) = run_with_input("[[ -n x\n[[ ]]\n[[ a ]] b\necho done\n");
+    assert_eq!(out, "done\n");
+    assert!(err.contains("did you forget `]]` for `[[`"), "{err}");
+    assert!(err.contains("unexpected token `]]`"), "{err}");
+    assert!(err.contains("unexpected token `b`"), "{err}");
+}
@@ -0,0 +1,140 @@
+//! Тесты отладочного вывода разбора и `set -o debugparse`.
+
+use super::super::debugparse::{dump_ast, dump_tokens};
+use super::super::options::{DumpFormat, Startup};
+use super::super::parser::{parse_line, tokenize};
+use super::repl::{run_with_input, run_with_options};
+
+#[test]
+fn pretty_tokens_show_spans_and_word_parts() {
+    let tokens = tokenize("echo \"$X\"y 2> err | wc").unwrap();
+    assert_eq!(
+        dump_tokens(&tokens, DumpFormat::Pretty),
+        "tokens:\n\
+         \x20 0..4 word \"echo\" = literal \"echo\"\n\
+         \x20 5..10 word \"$Xy\" = var X (quoted) + literal \"y\"\n\
+         \x20 11..17 redirect \"2>err\"\n\
+         \x20 18..19 pipe \"|\"\n\
+         \x20 20..22 word \"wc\" = literal \"wc\"\n"
+    );
+}
+
+#[test]
+fn pretty_ast_shows_words_before_expansion() {
+    let parsed = parse_line("V=1 && { echo $X; } > out").unwrap();
+    assert_eq!(
+        dump_ast(&parsed, DumpFormat::Pretty),
+        "ast:\n\
+         \x20 entry always\n\
+         \x20   assign V=\"1\"\n\
+         \x20 entry if-success\n\
+         \x20   pipeline\n\
+         \x20     group 7..25 brace\n\
+         \x20       entry always\n\
+         \x20         pipeline\n\
+         \x20           simple 9..16 [\"echo\", \"$X\"]\n\
+         \x20       redirect \">out\"\n"
+    );
+}
+
+#[test]
+fn json_dump_escapes_strings() {
+    let tokens = tokenize("echo 'a\"b'").unwrap();
+    assert_eq!(
+        dump_tokens(&tokens, DumpFormat::Json),
+        "{\"tokens\":[\
+         {\"kind\":\"word\",\"text\":\"echo\",\"span\":[0,4],\"parts\":[{\"kind\":\"literal\",\"text\":\"echo\",\"quoted\":false,\"span\":[0,4]}]},\
+         {\"kind\":\"word\",\"text\":\"a\\\"b\",\"span\":[5,10],\"parts\":[{\"kind\":\"literal\",\"text\":\"a\\\"b\",\"quoted\":true,\"span\":[6,9]}]}\
+         ]}\n"
+    );
+
+    let parsed = parse_line("cat <<< hi").unwrap();
+    assert_eq!(
+        dump_ast(&parsed, DumpFormat::Json),
+        "{\"ast\":{\"entries\":[{\"connector\":\"always\",\"assignments\":[],\"pipeline\":{\"commands\":[\
+         {\"kind\":\"simple\",\"span\":[0,10],\"words\":[\"cat\"],\"process_substs\":[],\"redirects\":[],\"here_input\":\"hi\\n\"}\
+         ]}}]}}\n"
+    );
+}
+
+#[test]
+fn cli_flags_select_dumps_and_format() {
+    let options = Startup::from_args(["se-cli", "--dump-ast", "--dump-format", "json"])
+        .unwrap()
+        .options;
+    assert!(options.dump_ast && !options.dump_tokens);
+    assert_eq!(options.dump_format, DumpFormat::Json);
+    assert!(Startup::from_args(["se-cli", "--dump-format", "xml"]).is_err());
+
+    let (code, out, err) = run_with_options("echo hi\nexit\n", options);
+    assert_eq!(code, 0);
+    assert_eq!(out, "hi\n");
+    assert!(err.starts_with("{\"ast\":{\"entries\":["), "stderr: {err}");
+    assert!(!err.contains("tokens"), "stderr: {err}");
+}
+
+#[test]
+fn set_o_debugparse_toggles_dumps() {
+    let (code, out, err) = run_with_input(
+        "set -o | grep debugparse\nset -o debugparse\necho a\nset +o debugparse\necho b\nset +o | grep debugparse\nexit\n",
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "debugparse     \toff\na\nb\nset +o debugparse\n");
+    assert!(
+        err.contains("tokens:\n  0..4 word \"echo\""),
+        "stderr: {err}"
+    );
+    assert!(
+        err.contains("simple 0..6 [\"echo\", \"a\"]"),
+        "stderr: {err}"
+    );
+    assert!(!err.contains("\"b\""), "stderr: {err}");
+}
+
+#[test]
+fn set_o_debug_expand_shows_expanded_words_when_commands_run() {
+    let (code, out, err) = run_with_input(
+        "set -o debug-expand\nX='a b'; echo $X > /dev/null && { cat; } <<< \"$X\"\nfalse && echo $((i+=1))\n[[ -n $E ]]\nset +o debug-expand\necho off\n",
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "a b\noff\n");
+    assert_eq!(
+        err,
+        "expand: assign X=\"a b\"\n\
+         expand: simple 9..28 [\"echo\", \"a\", \"b\"]\n\
+         \x20 redirect \">/dev/null\"\n\
+         expand: brace\n\
+         \x20 here-input \"a b\\n\"\n\
+         expand: simple 34..37 [\"cat\"]\n\
+         expand: simple 0..5 [\"false\"]\n\
+         expand: conditional [\"-n\", \"\"]\n\
+         expand: simple 0..19 [\"set\", \"+o\", \"debug-expand\"]\n"
+    );
+}
+
+#[test]
+fn json_debug_expand_prints_one_object_per_command() {
+    let mut options = Startup::default().options;
+    options.dump_expanded = true;
+    options.dump_format = DumpFormat::Json;
+    let (code, out, err) = run_with_options("X=1\necho \"$X\" '' > /dev/null\n", options);
+    assert_eq!(code, 0, "stderr: {err}");
+    assert!(out.is_empty());
+    assert_eq!(
+        err,
+        "{\"expand\":{\"kind\":\"assign\",\"name\":\"X\",\"value\":\"1\"}}\n\
+         {\"expand\":{\"kind\":\"simple\",\"span\":[0,24],\"words\":[\"echo\",\"1\",\"\"],\
+         \"redirects\":[\">/dev/null\"],\"here_input\":null}}\n"
+    );
+}
+
+#[test]
+fn set_rejects_unknown_options() {
+    let (code, _out, err) = run_with_input("set -o nope\necho $?\nset -z\nexit\n");
+    assert_eq!(code, 0);
+    assert!(
+        err.contains("set: nope: invalid option name"),
+        "stderr: {err}"
+    );
+    assert!(err.contains("set: -z: invalid option"), "stderr: {err}");
+}
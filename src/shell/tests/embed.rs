@@ -0,0 +1,168 @@
+//! Тесты встраиваемого API (`Shell`, `ShellBuilder`).
+
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::super::mock::{MockExecutor, MockResponse};
+use super::super::{BuiltinCall, Shell};
+
+/// Builtin `upper`: печатает stdin и аргументы заглавными буквами.
+fn upper(call: &mut BuiltinCall<'_>) -> std::io::Result<i32> {
+    let mut text = String::new();
+    call.stdin.read_to_string(&mut text)?;
+    text.push_str(&call.args.join(" "));
+    write!(call.stdout, "{}", text.to_uppercase())?;
+    Ok(0)
+}
+
+#[test]
+fn state_persists_between_evals() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let mut shell = Shell::builder().current_dir(dir.path()).build();
+
+    let out = shell.eval("X=1\nalias say='echo said'\ncd sub\nset -o pipefail");
+    assert!(out.status.success(), "stderr: {}", out.stderr_text());
+    assert_eq!(shell.cwd(), dir.path().join("sub"));
+    assert_eq!(out.vars.get("X").map(String::as_str), Some("1"));
+
+    let out = shell.eval("say $X\npwd\nfalse_cmd_xyz | cat");
+    let pwd = dir.path().join("sub");
+    assert_eq!(out.stdout_text(), format!("said 1\n{}\n", pwd.display()));
+    assert_eq!(out.status.code, 127);
+    assert!(!out.vars.contains_key("X"));
+    assert_eq!(
+        out.vars.get("PIPESTATUS").map(String::as_str),
+        Some("127 0")
+    );
+}
+
+#[test]
+fn variables_are_shared_with_the_host() {
+    let mut shell = Shell::builder()
+        .env_clear()
+        .envs([("A", "1"), ("B", "2")])
+        .env_remove("B")
+        .build();
+    shell.set_var("C", "3");
+    let out = shell.eval("echo $A-$B-$C\nA=changed");
+    assert_eq!(out.stdout_text(), "1--3\n");
+    assert_eq!(shell.var("A"), Some("changed"));
+    assert_eq!(shell.remove_var("C").as_deref(), Some("3"));
+
+    let mut vars: Vec<_> = shell
+        .vars()
+        .filter(|(name, _)| *name != "PIPESTATUS")
+        .collect();
+    vars.sort();
+    assert_eq!(vars, [("A", "changed")]);
+}
+
+#[test]
+fn errors_are_reported_per_line() {
+    let mut shell = Shell::new();
+    let out = shell.eval("echo 'open\necho next\nexit 4\necho unreachable");
+    assert_eq!(out.stdout_text(), "next\n");
+    assert_eq!((out.status.code, out.status.exited), (4, true));
+    assert!(out.stderr_text().starts_with("eval: line 1: Parse error"));
+
+    // После `exit` шелл можно использовать дальше.
+    let out = shell.eval("echo again");
+    assert_eq!(out.stdout_text(), "again\n");
+    assert!(!out.status.exited);
+}
+
+#[test]
+fn eval_to_streams_output() {
+    let mut shell = Shell::new();
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    let status = shell
+        .eval_to("echo out\nnope_cmd_xyz_embed", &mut out, &mut err)
+        .unwrap();
+    assert_eq!(status.code, 127);
+    assert_eq!(out, b"out\n");
+    assert!(String::from_utf8_lossy(&err).starts_with("nope_cmd_xyz_embed: command not found"));
+}
+
+#[test]
+fn custom_builtins_run_everywhere() {
+    let mut shell = Shell::builder()
+        .builtin("upper", upper)
+        .builtin("echo", |call| {
+            writeln!(call.stdout, "custom echo {}", call.args.join(","))?;
+            Ok(5)
+        })
+        .builtin("cd", |_| Ok(9))
+        .build();
+    let out = shell.eval(
+        "upper a b\necho x y\necho hi | upper | grep HI\n( upper <<< sub )\nprintf 'p\\nq\\n' | xargs upper\ntype upper\nwhich upper\ncd /\necho $PIPESTATUS",
+    );
+    assert_eq!(
+        out.stdout_text(),
+        "A B\
+         custom echo x,y\n\
+         CUSTOM ECHO HI\n\
+         SUB\n\
+         P Q\
+         upper is a shell builtin\n\
+         upper: shell built-in command\n\
+         custom echo 0\n"
+    );
+    assert_eq!(out.stderr_text(), "");
+}
+
+#[test]
+fn custom_builtin_error_fails_the_line() {
+    let mut shell = Shell::builder()
+        .builtin("broken", |_| Err(std::io::Error::other("disk on fire")))
+        .build();
+    let out = shell.eval("broken\necho after");
+    assert_eq!(out.stdout_text(), "after\n");
+    assert_eq!(out.stderr_text(), "eval: line 1: I/O error: disk on fire\n");
+}
+
+#[test]
+fn similar_custom_builtin_is_suggested() {
+    let mut shell = Shell::builder().builtin("deploy_app", upper).build();
+    let out = shell.eval("deploy_ap");
+    assert_eq!(
+        out.stderr_text(),
+        "deploy_ap: command not found\n  hint: did you mean `deploy_app`?\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn external_programs_get_empty_stdin() {
+    let mut shell = Shell::new();
+    let out = shell.eval("sh -c 'cat; echo done'\necho fed | sh -c 'cat'");
+    assert_eq!(out.stdout_text(), "done\nfed\n");
+
+    let mut shell = Shell::builder().env_clear().build();
+    let out = shell.eval("sh -c 'echo no'\n/bin/sh -c 'echo yes'");
+    assert_eq!(out.stdout_text(), "yes\n");
+    assert!(out.stderr_text().starts_with("sh: command not found"));
+}
+
+#[test]
+fn injected_executor_runs_external_programs() {
+    let mock = Arc::new(MockExecutor::new());
+    mock.respond("deploy", MockResponse::stdout("ok\n").with_exit_code(3));
+    let mut shell = Shell::builder()
+        .env_clear()
+        .env("STAGE", "prod")
+        .current_dir("/srv")
+        .executor(mock.clone())
+        .build();
+
+    let out = shell.eval("echo plan | deploy --stage $STAGE\necho $PIPESTATUS\ntype deploy");
+    assert_eq!(out.stdout_text(), "ok\n0 3\ndeploy is /mock/bin/deploy\n");
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].argv, ["deploy", "--stage", "prod"]);
+    assert_eq!(calls[0].env["STAGE"], "prod");
+    assert_eq!(calls[0].cwd, Path::new("/srv"));
+    assert_eq!(calls[0].stdin, b"plan\n");
+}
@@ -1,21 +1,43 @@
 //! Unit-тесты для запуска внешних команд.
 
 use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
 
-use super::super::executor::StdProcessExecutor;
+use super::super::executor::{
+    ProcessCommand, ProcessExecutor, ProcessInput, ProcessOutput, StdProcessExecutor,
+    closest_names, pipe, run_program,
+};
+use super::super::limits::ProcessLimits;
+use super::super::types::{RunResult, ShellResult};
 
 fn process_env_map() -> HashMap<String, String> {
     std::env::vars().collect()
 }
 
-#[cfg(windows)]
-#[test]
-fn run_external_captures_stdout_and_exit_code_windows() {
+/// Запускает `program` через `StdProcessExecutor` с захватом stdout.
+fn run_external(program: &str, args: &[&str], stdin: Option<&[u8]>) -> ShellResult<RunResult> {
     let executor = StdProcessExecutor::new();
     let env = process_env_map();
-    let args = vec!["/C".to_string(), "echo hi".to_string()];
+    let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+    let command = ProcessCommand {
+        program: Path::new(program),
+        args: &args,
+        env: &env,
+        cwd: Path::new("."),
+        inherit_fds: &[],
+        limits: &ProcessLimits::default(),
+    };
+    let stdin = stdin.map_or(ProcessInput::Inherit, |data| {
+        ProcessInput::Bytes(data.to_vec())
+    });
+    run_program(&executor, command, stdin, ProcessOutput::Capture)
+}
 
-    let result = executor.run_external("cmd", &args, &env, None).unwrap();
+#[cfg(windows)]
+#[test]
+fn run_external_captures_stdout_and_exit_code_windows() {
+    let result = run_external("cmd", &["/C", "echo hi"], None).unwrap();
     assert_eq!(result.exit_code, 0);
     let out = String::from_utf8_lossy(&result.stdout).to_string();
     assert!(out.to_lowercase().contains("hi"));
@@ -24,11 +46,7 @@ fn run_external_captures_stdout_and_exit_code_windows() {
 #[cfg(not(windows))]
 #[test]
 fn run_external_captures_stdout_and_exit_code_unix() {
-    let executor = StdProcessExecutor::new();
-    let env = process_env_map();
-    let args = vec!["-c".to_string(), "echo hi".to_string()];
-
-    let result = executor.run_external("sh", &args, &env, None).unwrap();
+    let result = run_external("sh", &["-c", "echo hi"], None).unwrap();
     assert_eq!(result.exit_code, 0);
     let out = String::from_utf8_lossy(&result.stdout).to_string();
     assert!(out.contains("hi"));
@@ -36,12 +54,7 @@ fn run_external_captures_stdout_and_exit_code_unix() {
 
 #[test]
 fn run_external_returns_command_not_found_for_missing_program() {
-    let executor = StdProcessExecutor::new();
-    let env = process_env_map();
-
-    let err = executor
-        .run_external("definitely-not-a-command-xyz-12345", &[], &env, None)
-        .unwrap_err();
+    let err = run_external("definitely-not-a-command-xyz-12345", &[], None).unwrap_err();
     let msg = err.to_string().to_lowercase();
     assert!(msg.contains("command not found"));
 }
@@ -49,13 +62,54 @@ fn run_external_returns_command_not_found_for_missing_program() {
 #[cfg(not(windows))]
 #[test]
 fn run_external_accepts_stdin_bytes_unix() {
+    let result = run_external("cat", &[], Some(b"hello")).unwrap();
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(result.stdout, b"hello");
+}
+
+#[cfg(not(windows))]
+#[test]
+fn run_external_connects_pipes_unix() {
     let executor = StdProcessExecutor::new();
     let env = process_env_map();
-    let args = vec![];
+    let (stdin_reader, mut stdin_writer) = pipe().unwrap();
+    let (mut stdout_reader, stdout_writer) = pipe().unwrap();
+    std::io::Write::write_all(&mut stdin_writer, b"piped").unwrap();
+    drop(stdin_writer);
 
+    let command = ProcessCommand {
+        program: Path::new("cat"),
+        args: &[],
+        env: &env,
+        cwd: Path::new("."),
+        inherit_fds: &[],
+        limits: &ProcessLimits::default(),
+    };
     let result = executor
-        .run_external("cat", &args, &env, Some(b"hello"))
+        .run(
+            command,
+            ProcessInput::Pipe(stdin_reader),
+            ProcessOutput::Pipe(stdout_writer),
+        )
         .unwrap();
     assert_eq!(result.exit_code, 0);
-    assert_eq!(result.stdout, b"hello");
+    assert!(result.stdout.is_empty());
+    let mut out = Vec::new();
+    stdout_reader.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"piped");
+}
+
+#[test]
+fn closest_names_uses_edit_distance_with_transpositions() {
+    let names = ["grep", "egrep", "cat", "cut", "ls", "mkdir", "sort"];
+    assert_eq!(closest_names("gerp", names), vec!["grep"]);
+    assert_eq!(closest_names("cst", names), vec!["cat", "cut"]);
+    assert_eq!(closest_names("sl", names), vec!["ls"]);
+    assert_eq!(closest_names("mkdri", names), vec!["mkdir"]);
+    assert_eq!(closest_names("grep", names), vec!["egrep"]);
+    assert!(closest_names("xyzzy", names).is_empty());
+    let short = [".", "[", "w", "cd", "ls"];
+    assert!(closest_names("g", short).is_empty());
+    assert!(closest_names("x", short).is_empty());
+    assert_eq!(closest_names("l", short), vec!["ls"]);
 }
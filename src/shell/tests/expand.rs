@@ -0,0 +1,198 @@
+//! Тесты подстановок в словах команды перед ее запуском.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::super::ShellState;
+use super::super::expand::expand_command;
+use super::super::parser::{ParseErrorKind, parse_line, parse_line_with_heredocs};
+use super::super::types::{
+    Command, CommandList, CommandSpec, Redirect, ShellError, ShellResult, Span, Stage,
+};
+use super::repl::run_with_input;
+
+fn env(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+/// Раскрывает первую команду первого пайплайна списка над состоянием `state`.
+fn expand_first(state: &mut ShellState, list: CommandList) -> ShellResult<CommandSpec> {
+    let pipeline = list.entries.into_iter().next().unwrap().pipeline.unwrap();
+    let command = pipeline.commands.into_iter().next().unwrap();
+    match expand_command(state, command)? {
+        Stage::Simple(spec) => Ok(spec),
+        other => panic!("expected simple command, got {other:?}"),
+    }
+}
+
+/// Разбирает строку из одной простой команды и раскрывает ее над окружением `env`.
+pub(super) fn expand_line(line: &str, env: &HashMap<String, String>) -> ShellResult<CommandSpec> {
+    let mut state = ShellState::new(env.clone(), PathBuf::from("/"));
+    expand_first(&mut state, parse_line(line).unwrap())
+}
+
+fn args(line: &str, env: &HashMap<String, String>) -> Vec<String> {
+    expand_line(line, env).unwrap().args
+}
+
+/// Ошибка подстановки: вид и место в строке.
+fn error(line: &str, env: &HashMap<String, String>) -> (ParseErrorKind, Span) {
+    match expand_line(line, env) {
+        Err(ShellError::Parse(err)) => (err.kind, err.span),
+        other => panic!("expected expansion error, got {other:?}"),
+    }
+}
+
+#[test]
+fn expands_vars_outside_single_quotes() {
+    let env = env(&[("FOO", "a b")]);
+    assert_eq!(args("echo $FOO", &env), ["a", "b"]);
+    assert_eq!(args("echo \"$FOO\"", &env), ["a b"]);
+    assert_eq!(args("echo '$FOO'", &env), ["$FOO"]);
+}
+
+#[test]
+fn expands_arithmetic_and_applies_assignments() {
+    let mut state = ShellState::new(env(&[("i", "2")]), PathBuf::from("/"));
+    let line = "echo $((i += 3)) \"$(( (i) * 2 ))\" $(($i-1))";
+    let spec = expand_first(&mut state, parse_line(line).unwrap()).unwrap();
+    assert_eq!(spec.args, ["5", "10", "4"]);
+    assert_eq!(state.env["i"], "5");
+
+    assert_eq!(args("echo '$((1+1))'", &HashMap::new()), ["$((1+1))"]);
+}
+
+#[test]
+fn arithmetic_errors_point_at_the_substitution() {
+    let (kind, span) = error("echo $((1/0)) x", &HashMap::new());
+    assert_eq!(kind.to_string(), "1/0: division by 0");
+    assert_eq!(span, Span::new(5, 13));
+}
+
+#[test]
+fn expansion_results_are_not_parsed_as_syntax() {
+    let env = env(&[("X", "a|b; c && d > f"), ("Q", "\"x y\" 'z'")]);
+
+    let cmd = expand_line("echo $X", &env).unwrap();
+    assert_eq!(cmd.args, ["a|b;", "c", "&&", "d", ">", "f"]);
+    assert!(cmd.redirects.is_empty());
+
+    assert_eq!(
+        args("echo $Q \"$X\"", &env),
+        ["\"x", "y\"", "'z'", "a|b; c && d > f"]
+    );
+}
+
+#[test]
+fn splits_unquoted_expansions_on_ifs() {
+    let env1 = env(&[("V", "  a \t b  "), ("P", "a::b:")]);
+    assert_eq!(
+        args("echo x$V\"$V\"y", &env1),
+        ["x", "a", "b", "  a \t b  y"]
+    );
+
+    let env2 = env(&[("IFS", ":"), ("P", "a::b:"), ("S", "a b")]);
+    assert_eq!(args("echo $P $S", &env2), ["a", "", "b", "a b"]);
+
+    let env3 = env(&[("IFS", ""), ("S", "a b")]);
+    assert_eq!(args("echo $S", &env3), ["a b"]);
+}
+
+#[test]
+fn empty_unquoted_expansion_disappears() {
+    let env = env(&[("E", "")]);
+    assert_eq!(args("echo $E \"$E\" '' $E$E", &env), ["", ""]);
+
+    let cmd = expand_line("$E echo hi", &env).unwrap();
+    assert_eq!(
+        (cmd.name.as_str(), cmd.args),
+        ("echo", vec!["hi".to_string()])
+    );
+
+    let cmd = expand_line("$E > out", &env).unwrap();
+    assert_eq!(cmd.name, "");
+    assert_eq!(cmd.redirects.len(), 1);
+}
+
+#[test]
+fn redirect_target_must_expand_to_one_word() {
+    let env = env(&[("F", "out file"), ("E", "")]);
+    assert_eq!(
+        error("echo hi > \"$F\" 2>$F.err", &env).0,
+        ParseErrorKind::AmbiguousRedirect("$F.err".to_string())
+    );
+    assert_eq!(
+        error("echo hi > $E", &env).0,
+        ParseErrorKind::AmbiguousRedirect("$E".to_string())
+    );
+    let cmd = expand_line("cat > \"$F\" <<< $F", &env).unwrap();
+    assert_eq!(
+        cmd.redirects,
+        [Redirect::Output {
+            fd: 1,
+            path: "out file".into(),
+            append: false
+        }]
+    );
+    assert_eq!(cmd.here_input.as_deref(), Some("out file\n"));
+}
+
+#[test]
+fn heredoc_body_is_expanded_unless_delimiter_is_quoted() {
+    let bodies = vec!["a \\$X $X $((1+1))\n".to_string(), "raw $X\n".to_string()];
+    let list =
+        parse_line_with_heredocs("cat <<EOF; cat <<'RAW'", &bodies, &HashMap::new()).unwrap();
+    let mut state = ShellState::new(env(&[("X", "val")]), PathBuf::from("/"));
+    let here_inputs: Vec<_> = list
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let list = CommandList {
+                entries: vec![entry],
+            };
+            expand_first(&mut state, list).unwrap().here_input
+        })
+        .collect();
+    assert_eq!(
+        here_inputs,
+        [
+            Some("a $X val 2\n".to_string()),
+            Some("raw $X\n".to_string())
+        ]
+    );
+}
+
+#[test]
+fn nested_errors_keep_positions_in_the_whole_line() {
+    let list = parse_line("cat <(echo > $F)").unwrap();
+    let Some(Command::Simple(cmd)) = list.entries[0]
+        .pipeline
+        .as_ref()
+        .map(|pipeline| &pipeline.commands[0])
+    else {
+        panic!("expected simple command");
+    };
+    let mut state = ShellState::new(env(&[("F", "a b")]), PathBuf::from("/"));
+    match expand_first(&mut state, cmd.process_substs[0].list.clone()) {
+        Err(ShellError::Parse(err)) => {
+            assert_eq!(
+                err.kind,
+                ParseErrorKind::AmbiguousRedirect("$F".to_string())
+            );
+            assert_eq!(err.span, Span::new(13, 15));
+        }
+        other => panic!("expected expansion error, got {other:?}"),
+    }
+}
+
+#[test]
+fn each_command_sees_effects_of_the_previous_ones() {
+    let (code, out, err) = run_with_input(
+        "x=ex y=it echo $x$y\necho \"[$X]\"; X=1; echo $X\nfalse && Q=1; echo \"[$Q]\"\ncd /; echo $PWD\ni=0; false && echo $((i+=1)); echo $i",
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "exit\n[]\n1\n[]\n/\n0\n");
+}
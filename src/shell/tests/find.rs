@@ -0,0 +1,211 @@
+//! Unit-тесты для `find` и `xargs`.
+
+use super::super::builtins::Builtin;
+use super::super::types::ShellControl;
+use super::builtins::run_in_dir as run_in;
+
+fn make_tree() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+    std::fs::write(dir.path().join("README.md"), b"readme\n").unwrap();
+    std::fs::write(dir.path().join("src/main.rs"), b"x.unwrap();\n").unwrap();
+    std::fs::write(dir.path().join("src/Lib.RS"), b"ok\n").unwrap();
+    std::fs::write(dir.path().join("src/nested/deep.rs"), vec![b'a'; 2048]).unwrap();
+    dir
+}
+
+#[test]
+fn find_filters_by_name_type_and_depth() {
+    let dir = make_tree();
+
+    let (control, out, err) = run_in(dir.path(), Builtin::Find, &[".", "-name", "*.rs"], None);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert!(err.is_empty());
+    assert_eq!(out, "./src/main.rs\n./src/nested/deep.rs\n");
+
+    let (_control, out, _err) = run_in(dir.path(), Builtin::Find, &[".", "-iname", "*.rs"], None);
+    assert_eq!(out, "./src/Lib.RS\n./src/main.rs\n./src/nested/deep.rs\n");
+
+    let (_control, out, _err) = run_in(dir.path(), Builtin::Find, &[".", "-type", "d"], None);
+    assert_eq!(out, ".\n./src\n./src/nested\n");
+
+    let (_control, out, _err) = run_in(
+        dir.path(),
+        Builtin::Find,
+        &[".", "-maxdepth", "1", "-type", "f"],
+        None,
+    );
+    assert_eq!(out, "./README.md\n");
+}
+
+#[test]
+fn find_supports_size_mtime_and_boolean_operators() {
+    let dir = make_tree();
+
+    let (_control, out, _err) = run_in(
+        dir.path(),
+        Builtin::Find,
+        &[".", "-type", "f", "-size", "+1k"],
+        None,
+    );
+    assert_eq!(out, "./src/nested/deep.rs\n");
+
+    let (_control, out, _err) = run_in(
+        dir.path(),
+        Builtin::Find,
+        &["src", "-type", "f", "-mtime", "-1"],
+        None,
+    );
+    assert_eq!(out, "src/Lib.RS\nsrc/main.rs\nsrc/nested/deep.rs\n");
+
+    let (_control, out, _err) = run_in(
+        dir.path(),
+        Builtin::Find,
+        &[".", "-name", "*.md", "-o", "-name", "main.rs"],
+        None,
+    );
+    assert_eq!(out, "./README.md\n./src/main.rs\n");
+
+    let (_control, out, _err) = run_in(
+        dir.path(),
+        Builtin::Find,
+        &["src", "-type", "f", "!", "-name", "*.rs"],
+        None,
+    );
+    assert_eq!(out, "src/Lib.RS\n");
+}
+
+#[test]
+fn find_print0_separates_with_nul() {
+    let dir = make_tree();
+    let (_control, out, _err) = run_in(
+        dir.path(),
+        Builtin::Find,
+        &["src", "-name", "*.rs", "-print0"],
+        None,
+    );
+    assert_eq!(out, "src/main.rs\0src/nested/deep.rs\0");
+}
+
+#[test]
+fn find_exec_runs_builtins_per_file_and_batched() {
+    let dir = make_tree();
+
+    let (control, out, err) = run_in(
+        dir.path(),
+        Builtin::Find,
+        &["src", "-name", "*.rs", "-exec", "echo", "got", "{}", ";"],
+        None,
+    );
+    assert_eq!(control, ShellControl::Continue(0));
+    assert!(err.is_empty());
+    assert_eq!(out, "got src/main.rs\ngot src/nested/deep.rs\n");
+
+    let (_control, out, _err) = run_in(
+        dir.path(),
+        Builtin::Find,
+        &["src", "-name", "*.rs", "-exec", "echo", "all", "{}", "+"],
+        None,
+    );
+    assert_eq!(out, "all src/main.rs src/nested/deep.rs\n");
+}
+
+#[test]
+fn find_reports_missing_start_and_unknown_primary() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let (control, _out, err) = run_in(dir.path(), Builtin::Find, &["missing"], None);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(err.starts_with("find: missing:"));
+
+    let (control, _out, err) = run_in(dir.path(), Builtin::Find, &[".", "-bogus"], None);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(err.starts_with("find:"));
+}
+
+#[test]
+fn xargs_groups_items_with_n_and_defaults_to_echo() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let (control, out, err) = run_in(dir.path(), Builtin::Xargs, &[], Some(b"a b\nc\n"));
+    assert_eq!(control, ShellControl::Continue(0));
+    assert!(err.is_empty());
+    assert_eq!(out, "a b c\n");
+
+    let (_control, out, _err) = run_in(
+        dir.path(),
+        Builtin::Xargs,
+        &["-n", "2", "echo", "x"],
+        Some(b"1 2 3 'four five'\n"),
+    );
+    assert_eq!(out, "x 1 2\nx 3 four five\n");
+}
+
+#[test]
+fn xargs_null_and_replace_modes() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let (_control, out, _err) = run_in(
+        dir.path(),
+        Builtin::Xargs,
+        &["-0", "-n", "1"],
+        Some(b"with space\0plain\0"),
+    );
+    assert_eq!(out, "with space\nplain\n");
+
+    let (_control, out, _err) = run_in(
+        dir.path(),
+        Builtin::Xargs,
+        &["-I", "{}", "echo", "[{}]"],
+        Some(b"one two\nthree\n"),
+    );
+    assert_eq!(out, "[one two]\n[three]\n");
+}
+
+#[test]
+fn xargs_parallel_keeps_output_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let (control, out, _err) = run_in(
+        dir.path(),
+        Builtin::Xargs,
+        &["-P", "4", "-n", "1", "echo"],
+        Some(b"1 2 3 4 5 6 7 8\n"),
+    );
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "1\n2\n3\n4\n5\n6\n7\n8\n");
+}
+
+#[test]
+fn xargs_dispatches_to_builtins_with_shell_cwd() {
+    let dir = make_tree();
+    let (control, out, err) = run_in(
+        dir.path(),
+        Builtin::Xargs,
+        &["cat"],
+        Some(b"README.md src/Lib.RS\n"),
+    );
+    assert_eq!(control, ShellControl::Continue(0));
+    assert!(err.is_empty());
+    assert_eq!(out, "readme\nok\n");
+}
+
+#[test]
+fn xargs_exit_codes_follow_gnu() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let (control, _out, _err) = run_in(dir.path(), Builtin::Xargs, &["cat"], Some(b"missing\n"));
+    assert_eq!(control, ShellControl::Continue(123));
+
+    let (control, _out, err) = run_in(
+        dir.path(),
+        Builtin::Xargs,
+        &["definitely-not-a-command-xyz"],
+        Some(b"a\n"),
+    );
+    assert_eq!(control, ShellControl::Continue(127));
+    assert!(err.starts_with("xargs: definitely-not-a-command-xyz:"));
+
+    let (control, out, _err) = run_in(dir.path(), Builtin::Xargs, &["-r", "echo", "x"], Some(b""));
+    assert_eq!(control, ShellControl::Continue(0));
+    assert!(out.is_empty());
+}
@@ -0,0 +1,246 @@
+//! Unit-тесты для файловых builtin-команд (`ls`, `mkdir`, `rm`, `cp`, `mv`, `touch`, `stat`).
+
+use super::super::builtins::Builtin;
+use super::super::types::ShellControl;
+use super::builtins::run_in_dir as run_in;
+use super::repl::run_with_input;
+
+#[test]
+fn mkdir_creates_relative_to_shell_cwd_and_p_creates_parents() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let (control, _out, err) = run_in(dir.path(), Builtin::Mkdir, &["a"], None);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert!(err.is_empty());
+    assert!(dir.path().join("a").is_dir());
+
+    let (control, _out, err) = run_in(dir.path(), Builtin::Mkdir, &["x/y/z"], None);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(err.starts_with("mkdir: x/y/z:"));
+
+    let (control, _out, _err) = run_in(dir.path(), Builtin::Mkdir, &["-p", "x/y/z", "a"], None);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert!(dir.path().join("x/y/z").is_dir());
+}
+
+#[test]
+fn ls_lists_sorted_entries_and_hides_dotfiles() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("b.txt"), b"").unwrap();
+    std::fs::write(dir.path().join("a.txt"), b"").unwrap();
+    std::fs::write(dir.path().join(".hidden"), b"").unwrap();
+
+    let (control, out, err) = run_in(dir.path(), Builtin::Ls, &[], None);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "a.txt\nb.txt\n");
+    assert!(err.is_empty());
+
+    let (_control, out, _err) = run_in(dir.path(), Builtin::Ls, &["-a"], None);
+    assert_eq!(out, ".\n..\n.hidden\na.txt\nb.txt\n");
+}
+
+#[test]
+fn ls_long_and_human_readable() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("big"), vec![0u8; 1536]).unwrap();
+
+    let (_control, out, _err) = run_in(dir.path(), Builtin::Ls, &["-l", "-h"], None);
+    let mut lines = out.lines();
+    assert_eq!(lines.next(), Some("total 2"));
+    let line = lines.next().unwrap();
+    assert!(line.starts_with('-'));
+    assert!(line.contains(" 1.5K "));
+    assert!(line.ends_with(" big"));
+}
+
+#[test]
+fn ls_recursive_prints_headers() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    std::fs::write(dir.path().join("sub/f"), b"").unwrap();
+
+    let (_control, out, _err) = run_in(dir.path(), Builtin::Ls, &["-R"], None);
+    assert_eq!(out, ".:\nsub\n\n./sub:\nf\n");
+}
+
+#[test]
+fn ls_missing_path_is_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let (control, out, err) = run_in(dir.path(), Builtin::Ls, &["nope"], None);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(out.is_empty());
+    assert!(err.starts_with("ls: nope:"));
+    assert!(!err.contains("os error"));
+}
+
+#[test]
+fn rm_requires_r_for_directories_and_f_ignores_missing() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("d/e")).unwrap();
+    std::fs::write(dir.path().join("f"), b"").unwrap();
+
+    let (control, _out, err) = run_in(dir.path(), Builtin::Rm, &["d"], None);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(err.contains("Is a directory"));
+
+    let (control, _out, err) = run_in(dir.path(), Builtin::Rm, &["-r", "d", "f"], None);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert!(err.is_empty());
+    assert!(!dir.path().join("d").exists());
+    assert!(!dir.path().join("f").exists());
+
+    let (control, _out, err) = run_in(dir.path(), Builtin::Rm, &["-f", "missing"], None);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert!(err.is_empty());
+}
+
+#[test]
+fn rm_interactive_reads_answers_from_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a"), b"").unwrap();
+    std::fs::write(dir.path().join("b"), b"").unwrap();
+
+    let (control, _out, err) = run_in(dir.path(), Builtin::Rm, &["-i", "a", "b"], Some(b"y\nn\n"));
+    assert_eq!(control, ShellControl::Continue(0));
+    assert!(err.contains("rm: remove 'a'?"));
+    assert!(!dir.path().join("a").exists());
+    assert!(dir.path().join("b").exists());
+}
+
+#[test]
+fn rm_interactive_reads_answers_from_a_pipe() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["a", "b", "c", "d"] {
+        std::fs::write(dir.path().join(name), b"").unwrap();
+    }
+
+    // `yes` не кончается: `rm -i` читает по строке на вопрос.
+    let script = format!(
+        "cd {}\necho y | rm -i a\nprintf 'n\\ny\\n' | rm -i b c\nyes | rm -i d\n",
+        dir.path().display()
+    );
+    let (code, _out, err) = run_with_input(&script);
+    assert_eq!(code, 0, "stderr: {err}");
+    assert!(err.contains("rm: remove 'a'?"));
+    assert!(!dir.path().join("a").exists());
+    assert!(dir.path().join("b").exists());
+    assert!(!dir.path().join("c").exists());
+    assert!(!dir.path().join("d").exists());
+}
+
+#[test]
+fn rm_refuses_dot_and_dotdot() {
+    let dir = tempfile::tempdir().unwrap();
+    let (control, _out, err) = run_in(dir.path(), Builtin::Rm, &["-rf", "."], None);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(err.contains("refusing"));
+    assert!(dir.path().exists());
+}
+
+#[test]
+fn cp_copies_files_and_directories() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("src.txt"), b"data").unwrap();
+    std::fs::create_dir_all(dir.path().join("tree/sub")).unwrap();
+    std::fs::write(dir.path().join("tree/sub/leaf"), b"leaf").unwrap();
+    std::fs::create_dir(dir.path().join("out")).unwrap();
+
+    let (control, _out, err) = run_in(dir.path(), Builtin::Cp, &["src.txt", "copy.txt"], None);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert!(err.is_empty());
+    assert_eq!(std::fs::read(dir.path().join("copy.txt")).unwrap(), b"data");
+
+    let (control, _out, err) = run_in(dir.path(), Builtin::Cp, &["tree", "out"], None);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(err.contains("-r not specified"));
+
+    let (control, _out, _err) = run_in(
+        dir.path(),
+        Builtin::Cp,
+        &["-r", "tree", "src.txt", "out"],
+        None,
+    );
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(
+        std::fs::read(dir.path().join("out/tree/sub/leaf")).unwrap(),
+        b"leaf"
+    );
+    assert!(dir.path().join("out/src.txt").is_file());
+}
+
+#[test]
+fn cp_multiple_sources_need_directory_target() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a"), b"").unwrap();
+    std::fs::write(dir.path().join("b"), b"").unwrap();
+
+    let (control, _out, err) = run_in(dir.path(), Builtin::Cp, &["a", "b", "c"], None);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(err.contains("is not a directory"));
+}
+
+#[test]
+fn mv_renames_and_moves_into_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a"), b"A").unwrap();
+    std::fs::create_dir(dir.path().join("d")).unwrap();
+
+    let (control, _out, err) = run_in(dir.path(), Builtin::Mv, &["a", "b"], None);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert!(err.is_empty());
+    assert!(!dir.path().join("a").exists());
+
+    let (control, _out, _err) = run_in(dir.path(), Builtin::Mv, &["b", "d"], None);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(std::fs::read(dir.path().join("d/b")).unwrap(), b"A");
+
+    let (control, _out, err) = run_in(dir.path(), Builtin::Mv, &["missing", "x"], None);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(err.starts_with("mv: missing:"));
+}
+
+#[test]
+fn touch_creates_files_and_sets_date() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let (control, _out, err) = run_in(
+        dir.path(),
+        Builtin::Touch,
+        &["-d", "2001-02-03 04:05:06", "f"],
+        None,
+    );
+    assert_eq!(control, ShellControl::Continue(0));
+    assert!(err.is_empty());
+    let mtime = std::fs::metadata(dir.path().join("f"))
+        .unwrap()
+        .modified()
+        .unwrap();
+    let secs = mtime
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    assert_eq!(secs, 981_173_106);
+
+    let (control, _out, _err) = run_in(dir.path(), Builtin::Touch, &["-c", "g"], None);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert!(!dir.path().join("g").exists());
+
+    let (control, _out, err) = run_in(dir.path(), Builtin::Touch, &["-d", "yesterday", "f"], None);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(err.contains("invalid date"));
+}
+
+#[test]
+fn stat_prints_size_type_and_times() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("f"), b"hello").unwrap();
+    run_in(dir.path(), Builtin::Touch, &["-d", "@0", "f"], None);
+
+    let (control, out, err) = run_in(dir.path(), Builtin::Stat, &["f", "nope"], None);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(out.contains("  File: f\n"));
+    assert!(out.contains("Size: 5"));
+    assert!(out.contains("Type: regular file"));
+    assert!(out.contains("Modify: 1970-01-01 00:00:00"));
+    assert!(err.starts_with("stat: nope:"));
+}
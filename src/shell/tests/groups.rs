@@ -0,0 +1,118 @@
+//! Тесты списков команд, групп `( ... )` / `{ ...; }`, перенаправлений и `cd`.
+
+use super::repl::run_with_input;
+
+#[test]
+fn lists_honor_connectors() {
+    let (code, out, err) = run_with_input(
+        "test 1 = 2 && echo no || echo yes; echo done\ntest 1 = 1 || echo skipped\nexit\n",
+    );
+    assert_eq!(code, 0);
+    assert_eq!(out, "yes\ndone\n");
+    assert!(err.is_empty());
+}
+
+#[test]
+fn command_not_found_does_not_stop_the_list() {
+    let (_code, out, err) =
+        run_with_input("definitely-not-a-command-xyz-12345 || echo fallback\nexit\n");
+    assert_eq!(out, "fallback\n");
+    assert!(err.contains("command not found"));
+}
+
+#[test]
+fn subshell_does_not_change_shell_state() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let root = dir.path().display();
+
+    let (code, out, err) = run_with_input(&format!(
+        "cd {root}\nX=1\n(X=2; cd sub; echo $X; pwd)\necho $X\npwd\n(exit 3) || echo failed\nexit\n"
+    ));
+    assert_eq!(code, 0);
+    assert_eq!(out, format!("2\n{root}/sub\n1\n{root}\nfailed\n"));
+    assert!(err.is_empty());
+}
+
+#[test]
+fn subshell_assignments_do_not_leak_into_the_same_line() {
+    let (code, out, err) = run_with_input(
+        "(Z=5); echo \"[$Z]\"\n(Y=1; echo $Y) | cat; echo \"[$Y]\"\nX=1; (X=2); echo $X",
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "[]\n1\n[]\n1\n");
+}
+
+#[test]
+fn brace_group_runs_in_current_state() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let root = dir.path().display();
+
+    let (code, out, _err) = run_with_input(&format!(
+        "cd {root}\n{{ X=5; cd sub; }}\necho $X\npwd\n{{ exit 4; }}\necho unreachable\n"
+    ));
+    assert_eq!(code, 4);
+    assert_eq!(out, format!("5\n{root}/sub\n"));
+}
+
+#[test]
+fn groups_work_as_pipeline_stages() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let root = dir.path().display();
+
+    let (_code, out, err) = run_with_input(&format!(
+        "cd {root}\n(cd sub && pwd) | cat\npwd\necho data | {{ echo header; cat; cat; }} | wc\n\
+         {{ cd sub; }} | cat\npwd\nexit\n"
+    ));
+    assert_eq!(out, format!("{root}/sub\n{root}\n2 2 12\n{root}\n"));
+    assert!(err.is_empty());
+}
+
+#[test]
+fn group_output_is_redirected_to_file() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("body"), "body text\n").unwrap();
+    let root = dir.path().display();
+
+    let (_code, out, err) = run_with_input(&format!(
+        "cd {root}\n{{ echo header; cat body; }} > out\ncat out\n\
+         {{ echo visible; cat missing; }} > all 2>&1\ncat all\nexit\n"
+    ));
+    assert_eq!(
+        out,
+        "header\nbody text\nvisible\ncat: missing: No such file or directory\n"
+    );
+    assert!(err.is_empty());
+}
+
+#[cfg(not(windows))]
+#[test]
+fn redirections_of_simple_commands() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().display();
+
+    let (_code, out, err) = run_with_input(&format!(
+        "cd {root}\necho a > f\necho b >> f\ncat < f\ncat missing 2> err\ncat < err\n\
+         echo to-stderr >&2 2>/dev/null\necho x > nodir/f\nexit\n"
+    ));
+    assert_eq!(out, "a\nb\ncat: missing: No such file or directory\n");
+    assert_eq!(err, "to-stderr\nnodir/f: No such file or directory\n");
+}
+
+#[test]
+fn cd_updates_pwd_and_oldpwd() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let root = dir.path().display();
+
+    let (_code, out, err) = run_with_input(&format!(
+        "cd {root}/sub/..\ncd sub\necho $PWD $OLDPWD\ncd -\ncd missing\ncd a b\nexit\n"
+    ));
+    assert_eq!(out, format!("{root}/sub {root}\n{root}\n"));
+    assert_eq!(
+        err,
+        "cd: missing: No such file or directory\ncd: too many arguments\n"
+    );
+}
@@ -0,0 +1,151 @@
+//! Тесты истории команд: запись, `history` и подстановки `!`.
+
+use std::collections::HashMap;
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::super::history::{History, HistoryError, expand};
+use super::super::options::{RcFile, Startup};
+use super::repl::{run_with_input, run_with_startup};
+
+fn history(lines: &[&str]) -> History {
+    let mut history = History::default();
+    for line in lines {
+        history.record(line, UNIX_EPOCH, &HashMap::new());
+    }
+    history
+}
+
+#[test]
+fn expands_events_outside_single_quotes() {
+    let h = history(&["echo one", "ls -l", "echo two"]);
+    let expand = |line| expand(line, &h);
+    assert_eq!(expand("!!"), Ok(Some("echo two".to_string())));
+    assert_eq!(expand("!1 | wc"), Ok(Some("echo one | wc".to_string())));
+    assert_eq!(expand("!-2"), Ok(Some("ls -l".to_string())));
+    assert_eq!(expand("!ec;!l"), Ok(Some("echo two;ls -l".to_string())));
+    assert_eq!(
+        expand("echo \"!!\""),
+        Ok(Some("echo \"echo two\"".to_string()))
+    );
+    // Кавычки, `\`, пробел, `=` и конец строки отключают подстановку.
+    for line in [
+        "echo '!!'",
+        "echo \\!!",
+        "[ ! -f x ]",
+        "[ a != b ]",
+        "echo hi!",
+        "echo \"hi!\"",
+    ] {
+        assert_eq!(expand(line), Ok(None), "{line}");
+    }
+    assert_eq!(
+        expand("!nope"),
+        Err(HistoryError::EventNotFound("!nope".to_string()))
+    );
+    assert_eq!(
+        expand("!9"),
+        Err(HistoryError::EventNotFound("!9".to_string()))
+    );
+}
+
+#[test]
+fn quick_substitution_replaces_first_match() {
+    let h = history(&["echo aa b"]);
+    assert_eq!(expand("^a^x", &h), Ok(Some("echo xa b".to_string())));
+    assert_eq!(expand("^b^c^", &h), Ok(Some("echo aa c".to_string())));
+    assert_eq!(
+        expand("^z^y", &h).unwrap_err().to_string(),
+        "^z^y: substitution failed"
+    );
+}
+
+#[test]
+fn histcontrol_and_histsize_limit_records() {
+    let env = HashMap::from([
+        ("HISTCONTROL".to_string(), "ignoreboth".to_string()),
+        ("HISTSIZE".to_string(), "2".to_string()),
+    ]);
+    let mut h = History::default();
+    for line in ["a", "a", " secret", "b", "c", ""] {
+        h.record(line, UNIX_EPOCH, &env);
+    }
+    let entries: Vec<_> = h.iter().map(|(n, e)| (n, e.line.as_str())).collect();
+    assert_eq!(entries, vec![(2, "b"), (3, "c")]);
+    assert_eq!(h.resolve(-1), Some(3));
+    assert_eq!(h.resolve(1), None);
+}
+
+#[test]
+fn file_text_round_trips_with_timestamps() {
+    let mut h = History::default();
+    h.record(
+        "echo a",
+        UNIX_EPOCH + Duration::from_secs(60),
+        &HashMap::new(),
+    );
+    h.record("ls", UNIX_EPOCH + Duration::from_secs(120), &HashMap::new());
+    let text = h.to_file_text();
+    assert_eq!(text, "#60\necho a\n#120\nls\n");
+
+    let mut loaded = History::default();
+    loaded.load(&text, &HashMap::new());
+    let entries: Vec<_> = loaded.iter().map(|(_, e)| e.clone()).collect();
+    let original: Vec<_> = h.iter().map(|(_, e)| e.clone()).collect();
+    assert_eq!(entries, original);
+}
+
+#[test]
+fn repl_records_and_expands_lines() {
+    let (code, out, err) = run_with_input(
+        "set -H\necho one\n!!\n^one^two\nhistory\n!nope\nhistory -d 3\nhistory 2\nhistory -c\nhistory\nexit\n",
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(
+        out,
+        "one\none\ntwo\n    1  set -H\n    2  echo one\n    3  echo one\n    4  echo two\n    5  history\n    5  history -d 3\n    6  history 2\n    1  history\n"
+    );
+    assert_eq!(err, "echo one\necho two\n!nope: event not found\n");
+}
+
+#[test]
+fn history_expansion_needs_interactive_shell_or_set_h() {
+    let (code, out, err) = run_with_input(
+        "echo $((!0)) hi!\nset -H\necho one\n!!\nset +H\necho !!\nset -o | grep histexpand\n",
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "1 hi!\none\none\n!!\nhistexpand     \toff\n");
+    assert_eq!(err, "echo one\n");
+
+    let dir = tempfile::tempdir().unwrap();
+    let rc = dir.path().join("rc");
+    let histfile = dir.path().join("hist");
+    std::fs::write(&rc, format!("HISTFILE={}\n", histfile.display())).unwrap();
+    let startup = Startup {
+        rcfile: RcFile::Path(rc),
+        interactive: true,
+        ..Startup::default()
+    };
+    let (code, out, err) = run_with_startup("echo one\n!!\n", startup);
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "one\none\n");
+    assert_eq!(err, "echo one\n");
+}
+
+#[test]
+fn history_builtin_writes_file_and_reports_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("hist");
+    let (code, out, err) = run_with_input(&format!(
+        "HISTTIMEFORMAT=%F\nhistory 1\nhistory -w {}\nhistory -d 99\nhistory -z\nhistory x\nexit\n",
+        file.display()
+    ));
+    assert_eq!(code, 0);
+    assert!(out.starts_with("    2  20"), "stdout: {out}");
+    assert!(out.ends_with(" history 1\n"), "stdout: {out}");
+    let text = std::fs::read_to_string(&file).unwrap();
+    assert!(text.contains("\nHISTTIMEFORMAT=%F\n#"), "file: {text}");
+    assert_eq!(
+        err,
+        "history: 99: history position out of range\nhistory: -z: invalid option\nhistory: x: numeric argument required\n"
+    );
+}
@@ -0,0 +1,143 @@
+//! Тесты лимитов внешних программ: `timeout`, `ulimit` и `--timeout`.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::super::limits::{Resource, parse_duration};
+use super::super::mock::{MockExecutor, MockResponse};
+use super::super::{Shell, Startup};
+use super::repl::{run_with_executor, run_with_input, run_with_startup};
+
+#[test]
+fn durations_accept_suffixes_and_fractions() {
+    assert_eq!(parse_duration("2"), Some(Duration::from_secs(2)));
+    assert_eq!(parse_duration("1.5s"), Some(Duration::from_millis(1500)));
+    assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+    assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
+    assert_eq!(parse_duration("0.5d"), Some(Duration::from_secs(43200)));
+    for bad in ["", "s", "-1", "1x", "1.2.3", "1 s"] {
+        assert_eq!(parse_duration(bad), None, "{bad:?}");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn timeout_kills_the_program_and_its_children() {
+    let started = Instant::now();
+    let (code, out, err) = run_with_input(
+        "timeout 0.3 sh -c 'sleep 5 & sleep 5; echo late'\necho $PIPESTATUS\ntimeout 0.2 sh -c 'sleep 5' | echo after\necho $PIPESTATUS\ntimeout 5 sh -c 'echo fast; exit 3'\necho $PIPESTATUS\ntimeout 0 sh -c 'echo unlimited'",
+    );
+    assert!(started.elapsed() < Duration::from_secs(4));
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "124\nafter\n124 0\nfast\n3\nunlimited\n");
+    assert_eq!(err, "");
+}
+
+#[test]
+fn timeout_reports_usage_errors() {
+    let (_, out, err) = run_with_input(
+        "timeout 5\necho $PIPESTATUS\ntimeout soon echo x\necho $PIPESTATUS\ntimeout -- 1 echo ok\ntype timeout ulimit",
+    );
+    assert_eq!(
+        out,
+        "125\n125\nok\ntimeout is a shell builtin\nulimit is a shell builtin\n"
+    );
+    assert_eq!(
+        err,
+        "timeout: missing operand\ntimeout: invalid time interval 'soon'\n"
+    );
+}
+
+#[test]
+fn timeout_stops_waiting_builtins() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("log");
+    std::fs::write(&path, "one\n").unwrap();
+    let started = Instant::now();
+    let (code, out, err) = run_with_input(&format!(
+        "timeout 1 tail -f {}\necho $PIPESTATUS",
+        path.display()
+    ));
+    assert!(started.elapsed() < Duration::from_secs(4));
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "one\n124\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn default_timeout_applies_to_every_pipeline() {
+    let startup = Startup {
+        timeout: Some(Duration::from_millis(300)),
+        ..Startup::default()
+    };
+    let started = Instant::now();
+    let (_, out, _) = run_with_startup(
+        "sh -c 'sleep 5' | sh -c 'cat; sleep 5'\necho $PIPESTATUS\nsh -c 'echo quick'\ntimeout 10 sh -c 'sleep 5'\necho $PIPESTATUS",
+        startup,
+    );
+    assert!(started.elapsed() < Duration::from_secs(4));
+    assert_eq!(out, "124 124\nquick\n124\n");
+
+    let mut shell = Shell::builder().timeout(Duration::from_millis(300)).build();
+    let out = shell.eval("sh -c 'sleep 5; echo late'\necho $PIPESTATUS");
+    assert_eq!(out.stdout_text(), "124\n");
+    assert_eq!(out.status.code, 0);
+
+    let startup = Startup::from_args(["se-cli", "--timeout", "2m"]).unwrap();
+    assert_eq!(startup.timeout, Some(Duration::from_secs(120)));
+    assert!(Startup::from_args(["se-cli", "--timeout", "later"]).is_err());
+}
+
+#[test]
+fn limits_reach_nested_programs() {
+    let mock = Arc::new(MockExecutor::new());
+    mock.respond("tool", MockResponse::stdout(""));
+    let (_, _, err) = run_with_executor(
+        "ulimit -n 16 -t unlimited\ntool\nprintf 'a\\n' | xargs tool\ntimeout 5 tool",
+        mock.clone(),
+    );
+    assert_eq!(err, "");
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 3);
+    for call in &calls {
+        assert_eq!(
+            call.limits.resources.get(&Resource::OpenFiles),
+            Some(&Some(16))
+        );
+        assert_eq!(call.limits.resources.get(&Resource::Cpu), Some(&None));
+    }
+    assert!(calls[0].limits.deadline.is_none());
+    assert!(calls[2].limits.deadline.is_some());
+}
+
+#[cfg(unix)]
+#[test]
+fn ulimit_sets_limits_for_children() {
+    let (code, out, err) = run_with_input(
+        "ulimit -n 32\nulimit -n\nsh -c 'ulimit -n'\n( ulimit -n 20 )\nulimit -v 1048576 -n\nulimit -nv\nulimit -a | cut -d ' ' -f 1",
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(
+        out,
+        "32\n32\n32\n\
+         open files                          (-n) 32\n\
+         virtual memory              (kbytes, -v) 1048576\n\
+         cpu\nopen\nmax\nvirtual\n"
+    );
+}
+
+#[test]
+fn ulimit_rejects_bad_values() {
+    let (_, out, err) = run_with_input(
+        "ulimit -n abc\necho $PIPESTATUS\nulimit -x\necho $PIPESTATUS\nulimit 5\nulimit -n unlimited\necho $PIPESTATUS",
+    );
+    assert_eq!(out, "1\n2\n1\n");
+    assert_eq!(
+        err,
+        "ulimit: abc: invalid number\n\
+         ulimit: -x: invalid option\n\
+         ulimit: usage: ulimit [-a] [-tvnu [limit]]\n\
+         ulimit: 5: invalid number\n\
+         ulimit: open files: cannot modify limit: Operation not permitted\n"
+    );
+}
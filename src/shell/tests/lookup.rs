@@ -0,0 +1,161 @@
+//! Тесты поиска команд в `PATH`: таблица `hash` и builtins `type`, `which`,
+//! `command`, `hash`.
+
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use super::repl::run_with_input;
+
+/// Каталог с исполняемыми скриптами `names`; каждый печатает `external NAME args`.
+fn bin_dir(names: &[&str]) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for name in names {
+        let path = dir.path().join(name);
+        std::fs::write(&path, format!("#!/bin/sh\necho external {name} \"$@\"\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    dir
+}
+
+fn display(path: &Path) -> String {
+    path.display().to_string()
+}
+
+#[test]
+fn type_reports_alias_builtin_file_and_hashed() {
+    let bin = bin_dir(&["tool", "grep"]);
+    let dir = display(bin.path());
+    let (code, out, err) = run_with_input(&format!(
+        "PATH={dir}\nalias t=tool\ntype t cd grep tool\ntool\ntype tool nope\ntype -t t grep tool nope\ntype -p grep tool\ntype -P grep\ntype -a grep\nexit\n"
+    ));
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(
+        out,
+        format!(
+            "t is aliased to `tool'\ncd is a shell builtin\ngrep is a shell builtin\ntool is {dir}/tool\n\
+             external tool\n\
+             tool is hashed ({dir}/tool)\n\
+             alias\nbuiltin\nfile\n\
+             {dir}/tool\n\
+             {dir}/grep\n\
+             grep is a shell builtin\ngrep is {dir}/grep\n"
+        )
+    );
+    assert_eq!(err, "type: nope: not found\n");
+}
+
+#[test]
+fn which_and_command_v_describe_names() {
+    let bin = bin_dir(&["tool"]);
+    let dir = display(bin.path());
+    let (code, out, err) = run_with_input(&format!(
+        "PATH={dir}\nalias t='tool -x'\nwhich t cat tool nope\ncommand -v t cat tool nope\ncommand -V tool\nexit\n"
+    ));
+    assert_eq!(code, 0);
+    assert_eq!(
+        out,
+        format!(
+            "t: aliased to tool -x\ncat: shell built-in command\n{dir}/tool\n\
+             alias t='tool -x'\ncat\n{dir}/tool\ntool is {dir}/tool\n"
+        )
+    );
+    assert_eq!(err, format!("which: no nope in ({dir})\n"));
+}
+
+#[test]
+fn command_prefers_program_from_path_over_builtin() {
+    let bin = bin_dir(&["grep"]);
+    let dir = display(bin.path());
+    let (code, out, err) = run_with_input(&format!(
+        "PATH={dir}\nalias grep='echo alias'\necho a | grep a\necho a | command grep a\ncommand grep -n a\ncommand -- command grep b\ncommand cd /\npwd\ncommand echo builtin\nexit\n"
+    ));
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(
+        out,
+        "alias a\nexternal grep a\nexternal grep -n a\nexternal grep b\n/\nbuiltin\n"
+    );
+}
+
+#[test]
+fn hash_tracks_programs_and_resets() {
+    let bin = bin_dir(&["tool", "other"]);
+    let dir = display(bin.path());
+    let (code, out, err) = run_with_input(&format!(
+        "PATH={dir}\nhash\ntool\ntool\nhash other cd\nhash\nhash -t tool\nhash -t tool other\nhash -d other\nhash -d other\nhash -r\nhash\nhash nope\ntool\nPATH={dir}:/bin\nhash\nhash -x\nexit\n"
+    ));
+    assert_eq!(code, 0);
+    assert_eq!(
+        out,
+        format!(
+            "hash: hash table empty\nexternal tool\nexternal tool\n\
+             hits\tcommand\n   0\t{dir}/other\n   2\t{dir}/tool\n\
+             {dir}/tool\ntool\t{dir}/tool\nother\t{dir}/other\n\
+             hash: hash table empty\nexternal tool\nhash: hash table empty\n"
+        )
+    );
+    assert_eq!(
+        err,
+        "hash: other: not found\nhash: nope: not found\nhash: -x: invalid option\n\
+         hash: usage: hash [-r] [-d|-t] [name ...]\n"
+    );
+}
+
+#[test]
+fn removed_program_is_looked_up_again() {
+    let first = bin_dir(&["tool"]);
+    let second = bin_dir(&["tool"]);
+    std::fs::write(second.path().join("tool"), "#!/bin/sh\necho second tool\n").unwrap();
+    let (a, b) = (display(first.path()), display(second.path()));
+    let (code, out, err) = run_with_input(&format!(
+        "PATH={a}:{b}\ntool\nrm {a}/tool\ntool\nhash -t tool\nexit\n"
+    ));
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, format!("external tool\nsecond tool\n{b}/tool\n"));
+}
+
+#[test]
+fn missing_and_unrunnable_commands_have_posix_statuses() {
+    let bin = bin_dir(&["tool"]);
+    let dir = display(bin.path());
+    std::fs::write(bin.path().join("plain"), "echo hi\n").unwrap();
+    let (code, out, err) = run_with_input(&format!(
+        "PATH={dir}\nnope_xyz\necho $PIPESTATUS\nplain\necho $PIPESTATUS\n{dir}\necho $PIPESTATUS\n./nope\necho $PIPESTATUS\necho a | nope_xyz | tool\necho $PIPESTATUS\nexit\n"
+    ));
+    assert_eq!(code, 0);
+    assert_eq!(out, "127\n126\n126\n127\nexternal tool\n0 127 0\n");
+    assert_eq!(
+        err,
+        format!(
+            "nope_xyz: command not found\nplain: Permission denied\n{dir}: Is a directory\n\
+             ./nope: No such file or directory\nnope_xyz: command not found\n"
+        )
+    );
+}
+
+#[test]
+fn not_found_suggests_similar_names() {
+    let bin = bin_dir(&["deploy"]);
+    let dir = display(bin.path());
+    let (_code, _out, err) = run_with_input(&format!(
+        "PATH={dir}\nalias hello='echo hi'\ngerp x\nhelo\ndeplyo\nmkdri a\nzzzzzz\ng\nx\nexit\n"
+    ));
+    assert_eq!(
+        err,
+        "gerp: command not found\n  hint: did you mean `grep`?\n\
+         helo: command not found\n  hint: did you mean `hello`?\n\
+         deplyo: command not found\n  hint: did you mean `deploy`?\n\
+         mkdri: command not found\n  hint: did you mean `mkdir`?\n\
+         zzzzzz: command not found\ng: command not found\nx: command not found\n"
+    );
+}
+
+#[test]
+fn signal_death_is_128_plus_signal() {
+    let (code, out, err) = run_with_input(
+        "sh -c 'kill -9 $$'\necho $PIPESTATUS\nsh -c 'kill -15 $$' | cat\necho $PIPESTATUS\nexit\n",
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "137\n143 0\n");
+}
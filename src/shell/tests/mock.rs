@@ -0,0 +1,102 @@
+//! Тесты запуска внешних команд через `MockExecutor`: процессы не создаются,
+//! программы отвечают из памяти.
+
+use std::sync::Arc;
+
+use super::super::mock::{MOCK_BIN, MockExecutor, MockResponse};
+use super::repl::run_with_executor;
+
+/// Мок с программой `upper`, которая печатает свой stdin заглавными буквами.
+fn mock() -> Arc<MockExecutor> {
+    let mock = Arc::new(MockExecutor::new());
+    mock.handle("upper", |call| {
+        MockResponse::stdout(String::from_utf8_lossy(&call.stdin).to_uppercase())
+    });
+    mock
+}
+
+#[test]
+fn records_argv_env_and_cwd() {
+    let mock = mock();
+    mock.respond("tool", MockResponse::stdout("done\n"));
+    let dir = tempfile::tempdir().unwrap();
+    let (code, out, err) = run_with_executor(
+        &format!(
+            "GREETING=hi\ncd {}\npwd\ntool a 'b c' $GREETING\nexit\n",
+            dir.path().display()
+        ),
+        mock.clone(),
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    let (cwd, rest) = out.split_once('\n').unwrap();
+    assert_eq!(rest, "done\n");
+
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 1);
+    let call = &calls[0];
+    assert_eq!(call.program, std::path::Path::new(MOCK_BIN).join("tool"));
+    assert_eq!(call.argv, ["tool", "a", "b c", "hi"]);
+    assert_eq!(call.env.get("GREETING").map(String::as_str), Some("hi"));
+    assert_eq!(call.cwd, std::path::Path::new(cwd));
+    assert!(call.stdin.is_empty());
+}
+
+#[test]
+fn pipeline_stages_pass_data_through_the_mock() {
+    let mock = mock();
+    let (code, out, err) = run_with_executor(
+        "echo hello | upper\nprintf 'one\\ntwo\\n' | upper | grep T\nexit\n",
+        mock.clone(),
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "HELLO\nTWO\n");
+    let stdins: Vec<_> = mock.calls().into_iter().map(|call| call.stdin).collect();
+    assert_eq!(stdins, [b"hello\n".to_vec(), b"one\ntwo\n".to_vec()]);
+}
+
+#[test]
+fn canned_exit_codes_and_stderr() {
+    let mock = mock();
+    mock.respond(
+        "fail",
+        MockResponse::default()
+            .with_stderr("fail: broken\n")
+            .with_exit_code(3),
+    );
+    let (code, out, err) = run_with_executor(
+        "fail\necho $PIPESTATUS\necho x | fail | upper\necho $PIPESTATUS\nfail && echo no\nexit\n",
+        mock,
+    );
+    assert_eq!(code, 0);
+    assert_eq!(out, "3\n0 3 0\n");
+    assert_eq!(err, "fail: broken\n".repeat(3));
+}
+
+#[test]
+fn lookup_builtins_see_mock_programs() {
+    let mock = mock();
+    mock.respond("grep", MockResponse::stdout("mock grep\n"));
+    let (code, out, err) = run_with_executor(
+        "type upper\nwhich upper\ncommand grep a\nhash -t grep\nexit\n",
+        mock.clone(),
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(
+        out,
+        format!("upper is {MOCK_BIN}/upper\n{MOCK_BIN}/upper\nmock grep\n{MOCK_BIN}/grep\n")
+    );
+    assert_eq!(mock.calls()[0].argv, ["grep", "a"]);
+}
+
+#[test]
+fn unregistered_program_is_not_found() {
+    let mock = mock();
+    let (code, out, err) = run_with_executor(
+        "qqzzxx_absent\necho $PIPESTATUS\necho a | qqzzxx_absent | upper\necho $PIPESTATUS\nexit\n",
+        mock.clone(),
+    );
+    assert_eq!(code, 0);
+    assert_eq!(out, "127\n0 127 0\n");
+    assert_eq!(err, "qqzzxx_absent: command not found\n".repeat(2));
+    assert_eq!(mock.calls().len(), 1);
+}
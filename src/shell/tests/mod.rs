@@ -2,8 +2,28 @@
 //!
 //! Здесь лежат unit-тесты отдельных компонентов и тесты REPL.
 
+mod aliases;
+mod arith;
 mod builtins;
+mod conditional;
 mod core;
+mod debugparse;
+mod embed;
 mod executor;
+mod expand;
+mod find;
+mod fs;
+mod groups;
+mod history;
+mod limits;
+mod lookup;
+mod mock;
+mod options;
 mod parser;
+mod procsubst;
 mod repl;
+mod restricted;
+mod startup;
+mod text;
+mod vfs;
+mod wordexp;
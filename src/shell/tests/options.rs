@@ -0,0 +1,97 @@
+//! Тесты опций `set -x`, `set -e`, `set -u`, `set -o pipefail` и `PIPESTATUS`.
+
+use super::repl::run_with_input;
+
+#[test]
+fn xtrace_prints_expanded_words_with_ps4() {
+    let (code, out, err) = run_with_input(
+        "X='a b'\nset -x\necho $X \"it's\" > /dev/null\nPS4='>> '; Y=1\necho ok | cat\nset +x\necho quiet\nexit\n",
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "ok\nquiet\n");
+    assert_eq!(
+        err,
+        "+ echo a b 'it'\\''s'\n+ PS4='>> '\n>> Y=1\n>> echo ok\n>> cat\n>> set +x\n"
+    );
+}
+
+#[test]
+fn errexit_stops_on_failure_outside_conditions() {
+    let (code, out, err) = run_with_input(
+        "set -e\nfalse || echo a\nfalse && echo b\ntrue && false || echo c\n(false; echo d)\necho e\n",
+    );
+    assert_eq!(code, 1, "stderr: {err}");
+    assert_eq!(out, "a\nc\n");
+
+    let (code, out, _err) = run_with_input("set -e\nno_such_command_xyz\necho after\n");
+    assert_eq!(code, 127);
+    assert_eq!(out, "");
+}
+
+#[test]
+fn nounset_rejects_unset_variables() {
+    let (code, out, err) = run_with_input(
+        "set -u\necho $NOPE\nE=; echo \"[$E]\"\ncat <<EOF\n$NOPE\nEOF\necho $(($NOPE + 1)) $((nope + 1))\nset +u\necho \"[$NOPE]\"\nexit\n",
+    );
+    assert_eq!(code, 0);
+    assert_eq!(out, "[]\n[]\n");
+    assert_eq!(
+        err.matches("NOPE: unbound variable").count(),
+        3,
+        "stderr: {err}"
+    );
+
+    let (code, out, _err) = run_with_input("set -eu\necho $NOPE\necho after\n");
+    assert_eq!(code, 1);
+    assert_eq!(out, "");
+}
+
+#[test]
+fn pipefail_and_pipestatus() {
+    let (code, out, err) = run_with_input(
+        "false | true\necho $PIPESTATUS\n(exit 3) | (exit 4) | true && echo ok\nset -o pipefail\n\
+         (exit 3) | (exit 4) | true || echo failed\n(exit 3) | (exit 4) | true\necho $PIPESTATUS\n\
+         false\necho $PIPESTATUS\nexit\n",
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "1 0\nok\nfailed\n3 4 0\n1\n");
+}
+
+#[test]
+fn pipestatus_elements_by_index() {
+    let (code, out, err) = run_with_input(
+        "true | false | (exit 3); echo ${PIPESTATUS[0]} ${PIPESTATUS[2]} [${PIPESTATUS[5]}] ${PIPESTATUS[@]} $((${PIPESTATUS[1]} + 1))\n\
+         X=v; echo ${X} ${X[0]} [${X[1]}] ${X[*]} ${X}y $X[1]\n\
+         set -u; echo ${PIPESTATUS[7]}\nexit\n",
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "0 3 [] 0 1 3 2\nv v [] v vy v[1]\n");
+    assert!(
+        err.contains("PIPESTATUS[7]: unbound variable"),
+        "stderr: {err}"
+    );
+}
+
+#[test]
+fn set_short_flags_show_in_option_list() {
+    let (code, out, err) = run_with_input("set -eux\nset +x\nset -o\nset -q\n");
+    assert_eq!(code, 2, "stderr: {err}");
+    assert_eq!(
+        out,
+        "debug-expand   \toff\ndebugparse     \toff\nerrexit        \ton\nhistexpand     \toff\nnounset        \ton\npipefail       \toff\nxtrace         \toff\n"
+    );
+    assert!(err.contains("set: -q: invalid option"), "stderr: {err}");
+}
+
+#[test]
+fn options_and_pipestatus_apply_within_the_same_line() {
+    let (code, out, err) = run_with_input(
+        "false | true; echo $PIPESTATUS\n(exit 3) | true; echo $PIPESTATUS; echo $PIPESTATUS\nset -u; echo $UNSET; echo after\necho next\nexit\n",
+    );
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "1 0\n3 0\n0\nnext\n");
+    assert!(
+        err.contains("UNSET: unbound variable at column 14"),
+        "stderr: {err}"
+    );
+}
@@ -1,150 +1,426 @@
 //! Unit-тесты для парсера командной строки.
 
-use super::super::parser::{ParseError, parse_line};
+use super::super::parser::{
+    HeredocSpec, ParseErrorKind, parse_line, parse_line_with_heredocs, pending_heredocs,
+};
+use super::super::types::{
+    Command, CommandList, Connector, GroupKind, HereInput, Redirect, SimpleCommand, Span,
+};
+use super::super::wordexp::Word;
 use std::collections::HashMap;
 
+/// Простые команды единственного пайплайна строки.
+fn commands(parsed: &CommandList) -> Vec<&SimpleCommand> {
+    assert_eq!(parsed.entries.len(), 1);
+    let pipeline = parsed.entries[0].pipeline.as_ref().unwrap();
+    pipeline.commands.iter().map(simple).collect()
+}
+
+fn simple(command: &Command) -> &SimpleCommand {
+    match command {
+        Command::Simple(spec) => spec,
+        other => panic!("expected simple command, got {other:?}"),
+    }
+}
+
+/// Тексты слов команды (имя и аргументы) до подстановок.
+fn words(command: &SimpleCommand) -> Vec<&str> {
+    command
+        .words
+        .iter()
+        .map(|word| word.text.as_str())
+        .collect()
+}
+
+/// Присваивания элемента: имя и текст слова до подстановок.
+fn assignments(parsed: &CommandList, entry: usize) -> Vec<(&str, &str)> {
+    parsed.entries[entry]
+        .assignments
+        .iter()
+        .map(|(name, word)| (name.as_str(), word.text.as_str()))
+        .collect()
+}
+
+/// Перенаправления с путями — текстами слов до подстановок.
+fn redirects(redirects: &[Redirect<Word>]) -> Vec<Redirect> {
+    redirects
+        .iter()
+        .map(|redirect| match redirect {
+            Redirect::Input(word) => Redirect::Input(word.text.clone()),
+            Redirect::Output { fd, path, append } => Redirect::Output {
+                fd: *fd,
+                path: path.text.clone(),
+                append: *append,
+            },
+            Redirect::OutputBoth { path, append } => Redirect::OutputBoth {
+                path: path.text.clone(),
+                append: *append,
+            },
+            Redirect::Duplicate { fd, target } => Redirect::Duplicate {
+                fd: *fd,
+                target: *target,
+            },
+        })
+        .collect()
+}
+
 #[test]
 fn tokenizes_basic_words() {
-    let env = HashMap::new();
-    let parsed = parse_line("echo hello world", &env).unwrap();
-    let pipeline = parsed.pipeline.unwrap();
-    assert_eq!(pipeline.commands.len(), 1);
-    assert_eq!(pipeline.commands[0].name, "echo");
-    assert_eq!(pipeline.commands[0].args, vec!["hello", "world"]);
+    let parsed = parse_line("echo hello world").unwrap();
+    let pipeline = commands(&parsed);
+    assert_eq!(pipeline.len(), 1);
+    assert_eq!(words(pipeline[0]), ["echo", "hello", "world"]);
 }
 
 #[test]
 fn tokenizes_quotes_as_single_arg() {
-    let env = HashMap::new();
-    let parsed = parse_line("echo \"Hello, world!\"", &env).unwrap();
-    let cmd = &parsed.pipeline.unwrap().commands[0];
-    assert_eq!(cmd.args, vec!["Hello, world!"]);
+    let parsed = parse_line("echo \"Hello, world!\"").unwrap();
+    assert_eq!(words(commands(&parsed)[0]), ["echo", "Hello, world!"]);
 }
 
 #[test]
 fn tokenizes_single_quotes_as_single_arg() {
-    let env = HashMap::new();
-    let parsed = parse_line("echo 'a b'", &env).unwrap();
-    let cmd = &parsed.pipeline.unwrap().commands[0];
-    assert_eq!(cmd.args, vec!["a b"]);
+    let parsed = parse_line("echo 'a b'").unwrap();
+    assert_eq!(words(commands(&parsed)[0]), ["echo", "a b"]);
 }
 
 #[test]
 fn preserves_empty_quoted_argument() {
-    let env = HashMap::new();
-    let parsed = parse_line("echo \"\" x", &env).unwrap();
-    let cmd = &parsed.pipeline.unwrap().commands[0];
-    assert_eq!(cmd.args, vec!["", "x"]);
+    let parsed = parse_line("echo \"\" x").unwrap();
+    let cmd = commands(&parsed)[0];
+    assert_eq!(words(cmd), ["echo", "", "x"]);
+    assert!(cmd.words[1].is_quoted());
 }
 
 #[test]
 fn parses_assignments_only() {
-    let env = HashMap::new();
-    let parsed = parse_line("FILE=example.txt", &env).unwrap();
-    assert_eq!(
-        parsed.assignments,
-        vec![("FILE".into(), "example.txt".into())]
-    );
-    assert!(parsed.pipeline.is_none());
+    let parsed = parse_line("FILE=example.txt").unwrap();
+    assert_eq!(assignments(&parsed, 0), [("FILE", "FILE=example.txt")]);
+    assert!(parsed.entries[0].pipeline.is_none());
 }
 
 #[test]
 fn parses_assignments_before_command() {
-    let env = HashMap::new();
-    let parsed = parse_line("x=ex y=it echo ok", &env).unwrap();
-    assert_eq!(parsed.assignments.len(), 2);
-    let cmd = &parsed.pipeline.unwrap().commands[0];
-    assert_eq!(cmd.name, "echo");
-    assert_eq!(cmd.args, vec!["ok"]);
+    let parsed = parse_line("x=ex y=$x echo ok").unwrap();
+    assert_eq!(assignments(&parsed, 0), [("x", "x=ex"), ("y", "y=$x")]);
+    assert_eq!(words(commands(&parsed)[0]), ["echo", "ok"]);
 }
 
 #[test]
 fn stops_parsing_assignments_on_invalid_name() {
     // `1x=...` невалидно как имя переменной => это команда, а не assignment.
-    let env = HashMap::new();
-    let parsed = parse_line("1x=bad echo ok", &env).unwrap();
-    assert!(parsed.assignments.is_empty());
-    let cmd = &parsed.pipeline.unwrap().commands[0];
-    assert_eq!(cmd.name, "1x=bad");
-    assert_eq!(cmd.args, vec!["echo", "ok"]);
+    let parsed = parse_line("1x=bad echo ok").unwrap();
+    assert!(parsed.entries[0].assignments.is_empty());
+    assert_eq!(words(commands(&parsed)[0]), ["1x=bad", "echo", "ok"]);
 }
 
 #[test]
 fn errors_on_unclosed_quote_double() {
-    let env = HashMap::new();
-    let err = parse_line("echo \"oops", &env).unwrap_err();
-    assert_eq!(err, ParseError::UnclosedQuote('"'));
+    let err = parse_line("echo \"oops").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::UnclosedQuote('"'));
 }
 
 #[test]
 fn errors_on_unclosed_quote_single() {
-    let env = HashMap::new();
-    let err = parse_line("echo 'oops", &env).unwrap_err();
-    assert_eq!(err, ParseError::UnclosedQuote('\''));
+    let err = parse_line("echo 'oops").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::UnclosedQuote('\''));
 }
 
 #[test]
-fn expands_vars_outside_single_quotes() {
-    let mut env = HashMap::new();
-    env.insert("FOO".to_string(), "bar".to_string());
+fn keeps_substitutions_unexpanded() {
+    let parsed = parse_line("echo $FOO \"$FOO\" '$FOO' $((i += 1))").unwrap();
+    let cmd = commands(&parsed)[0];
+    assert_eq!(words(cmd), ["echo", "$FOO", "$FOO", "$FOO", "$((i += 1))"]);
+    assert!(!cmd.words[1].is_plain() && !cmd.words[1].is_quoted());
+    assert!(cmd.words[2].is_quoted());
+    assert!(cmd.words[3].is_quoted() && cmd.words[3].parts.len() == 1);
+}
 
-    let parsed = parse_line("echo $FOO \"$FOO\"", &env).unwrap();
-    let cmd = &parsed.pipeline.unwrap().commands[0];
-    assert_eq!(cmd.args, vec!["bar", "bar"]);
+#[test]
+fn parses_pipelines() {
+    let parsed = parse_line("echo hi | wc").unwrap();
+    let pipeline = commands(&parsed);
+    assert_eq!(pipeline.len(), 2);
+    assert_eq!(words(pipeline[0]), ["echo", "hi"]);
+    assert_eq!(words(pipeline[1]), ["wc"]);
 }
 
 #[test]
-fn expansion_outside_quotes_splits_on_whitespace_from_value() {
-    let mut env = HashMap::new();
-    env.insert("FOO".to_string(), "a b".to_string());
+fn errors_on_empty_pipeline_segment() {
+    let err = parse_line("echo hi | | wc").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::EmptyPipelineSegment);
+}
 
-    let parsed = parse_line("echo $FOO", &env).unwrap();
-    let cmd = &parsed.pipeline.unwrap().commands[0];
-    assert_eq!(cmd.args, vec!["a", "b"]);
+#[test]
+fn double_bracket_keeps_or_operator_as_word() {
+    let parsed = parse_line("[[ a == b || -n x ]] 2>/dev/null | cat").unwrap();
+    let pipeline = &parsed.entries[0].pipeline.as_ref().unwrap().commands;
+    assert_eq!(pipeline.len(), 2);
+    let Command::Conditional(conditional) = &pipeline[0] else {
+        panic!("expected conditional, got {:?}", pipeline[0]);
+    };
+    let words: Vec<_> = conditional.words.iter().map(|w| w.text.as_str()).collect();
+    assert_eq!(words, ["a", "==", "b", "||", "-n", "x"]);
+    assert_eq!(conditional.redirects.len(), 1);
 }
 
 #[test]
-fn expansion_inside_double_quotes_does_not_split_on_whitespace_from_value() {
-    let mut env = HashMap::new();
-    env.insert("FOO".to_string(), "a b".to_string());
+fn arithmetic_syntax_errors_are_parse_errors() {
+    assert_eq!(
+        parse_line("echo $((1+2").unwrap_err().kind,
+        ParseErrorKind::UnterminatedArithmetic
+    );
+    // Ошибки вычисления (`1/0`) возникают только при выполнении команды.
+    assert!(parse_line("false && echo $((1/0))").is_ok());
+}
 
-    let parsed = parse_line("echo \"$FOO\"", &env).unwrap();
-    let cmd = &parsed.pipeline.unwrap().commands[0];
-    assert_eq!(cmd.args, vec!["a b"]);
+#[test]
+fn arithmetic_command_keeps_or_operator_as_word() {
+    let parsed = parse_line("((a||b)) | cat").unwrap();
+    let pipeline = commands(&parsed);
+    assert_eq!(pipeline.len(), 2);
+    assert_eq!(words(pipeline[0]), ["((a||b))"]);
 }
 
 #[test]
-fn does_not_expand_in_single_quotes() {
-    let mut env = HashMap::new();
-    env.insert("FOO".to_string(), "bar".to_string());
+fn heredoc_and_here_string_attach_to_their_command() {
+    let bodies = vec!["a \\$X $X $((1+1))\n".to_string(), "raw $X\n".to_string()];
 
-    let parsed = parse_line("echo '$FOO'", &env).unwrap();
-    let cmd = &parsed.pipeline.unwrap().commands[0];
-    assert_eq!(cmd.args, vec!["$FOO"]);
+    let parsed = parse_line_with_heredocs(
+        "cat <<EOF | grep a <<'RAW' | tr a b <<< $X",
+        &bodies,
+        &HashMap::new(),
+    )
+    .unwrap();
+    let commands = commands(&parsed);
+    assert_eq!(commands.len(), 3);
+    assert_eq!(words(commands[0]), ["cat"]);
+    let Some(HereInput::Heredoc(body)) = &commands[0].here_input else {
+        panic!("expected heredoc, got {:?}", commands[0].here_input);
+    };
+    assert_eq!(body.text, "a $X $X $((1+1))\n");
+    assert!(body.is_quoted());
+    assert_eq!(words(commands[1]), ["grep", "a"]);
+    assert_eq!(
+        commands[1].here_input,
+        Some(HereInput::Literal("raw $X\n".to_string()))
+    );
+    assert_eq!(words(commands[2]), ["tr", "a", "b"]);
+    let Some(HereInput::HereString(word)) = &commands[2].here_input else {
+        panic!("expected here-string, got {:?}", commands[2].here_input);
+    };
+    assert_eq!(word.text, "$X");
 }
 
 #[test]
-fn parses_pipelines() {
-    let env = HashMap::new();
-    let parsed = parse_line("echo hi | wc", &env).unwrap();
-    let pipeline = parsed.pipeline.unwrap();
-    assert_eq!(pipeline.commands.len(), 2);
-    assert_eq!(pipeline.commands[0].name, "echo");
-    assert_eq!(pipeline.commands[1].name, "wc");
+fn pending_heredocs_reports_delimiters_in_order() {
+    assert_eq!(
+        pending_heredocs("cat <<-'END' <<\"B\"x | wc"),
+        vec![
+            HeredocSpec {
+                delimiter: "END".to_string(),
+                strip_tabs: true,
+            },
+            HeredocSpec {
+                delimiter: "Bx".to_string(),
+                strip_tabs: false,
+            },
+        ]
+    );
+    assert!(pending_heredocs("echo '<<EOF' $((1 << 2))").is_empty());
+    assert!(pending_heredocs("(( x <<= 1 ))").is_empty());
+}
+
+#[test]
+fn heredoc_without_delimiter_is_error() {
+    assert_eq!(
+        parse_line("cat <<").unwrap_err().kind,
+        ParseErrorKind::MissingRedirectWord("<<")
+    );
+    assert_eq!(
+        parse_line("cat <<< | wc").unwrap_err().kind,
+        ParseErrorKind::MissingRedirectWord("<<<")
+    );
 }
 
 #[test]
-fn assignments_affect_expansion_later_in_line() {
-    let env = HashMap::new();
-    let parsed = parse_line("x=ex y=it echo $x$y", &env).unwrap();
-    let cmd = &parsed.pipeline.unwrap().commands[0];
-    assert_eq!(cmd.name, "echo");
-    assert_eq!(cmd.args, vec!["exit"]);
+fn parses_command_lists_with_connectors() {
+    let parsed = parse_line("X=1; false && echo a || echo b;").unwrap();
+    let entries = &parsed.entries;
+    assert_eq!(entries.len(), 4);
+    assert_eq!(assignments(&parsed, 0), [("X", "X=1")]);
+    assert!(entries[0].pipeline.is_none());
+    let connectors: Vec<_> = entries.iter().map(|e| e.connector).collect();
+    assert_eq!(
+        connectors,
+        vec![
+            Connector::Always,
+            Connector::Always,
+            Connector::IfSuccess,
+            Connector::IfFailure
+        ]
+    );
 }
 
 #[test]
-fn errors_on_empty_pipeline_segment() {
-    let env = HashMap::new();
-    let err = parse_line("echo hi | | wc", &env).unwrap_err();
-    assert_eq!(err, ParseError::EmptyPipelineSegment);
+fn parses_subshell_and_brace_group_with_redirects() {
+    let parsed = parse_line("(cd dir && pwd) | cat; { echo a; cat f; } >out 2>&1").unwrap();
+    let entries = &parsed.entries;
+    assert_eq!(entries.len(), 2);
+
+    let stages = &entries[0].pipeline.as_ref().unwrap().commands;
+    assert_eq!(stages.len(), 2);
+    let Command::Group(sub) = &stages[0] else {
+        panic!("expected subshell");
+    };
+    assert_eq!(sub.kind, GroupKind::Subshell);
+    assert_eq!(sub.body.entries.len(), 2);
+    assert_eq!(sub.body.entries[1].connector, Connector::IfSuccess);
+
+    let Command::Group(brace) = &entries[1].pipeline.as_ref().unwrap().commands[0] else {
+        panic!("expected brace group");
+    };
+    assert_eq!(brace.kind, GroupKind::Brace);
+    assert_eq!(brace.body.entries.len(), 2);
+    assert_eq!(
+        redirects(&brace.redirects),
+        vec![
+            Redirect::Output {
+                fd: 1,
+                path: "out".into(),
+                append: false
+            },
+            Redirect::Duplicate { fd: 2, target: 1 },
+        ]
+    );
+}
+
+#[test]
+fn parses_redirections_of_simple_command() {
+    let parsed = parse_line("sort <in 2>>err a >&2 &> $F").unwrap();
+    let cmd = commands(&parsed)[0];
+    assert_eq!(words(cmd), ["sort", "a"]);
+    assert_eq!(
+        redirects(&cmd.redirects),
+        vec![
+            Redirect::Input("in".into()),
+            Redirect::Output {
+                fd: 2,
+                path: "err".into(),
+                append: true
+            },
+            Redirect::Duplicate { fd: 1, target: 2 },
+            Redirect::OutputBoth {
+                path: "$F".into(),
+                append: false
+            },
+        ]
+    );
+    // Цифра, отделенная пробелом, — обычный аргумент; в `[[ ]]` `<`/`>` — операнды.
+    let parsed = parse_line("echo 2 > f").unwrap();
+    assert_eq!(words(commands(&parsed)[0]), ["echo", "2"]);
+    let parsed = parse_line("[[ a < b ]]").unwrap();
+    let pipeline = &parsed.entries[0].pipeline.as_ref().unwrap().commands;
+    let Command::Conditional(conditional) = &pipeline[0] else {
+        panic!("expected conditional, got {:?}", pipeline[0]);
+    };
+    let words: Vec<_> = conditional.words.iter().map(|w| w.text.as_str()).collect();
+    assert_eq!(words, ["a", "<", "b"]);
+}
+
+#[test]
+fn list_syntax_errors() {
+    let unexpected = |tok: &str| ParseErrorKind::UnexpectedToken(tok.to_string());
+    assert_eq!(
+        parse_line("echo a &&").unwrap_err().kind,
+        ParseErrorKind::UnexpectedEnd
+    );
+    assert_eq!(parse_line("; echo").unwrap_err().kind, unexpected(";"));
+    assert_eq!(
+        parse_line("(echo a").unwrap_err().kind,
+        ParseErrorKind::UnexpectedEnd
+    );
+    assert_eq!(parse_line("echo a)").unwrap_err().kind, unexpected(")"));
+    assert_eq!(
+        parse_line("{ echo a }").unwrap_err().kind,
+        ParseErrorKind::UnexpectedEnd
+    );
+    assert_eq!(parse_line("()").unwrap_err().kind, unexpected(")"));
+    assert_eq!(parse_line("sleep 1 &").unwrap_err().kind, unexpected("&"));
+    assert_eq!(
+        parse_line("echo >").unwrap_err().kind,
+        ParseErrorKind::MissingRedirectWord(">")
+    );
+}
+
+#[test]
+fn parses_process_substitutions_as_words() {
+    let parsed = parse_line("diff <(sort a | uniq) x >(cat) > out").unwrap();
+    let cmd = commands(&parsed)[0];
+    assert_eq!(words(cmd), ["diff", "", "x", ""]);
+    assert_eq!(cmd.redirects.len(), 1);
+    let substs: Vec<_> = cmd
+        .process_substs
+        .iter()
+        .map(|s| (s.word, s.output, s.list.entries.len()))
+        .collect();
+    assert_eq!(substs, vec![(1, false, 1), (3, true, 1)]);
+    let inner = cmd.process_substs[0].list.entries[0]
+        .pipeline
+        .as_ref()
+        .unwrap();
+    assert_eq!(inner.commands.len(), 2);
+
+    assert_eq!(
+        parse_line("cat <(echo a").unwrap_err().kind,
+        ParseErrorKind::UnexpectedEnd
+    );
+}
+
+#[test]
+fn unclosed_quote_points_at_opening_quote() {
+    let err = parse_line("echo ok; echo \"hello").unwrap_err();
+    assert_eq!(err.span, Span::new(14, 20));
+    assert_eq!(err.opened_at, Some(Span::new(14, 15)));
+    assert_eq!(
+        err.to_string(),
+        "unclosed quote: \" at column 15\n  \
+         echo ok; echo \"hello\n  \
+         \x20             ^^^^^^\n  \
+         expected: `\"`\n  \
+         hint: did you forget a closing `\"` opened at column 15?"
+    );
+}
+
+#[test]
+fn errors_list_expected_tokens_and_hints() {
+    let err = parse_line("echo a &&").unwrap_err();
+    assert_eq!(err.span, Span::new(9, 9));
+    assert_eq!(err.expected, vec!["command"]);
+
+    let err = parse_line("(cd /tmp; ls").unwrap_err();
+    assert_eq!(err.opened_at, Some(Span::new(0, 1)));
+    assert!(
+        err.to_string()
+            .ends_with("hint: did you forget a closing `)` for `(` opened at column 1?"),
+        "{err}"
+    );
+
+    let err = parse_line("echo $((1 + 2").unwrap_err();
+    assert_eq!(err.span, Span::new(5, 13));
+    assert!(err.to_string().contains("opened at column 6"), "{err}");
+
+    let err = parse_line("sleep 1 &").unwrap_err();
+    assert!(err.to_string().contains("not supported"), "{err}");
+}
+
+#[test]
+fn columns_count_characters_not_bytes() {
+    let err = parse_line("echo привет |").unwrap_err();
+    assert_eq!(err.kind, ParseErrorKind::EmptyPipelineSegment);
+    assert!(
+        err.to_string()
+            .starts_with("empty pipeline segment at column 14\n"),
+        "{err}"
+    );
 }
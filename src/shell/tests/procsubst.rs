@@ -0,0 +1,36 @@
+//! Тесты подстановки процессов `<(list)` и `>(list)`.
+
+#![cfg(not(windows))]
+
+use super::repl::run_with_input;
+
+#[test]
+fn input_substitutions_feed_builtins_and_external_programs() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a"), "b\na\n").unwrap();
+    std::fs::write(dir.path().join("b"), "a\n").unwrap();
+    let root = dir.path().display();
+
+    let (_code, out, err) = run_with_input(&format!(
+        "cd {root}\ncat <(sort a) <(echo x; echo y | tr y z)\ndiff <(sort a) <(sort b)\n\
+         sh -c 'cat \"$0\"' <(cat <(echo nested))\nexit\n"
+    ));
+    assert_eq!(out, "a\nb\nx\nz\n2d1\n< b\nnested\n");
+    assert!(err.is_empty());
+}
+
+#[test]
+fn output_substitution_receives_command_output() {
+    let (_code, out, err) = run_with_input(
+        "echo hi | tee >(wc) | cat\nsh -c 'echo to-sub > \"$0\"' >(tr a-z A-Z)\nexit\n",
+    );
+    assert_eq!(out, "hi\n1 1 3\nTO-SUB\n");
+    assert!(err.is_empty());
+}
+
+#[test]
+fn unused_substitution_does_not_block() {
+    let (_code, out, _err) = run_with_input("echo <(echo ignored) done\nexit\n");
+    assert!(out.starts_with("/dev/fd/"));
+    assert!(out.ends_with(" done\n"));
+}
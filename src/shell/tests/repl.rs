@@ -1,15 +1,58 @@
 //! Тесты поведения REPL.
 
 use std::io::Cursor;
+use std::sync::Arc;
 
-use super::super::run_repl;
+use super::super::executor::{SharedExecutor, StdProcessExecutor};
+use super::super::options::ShellOptions;
+use super::super::{Startup, run_repl_with_executor};
 
 use tempfile::NamedTempFile;
 
-fn run_with_input(input: &str) -> (i32, String, String) {
+pub(super) fn run_with_input(input: &str) -> (i32, String, String) {
+    run_with_options(input, ShellOptions::default())
+}
+
+/// Запускает REPL с опциями `options` (как флаги командной строки).
+pub(super) fn run_with_options(input: &str, options: ShellOptions) -> (i32, String, String) {
+    run_with_startup(
+        input,
+        Startup {
+            options,
+            ..Startup::default()
+        },
+    )
+}
+
+/// Запускает REPL с параметрами запуска `startup`.
+pub(super) fn run_with_startup(input: &str, startup: Startup) -> (i32, String, String) {
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    let code = run_repl_with_executor(
+        Cursor::new(input.as_bytes()),
+        &mut out,
+        &mut err,
+        startup,
+        Arc::new(StdProcessExecutor::new()),
+    );
+    (
+        code,
+        String::from_utf8_lossy(&out).to_string(),
+        String::from_utf8_lossy(&err).to_string(),
+    )
+}
+
+/// Запускает REPL, в котором внешние программы запускает `executor`.
+pub(super) fn run_with_executor(input: &str, executor: SharedExecutor) -> (i32, String, String) {
     let mut out = Vec::new();
     let mut err = Vec::new();
-    let code = run_repl(Cursor::new(input.as_bytes()), &mut out, &mut err);
+    let code = run_repl_with_executor(
+        Cursor::new(input.as_bytes()),
+        &mut out,
+        &mut err,
+        Startup::default(),
+        executor,
+    );
     (
         code,
         String::from_utf8_lossy(&out).to_string(),
@@ -168,3 +211,139 @@ fn external_command_runs_on_unix() {
     assert_eq!(code, 0);
     assert!(out.contains("hi"));
 }
+
+#[test]
+fn cat_dash_splices_pipeline_stdin_between_files() {
+    let mut tmp = NamedTempFile::new().unwrap();
+    std::io::Write::write_all(&mut tmp, b"body\n").unwrap();
+    let path = tmp.path().to_string_lossy();
+
+    let (_code, out, err) = run_with_input(&format!("echo header | cat - \"{path}\"\nexit\n"));
+    assert_eq!(out, "header\nbody\n");
+    assert!(err.is_empty());
+}
+
+#[test]
+fn text_builtins_compose_in_pipeline() {
+    let (_code, out, err) = run_with_input(
+        "printf 'b\\na\\nb\\nc\\n' | sort | uniq -c | sort -n -r | head -n 1\nexit\n",
+    );
+    assert_eq!(out, "      2 b\n");
+    assert!(err.is_empty());
+}
+
+#[test]
+fn find_pipes_into_xargs_grep() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/a.rs"), b"let x = y.unwrap();\nok\n").unwrap();
+    std::fs::write(dir.path().join("src/b.rs"), b"fine\nz.unwrap()\n").unwrap();
+    std::fs::write(dir.path().join("src/c.txt"), b"w.unwrap()\n").unwrap();
+    let root = dir.path().to_string_lossy();
+
+    let (_code, out, err) = run_with_input(&format!(
+        "find \"{root}\" -name '*.rs' | xargs grep -n unwrap\nexit\n"
+    ));
+    assert!(err.is_empty(), "{err}");
+    assert_eq!(
+        out,
+        format!("{root}/src/a.rs:1:let x = y.unwrap();\n{root}/src/b.rs:2:z.unwrap()\n")
+    );
+}
+
+#[test]
+fn conditionals_work_in_repl() {
+    let (_code, out, err) = run_with_input(
+        "X=hello\n[[ $X =~ ^h.*o$ || -z $X ]] | cat\ntest -n \"$X\"\n[ -n \"$X\"\nexit\n",
+    );
+    assert!(out.is_empty());
+    assert_eq!(err, "[: missing ']'\n");
+}
+
+#[test]
+fn let_and_arithmetic_command_update_shell_variables() {
+    let (_code, out, err) = run_with_input(
+        "i=1\nlet i+=2 'j = i * 10'\n(( i++ ))\necho $i $j $((i * j))\n(( 1 / 0 ))\nexit\n",
+    );
+    assert_eq!(out, "4 30 120\n");
+    assert_eq!(err, "((: 1 / 0: division by 0\n");
+}
+
+#[test]
+fn arithmetic_runs_only_when_its_command_runs() {
+    let (code, out, err) = run_with_input(
+        "i=0; false && echo $((i+=1)); echo $i\n(echo $((i=5))); echo $i\ni=0; (( i++ )); echo $i\nfalse && echo $((1/0)); echo next\nexit\n",
+    );
+    assert_eq!(code, 0);
+    assert_eq!(out, "0\n5\n0\n1\nnext\n");
+    assert!(err.is_empty(), "stderr: {err}");
+}
+
+#[test]
+fn arithmetic_command_in_pipeline_does_not_change_shell_state() {
+    let (_code, out, err) = run_with_input("i=1\nlet i=5 | cat\necho $i\nexit\n");
+    assert_eq!(out, "1\n");
+    assert!(err.is_empty());
+}
+
+#[test]
+fn heredoc_body_is_read_from_following_lines() {
+    let (_code, out, err) = run_with_input(
+        "NAME=world\ncat <<EOF | grep -n .\nhello $NAME\n\\$NAME $((2*3))\nEOF\necho after\nexit\n",
+    );
+    assert_eq!(out, "1:hello world\n2:$NAME 6\nafter\n");
+    assert!(err.is_empty());
+}
+
+#[test]
+fn quoted_heredoc_delimiter_disables_expansion_and_dash_strips_tabs() {
+    let (_code, out, err) =
+        run_with_input("cat <<'EOF'\n$HOME $((1+1))\nEOF\ncat <<-END\n\t\tinner\n\tEND\nexit\n");
+    assert_eq!(out, "$HOME $((1+1))\ninner\n");
+    assert!(err.is_empty());
+}
+
+#[test]
+fn unterminated_heredoc_warns_and_uses_rest_of_input() {
+    let (_code, out, err) = run_with_input("cat <<EOF\nline\n");
+    assert_eq!(out, "line\n");
+    assert_eq!(
+        err,
+        "warning: here-document delimited by end-of-file (wanted `EOF`)\n"
+    );
+}
+
+#[test]
+fn here_string_feeds_builtin_stdin() {
+    let (_code, out, err) = run_with_input("X='b a'\ntr ab AB <<< \"$X\"\nexit\n");
+    assert_eq!(out, "B A\n");
+    assert!(err.is_empty());
+}
+
+#[cfg(not(windows))]
+#[test]
+fn here_input_reaches_external_process() {
+    let (_code, out, err) = run_with_input(
+        "sh -c 'read x; echo got $x' <<< hi\nsh -c 'cat' <<EOF | cat\nbody\nEOF\nexit\n",
+    );
+    assert_eq!(out, "got hi\nbody\n");
+    assert!(err.is_empty());
+}
+
+#[test]
+fn variable_values_are_not_reparsed() {
+    let (_code, out, err) =
+        run_with_input("X='a|b && exit 3'\necho $X\nY=\"$X\"; echo \"$Y\"\nexit\n");
+    assert_eq!(out, "a|b && exit 3\na|b && exit 3\n");
+    assert!(err.is_empty());
+}
+
+#[test]
+fn empty_command_only_opens_redirections() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("created");
+    let (code, _out, err) = run_with_input(&format!("E=\n$E > '{}'\nexit\n", path.display()));
+    assert_eq!(code, 0);
+    assert!(err.is_empty(), "stderr: {err}");
+    assert!(path.exists());
+}
@@ -0,0 +1,195 @@
+//! Тесты ограниченного режима (`--restricted`).
+
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::super::mock::{MockExecutor, MockResponse};
+use super::super::restrict::Restrictions;
+use super::super::{Startup, run_repl_with_executor};
+
+/// Мок с программами `ping` и `curl`; в ограниченном режиме разрешен только `ping`.
+fn mock() -> Arc<MockExecutor> {
+    let mock = Arc::new(MockExecutor::new());
+    mock.respond("ping", MockResponse::stdout("pong\n"));
+    mock.respond("curl", MockResponse::stdout("fetched\n"));
+    mock
+}
+
+/// Запускает ограниченный шелл с корнем `root`.
+fn run_restricted(input: &str, root: &Path, executor: Arc<MockExecutor>) -> (i32, String, String) {
+    let startup = Startup {
+        restricted: Some(Restrictions {
+            root: root.to_path_buf(),
+            allowed: ["ping".to_string()].into(),
+        }),
+        ..Startup::default()
+    };
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    let code = run_repl_with_executor(
+        Cursor::new(input.as_bytes()),
+        &mut out,
+        &mut err,
+        startup,
+        executor,
+    );
+    (
+        code,
+        String::from_utf8_lossy(&out).to_string(),
+        String::from_utf8_lossy(&err).to_string(),
+    )
+}
+
+#[test]
+fn only_allowed_programs_run() {
+    let mock = mock();
+    let (code, out, err) = run_restricted(
+        "ping\ncurl -o x\necho a | curl\ncommand curl\n/mock/bin/ping\necho builtins | grep b\nprintf 'a\\n' | xargs curl\necho done",
+        Path::new("."),
+        mock.clone(),
+    );
+    assert_eq!(code, 0);
+    assert_eq!(out, "pong\nbuiltins\ndone\n");
+    assert_eq!(
+        err,
+        "curl: restricted: command is not allowed\n\
+         curl: restricted: command is not allowed\n\
+         curl: restricted: command is not allowed\n\
+         /mock/bin/ping: restricted: cannot specify `/' in command names\n\
+         curl: restricted: command is not allowed\n"
+    );
+    let programs: Vec<_> = mock.calls().into_iter().map(|c| c.program).collect();
+    assert_eq!(programs, [Path::new("/mock/bin/ping")]);
+}
+
+#[test]
+fn output_redirections_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let (_, out, err) = run_restricted(
+        &format!(
+            "cd {}\necho x > out; echo skipped\n{{ echo y; }} >> out\necho z &> out\necho ok 2>&1 | cat",
+            dir.path().display()
+        ),
+        dir.path(),
+        mock(),
+    );
+    assert_eq!(out, "ok\n");
+    assert_eq!(
+        err,
+        "out: restricted: cannot redirect output\n\
+         out: restricted: cannot redirect output\n\
+         out: restricted: cannot redirect output\n"
+    );
+    assert!(!dir.path().join("out").exists());
+}
+
+#[test]
+fn protected_variables_cannot_change() {
+    let (_, out, err) = run_restricted(
+        "OLD=$PATH\nPATH=/tmp\nSHELL=/bin/sh ping\nX=$((ENV=1))\nlet SE_CLI_ENV=2\n(( PATH = 3 ))\nMY_VAR=ok\n[ \"$PATH\" = \"$OLD\" ] && echo $MY_VAR",
+        Path::new("."),
+        mock(),
+    );
+    assert_eq!(out, "ok\n");
+    assert_eq!(
+        err,
+        "PATH: restricted: cannot change variable\n\
+         SHELL: restricted: cannot change variable\n\
+         ENV: restricted: cannot change variable\n\
+         SE_CLI_ENV: restricted: cannot change variable\n\
+         PATH: restricted: cannot change variable\n"
+    );
+}
+
+#[test]
+fn cd_stays_inside_the_root() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let root = dir.path().join("sub");
+    let (code, out, err) = run_restricted(
+        &format!(
+            "cd {}\ncd ..; echo skipped\ncd /\npwd\ncd . && echo same",
+            root.display()
+        ),
+        &root,
+        mock(),
+    );
+    assert_eq!(code, 0);
+    assert_eq!(out, format!("{}\nsame\n", root.display()));
+    assert_eq!(
+        err,
+        format!(
+            "cd: {}: restricted: outside of {root}\n\
+             cd: /: restricted: outside of {root}\n",
+            dir.path().display(),
+            root = root.display()
+        )
+    );
+}
+
+#[test]
+fn restricted_flags_are_parsed() {
+    let startup = Startup::from_args([
+        "se-cli",
+        "-r",
+        "--restricted-root",
+        "/srv",
+        "--allow",
+        "ping",
+        "--allow",
+        "df",
+    ])
+    .unwrap();
+    let restrictions = startup.restricted.unwrap();
+    assert_eq!(restrictions.root, Path::new("/srv"));
+    assert_eq!(
+        restrictions.allowed.into_iter().collect::<Vec<_>>(),
+        ["df", "ping"]
+    );
+
+    let startup = Startup::from_args(["se-cli", "--restricted"]).unwrap();
+    assert_eq!(startup.restricted.unwrap().root, Path::new("."));
+    assert!(Startup::from_args(["se-cli"]).unwrap().restricted.is_none());
+    assert!(Startup::from_args(["se-cli", "--allow", "ping"]).is_err());
+}
+
+#[test]
+fn file_builtins_cannot_write_outside_the_root() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("root");
+    std::fs::create_dir(&root).unwrap();
+    std::fs::write(dir.path().join("outside"), "keep\n").unwrap();
+    std::fs::write(root.join("inside"), "data\n").unwrap();
+    let (code, out, err) = run_restricted(
+        &format!(
+            "cd {}\necho x | tee ../outside\nrm ../outside\ncp inside ../copy\nmv inside ../moved\nmkdir ../new\ntouch ../outside\nrm -r {}\ncp inside copy\ncat ../outside copy",
+            root.display(),
+            root.display()
+        ),
+        &root,
+        mock(),
+    );
+    assert_eq!(code, 0);
+    assert_eq!(out, "x\nkeep\ndata\n");
+    let msg = format!("restricted: outside of {}", root.display());
+    assert_eq!(
+        err,
+        format!(
+            "tee: ../outside: {msg}\n\
+             rm: ../outside: {msg}\n\
+             cp: inside: {msg}\n\
+             mv: inside: {msg}\n\
+             mkdir: ../new: {msg}\n\
+             touch: ../outside: {msg}\n\
+             rm: {}: {msg}\n",
+            root.display()
+        )
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("outside")).unwrap(),
+        "keep\n"
+    );
+    assert!(!dir.path().join("copy").exists());
+    assert!(root.join("inside").exists());
+}
@@ -0,0 +1,140 @@
+//! Тесты стартового файла (`--rcfile`, `--norc`, `$SE_CLI_ENV`) и `source` / `.`.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::super::Startup;
+use super::super::options::RcFile;
+use super::repl::{run_with_input, run_with_startup};
+
+#[test]
+fn source_runs_file_in_current_state() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    std::fs::write(
+        dir.path().join("lib.sh"),
+        "# настройки\nGREETING=hello # комментарий\nalias greet='echo $GREETING'\ncd sub\necho \"in $1 $2#\"\n",
+    )
+    .unwrap();
+    let dir = dir.path().display();
+
+    let (code, out, err) = run_with_input(&format!(
+        "cd {dir}\nsource lib.sh a 'b c'\necho [$1] $GREETING\ngreet world\n. ../lib.sh\npwd\nexit\n"
+    ));
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(
+        out,
+        format!("in a b c#\n[] hello\nhello world\nin  #\n{dir}/sub\n")
+    );
+    assert!(
+        err.contains("cd: sub: No such file or directory"),
+        "stderr: {err}"
+    );
+}
+
+#[test]
+fn source_reports_errors_with_file_and_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("bad.sh");
+    std::fs::write(
+        &script,
+        "echo one\necho \"two\necho three\nexit 4\necho unreachable\n",
+    )
+    .unwrap();
+    let script = script.display();
+
+    let (code, out, err) = run_with_input(&format!("source {script}\necho no\n"));
+    assert_eq!(code, 4);
+    assert_eq!(out, "one\nthree\n");
+    assert!(
+        err.starts_with(&format!("{script}: line 2: Parse error: ")),
+        "stderr: {err}"
+    );
+
+    let (code, _out, err) = run_with_input("source\nsource /no/such/file\nexit\n");
+    assert_eq!(code, 0);
+    assert_eq!(
+        err,
+        "source: filename argument required\nsource: /no/such/file: No such file or directory\n"
+    );
+}
+
+#[test]
+fn source_reads_pipe_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("upper.sh");
+    std::fs::write(&script, "tr a-z A-Z\n").unwrap();
+
+    let (code, out, err) =
+        run_with_input(&format!("echo hi | source {}\nexit\n", script.display()));
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "HI\n");
+}
+
+#[test]
+fn rcfile_runs_before_interactive_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let rc = dir.path().join("rc");
+    std::fs::write(&rc, "alias ll='echo listed'\nset -o pipefail\n").unwrap();
+
+    let startup = Startup::from_args(["se-cli", "--rcfile", rc.to_str().unwrap(), "-i"]).unwrap();
+    let (code, out, err) = run_with_startup("ll\nset -o | grep pipefail\nexit\n", startup);
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "listed\npipefail       \ton\n");
+
+    // Неинтерактивный шелл rc-файл не читает.
+    let startup = Startup::from_args(["se-cli", "--rcfile", rc.to_str().unwrap()]).unwrap();
+    let (_code, _out, err) = run_with_startup("ll\nexit\n", startup);
+    assert!(err.contains("ll: command not found"), "stderr: {err}");
+
+    let startup = Startup {
+        rcfile: RcFile::Path(dir.path().join("missing")),
+        interactive: true,
+        ..Startup::default()
+    };
+    let (code, out, err) = run_with_startup("echo still\n", startup);
+    assert_eq!(code, 0);
+    assert_eq!(out, "still\n");
+    assert!(
+        err.starts_with("se-cli: ") && err.contains("missing: No such file or directory"),
+        "stderr: {err}"
+    );
+}
+
+#[test]
+fn exit_in_rcfile_stops_the_shell() {
+    let dir = tempfile::tempdir().unwrap();
+    let rc = dir.path().join("rc");
+    std::fs::write(&rc, "exit 3\n").unwrap();
+    let startup = Startup {
+        rcfile: RcFile::Path(rc),
+        interactive: true,
+        ..Startup::default()
+    };
+    let (code, out, _err) = run_with_startup("echo never\n", startup);
+    assert_eq!(code, 3);
+    assert_eq!(out, "");
+}
+
+#[test]
+fn startup_file_depends_on_mode() {
+    let home = tempfile::tempdir().unwrap();
+    let mut env = HashMap::from([
+        ("HOME".to_string(), home.path().display().to_string()),
+        ("SE_CLI_ENV".to_string(), "env.sh".to_string()),
+    ]);
+    let interactive = Startup::from_args(["se-cli", "-i"]).unwrap();
+    assert_eq!(interactive.startup_file(&env), None);
+    std::fs::write(home.path().join(".se_clirc"), "").unwrap();
+    assert_eq!(
+        interactive.startup_file(&env),
+        Some(home.path().join(".se_clirc"))
+    );
+    let norc = Startup::from_args(["se-cli", "-i", "--norc", "--rcfile", "x"]).unwrap();
+    assert_eq!(norc.startup_file(&env), None);
+
+    let batch = Startup::from_args(["se-cli"]).unwrap();
+    assert_eq!(batch.startup_file(&env), Some(PathBuf::from("env.sh")));
+    env.insert("SE_CLI_ENV".to_string(), String::new());
+    assert_eq!(batch.startup_file(&env), None);
+}
@@ -0,0 +1,232 @@
+//! Unit-тесты для текстовых builtin-команд (`head`, `tail`, `sort`, `uniq`, `cut`, `tr`, `tee`).
+
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use super::super::builtins::Builtin;
+use super::super::types::ShellControl;
+use super::builtins::{run, run_with_stdin};
+use super::repl::run_with_input;
+
+const NUMBERS: &[u8] = b"1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+
+#[test]
+fn head_defaults_to_ten_lines() {
+    let (control, out, err) = run_with_stdin(Builtin::Head, &[], NUMBERS);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out.lines().count(), 10);
+    assert!(out.ends_with("10\n"));
+    assert!(err.is_empty());
+}
+
+#[test]
+fn head_supports_lines_bytes_and_shorthand() {
+    let (_c, out, _e) = run_with_stdin(Builtin::Head, &["-n", "2"], NUMBERS);
+    assert_eq!(out, "1\n2\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Head, &["-3"], NUMBERS);
+    assert_eq!(out, "1\n2\n3\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Head, &["-c", "3"], NUMBERS);
+    assert_eq!(out, "1\n2");
+    let (_c, out, _e) = run_with_stdin(Builtin::Head, &["-n", "-10"], NUMBERS);
+    assert_eq!(out, "1\n2\n");
+}
+
+#[test]
+fn head_prints_headers_for_multiple_files() {
+    let mut a = tempfile::NamedTempFile::new().unwrap();
+    a.write_all(b"a1\na2\n").unwrap();
+    let mut b = tempfile::NamedTempFile::new().unwrap();
+    b.write_all(b"b1\n").unwrap();
+    let pa = a.path().to_string_lossy().to_string();
+    let pb = b.path().to_string_lossy().to_string();
+
+    let (control, out, _e) = run(Builtin::Head, &["-n", "1", &pa, &pb]);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, format!("==> {pa} <==\na1\n\n==> {pb} <==\nb1\n"));
+}
+
+#[test]
+fn head_invalid_count_is_error() {
+    let (control, _out, err) = run_with_stdin(Builtin::Head, &["-n", "x"], NUMBERS);
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(err.contains("invalid number of lines"));
+}
+
+#[test]
+fn tail_supports_last_and_from_forms() {
+    let (_c, out, _e) = run_with_stdin(Builtin::Tail, &["-n", "2"], NUMBERS);
+    assert_eq!(out, "11\n12\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Tail, &["-n", "+11"], NUMBERS);
+    assert_eq!(out, "11\n12\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Tail, &["-c", "3"], NUMBERS);
+    assert_eq!(out, "12\n");
+}
+
+#[test]
+fn tail_follow_prints_appended_data_until_file_is_removed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("log.txt");
+    std::fs::write(&path, b"one\n").unwrap();
+    let path_str = path.to_string_lossy().to_string();
+
+    let writer_path = path.clone();
+    let writer = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(250));
+        let mut f = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&writer_path)
+            .unwrap();
+        f.write_all(b"two\n").unwrap();
+        drop(f);
+        std::thread::sleep(std::time::Duration::from_millis(400));
+        std::fs::remove_file(&writer_path).unwrap();
+    });
+
+    let (control, out, _e) = run(Builtin::Tail, &["-f", &path_str]);
+    writer.join().unwrap();
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "one\ntwo\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn pipe_consumers_stop_reading_an_endless_producer() {
+    let started = Instant::now();
+    let (code, out, err) = run_with_input(
+        "yes | head -n 2\necho $PIPESTATUS\nyes | grep -n -m 1 y\nyes | cat | tail -n +2 | head -c 4\necho\ntimeout 1 yes | tail -n 1\ntimeout 1 yes | wc > /dev/null\necho $PIPESTATUS",
+    );
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert_eq!(code, 0, "stderr: {err}");
+    assert_eq!(out, "y\ny\n141 0\n1:y\ny\ny\n\ny\n124 0\n");
+}
+
+#[test]
+fn sort_default_numeric_reverse_and_unique() {
+    let input = b"b\n10\na\n9\nb\n";
+    let (_c, out, _e) = run_with_stdin(Builtin::Sort, &[], input);
+    assert_eq!(out, "10\n9\na\nb\nb\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Sort, &["-n"], b"10\n9\n-1\n");
+    assert_eq!(out, "-1\n9\n10\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Sort, &["-r", "-u"], input);
+    assert_eq!(out, "b\na\n9\n10\n");
+}
+
+#[test]
+fn sort_by_key_with_separator() {
+    let input = b"bob:30\nalice:25\ncarol:35\n";
+    let (control, out, err) = run_with_stdin(Builtin::Sort, &["-t", ":", "-k", "2", "-n"], input);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "alice:25\nbob:30\ncarol:35\n");
+    assert!(err.is_empty());
+}
+
+#[test]
+fn sort_key_modifiers_apply_to_their_key() {
+    let input = b"b:10\na:9\nc:10\nd: 2\n";
+    let (control, out, err) =
+        run_with_stdin(Builtin::Sort, &["-t", ":", "-k2,2nr", "-k1,1"], input);
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "b:10\nc:10\na:9\nd: 2\n");
+    assert!(err.is_empty());
+    // Ключ с модификаторами не наследует `-r`; ключ без них — наследует.
+    let (_c, out, _e) = run_with_stdin(Builtin::Sort, &["-r", "-t", ":", "-k2n", "-k1"], input);
+    assert_eq!(out, "d: 2\na:9\nc:10\nb:10\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Sort, &["-t", ":", "-k2b"], input);
+    assert_eq!(out, "b:10\nc:10\nd: 2\na:9\n");
+}
+
+#[test]
+fn sort_rejects_unsupported_key_modifiers() {
+    let (control, out, err) = run_with_stdin(Builtin::Sort, &["-k2,2M"], b"a\n");
+    assert_eq!(control, ShellControl::Continue(2));
+    assert!(out.is_empty());
+    assert_eq!(err, "sort: 2,2M: unsupported key modifier 'M'\n");
+    let (control, _out, err) = run_with_stdin(Builtin::Sort, &["-k1.2"], b"a\n");
+    assert_eq!(control, ShellControl::Continue(2));
+    assert_eq!(
+        err,
+        "sort: 1.2: character offsets in keys are not supported\n"
+    );
+}
+
+#[test]
+fn sort_invalid_key_is_error() {
+    let (control, _out, err) = run_with_stdin(Builtin::Sort, &["-k", "0"], b"a\n");
+    assert_eq!(control, ShellControl::Continue(2));
+    assert!(err.contains("invalid key"));
+}
+
+#[test]
+fn uniq_counts_and_filters_groups() {
+    let input = b"a\na\nb\nc\nc\nc\n";
+    let (_c, out, _e) = run_with_stdin(Builtin::Uniq, &[], input);
+    assert_eq!(out, "a\nb\nc\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Uniq, &["-c"], input);
+    assert_eq!(out, "      2 a\n      1 b\n      3 c\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Uniq, &["-d"], input);
+    assert_eq!(out, "a\nc\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Uniq, &["-u"], input);
+    assert_eq!(out, "b\n");
+}
+
+#[test]
+fn cut_selects_fields_and_chars() {
+    let input = b"a:b:c:d\nno-delim\n";
+    let (_c, out, _e) = run_with_stdin(Builtin::Cut, &["-d", ":", "-f", "1,3-"], input);
+    assert_eq!(out, "a:c:d\nno-delim\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Cut, &["-d", ":", "-f", "2", "-s"], input);
+    assert_eq!(out, "b\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Cut, &["-c", "-3"], b"abcdef\n");
+    assert_eq!(out, "abc\n");
+}
+
+#[test]
+fn cut_requires_list() {
+    let (control, _out, err) = run_with_stdin(Builtin::Cut, &[], b"a\n");
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(err.contains("-f or -c"));
+}
+
+#[test]
+fn tr_translates_ranges_and_classes() {
+    let (_c, out, _e) = run_with_stdin(Builtin::Tr, &["a-z", "A-Z"], b"hello\n");
+    assert_eq!(out, "HELLO\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Tr, &["[:lower:]", "[:upper:]"], b"abc\n");
+    assert_eq!(out, "ABC\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Tr, &["abc", "x"], b"aabbcc\n");
+    assert_eq!(out, "xxxxxx\n");
+}
+
+#[test]
+fn tr_deletes_and_squeezes() {
+    let (_c, out, _e) = run_with_stdin(Builtin::Tr, &["-d", "[:digit:]"], b"a1b2c3\n");
+    assert_eq!(out, "abc\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Tr, &["-s", " "], b"a   b  c\n");
+    assert_eq!(out, "a b c\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Tr, &["-s", "a-z", "A-Z"], b"aabbc\n");
+    assert_eq!(out, "ABC\n");
+    let (_c, out, _e) = run_with_stdin(Builtin::Tr, &["-d", "-s", "0-9", "\\n"], b"a1\n\n2b\n");
+    assert_eq!(out, "a\nb\n");
+}
+
+#[test]
+fn tr_reports_bad_range() {
+    let (control, _out, err) = run_with_stdin(Builtin::Tr, &["z-a", "x"], b"a\n");
+    assert_eq!(control, ShellControl::Continue(1));
+    assert!(err.contains("reverse order"));
+}
+
+#[test]
+fn tee_writes_stdout_and_files_with_append() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("out.txt");
+    let path_str = path.to_string_lossy().to_string();
+
+    let (control, out, err) = run_with_stdin(Builtin::Tee, &[&path_str], b"one\n");
+    assert_eq!(control, ShellControl::Continue(0));
+    assert_eq!(out, "one\n");
+    assert!(err.is_empty());
+
+    let (_c, _out, _e) = run_with_stdin(Builtin::Tee, &["-a", &path_str], b"two\n");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+}
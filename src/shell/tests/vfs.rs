@@ -0,0 +1,315 @@
+//! Тесты файловых систем `vfs` и работы шелла с ними.
+
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use super::super::Shell;
+use super::super::vfs::{FileSystem, MemoryFs, Metadata, OsFs, OverlayFs, ReadOnlyFs};
+
+fn text(fs: &dyn FileSystem, path: &str) -> String {
+    String::from_utf8(fs.read(Path::new(path)).unwrap()).unwrap()
+}
+
+fn error(result: std::io::Result<impl Sized>) -> String {
+    match result {
+        Ok(_) => panic!("expected an error"),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[test]
+fn memory_fs_reads_writes_and_reports_errors() {
+    let fs = MemoryFs::new()
+        .with_file("/a/b/file.txt", "data")
+        .with_dir("/empty");
+    assert_eq!(fs.current_dir().unwrap(), Path::new("/"));
+    assert!(fs.metadata(Path::new("/a/b")).unwrap().is_dir());
+    let meta = fs.metadata(Path::new("/a/./b/../b/file.txt")).unwrap();
+    assert!(meta.is_file());
+    assert_eq!(meta.size(), 4);
+    assert_eq!(meta.mode(), Metadata::file(4).mode());
+    assert!(fs.metadata(Path::new("/empty")).unwrap().is_dir());
+    assert!(fs.metadata(Path::new("/")).unwrap().is_dir());
+
+    fs.open_write(Path::new("/a/b/file.txt"), true)
+        .unwrap()
+        .write_all(b"+more")
+        .unwrap();
+    assert_eq!(text(&fs, "/a/b/file.txt"), "data+more");
+    fs.open_write(Path::new("/a/b/file.txt"), false).unwrap();
+    assert_eq!(text(&fs, "/a/b/file.txt"), "");
+
+    assert_eq!(
+        error(fs.read(Path::new("/missing"))),
+        "No such file or directory"
+    );
+    assert_eq!(error(fs.read(Path::new("/a"))), "Is a directory");
+    assert_eq!(
+        error(fs.open_write(Path::new("/no/dir/f"), false)),
+        "No such file or directory"
+    );
+    assert_eq!(
+        error(fs.open_write(Path::new("/a/b/file.txt/x"), false)),
+        "Not a directory"
+    );
+    assert_eq!(
+        error(fs.open_write(Path::new("/empty"), true)),
+        "Is a directory"
+    );
+}
+
+#[test]
+fn memory_fs_clones_share_contents() {
+    let fs = MemoryFs::new();
+    let copy = fs.clone();
+    copy.open_write(Path::new("/f"), false)
+        .unwrap()
+        .write_all(b"shared")
+        .unwrap();
+    assert_eq!(text(&fs, "/f"), "shared");
+}
+
+#[test]
+fn builtins_and_redirections_use_the_shell_filesystem() {
+    let fs = MemoryFs::new()
+        .with_file("/home/notes.txt", "b\na\nc\na\n")
+        .with_file("/home/script.sh", "cd /home\nLOADED=$1\n");
+    let mut shell = Shell::builder().filesystem(fs.clone()).build();
+    let out = shell.eval(
+        "pwd\nsource /home/script.sh yes\npwd\ncat notes.txt | sort | uniq > sorted\nwc sorted\ngrep -n a < notes.txt\nhead -n 1 sorted >> sorted\ncat sorted | tee copy | tail -n 1\ncd /nope\ncat missing\necho $LOADED",
+    );
+    assert_eq!(out.stdout_text(), "/\n/home\n3 3 6\n2:a\n4:a\na\nyes\n");
+    assert_eq!(
+        out.stderr_text(),
+        "cd: /nope: No such file or directory\ncat: missing: No such file or directory\n"
+    );
+    assert_eq!(text(&fs, "/home/sorted"), "a\nb\nc\na\n");
+    assert_eq!(text(&fs, "/home/copy"), "a\nb\nc\na\n");
+}
+
+#[test]
+fn relative_current_dir_starts_from_filesystem_root() {
+    let fs = MemoryFs::new().with_file("/srv/app/config", "port=80\n");
+    let mut shell = Shell::builder()
+        .filesystem(fs)
+        .current_dir("srv/app")
+        .builtin("size", |call| {
+            let meta = call.fs.metadata(&call.cwd.join(&call.args[0]))?;
+            writeln!(call.stdout, "{}", meta.size())?;
+            Ok(0)
+        })
+        .build();
+    let out = shell.eval("pwd\ncat config\nsize config");
+    assert_eq!(out.stdout_text(), "/srv/app\nport=80\n8\n");
+}
+
+#[test]
+fn tail_follow_polls_the_shell_filesystem_until_the_deadline() {
+    let fs = MemoryFs::new().with_file("/log", "one\n");
+    let writer_fs = fs.clone();
+    let writer = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(150));
+        let mut file = writer_fs.open_write(Path::new("/log"), true).unwrap();
+        file.write_all(b"two\n").unwrap();
+    });
+    let mut shell = Shell::builder()
+        .filesystem(fs)
+        .timeout(Duration::from_millis(500))
+        .build();
+
+    let start = Instant::now();
+    let out = shell.eval("tail -f /log");
+    writer.join().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(3));
+    assert_eq!(out.stdout_text(), "one\ntwo\n");
+    assert_eq!(out.status.code, 124);
+}
+
+#[test]
+fn tail_follow_rereads_a_truncated_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("log");
+    std::fs::write(&log, "one\ntwo\n").unwrap();
+    let writer_log = log.clone();
+    let writer = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(150));
+        std::fs::write(&writer_log, "x\n").unwrap();
+        std::thread::sleep(Duration::from_millis(150));
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&writer_log)
+            .unwrap();
+        file.write_all(b"y\n").unwrap();
+    });
+    let mut shell = Shell::builder()
+        .current_dir(dir.path())
+        .timeout(Duration::from_millis(700))
+        .build();
+
+    let out = shell.eval("tail -f log");
+    writer.join().unwrap();
+    assert_eq!(out.stdout_text(), "one\ntwo\nx\ny\n");
+    assert_eq!(out.status.code, 124);
+}
+
+#[test]
+fn read_from_returns_the_rest_of_a_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("f");
+    std::fs::write(&path, "hello\n").unwrap();
+    let memory = MemoryFs::new().with_file("/f", "hello\n");
+    let filesystems: [(&dyn FileSystem, &Path); 3] = [
+        (&OsFs, &path),
+        (&memory, Path::new("/f")),
+        (&ReadOnlyFs::new(memory.clone()), Path::new("/f")),
+    ];
+    for (fs, path) in filesystems {
+        assert_eq!(fs.read_from(path, 0).unwrap(), b"hello\n");
+        assert_eq!(fs.read_from(path, 2).unwrap(), b"llo\n");
+        assert_eq!(fs.read_from(path, 10).unwrap(), b"");
+    }
+    assert_eq!(
+        error(memory.read_from(Path::new("/nope"), 1)),
+        "No such file or directory"
+    );
+}
+
+#[test]
+fn read_only_fs_rejects_writes() {
+    let fs = ReadOnlyFs::new(MemoryFs::new().with_file("/in", "x\n"));
+    let mut shell = Shell::builder().filesystem(fs.clone()).build();
+    let out = shell.eval("cat in\necho y > out\necho z | tee in\necho $PIPESTATUS");
+    assert_eq!(out.stdout_text(), "x\nz\n0 1\n");
+    assert_eq!(
+        out.stderr_text(),
+        "out: Read-only file system\ntee: in: Read-only file system\n"
+    );
+    assert_eq!(text(&fs, "/in"), "x\n");
+}
+
+#[test]
+fn overlay_keeps_the_lower_filesystem_unchanged() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("log"), "old\n").unwrap();
+    let overlay = OverlayFs::new(OsFs);
+    let mut shell = Shell::builder()
+        .filesystem(overlay.clone())
+        .current_dir(dir.path())
+        .build();
+    let out = shell.eval("echo new >> log\necho fresh > created\ncat log created\necho x > nope/f");
+    assert_eq!(out.stdout_text(), "old\nnew\nfresh\n");
+    assert_eq!(out.stderr_text(), "nope/f: No such file or directory\n");
+
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("log")).unwrap(),
+        "old\n"
+    );
+    assert!(!dir.path().join("created").exists());
+    let changed = overlay.changes();
+    assert_eq!(
+        text(changed, &dir.path().join("created").to_string_lossy()),
+        "fresh\n"
+    );
+}
+
+#[test]
+fn memory_fs_manages_directories() {
+    let fs = MemoryFs::new()
+        .with_file("/a/f", "1")
+        .with_file("/a/sub/g", "2");
+    let mut names = fs.read_dir(Path::new("/a")).unwrap();
+    names.sort();
+    assert_eq!(names, ["f", "sub"]);
+    assert_eq!(error(fs.read_dir(Path::new("/a/f"))), "Not a directory");
+
+    fs.create_dir(Path::new("/b")).unwrap();
+    assert_eq!(error(fs.create_dir(Path::new("/b"))), "File exists");
+    assert_eq!(
+        error(fs.create_dir(Path::new("/x/y"))),
+        "No such file or directory"
+    );
+    fs.create_dir_all(Path::new("/x/y/z")).unwrap();
+    assert!(fs.metadata(Path::new("/x/y")).unwrap().is_dir());
+
+    fs.rename(Path::new("/a"), Path::new("/b/a")).unwrap();
+    assert_eq!(text(&fs, "/b/a/sub/g"), "2");
+    assert!(fs.metadata(Path::new("/a")).is_err());
+    assert_eq!(
+        error(fs.rename(Path::new("/b"), Path::new("/b/a/inside"))),
+        "Invalid argument"
+    );
+
+    fs.copy(Path::new("/b/a/f"), Path::new("/copy")).unwrap();
+    assert_eq!(text(&fs, "/copy"), "1");
+    assert_eq!(error(fs.remove_file(Path::new("/b"))), "Is a directory");
+    fs.remove_file(Path::new("/copy")).unwrap();
+    fs.remove_dir_all(Path::new("/b")).unwrap();
+    assert!(fs.metadata(Path::new("/b/a/f")).is_err());
+
+    let old = std::time::UNIX_EPOCH + std::time::Duration::from_secs(86_400);
+    fs.set_times(Path::new("/x"), old, old).unwrap();
+    assert_eq!(fs.metadata(Path::new("/x")).unwrap().modified(), Some(old));
+}
+
+#[test]
+fn file_builtins_work_on_memory_fs() {
+    let fs = MemoryFs::new()
+        .with_file("/data/a.txt", "alpha\n")
+        .with_file("/data/logs/old.log", "x\n");
+    let mut shell = Shell::builder().filesystem(fs.clone()).build();
+    let out = shell.eval(
+        "cd /data\nls\n[ -f a.txt ] && echo file\n[ -d logs ] && echo dir\n[ -e nope ] || echo missing\n\
+         mkdir -p new/deep\ncp a.txt new/deep/b.txt\ncp -r logs saved\nmv a.txt moved.txt\ntouch -d 2001-02-03 stamp\n\
+         find . -name '*.txt'\nrm -r logs\nls -R saved\nstat stamp | grep Modify\n[ moved.txt -nt stamp ] && echo newer",
+    );
+    assert_eq!(
+        out.stdout_text(),
+        "a.txt\nlogs\nfile\ndir\nmissing\n./moved.txt\n./new/deep/b.txt\n\
+         saved:\nold.log\nModify: 2001-02-03 00:00:00\nnewer\n"
+    );
+    assert_eq!(out.stderr_text(), "");
+    assert_eq!(text(&fs, "/data/new/deep/b.txt"), "alpha\n");
+    assert_eq!(text(&fs, "/data/saved/old.log"), "x\n");
+    assert!(fs.metadata(Path::new("/data/logs")).is_err());
+    assert!(fs.metadata(Path::new("/data/a.txt")).is_err());
+}
+
+#[test]
+fn file_builtins_cannot_change_read_only_fs() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("keep"), "x").unwrap();
+    let mut shell = Shell::builder()
+        .filesystem(ReadOnlyFs::new(OsFs))
+        .current_dir(dir.path())
+        .build();
+    let out = shell.eval(
+        "ls\n[ -f keep ] && echo present\nrm keep\nrm -rf .\nmkdir d\ncp keep copy\nmv keep moved\ntouch keep\nls",
+    );
+    assert_eq!(out.stdout_text(), "keep\npresent\nkeep\n");
+    assert_eq!(
+        out.stderr_text(),
+        "rm: keep: Read-only file system\n\
+         rm: refusing to remove '.' or '..' directory: skipping '.'\n\
+         mkdir: d: Read-only file system\n\
+         cp: keep: Read-only file system\n\
+         mv: keep: Read-only file system\n\
+         touch: keep: Read-only file system\n"
+    );
+    assert!(dir.path().join("keep").exists());
+}
+
+#[test]
+fn overlay_hides_removed_files() {
+    let base = MemoryFs::new()
+        .with_file("/srv/a", "1")
+        .with_file("/srv/sub/b", "2");
+    let overlay = OverlayFs::new(base.clone());
+    let mut shell = Shell::builder().filesystem(overlay.clone()).build();
+    let out = shell
+        .eval("cd /srv\nrm a\nrm -r sub\nls\nmkdir sub\nls sub\necho c > c\nmv c d\nls\ncat sub/b");
+    assert_eq!(out.stdout_text(), "d\nsub\n");
+    assert_eq!(out.stderr_text(), "cat: sub/b: No such file or directory\n");
+    assert_eq!(text(&base, "/srv/a"), "1");
+    assert_eq!(text(&base, "/srv/sub/b"), "2");
+}
@@ -0,0 +1,100 @@
+//! Тесты раскрытия фигурных скобок и тильды.
+
+use std::collections::HashMap;
+
+use super::expand::expand_line;
+use super::repl::run_with_input;
+
+/// Аргументы `echo` после всех раскрытий строки `echo <words>`.
+fn expand(words: &str, env: &HashMap<String, String>) -> String {
+    expand_line(&format!("echo {words}"), env)
+        .unwrap()
+        .args
+        .join(" ")
+}
+
+fn braces(word: &str) -> String {
+    expand(word, &HashMap::new())
+}
+
+fn env(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn expands_comma_lists_with_prefix_suffix_and_nesting() {
+    assert_eq!(braces("a{b,c}d"), "abd acd");
+    assert_eq!(braces("{a,b}{1,2}"), "a1 a2 b1 b2");
+    assert_eq!(braces("x{a,{b,c}}"), "xa xb xc");
+    assert_eq!(braces("src/{bin,}"), "src/bin src/");
+}
+
+#[test]
+fn expands_numeric_and_char_ranges() {
+    assert_eq!(braces("{1..5}"), "1 2 3 4 5");
+    assert_eq!(braces("{5..1..2}"), "5 3 1");
+    assert_eq!(braces("{01..10..3}"), "01 04 07 10");
+    assert_eq!(braces("{-1..1}"), "-1 0 1");
+    assert_eq!(braces("{a..e..2}"), "a c e");
+    assert_eq!(braces("{C..A}"), "C B A");
+}
+
+#[test]
+fn leaves_invalid_and_quoted_braces_alone() {
+    assert_eq!(braces("{}"), "{}");
+    assert_eq!(braces("{a}"), "{a}");
+    assert_eq!(braces("{1..x}"), "{1..x}");
+    assert_eq!(braces("'{a,b}'"), "{a,b}");
+    assert_eq!(braces("\"{a,b}\"c"), "{a,b}c");
+    assert_eq!(braces("{a,'b,c'}"), "a b,c");
+    assert_eq!(braces("{"), "{");
+}
+
+#[test]
+fn expands_tilde_prefixes() {
+    let env = env(&[("HOME", "/home/me"), ("PWD", "/work"), ("OLDPWD", "/old")]);
+    assert_eq!(
+        expand("~/notes.txt ~ ~+ ~-", &env),
+        "/home/me/notes.txt /home/me /work /old"
+    );
+    assert_eq!(
+        expand("P=~/bin:~/sbin a~ \"~\" '~'", &env),
+        "P=/home/me/bin:/home/me/sbin a~ ~ ~"
+    );
+    assert_eq!(expand("~no_such_user_xyz/x", &env), "~no_such_user_xyz/x");
+}
+
+#[test]
+fn home_directory_is_not_split_or_reexpanded() {
+    let env = env(&[("HOME", "/home/my {a,b} dir")]);
+    assert_eq!(expand("~/x", &env), "/home/my {a,b} dir/x");
+    assert_eq!(
+        expand_line("echo ~", &env).unwrap().args,
+        ["/home/my {a,b} dir"]
+    );
+}
+
+#[test]
+fn repl_expands_braces_before_variables() {
+    let (code, out, err) = run_with_input("A=x\necho {$A,y}-{1..3}\nexit\n");
+    assert_eq!(code, 0, "stderr: {err}");
+    assert!(out.contains("x-1 x-2 x-3 y-1 y-2 y-3\n"), "stdout: {out}");
+}
+
+#[cfg(not(windows))]
+#[test]
+fn repl_creates_directories_from_brace_list() {
+    let dir = tempfile::tempdir().unwrap();
+    let script = format!(
+        "cd {}\nmkdir -p src/{{bin,lib}}\necho ~+/src\nexit\n",
+        dir.path().display()
+    );
+    let (code, out, err) = run_with_input(&script);
+    assert_eq!(code, 0, "stderr: {err}");
+    assert!(dir.path().join("src/bin").is_dir());
+    assert!(dir.path().join("src/lib").is_dir());
+    assert!(out.contains("/src\n"), "stdout: {out}");
+}
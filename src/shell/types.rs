@@ -3,31 +3,266 @@
 use std::fmt;
 
 use super::parser::ParseError;
+use super::wordexp::Word;
 
-/// Спецификация команды после разбора строки.
+/// Байтовый диапазон `[start, end)` во входной строке.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Span {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+impl Span {
+    pub(crate) fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// Диапазон от начала `self` до конца `other`.
+    pub(crate) fn to(self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
+}
+
+/// Простая команда, как она записана в строке: подстановки в ее словах
+/// выполняются непосредственно перед запуском (см. `expand`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SimpleCommand {
+    /// Место команды в строке: от первого до последнего токена.
+    pub(crate) span: Span,
+    /// Слова команды (имя и аргументы); на месте подстановки процесса — пустое слово.
+    pub(crate) words: Vec<Word>,
+    pub(crate) here_input: Option<HereInput>,
+    /// Перенаправления в порядке записи.
+    pub(crate) redirects: Vec<Redirect<Word>>,
+    /// Подстановки процессов `<(list)` / `>(list)` среди слов команды.
+    pub(crate) process_substs: Vec<ProcessSubst>,
+}
+
+/// Источник stdin команды из самой строки.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum HereInput {
+    /// Тело here-document с терминатором в кавычках (`<<'EOF'`): без подстановок.
+    Literal(String),
+    /// Тело here-document `<<EOF`: части `$NAME` и `$((...))` (в кавычках).
+    Heredoc(Word),
+    /// Here-string `<<<word`: слово и перевод строки.
+    HereString(Word),
+}
+
+/// Простая команда после подстановок: готова к запуску.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct CommandSpec {
+    /// Место команды в строке: от первого до последнего токена.
+    pub(crate) span: Span,
     /// Имя команды (builtin или внешняя).
     pub(crate) name: String,
     /// Аргументы команды (без имени).
     pub(crate) args: Vec<String>,
+    /// Текст для stdin из here-document (`<<EOF`) или here-string (`<<<word`).
+    pub(crate) here_input: Option<String>,
+    /// Перенаправления в порядке записи.
+    pub(crate) redirects: Vec<Redirect>,
+    /// Подстановки процессов `<(list)` / `>(list)` среди слов команды.
+    pub(crate) process_substs: Vec<ProcessSubst>,
+}
+
+/// Подстановка процесса: слово команды заменяется путем `/dev/fd/N` к pipe,
+/// на другом конце которого работает `list`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ProcessSubst {
+    /// Номер слова: 0 — имя команды, `i` — `args[i - 1]` (у `SimpleCommand` —
+    /// номер в `words` до подстановок).
+    pub(crate) word: usize,
+    /// `>(list)`: команда пишет в подстановку; `<(list)` — читает из нее.
+    pub(crate) output: bool,
+    pub(crate) list: CommandList,
+}
+
+/// Перенаправление ввода/вывода; `P` — путь (`Word` до подстановок).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Redirect<P = String> {
+    /// `< path`: stdin из файла.
+    Input(P),
+    /// `> path`, `>> path`, `2> path`, `2>> path` (`fd` — 1 или 2).
+    Output { fd: u32, path: P, append: bool },
+    /// `&> path`, `&>> path`: stdout и stderr в один файл.
+    OutputBoth { path: P, append: bool },
+    /// `2>&1`, `>&2`: `fd` пишет туда же, куда сейчас пишет `target`.
+    Duplicate { fd: u32, target: u32 },
+}
+
+impl<P: fmt::Display> fmt::Display for Redirect<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let append = |append: &bool| if *append { ">>" } else { ">" };
+        match self {
+            Redirect::Input(path) => write!(f, "<{path}"),
+            Redirect::Output {
+                fd: 1,
+                path,
+                append: a,
+            } => write!(f, "{}{path}", append(a)),
+            Redirect::Output {
+                fd,
+                path,
+                append: a,
+            } => write!(f, "{fd}{}{path}", append(a)),
+            Redirect::OutputBoth { path, append: a } => write!(f, "&{}{path}", append(a)),
+            Redirect::Duplicate { fd: 1, target } => write!(f, ">&{target}"),
+            Redirect::Duplicate { fd, target } => write!(f, "{fd}>&{target}"),
+        }
+    }
+}
+
+/// Вид группы команд.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GroupKind {
+    /// `( ... )`: выполняется над копией состояния шелла.
+    Subshell,
+    /// `{ ...; }`: выполняется в текущем состоянии.
+    Brace,
+}
+
+/// Группа команд `( list )` или `{ list; }` со своими перенаправлениями.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GroupSpec {
+    pub(crate) kind: GroupKind,
+    /// Место группы в строке: от `(`/`{` до последнего перенаправления.
+    pub(crate) span: Span,
+    pub(crate) body: CommandList,
+    /// Here-document или here-string после группы.
+    pub(crate) here_input: Option<HereInput>,
+    pub(crate) redirects: Vec<Redirect<Word>>,
+}
+
+/// Условная команда `[[ expr ]]`.
+///
+/// Слова выражения не делятся на поля и не пропадают, если раскрылись в пустоту
+/// (`[[ -n $EMPTY ]]` — ложь), а кавычки в них отключают шаблоны справа от
+/// `==`, `!=` и `=~`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ConditionalCommand {
+    /// Место команды в строке: от `[[` до последнего перенаправления.
+    pub(crate) span: Span,
+    /// Слова между `[[` и `]]`.
+    pub(crate) words: Vec<Word>,
+    pub(crate) redirects: Vec<Redirect<Word>>,
+}
+
+/// Слово выражения `[[ ]]` после подстановок.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CondWord {
+    /// Части слова по порядку; `true` — часть в кавычках.
+    pub(crate) parts: Vec<(String, bool)>,
+    /// Слово записано без кавычек и подстановок: только такое может быть
+    /// оператором (`&&`, `-n`, `==`), а `"-n"` и `$OP` — всегда операнды.
+    pub(crate) operator: bool,
+}
+
+impl CondWord {
+    /// Слово без кавычек (аргумент `test` и `[`).
+    pub(crate) fn plain(text: &str) -> Self {
+        CondWord {
+            parts: vec![(text.to_string(), false)],
+            operator: true,
+        }
+    }
+
+    /// Значение слова.
+    pub(crate) fn text(&self) -> String {
+        self.parts.iter().map(|(text, _)| text.as_str()).collect()
+    }
+}
+
+/// `[[ expr ]]` после подстановок в словах и перенаправлениях.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ConditionalStage {
+    pub(crate) words: Vec<CondWord>,
+    pub(crate) redirects: Vec<Redirect>,
+}
+
+/// Стадия пайплайна.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Command {
+    Simple(SimpleCommand),
+    Group(GroupSpec),
+    Conditional(ConditionalCommand),
+}
+
+/// Группа после подстановок в ее перенаправлениях и here-input; команды тела
+/// раскрываются по мере выполнения.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GroupStage {
+    pub(crate) kind: GroupKind,
+    pub(crate) body: CommandList,
+    /// Текст для stdin из here-document или here-string после группы.
+    pub(crate) here_input: Option<String>,
+    pub(crate) redirects: Vec<Redirect>,
+}
+
+/// Стадия пайплайна после подстановок: то, что запускает исполнитель.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Stage {
+    Simple(CommandSpec),
+    Group(GroupStage),
+    Conditional(ConditionalStage),
 }
 
 /// Конвейер команд (pipeline) вида `cmd1 | cmd2 | cmd3`.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct Pipeline {
-    pub(crate) commands: Vec<CommandSpec>,
+    pub(crate) commands: Vec<Command>,
+}
+
+/// Условие выполнения элемента списка относительно предыдущего.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Connector {
+    /// Первый элемент или после `;`.
+    Always,
+    /// После `&&`: только если предыдущий код возврата 0.
+    IfSuccess,
+    /// После `||`: только если предыдущий код возврата не 0.
+    IfFailure,
+}
+
+/// Элемент списка команд: присваивания-префиксы и (необязательный) пайплайн.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ListEntry {
+    pub(crate) connector: Connector,
+    /// Присваивания `NAME=value`: имя и слово целиком (значение раскрывается,
+    /// когда элемент выполняется).
+    pub(crate) assignments: Vec<(String, Word)>,
+    pub(crate) pipeline: Option<Pipeline>,
+}
+
+/// Список команд, разделенных `;`, `&&`, `||`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CommandList {
+    pub(crate) entries: Vec<ListEntry>,
 }
 
 /// Результат исполнения внешней команды.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct RunResult {
+pub struct RunResult {
     /// Код возврата процесса.
-    pub(crate) exit_code: i32,
+    pub exit_code: i32,
     /// Содержимое stdout процесса.
-    pub(crate) stdout: Vec<u8>,
+    pub stdout: Vec<u8>,
     /// Содержимое stderr процесса.
-    pub(crate) stderr: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// Запуск команды по обычным правилам шелла (builtin или внешняя программа).
+///
+/// Нужен builtins, которые сами запускают команды (`xargs`, `find -exec`).
+/// Вывод команды захватывается и возвращается целиком.
+pub(crate) trait CommandRunner: Sync {
+    /// Запускает команду `name` с аргументами `args` и (опционально) stdin.
+    fn run_command(
+        &self,
+        name: &str,
+        args: &[String],
+        stdin: Option<&[u8]>,
+    ) -> ShellResult<RunResult>;
 }
 
 /// Потоки вывода интерпретатора.
@@ -54,8 +289,12 @@ pub(crate) enum ShellError {
     Parse(ParseError),
     /// Ошибка ввода/вывода.
     Io(std::io::Error),
+    /// Программу не удалось запустить (статусы 126/127).
+    Spawn(SpawnError),
     /// Ошибка запуска внешнего процесса.
     Process(String),
+    /// Команда запрещена ограниченным режимом (`--restricted`).
+    Restricted(String),
 }
 
 impl fmt::Display for ShellError {
@@ -63,8 +302,77 @@ impl fmt::Display for ShellError {
         match self {
             ShellError::Parse(e) => write!(f, "Parse error: {e}"),
             ShellError::Io(e) => write!(f, "I/O error: {e}"),
+            ShellError::Spawn(e) => write!(f, "{e}"),
             ShellError::Process(msg) => write!(f, "Process error: {msg}"),
+            ShellError::Restricted(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+/// Программу не удалось запустить.
+///
+/// `Display` печатает сообщение как bash и подсказку с похожими именами:
+///
+/// ```text
+/// gerp: command not found
+///   hint: did you mean `grep`?
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SpawnError {
+    /// Имя команды или путь к программе.
+    pub(crate) name: String,
+    pub(crate) kind: SpawnErrorKind,
+    /// Похожие имена команд (для `NotFound`).
+    pub(crate) suggestions: Vec<String>,
+}
+
+/// Причина, по которой программа не запустилась.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SpawnErrorKind {
+    /// Нет ни builtin, ни программы в `PATH` (или файла по пути с `/`).
+    NotFound,
+    /// Файл есть, но его нельзя выполнить (нет прав, каталог, неверный формат).
+    NotExecutable(String),
+}
+
+impl SpawnError {
+    pub(crate) fn new(name: impl Into<String>, kind: SpawnErrorKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            suggestions: Vec::new(),
+        }
+    }
+
+    /// Код возврата команды: 127 — не найдена, 126 — не запускается (как в POSIX).
+    pub(crate) fn exit_code(&self) -> i32 {
+        match self.kind {
+            SpawnErrorKind::NotFound => 127,
+            SpawnErrorKind::NotExecutable(_) => 126,
+        }
+    }
+}
+
+impl fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = &self.name;
+        match &self.kind {
+            SpawnErrorKind::NotFound if name.contains('/') => {
+                write!(f, "{name}: No such file or directory")?
+            }
+            SpawnErrorKind::NotFound => write!(f, "{name}: command not found")?,
+            SpawnErrorKind::NotExecutable(reason) => write!(f, "{name}: {reason}")?,
+        }
+        if let Some((last, rest)) = self.suggestions.split_last() {
+            let quoted = |s: &String| format!("`{s}`");
+            let mut names = rest.iter().map(quoted).collect::<Vec<_>>().join(", ");
+            if !names.is_empty() {
+                names.push_str(" or ");
+            }
+            names.push_str(&quoted(last));
+            write!(f, "\n  hint: did you mean {names}?")?;
         }
+        Ok(())
     }
 }
 
@@ -0,0 +1,954 @@
+//! Файловая система шелла.
+//!
+//! Builtins, перенаправления, `cd` и `source` обращаются к файлам через трейт
+//! `FileSystem`, а не через `std::fs`: так шелл можно запустить в песочнице или
+//! проверить без временных файлов. Реализации:
+//! - `OsFs` — файловая система ОС (по умолчанию);
+//! - `MemoryFs` — файлы и каталоги в памяти;
+//! - `OverlayFs` — чтение из нижней файловой системы, запись в память поверх нее;
+//! - `ReadOnlyFs` — любая файловая система без права записи.
+//!
+//! Файловую систему выбирает `ShellBuilder::filesystem`. Внешние программы
+//! работают с файловой системой ОС.
+//!
+//! ```
+//! use se_cli::Shell;
+//! use se_cli::vfs::{FileSystem, MemoryFs};
+//!
+//! let fs = MemoryFs::new().with_file("/data/names.txt", "ann\nbob\n");
+//! let mut shell = Shell::builder().filesystem(fs.clone()).build();
+//! let out = shell.eval("cd /data\ngrep b names.txt > found.txt\ncat found.txt | wc");
+//! assert_eq!(out.stdout_text(), "1 1 4\n");
+//! assert_eq!(fs.read("/data/found.txt".as_ref())?, b"bob\n");
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+/// Файловая система шелла (общая для копий состояния).
+pub(crate) type SharedFileSystem = Arc<dyn FileSystem>;
+
+/// Операции с файлами, которые нужны шеллу.
+///
+/// Пути абсолютные: шелл разрешает их от своей рабочей директории. Ошибки —
+/// `io::Error` с текстом как у ОС (`No such file or directory`, `Is a directory`),
+/// шелл печатает его в сообщениях команд.
+pub trait FileSystem: Send + Sync {
+    /// Начальная рабочая директория шелла.
+    fn current_dir(&self) -> io::Result<PathBuf>;
+
+    /// Сведения о файле (по символическим ссылкам — об их цели).
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    /// Сведения о файле без перехода по символической ссылке. По умолчанию —
+    /// `metadata` (ссылок в файловой системе нет).
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.metadata(path)
+    }
+
+    /// Путь без `.`, `..` и символических ссылок (для `test -ef`). Файл должен
+    /// существовать.
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.metadata(path)?;
+        Ok(normalize(path))
+    }
+
+    /// Содержимое файла целиком.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Содержимое файла с байта `offset` (пусто, если файл короче), для
+    /// `tail -f`. По умолчанию — `read` и отрезание начала; `OsFs` читает
+    /// только хвост.
+    fn read_from(&self, path: &Path, offset: u64) -> io::Result<Vec<u8>> {
+        let mut data = self.read(path)?;
+        let start = usize::try_from(offset).map_or(data.len(), |n| n.min(data.len()));
+        data.drain(..start);
+        Ok(data)
+    }
+
+    /// Имена элементов каталога (без `.` и `..`) в любом порядке.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<String>>;
+
+    /// Открывает файл на запись: создает его, если нет, и очищает, если
+    /// `append` — `false`. Каталог файла должен существовать.
+    fn open_write(&self, path: &Path, append: bool) -> io::Result<Box<dyn Write + Send>>;
+
+    /// Создает каталог; родительский каталог должен существовать.
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// Создает каталог вместе с недостающими родительскими; существующий
+    /// каталог — не ошибка.
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut dirs: Vec<_> = path.ancestors().collect();
+        dirs.reverse();
+        for dir in dirs {
+            match self.metadata(dir) {
+                Ok(meta) if meta.is_dir() => {}
+                Ok(_) => return Err(already_exists()),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => self.create_dir(dir)?,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Удаляет файл (не каталог).
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Удаляет каталог со всем содержимым.
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Переименовывает файл или каталог; существующий файл `to` заменяется.
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Копирует содержимое файла `from` в `to` (создает или заменяет `to`).
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let data = self.read(from)?;
+        self.open_write(to, false)?.write_all(&data)
+    }
+
+    /// Задает время доступа и изменения существующего файла или каталога.
+    fn set_times(&self, path: &Path, accessed: SystemTime, modified: SystemTime) -> io::Result<()>;
+}
+
+/// Вид файла.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    File,
+    Dir,
+    Symlink,
+    /// Устройство, сокет, FIFO.
+    Other,
+}
+
+/// Сведения о файле: вид, размер, права, владелец и время.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata {
+    kind: FileKind,
+    size: u64,
+    mode: u32,
+    links: u64,
+    uid: u32,
+    gid: u32,
+    accessed: Option<SystemTime>,
+    modified: Option<SystemTime>,
+}
+
+impl Metadata {
+    fn new(kind: FileKind, size: u64, mode: u32, links: u64) -> Self {
+        Self {
+            kind,
+            size,
+            mode,
+            links,
+            uid: 0,
+            gid: 0,
+            accessed: None,
+            modified: None,
+        }
+    }
+
+    /// Обычный файл размером `size` байт (права `0644`).
+    pub fn file(size: u64) -> Self {
+        Self::new(FileKind::File, size, 0o644, 1)
+    }
+
+    /// Каталог (права `0755`).
+    pub fn dir() -> Self {
+        Self::new(FileKind::Dir, 0, 0o755, 2)
+    }
+
+    /// Символическая ссылка (для `symlink_metadata`).
+    pub fn symlink() -> Self {
+        Self::new(FileKind::Symlink, 0, 0o777, 1)
+    }
+
+    /// Те же сведения с правами `mode` (биты `0o7777`).
+    pub fn with_mode(mut self, mode: u32) -> Self {
+        self.mode = mode & 0o7777;
+        self
+    }
+
+    /// Те же сведения с временем доступа и изменения.
+    pub fn with_times(mut self, accessed: SystemTime, modified: SystemTime) -> Self {
+        self.accessed = Some(accessed);
+        self.modified = Some(modified);
+        self
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.kind == FileKind::Dir
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.kind == FileKind::File
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.kind == FileKind::Symlink
+    }
+
+    /// Размер файла в байтах (для каталога — 0).
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Права доступа (биты `0o7777`).
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    /// Число жестких ссылок.
+    pub fn links(&self) -> u64 {
+        self.links
+    }
+
+    /// Владелец (uid) и группа (gid); вне unix и в памяти — 0.
+    pub fn owner(&self) -> (u32, u32) {
+        (self.uid, self.gid)
+    }
+
+    /// Время последнего доступа, если известно.
+    pub fn accessed(&self) -> Option<SystemTime> {
+        self.accessed
+    }
+
+    /// Время последнего изменения, если известно.
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+}
+
+impl From<std::fs::Metadata> for Metadata {
+    fn from(meta: std::fs::Metadata) -> Self {
+        let ft = meta.file_type();
+        let kind = if ft.is_symlink() {
+            FileKind::Symlink
+        } else if ft.is_dir() {
+            FileKind::Dir
+        } else if ft.is_file() {
+            FileKind::File
+        } else {
+            FileKind::Other
+        };
+
+        #[cfg(unix)]
+        let (mode, links, uid, gid) = {
+            use std::os::unix::fs::MetadataExt;
+            (meta.mode() & 0o7777, meta.nlink(), meta.uid(), meta.gid())
+        };
+        #[cfg(not(unix))]
+        let (mode, links, uid, gid) = {
+            let base = if ft.is_dir() { 0o755 } else { 0o644 };
+            let mode = if meta.permissions().readonly() {
+                base & !0o222
+            } else {
+                base
+            };
+            (mode, 1, 0, 0)
+        };
+
+        Self {
+            kind,
+            size: meta.len(),
+            mode,
+            links,
+            uid,
+            gid,
+            accessed: meta.accessed().ok(),
+            modified: meta.modified().ok(),
+        }
+    }
+}
+
+/// Файловая система ОС (`std::fs`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsFs;
+
+impl FileSystem for OsFs {
+    fn current_dir(&self) -> io::Result<PathBuf> {
+        std::env::current_dir()
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        std::fs::metadata(path).map(Metadata::from)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        std::fs::symlink_metadata(path).map(Metadata::from)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        std::fs::canonicalize(path)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn read_from(&self, path: &Path, offset: u64) -> io::Result<Vec<u8>> {
+        let mut file = std::fs::File::open(path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<String>> {
+        std::fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.file_name().to_string_lossy().into_owned()))
+            .collect()
+    }
+
+    fn open_write(&self, path: &Path, append: bool) -> io::Result<Box<dyn Write + Send>> {
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)?;
+        Ok(Box::new(file))
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        std::fs::create_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        std::fs::remove_file(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        std::fs::remove_dir_all(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::rename(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::copy(from, to).map(|_| ())
+    }
+
+    fn set_times(&self, path: &Path, accessed: SystemTime, modified: SystemTime) -> io::Result<()> {
+        let times = std::fs::FileTimes::new()
+            .set_accessed(accessed)
+            .set_modified(modified);
+        std::fs::File::open(path)?.set_times(times)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    File(Vec<u8>),
+    Dir,
+}
+
+/// Узел `MemoryFs` и его время доступа и изменения.
+#[derive(Debug, Clone)]
+struct Entry {
+    node: Node,
+    accessed: SystemTime,
+    modified: SystemTime,
+}
+
+impl Entry {
+    fn new(node: Node) -> Self {
+        let now = SystemTime::now();
+        Self {
+            node,
+            accessed: now,
+            modified: now,
+        }
+    }
+
+    fn metadata(&self) -> Metadata {
+        let meta = match &self.node {
+            Node::File(data) => Metadata::file(data.len() as u64),
+            Node::Dir => Metadata::dir(),
+        };
+        meta.with_times(self.accessed, self.modified)
+    }
+}
+
+/// Файлы и каталоги в памяти; корень `/` — рабочая директория по умолчанию.
+///
+/// Копии (`clone`) разделяют содержимое: приложение может оставить копию себе и
+/// читать файлы, которые записали команды.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFs {
+    nodes: Arc<Mutex<BTreeMap<PathBuf, Entry>>>,
+}
+
+impl MemoryFs {
+    /// Пустая файловая система (только корень).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Добавляет файл `path` с содержимым `contents` (и недостающие каталоги).
+    pub fn with_file(self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) -> Self {
+        self.insert(path.as_ref(), Node::File(contents.into()));
+        self
+    }
+
+    /// Добавляет каталог `path` (и недостающие родительские).
+    pub fn with_dir(self, path: impl AsRef<Path>) -> Self {
+        self.insert(path.as_ref(), Node::Dir);
+        self
+    }
+
+    fn nodes(&self) -> MutexGuard<'_, BTreeMap<PathBuf, Entry>> {
+        self.nodes.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Узел по пути; корень — всегда каталог.
+    fn get(&self, path: &Path) -> Option<Entry> {
+        let path = key(path);
+        if path.parent().is_none() {
+            return Some(Entry::new(Node::Dir));
+        }
+        self.nodes().get(&path).cloned()
+    }
+
+    /// Записывает узел, создавая недостающие каталоги.
+    fn insert(&self, path: &Path, node: Node) {
+        let path = key(path);
+        let mut nodes = self.nodes();
+        for dir in path.ancestors().skip(1) {
+            if dir.parent().is_some() && !nodes.contains_key(dir) {
+                nodes.insert(dir.to_path_buf(), Entry::new(Node::Dir));
+            }
+        }
+        nodes.insert(path, Entry::new(node));
+    }
+
+    /// Удаляет узел вместе с содержимым (если это каталог).
+    fn remove_tree(&self, path: &Path) {
+        let path = key(path);
+        self.nodes().retain(|p, _| !p.starts_with(&path));
+    }
+
+    /// Проверяет, что родительский каталог `path` существует.
+    fn check_parent(&self, path: &Path) -> io::Result<()> {
+        let parent = key(path).parent().map(Path::to_path_buf);
+        match parent.and_then(|parent| self.get(&parent)).map(|e| e.node) {
+            Some(Node::Dir) => Ok(()),
+            Some(Node::File(_)) => Err(not_a_directory()),
+            None => Err(not_found()),
+        }
+    }
+
+    /// Писатель, дописывающий в существующий файл `path`.
+    fn writer(&self, path: &Path) -> Box<dyn Write + Send> {
+        Box::new(MemoryWriter {
+            fs: self.clone(),
+            path: key(path),
+        })
+    }
+}
+
+impl FileSystem for MemoryFs {
+    fn current_dir(&self) -> io::Result<PathBuf> {
+        Ok(PathBuf::from("/"))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.get(path).map(|e| e.metadata()).ok_or_else(not_found)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.get(path).map(|e| e.node) {
+            Some(Node::File(data)) => Ok(data),
+            Some(Node::Dir) => Err(is_a_directory()),
+            None => Err(not_found()),
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<String>> {
+        match self.get(path).map(|e| e.node) {
+            Some(Node::Dir) => {}
+            Some(Node::File(_)) => return Err(not_a_directory()),
+            None => return Err(not_found()),
+        }
+        let dir = key(path);
+        Ok(self
+            .nodes()
+            .keys()
+            .filter(|p| p.parent() == Some(dir.as_path()))
+            .filter_map(|p| p.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect())
+    }
+
+    fn open_write(&self, path: &Path, append: bool) -> io::Result<Box<dyn Write + Send>> {
+        self.check_parent(path)?;
+        match self.get(path).map(|e| e.node) {
+            Some(Node::Dir) => return Err(is_a_directory()),
+            Some(Node::File(_)) if append => {}
+            _ => self.insert(path, Node::File(Vec::new())),
+        }
+        Ok(self.writer(path))
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        self.check_parent(path)?;
+        if self.get(path).is_some() {
+            return Err(already_exists());
+        }
+        self.insert(path, Node::Dir);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        match self.get(path).map(|e| e.node) {
+            Some(Node::File(_)) => {
+                self.remove_tree(path);
+                Ok(())
+            }
+            Some(Node::Dir) => Err(is_a_directory()),
+            None => Err(not_found()),
+        }
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        match self.get(path).map(|e| e.node) {
+            Some(Node::Dir) => {
+                self.remove_tree(path);
+                Ok(())
+            }
+            Some(Node::File(_)) => Err(not_a_directory()),
+            None => Err(not_found()),
+        }
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (from, to) = (key(from), key(to));
+        let source = self.get(&from).ok_or_else(not_found)?;
+        if from == to {
+            return Ok(());
+        }
+        self.check_parent(&to)?;
+        check_rename_target(self, &source.metadata(), &from, &to)?;
+
+        let mut nodes = self.nodes();
+        nodes.retain(|p, _| !p.starts_with(&to));
+        let moved: Vec<_> = nodes
+            .keys()
+            .filter(|p| p.starts_with(&from))
+            .cloned()
+            .collect();
+        for path in moved {
+            if let Some(entry) = nodes.remove(&path)
+                && let Ok(rest) = path.strip_prefix(&from)
+            {
+                nodes.insert(to.join(rest), entry);
+            }
+        }
+        Ok(())
+    }
+
+    fn set_times(&self, path: &Path, accessed: SystemTime, modified: SystemTime) -> io::Result<()> {
+        let path = key(path);
+        if path.parent().is_none() {
+            return Ok(());
+        }
+        match self.nodes().get_mut(&path) {
+            Some(entry) => {
+                entry.accessed = accessed;
+                entry.modified = modified;
+                Ok(())
+            }
+            None => Err(not_found()),
+        }
+    }
+}
+
+/// Дописывает данные в файл `MemoryFs`.
+struct MemoryWriter {
+    fs: MemoryFs,
+    path: PathBuf,
+}
+
+impl Write for MemoryWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.fs.nodes().get_mut(&self.path) {
+            Some(Entry {
+                node: Node::File(data),
+                modified,
+                ..
+            }) => {
+                data.extend_from_slice(buf);
+                *modified = SystemTime::now();
+            }
+            _ => return Err(not_found()),
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Запись в память поверх другой файловой системы.
+///
+/// Чтение ищет файл сначала в записанных, затем в нижней файловой системе;
+/// запись (в том числе дописывание в файл нижней системы) попадает только в
+/// память, нижняя система не меняется. Удаленные файлы нижней системы
+/// запоминаются и больше не видны.
+///
+/// ```
+/// use se_cli::Shell;
+/// use se_cli::vfs::{FileSystem, MemoryFs, OverlayFs};
+///
+/// let base = MemoryFs::new().with_file("/log", "old\n");
+/// let overlay = OverlayFs::new(base.clone());
+/// let mut shell = Shell::builder().filesystem(overlay.clone()).build();
+/// shell.eval("echo new >> /log");
+/// assert_eq!(overlay.read("/log".as_ref())?, b"old\nnew\n");
+/// assert_eq!(base.read("/log".as_ref())?, b"old\n");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct OverlayFs {
+    lower: Arc<dyn FileSystem>,
+    upper: MemoryFs,
+    /// Удаленные пути: они и их содержимое в нижней системе скрыты.
+    removed: Arc<Mutex<BTreeSet<PathBuf>>>,
+}
+
+impl OverlayFs {
+    pub fn new(lower: impl FileSystem + 'static) -> Self {
+        Self {
+            lower: Arc::new(lower),
+            upper: MemoryFs::new(),
+            removed: Arc::default(),
+        }
+    }
+
+    /// Файлы, записанные поверх нижней файловой системы.
+    pub fn changes(&self) -> &MemoryFs {
+        &self.upper
+    }
+
+    /// Путь нижней системы скрыт удалением (его или каталога, в котором он лежит).
+    fn hidden(&self, path: &Path) -> bool {
+        let removed = self.removed.lock().unwrap_or_else(|e| e.into_inner());
+        key(path).ancestors().any(|p| removed.contains(p))
+    }
+
+    /// Файловая система, в которой сейчас лежит `path`, или `NotFound`.
+    fn layer(&self, path: &Path) -> io::Result<&dyn FileSystem> {
+        if self.upper.get(path).is_some() {
+            Ok(&self.upper)
+        } else if self.hidden(path) {
+            Err(not_found())
+        } else {
+            Ok(self.lower.as_ref())
+        }
+    }
+
+    /// Удаляет путь из памяти и скрывает его в нижней системе.
+    fn remove(&self, path: &Path) {
+        self.upper.remove_tree(path);
+        self.removed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key(path));
+    }
+
+    /// Копирует файл или каталог нижней системы в память (для `set_times`).
+    fn copy_up(&self, path: &Path) -> io::Result<()> {
+        if self.upper.get(path).is_some() {
+            return Ok(());
+        }
+        let node = if self.metadata(path)?.is_dir() {
+            Node::Dir
+        } else {
+            Node::File(self.read(path)?)
+        };
+        self.upper.insert(path, node);
+        Ok(())
+    }
+}
+
+impl FileSystem for OverlayFs {
+    fn current_dir(&self) -> io::Result<PathBuf> {
+        self.lower.current_dir()
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.layer(path)?.metadata(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.layer(path)?.symlink_metadata(path)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.layer(path)?.read(path)
+    }
+
+    fn read_from(&self, path: &Path, offset: u64) -> io::Result<Vec<u8>> {
+        self.layer(path)?.read_from(path, offset)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<String>> {
+        if !self.metadata(path)?.is_dir() {
+            return Err(not_a_directory());
+        }
+        let mut names = BTreeSet::new();
+        if self.upper.get(path).is_some() {
+            names.extend(self.upper.read_dir(path)?);
+        }
+        if !self.hidden(path)
+            && let Ok(lower) = self.lower.read_dir(path)
+        {
+            names.extend(
+                lower
+                    .into_iter()
+                    .filter(|name| !self.hidden(&path.join(name))),
+            );
+        }
+        Ok(names.into_iter().collect())
+    }
+
+    fn open_write(&self, path: &Path, append: bool) -> io::Result<Box<dyn Write + Send>> {
+        let parent = key(path).parent().map(Path::to_path_buf);
+        match parent.map(|parent| self.metadata(&parent)) {
+            Some(Ok(meta)) if meta.is_dir() => {}
+            Some(Ok(_)) => return Err(not_a_directory()),
+            _ => return Err(not_found()),
+        }
+        let contents = match self.metadata(path) {
+            Ok(meta) if meta.is_dir() => return Err(is_a_directory()),
+            Ok(_) if append => self.read(path)?,
+            _ => Vec::new(),
+        };
+        self.upper.insert(path, Node::File(contents));
+        Ok(self.upper.writer(path))
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let parent = key(path).parent().map(Path::to_path_buf);
+        match parent.map(|parent| self.metadata(&parent)) {
+            Some(Ok(meta)) if meta.is_dir() => {}
+            Some(Ok(_)) => return Err(not_a_directory()),
+            _ => return Err(not_found()),
+        }
+        if self.metadata(path).is_ok() {
+            return Err(already_exists());
+        }
+        self.upper.insert(path, Node::Dir);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        if self.symlink_metadata(path)?.is_dir() {
+            return Err(is_a_directory());
+        }
+        self.remove(path);
+        Ok(())
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        if !self.symlink_metadata(path)?.is_dir() {
+            return Err(not_a_directory());
+        }
+        self.remove(path);
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let source = self.symlink_metadata(from)?;
+        if key(from) == key(to) {
+            return Ok(());
+        }
+        check_rename_target(self, &source, &key(from), &key(to))?;
+        if let Ok(target) = self.metadata(to) {
+            if target.is_dir() {
+                self.remove_dir_all(to)?;
+            } else {
+                self.remove_file(to)?;
+            }
+        }
+        copy_tree(self, from, to)?;
+        if source.is_dir() {
+            self.remove_dir_all(from)
+        } else {
+            self.remove_file(from)
+        }
+    }
+
+    fn set_times(&self, path: &Path, accessed: SystemTime, modified: SystemTime) -> io::Result<()> {
+        self.copy_up(path)?;
+        self.upper.set_times(path, accessed, modified)
+    }
+}
+
+/// Файловая система только для чтения: запись — ошибка `Read-only file system`.
+#[derive(Clone)]
+pub struct ReadOnlyFs {
+    inner: Arc<dyn FileSystem>,
+}
+
+impl ReadOnlyFs {
+    pub fn new(inner: impl FileSystem + 'static) -> Self {
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+}
+
+impl FileSystem for ReadOnlyFs {
+    fn current_dir(&self) -> io::Result<PathBuf> {
+        self.inner.current_dir()
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.inner.metadata(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.inner.symlink_metadata(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.inner.canonicalize(path)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.inner.read(path)
+    }
+
+    fn read_from(&self, path: &Path, offset: u64) -> io::Result<Vec<u8>> {
+        self.inner.read_from(path, offset)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<String>> {
+        self.inner.read_dir(path)
+    }
+
+    fn open_write(&self, _path: &Path, _append: bool) -> io::Result<Box<dyn Write + Send>> {
+        Err(read_only())
+    }
+
+    fn create_dir(&self, _path: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    fn remove_file(&self, _path: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    fn remove_dir_all(&self, _path: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    fn rename(&self, _from: &Path, _to: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    fn copy(&self, _from: &Path, _to: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    fn set_times(
+        &self,
+        _path: &Path,
+        _accessed: SystemTime,
+        _modified: SystemTime,
+    ) -> io::Result<()> {
+        Err(read_only())
+    }
+}
+
+/// Проверки `rename` для файловых систем в памяти (ключи `from` и `to` уже
+/// нормализованы): каталог нельзя перенести внутрь себя, файл — на место
+/// каталога, каталог — на место файла или непустого каталога.
+fn check_rename_target(
+    fs: &dyn FileSystem,
+    source: &Metadata,
+    from: &Path,
+    to: &Path,
+) -> io::Result<()> {
+    if source.is_dir() && to.starts_with(from) && to != from {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Invalid argument",
+        ));
+    }
+    match fs.metadata(to) {
+        Ok(target) if target.is_dir() && !source.is_dir() => Err(is_a_directory()),
+        Ok(target) if !target.is_dir() && source.is_dir() => Err(not_a_directory()),
+        Ok(target) if target.is_dir() && !fs.read_dir(to)?.is_empty() => Err(io::Error::new(
+            io::ErrorKind::DirectoryNotEmpty,
+            "Directory not empty",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Копирует файл или каталог с содержимым внутри одной файловой системы.
+pub(crate) fn copy_tree(fs: &dyn FileSystem, from: &Path, to: &Path) -> io::Result<()> {
+    if fs.metadata(from)?.is_dir() {
+        fs.create_dir_all(to)?;
+        for name in fs.read_dir(from)? {
+            copy_tree(fs, &from.join(&name), &to.join(&name))?;
+        }
+        Ok(())
+    } else {
+        fs.copy(from, to)
+    }
+}
+
+/// Сокращает `.` и `..` в пути без обращения к файловой системе.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() && !out.has_root() {
+                    out.push(component);
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Ключ узла `MemoryFs`: путь от корня без `.` и `..`.
+fn key(path: &Path) -> PathBuf {
+    normalize(&Path::new("/").join(path))
+}
+
+fn not_found() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "No such file or directory")
+}
+
+fn is_a_directory() -> io::Error {
+    io::Error::new(io::ErrorKind::IsADirectory, "Is a directory")
+}
+
+fn not_a_directory() -> io::Error {
+    io::Error::new(io::ErrorKind::NotADirectory, "Not a directory")
+}
+
+fn already_exists() -> io::Error {
+    io::Error::new(io::ErrorKind::AlreadyExists, "File exists")
+}
+
+fn read_only() -> io::Error {
+    io::Error::new(io::ErrorKind::ReadOnlyFilesystem, "Read-only file system")
+}
@@ -0,0 +1,486 @@
+//! Слова командной строки и раскрытия, которые выполняются до подстановок `$`:
+//! фигурные скобки и тильда.
+//!
+//! Порядок как в bash: сначала `{a,b}` / `{1..10}`, затем `~`, затем `$NAME` и
+//! `$((...))` (их выполняет `expand` перед запуском команды). Раскрытия
+//! работают с уже разобранным словом: учитываются только символы вне кавычек,
+//! а подстановки `$` — неделимые части слова. Подстановка путей по шаблонам
+//! (`*.rs`) в шелле не реализована.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::types::Span;
+
+/// Часть слова до подстановок.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum WordPart {
+    /// Текст как есть; `quoted` — внутри кавычек.
+    Literal {
+        text: String,
+        quoted: bool,
+        span: Span,
+    },
+    /// `$NAME`.
+    Var {
+        name: String,
+        quoted: bool,
+        span: Span,
+    },
+    /// `$((expr))`.
+    Arith {
+        expr: String,
+        quoted: bool,
+        span: Span,
+    },
+}
+
+impl WordPart {
+    pub(crate) fn span(&self) -> Span {
+        match self {
+            WordPart::Literal { span, .. }
+            | WordPart::Var { span, .. }
+            | WordPart::Arith { span, .. } => *span,
+        }
+    }
+
+    pub(crate) fn quoted(&self) -> bool {
+        match self {
+            WordPart::Literal { quoted, .. }
+            | WordPart::Var { quoted, .. }
+            | WordPart::Arith { quoted, .. } => *quoted,
+        }
+    }
+
+    /// Часть так, как она записана в строке (без кавычек).
+    fn source(&self) -> String {
+        match self {
+            WordPart::Literal { text, .. } => text.clone(),
+            // `$10` — это `$1` и `0`, а `$A[1]` — `$A` и `[1]`.
+            WordPart::Var { name, .. }
+                if name.contains('[')
+                    || name.len() > 1 && name.starts_with(|c: char| c.is_ascii_digit()) =>
+            {
+                format!("${{{name}}}")
+            }
+            WordPart::Var { name, .. } => format!("${name}"),
+            WordPart::Arith { expr, .. } => format!("$(({expr}))"),
+        }
+    }
+}
+
+/// Слово строки: результат токенизации, подстановки еще не выполнены.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Word {
+    /// Текст слова без кавычек; подстановки записаны как в исходной строке
+    /// (`$X`). Используется для синтаксиса: `{`, `[[`, `2>`, терминатор `<<EOF`.
+    pub(crate) text: String,
+    pub(crate) parts: Vec<WordPart>,
+    /// Слово в исходной строке вместе с кавычками.
+    pub(crate) span: Span,
+}
+
+impl fmt::Display for Word {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl Word {
+    /// Собирает слово из частей, склеивая соседние куски текста.
+    fn from_parts(parts: Vec<WordPart>, span: Span) -> Word {
+        let mut word = Word {
+            span,
+            ..Word::default()
+        };
+        for part in parts {
+            word.text.push_str(&part.source());
+            if let (
+                Some(WordPart::Literal {
+                    text,
+                    quoted,
+                    span: last,
+                }),
+                WordPart::Literal {
+                    text: next,
+                    quoted: next_quoted,
+                    span: next_span,
+                },
+            ) = (word.parts.last_mut(), &part)
+                && quoted == next_quoted
+            {
+                text.push_str(next);
+                *last = last.to(*next_span);
+                continue;
+            }
+            word.parts.push(part);
+        }
+        word
+    }
+
+    /// Есть ли в слове кавычки (`""` — тоже слово).
+    pub(crate) fn is_quoted(&self) -> bool {
+        self.parts.iter().any(WordPart::quoted)
+    }
+
+    /// Слово — ключевое слово `keyword` (без кавычек и подстановок).
+    pub(crate) fn is_keyword(&self, keyword: &str) -> bool {
+        self.text == keyword && self.is_plain()
+    }
+
+    /// В слове нет кавычек и подстановок: только обычный текст.
+    pub(crate) fn is_plain(&self) -> bool {
+        self.parts
+            .iter()
+            .all(|part| matches!(part, WordPart::Literal { quoted: false, .. }))
+    }
+
+    /// Переносит слово и все его части на место `span` (для слов из текста
+    /// алиаса: ошибки указывают на имя алиаса в строке).
+    pub(crate) fn relocate(&mut self, span: Span) {
+        self.span = span;
+        for part in &mut self.parts {
+            match part {
+                WordPart::Literal { span: at, .. }
+                | WordPart::Var { span: at, .. }
+                | WordPart::Arith { span: at, .. } => *at = span,
+            }
+        }
+    }
+
+    /// Открывающая кавычка (`at` — позиция сразу после нее): даже пустые
+    /// кавычки дают слово.
+    pub(crate) fn open_quote(&mut self, at: usize) {
+        self.parts.push(WordPart::Literal {
+            text: String::new(),
+            quoted: true,
+            span: Span::new(at, at),
+        });
+    }
+
+    /// Убирает пустую часть от `open_quote`, если следующая часть тоже в
+    /// кавычках: слово и так останется словом в кавычках (`<<"EOF"`, `"$X"`).
+    fn drop_empty_quote(&mut self, quoted: bool) {
+        if quoted
+            && let Some(WordPart::Literal {
+                text, quoted: true, ..
+            }) = self.parts.last()
+            && text.is_empty()
+        {
+            self.parts.pop();
+        }
+    }
+
+    pub(crate) fn push_char(&mut self, ch: char, quoted: bool, span: Span) {
+        self.text.push(ch);
+        self.drop_empty_quote(quoted);
+        match self.parts.last_mut() {
+            Some(WordPart::Literal {
+                text,
+                quoted: last_quoted,
+                span: last,
+            }) if *last_quoted == quoted => {
+                text.push(ch);
+                *last = last.to(span);
+            }
+            _ => self.parts.push(WordPart::Literal {
+                text: ch.to_string(),
+                quoted,
+                span,
+            }),
+        }
+    }
+
+    pub(crate) fn push_var(&mut self, name: String, quoted: bool, span: Span) {
+        self.text.push('$');
+        self.text.push_str(&name);
+        self.drop_empty_quote(quoted);
+        self.parts.push(WordPart::Var { name, quoted, span });
+    }
+
+    pub(crate) fn push_arith(&mut self, expr: String, quoted: bool, span: Span) {
+        self.text.push_str(&format!("$(({expr}))"));
+        self.drop_empty_quote(quoted);
+        self.parts.push(WordPart::Arith { expr, quoted, span });
+    }
+
+    /// Имя переменной, если слово — присваивание `NAME=value` (имя без кавычек).
+    pub(crate) fn assignment_name(&self) -> Option<&str> {
+        let Some(WordPart::Literal {
+            text,
+            quoted: false,
+            ..
+        }) = self.parts.first()
+        else {
+            return None;
+        };
+        let name = text.split_once('=')?.0;
+        let mut chars = name.chars();
+        let first = chars.next()?;
+        ((first == '_' || first.is_ascii_alphabetic())
+            && chars.all(|c| c == '_' || c.is_ascii_alphanumeric()))
+        .then_some(name)
+    }
+}
+
+/// Разбивает текст слова на части по одному символу (подстановки — как есть).
+fn explode(parts: &[WordPart]) -> Vec<WordPart> {
+    let mut units = Vec::new();
+    for part in parts {
+        match part {
+            WordPart::Literal { text, quoted, span } if !text.is_empty() => {
+                let mut start = span.start;
+                for ch in text.chars() {
+                    let end = (start + ch.len_utf8()).min(span.end).max(start);
+                    units.push(WordPart::Literal {
+                        text: ch.to_string(),
+                        quoted: *quoted,
+                        span: Span::new(start, end),
+                    });
+                    start = end;
+                }
+            }
+            _ => units.push(part.clone()),
+        }
+    }
+    units
+}
+
+/// Символ части, если это один символ вне кавычек.
+fn unquoted_char(unit: &WordPart) -> Option<char> {
+    match unit {
+        WordPart::Literal {
+            text,
+            quoted: false,
+            ..
+        } => {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Раскрывает фигурные скобки в слове: `a{b,c}d` → `abd acd`, `{1..3}` → `1 2 3`.
+///
+/// Скобки без запятой и без корректной последовательности остаются как есть
+/// (`{}`, `{a}`, `{1..x}`), так же как `${...}` и скобки в кавычках.
+pub(crate) fn expand_braces(word: &Word) -> Vec<Word> {
+    expand_units(&explode(&word.parts))
+        .into_iter()
+        .map(|parts| Word::from_parts(parts, word.span))
+        .collect()
+}
+
+fn expand_units(units: &[WordPart]) -> Vec<Vec<WordPart>> {
+    for open in 0..units.len() {
+        if unquoted_char(&units[open]) != Some('{')
+            || (open > 0 && unquoted_char(&units[open - 1]) == Some('$'))
+        {
+            continue;
+        }
+        // Парная `}` и запятые верхнего уровня.
+        let mut depth = 0usize;
+        let mut commas = Vec::new();
+        let mut close = None;
+        for (i, unit) in units.iter().enumerate().skip(open + 1) {
+            match unquoted_char(unit) {
+                Some('{') => depth += 1,
+                Some('}') if depth == 0 => {
+                    close = Some(i);
+                    break;
+                }
+                Some('}') => depth -= 1,
+                Some(',') if depth == 0 => commas.push(i),
+                _ => {}
+            }
+        }
+        let Some(close) = close else {
+            continue;
+        };
+
+        let alternatives: Vec<Vec<WordPart>> = if commas.is_empty() {
+            let span = units[open].span().to(units[close].span());
+            let body: Option<String> = units[open + 1..close].iter().map(unquoted_char).collect();
+            match body.and_then(|body| expand_sequence(&body)) {
+                Some(items) => items
+                    .into_iter()
+                    .map(|text| {
+                        vec![WordPart::Literal {
+                            text,
+                            quoted: false,
+                            span,
+                        }]
+                    })
+                    .collect(),
+                None => continue,
+            }
+        } else {
+            let mut bounds = vec![open];
+            bounds.extend(&commas);
+            bounds.push(close);
+            bounds
+                .windows(2)
+                .map(|pair| units[pair[0] + 1..pair[1]].to_vec())
+                .collect()
+        };
+
+        let prefix = &units[..open];
+        let suffix = &units[close + 1..];
+        return alternatives
+            .into_iter()
+            .flat_map(|mut alt| {
+                alt.extend_from_slice(suffix);
+                expand_units(&alt)
+            })
+            .map(|rest| prefix.iter().cloned().chain(rest).collect())
+            .collect();
+    }
+    vec![units.to_vec()]
+}
+
+/// Последовательность `A..B[..STEP]`: целые числа (с выравниванием нулями,
+/// если у границы ведущий ноль) или одиночные ASCII-буквы.
+fn expand_sequence(body: &str) -> Option<Vec<String>> {
+    let parts: Vec<&str> = body.split("..").collect();
+    let (start, end, step) = match parts.as_slice() {
+        [a, b] => (*a, *b, None),
+        [a, b, s] => (*a, *b, Some(s.parse::<i64>().ok()?)),
+        _ => return None,
+    };
+    let step = step.map(i64::unsigned_abs).unwrap_or(1).max(1);
+
+    if let (Ok(a), Ok(b)) = (start.parse::<i64>(), end.parse::<i64>()) {
+        let padded = |s: &str| {
+            let digits = s.trim_start_matches('-');
+            digits.len() > 1 && digits.starts_with('0')
+        };
+        let width = if padded(start) || padded(end) {
+            start.len().max(end.len())
+        } else {
+            0
+        };
+        return Some(
+            range(a, b, step)
+                .map(|n| {
+                    if n < 0 {
+                        format!("-{:0>w$}", n.unsigned_abs(), w = width.saturating_sub(1))
+                    } else {
+                        format!("{n:0>width$}")
+                    }
+                })
+                .collect(),
+        );
+    }
+
+    let letter = |s: &str| {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphabetic() => Some(c as i64),
+            _ => None,
+        }
+    };
+    let (a, b) = (letter(start)?, letter(end)?);
+    Some(
+        range(a, b, step)
+            .filter_map(|n| u8::try_from(n).ok())
+            .map(|n| (n as char).to_string())
+            .collect(),
+    )
+}
+
+/// Значения от `a` до `b` включительно с шагом `step` (в обе стороны).
+fn range(a: i64, b: i64, step: u64) -> impl Iterator<Item = i64> {
+    let step = step as i64;
+    let count = (a.abs_diff(b) / step as u64) as i64;
+    let dir = if a <= b { 1 } else { -1 };
+    (0..=count).map(move |i| a + dir * i * step)
+}
+
+/// Раскрывает `~` в начале слова, а для присваиваний — в начале значения и после `:`.
+///
+/// `~` — `$HOME` (или домашняя директория из `/etc/passwd`), `~user` — домашняя
+/// директория пользователя, `~+` — `$PWD`, `~-` — `$OLDPWD`. Если значение
+/// неизвестно или в префиксе есть кавычки и подстановки (`~"x"`, `~$U`), слово
+/// не меняется. Значение вставляется как текст в кавычках: оно не делится на поля.
+pub(crate) fn expand_tilde(word: &Word, env: &HashMap<String, String>) -> Word {
+    let mut units = explode(&word.parts);
+    let assignment = word.assignment_name().map(str::len);
+    let starts: Vec<usize> = match assignment {
+        None => vec![0],
+        Some(eq) => std::iter::once(eq + 1)
+            .chain(
+                units
+                    .iter()
+                    .enumerate()
+                    .skip(eq + 1)
+                    .filter(|(_, unit)| unquoted_char(unit) == Some(':'))
+                    .map(|(i, _)| i + 1),
+            )
+            .collect(),
+    };
+    let stops = |c: char| c == '/' || (assignment.is_some() && c == ':');
+
+    // С конца, чтобы замены не сдвигали еще не обработанные позиции.
+    for &start in starts.iter().rev() {
+        if units.get(start).and_then(unquoted_char) != Some('~') {
+            continue;
+        }
+        let mut end = start + 1;
+        let mut user = String::new();
+        let mut plain = true;
+        while let Some(unit) = units.get(end) {
+            match unquoted_char(unit) {
+                Some(c) if stops(c) => break,
+                Some(c) => {
+                    user.push(c);
+                    end += 1;
+                }
+                None => {
+                    plain = false;
+                    break;
+                }
+            }
+        }
+        if !plain {
+            continue;
+        }
+        if let Some(home) = tilde_home(&user, env) {
+            let span = units[start].span().to(units[end - 1].span());
+            units.splice(
+                start..end,
+                [WordPart::Literal {
+                    text: home,
+                    quoted: true,
+                    span,
+                }],
+            );
+        }
+    }
+    Word::from_parts(units, word.span)
+}
+
+fn tilde_home(user: &str, env: &HashMap<String, String>) -> Option<String> {
+    match user {
+        "" => env.get("HOME").cloned().or_else(|| {
+            env.get("USER")
+                .or_else(|| env.get("LOGNAME"))
+                .and_then(|name| passwd_home(name))
+        }),
+        "+" => env.get("PWD").cloned(),
+        "-" => env.get("OLDPWD").cloned(),
+        name => passwd_home(name),
+    }
+}
+
+/// Домашняя директория пользователя из `/etc/passwd`.
+fn passwd_home(user: &str) -> Option<String> {
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        (fields.len() >= 6 && fields[0] == user).then(|| fields[5].to_string())
+    })
+}